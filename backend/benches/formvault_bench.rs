@@ -1,5 +1,6 @@
 use criterion::{Criterion, criterion_group, criterion_main};
-use formvault::{run, spawn_app};
+use formvault::testing::TestApp;
+use formvault::{Settings, run_with};
 use std::sync::atomic::{AtomicU16, Ordering};
 
 // Static counter for unique ports
@@ -12,15 +13,18 @@ fn get_unique_port() -> u16 {
 fn run_benchmark(c: &mut Criterion) {
     let rt = tokio::runtime::Runtime::new().unwrap();
 
+    // Only run if DATABASE_URL is available
+    dotenv::dotenv().ok();
+    let Ok(mut settings) = Settings::from_env() else {
+        return;
+    };
+    // Use port 0 for random available port to avoid conflicts
+    settings.port = 0;
+
     c.bench_function("run_full_startup", |b| {
         b.iter_with_large_drop(|| {
             rt.block_on(async {
-                // Use port 0 for random available port to avoid conflicts
-                unsafe {
-                    std::env::set_var("PORT", "0");
-                }
-
-                match run().await {
+                match run_with(settings.clone()).await {
                     Ok((addr, _server)) => {
                         // Server will be dropped automatically, stopping it
                         Some(addr)
@@ -32,25 +36,20 @@ fn run_benchmark(c: &mut Criterion) {
     });
 }
 
-fn spawn_app_benchmark(c: &mut Criterion) {
+fn test_app_benchmark(c: &mut Criterion) {
     let rt = tokio::runtime::Runtime::new().unwrap();
 
-    c.bench_function("spawn_app_startup", |b| {
+    // Only run if DATABASE_URL is available
+    dotenv::dotenv().ok();
+    let Ok(database_url) = std::env::var("DATABASE_URL") else {
+        return;
+    };
+
+    c.bench_function("test_app_startup", |b| {
+        // Creating and migrating the database is part of the measurement,
+        // dropping it is not
         b.iter_with_large_drop(|| {
-            rt.block_on(async {
-                // Use port 0 for random available port
-                unsafe {
-                    std::env::set_var("PORT", "0");
-                }
-
-                // spawn_app returns just the address, server runs in background
-                let addr = spawn_app().await;
-
-                // Give a moment for the server to fully start
-                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-
-                addr
-            })
+            rt.block_on(TestApp::spawn_with(Settings::new(database_url.clone())))
         })
     });
 }
@@ -151,7 +150,7 @@ fn run_config_only_benchmark(c: &mut Criterion) {
 criterion_group!(
     benches,
     run_benchmark,
-    spawn_app_benchmark,
+    test_app_benchmark,
     database_connection_benchmark,
     env_parsing_benchmark,
     address_formatting_benchmark,
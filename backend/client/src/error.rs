@@ -0,0 +1,97 @@
+use std::fmt;
+
+use formvault::errors::{ErrorResponse, FormVaultError};
+use reqwest::StatusCode;
+
+/// Everything that can go wrong talking to FormVault
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request never got a response: connection, TLS or body decoding
+    Http(reqwest::Error),
+    /// The API answered with an error status
+    Api {
+        status: StatusCode,
+        error: ErrorResponse,
+    },
+    /// Submission data could not be encrypted to the form's key
+    Encryption(FormVaultError),
+    /// A method that needs an API key was called without one
+    MissingApiKey,
+    InvalidUrl(String),
+}
+
+impl ClientError {
+    /// HTTP status of an API error
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// True for a 404 from the API
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    /// ID of the failed request, to quote when reporting a problem
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { error, .. } => error.request_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Machine readable code of an API error, e.g. `VALIDATION_ERROR`
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { error, .. } => Some(&error.code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http(e) => write!(f, "request failed: {}", e),
+            ClientError::Api { status, error } => {
+                write!(f, "{} ({}): {}", status, error.code, error.error)?;
+                if let Some(details) = &error.details {
+                    write!(f, ": {}", details.join("; "))?;
+                }
+                if let Some(request_id) = &error.request_id {
+                    write!(f, " (request {})", request_id)?;
+                }
+                Ok(())
+            }
+            ClientError::Encryption(e) => write!(f, "{}", e),
+            ClientError::MissingApiKey => write!(f, "this call needs an API key"),
+            ClientError::InvalidUrl(url) => write!(f, "invalid base URL {:?}", url),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        ClientError::Http(err)
+    }
+}
+
+impl From<FormVaultError> for ClientError {
+    fn from(err: FormVaultError) -> Self {
+        ClientError::Encryption(err)
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,584 @@
+/*!
+# FormVault Client
+
+Typed access to the FormVault API for Rust services. Requests and
+responses use the same serde models as the server, so the two can't drift
+apart.
+
+Submissions can be sent as plain fields, which the server encrypts, or
+encrypted here first with [`Client::submit_encrypted`] so the plaintext
+never leaves your service.
+
+```rust,no_run
+use std::collections::HashMap;
+use formvault_client::{Client, NewForm};
+
+# async fn example() -> formvault_client::ClientResult<()> {
+let client = Client::new("https://api.example.com")?.with_api_key("fv_...");
+let form = client.create_form(&NewForm::named("Contact")).await?;
+
+let data = HashMap::from([("email".to_string(), "ada@example.com".to_string())]);
+let receipt = client.submit_encrypted(form.id, &data).await?;
+println!("stored submission {}", receipt.id);
+# Ok(())
+# }
+```
+*/
+
+mod error;
+
+use std::collections::HashMap;
+
+use formvault::errors::{ErrorResponse, FormVaultError};
+use formvault::models::forms::field_definition::{FieldDefinition, FieldType};
+use formvault::models::forms::form_schema::{EffectiveKey, FormSchema, SubmissionKey};
+use formvault::models::forms::notification_target::{ChannelKind, NotificationTarget};
+use formvault::models::forms::submission::{
+    EncryptedPayload, FormSubmission, SubmissionReceipt, SubmissionStatus,
+};
+use formvault::models::public_key::{PublicKeyVersion, fingerprint};
+use formvault::models::users::developer::DeveloperProfile;
+use formvault::models::users::session::NewSession;
+use formvault::models::users::two_factor::{RecoveryCodes, TwoFactorStatus};
+use formvault::repositories::encryption::encrypt_form_data;
+use formvault::repositories::public_keys::KeySummary;
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+pub use error::{ClientError, ClientResult};
+
+/// Header the second factor of sensitive requests goes in
+const TOTP_HEADER: &str = "X-TOTP-Code";
+
+/// Returned by [`Client::register`]; the API key is only shown once
+#[derive(Debug, Clone, Deserialize)]
+pub struct Registration {
+    #[serde(flatten)]
+    pub developer: DeveloperProfile,
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NewForm {
+    pub name: String,
+    /// Defaults to the oldest team the developer owns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_id: Option<Uuid>,
+    /// A key for this form only, instead of inheriting the developer's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Origins submissions are accepted from, such as
+    /// `https://*.example.com`; empty accepts every origin
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_origins: Vec<String>,
+    /// Where browsers go after a successful HTML form post, instead of the
+    /// hosted thank-you page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success_url: Option<String>,
+    /// Where browsers go after a failed HTML form post, instead of the
+    /// hosted error page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_url: Option<String>,
+    /// Refuse plaintext fields, so submissions must be encrypted before they
+    /// are sent, as [`Client::submit_encrypted`] does
+    pub zero_knowledge: bool,
+}
+
+impl NewForm {
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewField {
+    pub name: String,
+    pub field_type: FieldType,
+    pub required: bool,
+    /// See [`formvault::models::forms::field_definition::ValidationRules`]
+    pub validation_rules: serde_json::Value,
+}
+
+impl NewField {
+    pub fn new(name: impl Into<String>, field_type: FieldType) -> Self {
+        Self {
+            name: name.into(),
+            field_type,
+            required: false,
+            validation_rules: json!({}),
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn rules(mut self, validation_rules: serde_json::Value) -> Self {
+        self.validation_rules = validation_rules;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewNotificationTarget {
+    pub channel: ChannelKind,
+    /// Webhook URL, or an email address for [`ChannelKind::Email`]
+    pub destination: String,
+    /// Statuses that trigger the target; empty means all
+    pub status_filter: Vec<SubmissionStatus>,
+}
+
+/// Changes to a form's settings; `None` leaves a setting as it is
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FormUpdate {
+    /// Replaces the allowed origins; an empty list accepts every origin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_origins: Option<Vec<String>>,
+    /// An empty string goes back to the hosted thank-you page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success_url: Option<String>,
+    /// An empty string goes back to the hosted error page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zero_knowledge: Option<bool>,
+}
+
+/// Changes to a notification target; `None` leaves a setting as it is
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NotificationTargetUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destination: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_filter: Option<Vec<SubmissionStatus>>,
+}
+
+/// Pagination for submission listings
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Page {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+}
+
+/// A FormVault API client. Cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    api_key: Option<String>,
+    two_factor_code: Option<String>,
+}
+
+impl Client {
+    /// A client for the API at `base_url`, e.g. `https://api.example.com`
+    pub fn new(base_url: impl AsRef<str>) -> ClientResult<Self> {
+        let raw = base_url.as_ref();
+        // Without the trailing slash joins would replace the last segment
+        let mut base_url = Url::parse(raw).map_err(|_| ClientError::InvalidUrl(raw.into()))?;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url,
+            api_key: None,
+            two_factor_code: None,
+        })
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Send `code`, from the account's authenticator app or one of its
+    /// recovery codes, with the requests that need a second factor once
+    /// two-factor authentication is enabled. Authenticator codes only last
+    /// about 30 seconds and work once, so set it right before the request.
+    pub fn with_two_factor_code(mut self, code: impl Into<String>) -> Self {
+        self.two_factor_code = Some(code.into());
+        self
+    }
+
+    /// Use a preconfigured HTTP client, e.g. with timeouts or a proxy
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    fn request(&self, method: Method, path: &str) -> ClientResult<RequestBuilder> {
+        let url = self
+            .base_url
+            .join(path.trim_start_matches('/'))
+            .map_err(|_| ClientError::InvalidUrl(path.into()))?;
+        Ok(self.http.request(method, url))
+    }
+
+    fn authed(&self, method: Method, path: &str) -> ClientResult<RequestBuilder> {
+        let api_key = self.api_key.as_ref().ok_or(ClientError::MissingApiKey)?;
+        let request = self.request(method, path)?.bearer_auth(api_key);
+        Ok(match &self.two_factor_code {
+            Some(code) => request.header(TOTP_HEADER, code),
+            None => request,
+        })
+    }
+
+    /// Turn error statuses into [`ClientError::Api`]
+    async fn check(response: Response) -> ClientResult<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let request_id = response
+            .headers()
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await?;
+        let mut error = serde_json::from_str(&body).unwrap_or_else(|_| ErrorResponse {
+            error: if body.is_empty() {
+                status.canonical_reason().unwrap_or("Request failed").into()
+            } else {
+                body
+            },
+            code: format!("HTTP_{}", status.as_u16()),
+            details: None,
+            request_id: None,
+        });
+        // Errors raised before the handler, such as a malformed body, only
+        // carry the ID in the header
+        error.request_id = error.request_id.or(request_id);
+        Err(ClientError::Api { status, error })
+    }
+
+    async fn send<T: DeserializeOwned>(request: RequestBuilder) -> ClientResult<T> {
+        Ok(Self::check(request.send().await?).await?.json().await?)
+    }
+
+    async fn send_empty(request: RequestBuilder) -> ClientResult<()> {
+        Self::check(request.send().await?).await?;
+        Ok(())
+    }
+
+    // Account
+
+    /// Create a developer account. The client's API key is not changed;
+    /// use [`Client::with_api_key`] with the returned key.
+    pub async fn register(
+        &self,
+        name: &str,
+        email: &str,
+        public_key: &str,
+    ) -> ClientResult<Registration> {
+        let body = json!({ "name": name, "email": email, "public_key": public_key });
+        Self::send(self.request(Method::POST, "developers")?.json(&body)).await
+    }
+
+    pub async fn me(&self) -> ClientResult<DeveloperProfile> {
+        Self::send(self.authed(Method::GET, "developers/me")?).await
+    }
+
+    /// Replace the API key in use; the current one stops working
+    pub async fn rotate_api_key(&self) -> ClientResult<String> {
+        #[derive(Deserialize)]
+        struct Rotated {
+            api_key: String,
+        }
+        let rotated: Rotated =
+            Self::send(self.authed(Method::POST, "developers/me/api_key")?).await?;
+        Ok(rotated.api_key)
+    }
+
+    /// Switch to a new public key, retiring the current version
+    pub async fn rotate_public_key(&self, public_key: &str) -> ClientResult<PublicKeyVersion> {
+        let request = self
+            .authed(Method::PUT, "developers/me/public_key")?
+            .json(&json!({ "public_key": public_key }));
+        Self::send(request).await
+    }
+
+    pub async fn public_keys(&self) -> ClientResult<Vec<KeySummary>> {
+        Self::send(self.authed(Method::GET, "developers/me/public_keys")?).await
+    }
+
+    pub async fn deactivate(&self) -> ClientResult<()> {
+        Self::send_empty(self.authed(Method::DELETE, "developers/me")?).await
+    }
+
+    /// Mail a new verification link to the account's address
+    pub async fn resend_verification(&self) -> ClientResult<()> {
+        Self::send_empty(self.authed(Method::POST, "developers/me/verification")?).await
+    }
+
+    /// Verify an email address with the token from its verification link
+    pub async fn verify_email(&self, token: &str) -> ClientResult<DeveloperProfile> {
+        let request = self
+            .request(Method::POST, "auth/verify")?
+            .json(&json!({ "token": token }));
+        Self::send(request).await
+    }
+
+    /// Mail a dashboard login link to `email`, if it has an account
+    pub async fn request_login(&self, email: &str) -> ClientResult<()> {
+        let request = self
+            .request(Method::POST, "auth/login")?
+            .json(&json!({ "email": email }));
+        Self::send_empty(request).await
+    }
+
+    /// Start a dashboard session with the token from a login link.
+    ///
+    /// The session itself lives in a cookie meant for browsers, which this
+    /// client does not keep; it keeps authenticating with its API key.
+    /// Accounts with two-factor authentication need
+    /// [`Client::with_two_factor_code`] as well.
+    pub async fn start_session(&self, token: &str) -> ClientResult<NewSession> {
+        let request = self
+            .request(Method::POST, "auth/session")?
+            .json(&json!({ "token": token, "code": self.two_factor_code }));
+        Self::send(request).await
+    }
+
+    /// Whether two-factor authentication is on. It is turned on from the
+    /// dashboard, as only a session can enroll an authenticator.
+    pub async fn two_factor_status(&self) -> ClientResult<TwoFactorStatus> {
+        Self::send(self.authed(Method::GET, "developers/me/two_factor")?).await
+    }
+
+    /// Replace the recovery codes, returning the new ones; needs
+    /// [`Client::with_two_factor_code`]
+    pub async fn regenerate_recovery_codes(&self) -> ClientResult<Vec<String>> {
+        let codes: RecoveryCodes =
+            Self::send(self.authed(Method::POST, "developers/me/two_factor/recovery_codes")?)
+                .await?;
+        Ok(codes.recovery_codes)
+    }
+
+    /// Turn two-factor authentication off; needs
+    /// [`Client::with_two_factor_code`]
+    pub async fn disable_two_factor(&self) -> ClientResult<()> {
+        Self::send_empty(self.authed(Method::DELETE, "developers/me/two_factor")?).await
+    }
+
+    // Forms
+
+    pub async fn list_forms(&self) -> ClientResult<Vec<FormSchema>> {
+        Self::send(self.authed(Method::GET, "forms")?).await
+    }
+
+    pub async fn create_form(&self, form: &NewForm) -> ClientResult<FormSchema> {
+        Self::send(self.authed(Method::POST, "forms")?.json(form)).await
+    }
+
+    pub async fn get_form(&self, form_id: Uuid) -> ClientResult<FormSchema> {
+        Self::send(self.authed(Method::GET, &format!("forms/{}", form_id))?).await
+    }
+
+    pub async fn update_form(
+        &self,
+        form_id: Uuid,
+        update: &FormUpdate,
+    ) -> ClientResult<FormSchema> {
+        Self::send(
+            self.authed(Method::PATCH, &format!("forms/{}", form_id))?
+                .json(update),
+        )
+        .await
+    }
+
+    /// The key the form's submissions are encrypted to, and where it comes from
+    pub async fn form_key(&self, form_id: Uuid) -> ClientResult<EffectiveKey> {
+        Self::send(self.authed(Method::GET, &format!("forms/{}/key", form_id))?).await
+    }
+
+    /// Pin the form to one of your key versions, or unpin it with `None`
+    pub async fn pin_form_key(
+        &self,
+        form_id: Uuid,
+        key_id: Option<Uuid>,
+    ) -> ClientResult<FormSchema> {
+        let request = self
+            .authed(Method::PUT, &format!("forms/{}/key", form_id))?
+            .json(&json!({ "key_id": key_id }));
+        Self::send(request).await
+    }
+
+    /// Give the form a new key of its own
+    pub async fn rekey_form(&self, form_id: Uuid, public_key: &str) -> ClientResult<FormSchema> {
+        let request = self
+            .authed(Method::PUT, &format!("forms/{}/public_key", form_id))?
+            .json(&json!({ "public_key": public_key }));
+        Self::send(request).await
+    }
+
+    // Fields
+
+    pub async fn list_fields(&self, form_id: Uuid) -> ClientResult<Vec<FieldDefinition>> {
+        Self::send(self.authed(Method::GET, &format!("forms/{}/fields", form_id))?).await
+    }
+
+    pub async fn add_field(
+        &self,
+        form_id: Uuid,
+        field: &NewField,
+    ) -> ClientResult<FieldDefinition> {
+        let request = self
+            .authed(Method::POST, &format!("forms/{}/fields", form_id))?
+            .json(field);
+        Self::send(request).await
+    }
+
+    pub async fn remove_field(&self, form_id: Uuid, field_id: Uuid) -> ClientResult<()> {
+        let path = format!("forms/{}/fields/{}", form_id, field_id);
+        Self::send_empty(self.authed(Method::DELETE, &path)?).await
+    }
+
+    // Submissions
+
+    pub async fn list_submissions(
+        &self,
+        form_id: Uuid,
+        page: Page,
+    ) -> ClientResult<Vec<FormSubmission>> {
+        let request = self
+            .authed(Method::GET, &format!("forms/{}/submissions", form_id))?
+            .query(&page);
+        Self::send(request).await
+    }
+
+    pub async fn get_submission(
+        &self,
+        form_id: Uuid,
+        submission_id: Uuid,
+    ) -> ClientResult<FormSubmission> {
+        let path = format!("forms/{}/submissions/{}", form_id, submission_id);
+        Self::send(self.authed(Method::GET, &path)?).await
+    }
+
+    /// Every submission of the form, for `formvault decrypt`
+    pub async fn export_submissions(&self, form_id: Uuid) -> ClientResult<Vec<FormSubmission>> {
+        Self::send(self.authed(Method::GET, &format!("forms/{}/export", form_id))?).await
+    }
+
+    /// The key to encrypt submissions to; needs no API key.
+    ///
+    /// Check the fingerprint against one you trust before relying on it.
+    pub async fn submission_key(&self, form_id: Uuid) -> ClientResult<SubmissionKey> {
+        let key: SubmissionKey =
+            Self::send(self.request(Method::GET, &format!("f/{}/key", form_id))?).await?;
+        if fingerprint(&key.public_key)? != key.fingerprint {
+            return Err(ClientError::Encryption(FormVaultError::InvalidPublicKey));
+        }
+        Ok(key)
+    }
+
+    /// Submit plain fields; the server validates and encrypts them
+    pub async fn submit(
+        &self,
+        form_id: Uuid,
+        data: &HashMap<String, String>,
+    ) -> ClientResult<SubmissionReceipt> {
+        Self::send(
+            self.request(Method::POST, &format!("f/{}", form_id))?
+                .json(data),
+        )
+        .await
+    }
+
+    /// Encrypt `data` here and submit only the envelope.
+    ///
+    /// Fetches the form's key first; use [`Client::submit_encrypted_to`]
+    /// to reuse a key you already fetched and verified.
+    pub async fn submit_encrypted(
+        &self,
+        form_id: Uuid,
+        data: &HashMap<String, String>,
+    ) -> ClientResult<SubmissionReceipt> {
+        let key = self.submission_key(form_id).await?;
+        self.submit_encrypted_to(&key, data).await
+    }
+
+    /// Encrypt `data` to `key` and submit the envelope to the key's form.
+    ///
+    /// The server refuses envelopes for a key the form no longer uses.
+    pub async fn submit_encrypted_to(
+        &self,
+        key: &SubmissionKey,
+        data: &HashMap<String, String>,
+    ) -> ClientResult<SubmissionReceipt> {
+        let (encrypted_data, encrypted_key) = encrypt_form_data(data, &key.public_key).await?;
+        let payload = EncryptedPayload {
+            encrypted_data,
+            encrypted_key,
+            key_id: key.key_id,
+        };
+        let path = format!("f/{}", key.form_id);
+        Self::send(self.request(Method::POST, &path)?.json(&payload)).await
+    }
+
+    // Notifications
+
+    pub async fn list_notification_targets(
+        &self,
+        form_id: Uuid,
+    ) -> ClientResult<Vec<NotificationTarget>> {
+        Self::send(self.authed(Method::GET, &format!("forms/{}/notifications", form_id))?).await
+    }
+
+    pub async fn create_notification_target(
+        &self,
+        form_id: Uuid,
+        target: &NewNotificationTarget,
+    ) -> ClientResult<NotificationTarget> {
+        let request = self
+            .authed(Method::POST, &format!("forms/{}/notifications", form_id))?
+            .json(target);
+        Self::send(request).await
+    }
+
+    /// Shorthand for a JSON webhook notified about every submission
+    pub async fn add_webhook(&self, form_id: Uuid, url: &str) -> ClientResult<NotificationTarget> {
+        let target = NewNotificationTarget {
+            channel: ChannelKind::Webhook,
+            destination: url.to_string(),
+            status_filter: Vec::new(),
+        };
+        self.create_notification_target(form_id, &target).await
+    }
+
+    pub async fn update_notification_target(
+        &self,
+        form_id: Uuid,
+        target_id: Uuid,
+        update: &NotificationTargetUpdate,
+    ) -> ClientResult<NotificationTarget> {
+        let path = format!("forms/{}/notifications/{}", form_id, target_id);
+        Self::send(self.authed(Method::PATCH, &path)?.json(update)).await
+    }
+
+    pub async fn delete_notification_target(
+        &self,
+        form_id: Uuid,
+        target_id: Uuid,
+    ) -> ClientResult<()> {
+        let path = format!("forms/{}/notifications/{}", form_id, target_id);
+        Self::send_empty(self.authed(Method::DELETE, &path)?).await
+    }
+}
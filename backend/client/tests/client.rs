@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use formvault::decrypt::{PrivateKey, decrypt_form_data};
+use formvault::models::forms::field_definition::FieldType;
+use formvault::models::forms::form_schema::KeySource;
+use formvault::models::forms::submission::SubmissionStatus;
+use formvault::models::public_key::PublicKey;
+use formvault::testing::TestApp;
+use formvault_client::{
+    Client, ClientError, FormUpdate, NewField, NewForm, NotificationTargetUpdate, Page,
+};
+use serde_json::json;
+
+const X25519_PRIVATE: &str = include_str!("../../tests/fixtures/x25519.pem");
+const X25519_PUBLIC: &str = include_str!("../../tests/fixtures/x25519.pub.pem");
+
+/// A fresh server and a client for a newly registered, verified developer
+async fn signed_up(public_key: &str) -> (TestApp, Client) {
+    let app = TestApp::spawn().await;
+    let client = Client::new(&app.base_url).unwrap();
+    let registration = client
+        .register("Client", "client@example.com", public_key)
+        .await
+        .unwrap();
+    assert_eq!(registration.developer.name, "Client");
+    assert!(registration.developer.email_verified_at.is_none());
+
+    let email = app.outbox.last_to("client@example.com").unwrap();
+    let developer = client.verify_email(email.token().unwrap()).await.unwrap();
+    assert!(developer.email_verified_at.is_some());
+    (app, client.with_api_key(registration.api_key))
+}
+
+fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn account_management() {
+    let (app, client) = signed_up(&PublicKey::x25519_pem(&[11; 32])).await;
+    let me = client.me().await.unwrap();
+    assert!(me.is_active);
+
+    let key = client
+        .rotate_public_key(&PublicKey::x25519_pem(&[12; 32]))
+        .await
+        .unwrap();
+    assert_eq!(key.version, 2);
+    assert_eq!(client.public_keys().await.unwrap().len(), 2);
+
+    let rotated = client.rotate_api_key().await.unwrap();
+    let Err(old) = client.me().await else {
+        panic!("the old API key must stop working");
+    };
+    assert_eq!(old.status(), Some(reqwest::StatusCode::UNAUTHORIZED));
+    assert_eq!(old.code(), Some("UNAUTHORIZED"));
+
+    let client = client.with_api_key(rotated);
+    assert_eq!(client.me().await.unwrap().id, me.id);
+    let verified = client.resend_verification().await.unwrap_err();
+    assert_eq!(verified.code(), Some("VALIDATION_ERROR"));
+
+    client.request_login(&me.email).await.unwrap();
+    let email = app.outbox.last_to(&me.email).unwrap();
+    let session = client.start_session(email.token().unwrap()).await.unwrap();
+    assert!(!session.csrf_token.is_empty());
+
+    // Two-factor authentication is off until enrolled from the dashboard
+    let status = client.two_factor_status().await.unwrap();
+    assert!(!status.enabled);
+    assert_eq!(status.recovery_codes_left, 0);
+    let off = client.regenerate_recovery_codes().await.unwrap_err();
+    assert_eq!(off.code(), Some("VALIDATION_ERROR"));
+
+    client.deactivate().await.unwrap();
+}
+
+#[tokio::test]
+async fn forms_fields_and_plain_submissions() {
+    let (_app, client) = signed_up(X25519_PUBLIC).await;
+    let form = client
+        .create_form(&NewForm::named("Contact"))
+        .await
+        .unwrap();
+    assert_eq!(client.list_forms().await.unwrap()[0].id, form.id);
+    assert_eq!(client.get_form(form.id).await.unwrap().name, "Contact");
+    let update = FormUpdate {
+        allowed_origins: Some(vec!["https://*.example.com".to_string()]),
+        success_url: Some("https://example.com/thanks".to_string()),
+        ..FormUpdate::default()
+    };
+    let updated = client.update_form(form.id, &update).await.unwrap();
+    assert_eq!(updated.allowed_origins, vec!["https://*.example.com"]);
+    assert_eq!(
+        updated.success_url.as_deref(),
+        Some("https://example.com/thanks")
+    );
+    assert_eq!(updated.error_url, None);
+
+    let email = client
+        .add_field(
+            form.id,
+            &NewField::new("email", FieldType::Email).required(),
+        )
+        .await
+        .unwrap();
+    let topic = client
+        .add_field(
+            form.id,
+            &NewField::new("topic", FieldType::Select)
+                .rules(json!({ "options": ["sales", "support"] })),
+        )
+        .await
+        .unwrap();
+    assert_eq!((email.position, topic.position), (0, 1));
+
+    let duplicate = client
+        .add_field(form.id, &NewField::new("email", FieldType::Text))
+        .await
+        .unwrap_err();
+    assert_eq!(duplicate.code(), Some("VALIDATION_ERROR"));
+
+    let Err(ClientError::Api { error, .. }) = client
+        .submit(form.id, &fields(&[("email", "nope"), ("topic", "jobs")]))
+        .await
+    else {
+        panic!("invalid data must be rejected");
+    };
+    assert_eq!(error.details.unwrap().len(), 2);
+
+    let receipt = client
+        .submit(
+            form.id,
+            &fields(&[("email", "ada@example.com"), ("topic", "sales")]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(receipt.status, SubmissionStatus::New);
+
+    let submission = client.get_submission(form.id, receipt.id).await.unwrap();
+    let keys = [PrivateKey::from_pem(X25519_PRIVATE).unwrap()];
+    let data =
+        decrypt_form_data(&submission.encrypted_data, &submission.encrypted_key, &keys).unwrap();
+    assert_eq!(data["topic"], "sales");
+
+    client.remove_field(form.id, topic.id).await.unwrap();
+    assert_eq!(client.list_fields(form.id).await.unwrap().len(), 1);
+    assert!(
+        client
+            .remove_field(form.id, topic.id)
+            .await
+            .unwrap_err()
+            .is_not_found()
+    );
+}
+
+#[tokio::test]
+async fn encrypted_submissions_never_send_plaintext() {
+    let (_app, client) = signed_up(&PublicKey::x25519_pem(&[13; 32])).await;
+    let form = client
+        .create_form(&NewForm {
+            public_key: Some(X25519_PUBLIC.to_string()),
+            zero_knowledge: true,
+            ..NewForm::named("Zero knowledge")
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        client.form_key(form.id).await.unwrap().source,
+        KeySource::Own
+    );
+
+    // Submitters don't need an API key
+    let anonymous = Client::new(client.base_url().as_str()).unwrap();
+    let key = anonymous.submission_key(form.id).await.unwrap();
+    let receipt = anonymous
+        .submit_encrypted_to(&key, &fields(&[("message", "secret")]))
+        .await
+        .unwrap();
+    assert_eq!(receipt.key_id, Some(key.key_id));
+    let plaintext = anonymous
+        .submit(form.id, &fields(&[("message", "secret")]))
+        .await
+        .unwrap_err();
+    assert_eq!(plaintext.code(), Some("ENCRYPTION_REQUIRED"));
+
+    let exported = client.export_submissions(form.id).await.unwrap();
+    let keys = [PrivateKey::from_pem(X25519_PRIVATE).unwrap()];
+    let data = decrypt_form_data(
+        &exported[0].encrypted_data,
+        &exported[0].encrypted_key,
+        &keys,
+    )
+    .unwrap();
+    assert_eq!(data["message"], "secret");
+
+    // After a re-key, envelopes for the old key are refused
+    client
+        .rekey_form(form.id, &PublicKey::x25519_pem(&[14; 32]))
+        .await
+        .unwrap();
+    let stale = anonymous
+        .submit_encrypted_to(&key, &fields(&[("message", "late")]))
+        .await
+        .unwrap_err();
+    assert_eq!(stale.code(), Some("VALIDATION_ERROR"));
+    anonymous
+        .submit_encrypted(form.id, &fields(&[("message", "fresh")]))
+        .await
+        .unwrap();
+
+    let listed = client
+        .list_submissions(
+            form.id,
+            Page {
+                limit: Some(10),
+                offset: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 2);
+}
+
+#[tokio::test]
+async fn webhooks() {
+    let (_app, client) = signed_up(X25519_PUBLIC).await;
+    let form = client.create_form(&NewForm::named("Hooks")).await.unwrap();
+
+    let hook = client
+        .add_webhook(form.id, "https://example.com/hook")
+        .await
+        .unwrap();
+    let updated = client
+        .update_notification_target(
+            form.id,
+            hook.id,
+            &NotificationTargetUpdate {
+                enabled: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!updated.enabled);
+    assert_eq!(
+        client
+            .list_notification_targets(form.id)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    client
+        .delete_notification_target(form.id, hook.id)
+        .await
+        .unwrap();
+    assert!(
+        client
+            .list_notification_targets(form.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let invalid = client.add_webhook(form.id, "not a url").await.unwrap_err();
+    assert_eq!(invalid.status(), Some(reqwest::StatusCode::BAD_REQUEST));
+}
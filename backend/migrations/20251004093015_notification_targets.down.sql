@@ -0,0 +1,35 @@
+-- FormVault Database Down Migration Script
+-- Version: 003_create_notification_targets (DOWN)
+-- Description: Rollback notification targets and restore the single webhook_url
+
+ALTER TABLE form_schemas ADD COLUMN webhook_url TEXT;
+
+UPDATE form_schemas fs
+SET webhook_url = (
+    SELECT t.destination
+    FROM form_notification_targets t
+    WHERE t.form_id = fs.id AND t.channel = 'webhook'
+    ORDER BY t.created_at
+    LIMIT 1
+);
+
+DROP INDEX IF EXISTS idx_notification_deliveries_submission;
+DROP INDEX IF EXISTS idx_notification_targets_form;
+
+DROP TABLE IF EXISTS notification_deliveries;
+DROP TABLE IF EXISTS form_notification_targets;
+
+ALTER TABLE form_submissions
+    ALTER COLUMN status TYPE TEXT
+    USING (CASE
+        WHEN status = 'Failed' AND failure_reason IS NOT NULL THEN 'Failed: ' || failure_reason
+        ELSE status::TEXT
+    END);
+ALTER TABLE form_submissions
+    ADD CONSTRAINT form_submissions_status_check
+    CHECK (status IN ('New', 'Processing', 'Delivered', 'Archived') OR status LIKE 'Failed%');
+ALTER TABLE form_submissions DROP COLUMN failure_reason;
+
+DROP TYPE IF EXISTS delivery_status;
+DROP TYPE IF EXISTS notification_channel;
+DROP TYPE IF EXISTS submission_status;
@@ -0,0 +1,91 @@
+-- FormVault Database Migration Script
+-- Version: 003_create_notification_targets
+-- Description: Fan-out notification targets per form with delivery tracking
+
+-- ========================
+-- ENUMS
+-- ========================
+
+-- submission_status enum (matches SubmissionStatus in the models)
+CREATE TYPE submission_status AS ENUM (
+    'New',
+    'Processing',
+    'Delivered',
+    'Failed',
+    'Archived'
+);
+
+-- notification_channel enum
+CREATE TYPE notification_channel AS ENUM (
+    'webhook',
+    'email',
+    'slack',
+    'discord'
+);
+
+-- delivery_status enum
+CREATE TYPE delivery_status AS ENUM (
+    'pending',
+    'delivered',
+    'failed'
+);
+
+-- ========================
+-- SUBMISSIONS
+-- ========================
+
+-- Failure details move out of the status column into their own column
+ALTER TABLE form_submissions ADD COLUMN failure_reason TEXT;
+
+UPDATE form_submissions
+SET failure_reason = NULLIF(TRIM(LEADING ':' FROM SUBSTRING(status FROM 7)), '')
+WHERE status LIKE 'Failed%';
+
+ALTER TABLE form_submissions DROP CONSTRAINT IF EXISTS form_submissions_status_check;
+ALTER TABLE form_submissions
+    ALTER COLUMN status TYPE submission_status
+    USING (CASE WHEN status LIKE 'Failed%' THEN 'Failed' ELSE status END)::submission_status;
+
+-- ========================
+-- TABLES
+-- ========================
+
+-- form_notification_targets table (NotificationTarget)
+CREATE TABLE form_notification_targets (
+    id UUID PRIMARY KEY,
+    form_id UUID NOT NULL REFERENCES form_schemas(id) ON DELETE CASCADE,
+    channel notification_channel NOT NULL,
+    destination TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    status_filter submission_status[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- notification_deliveries table (NotificationDelivery)
+CREATE TABLE notification_deliveries (
+    id UUID PRIMARY KEY,
+    target_id UUID NOT NULL REFERENCES form_notification_targets(id) ON DELETE CASCADE,
+    submission_id UUID NOT NULL REFERENCES form_submissions(id) ON DELETE CASCADE,
+    status delivery_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (target_id, submission_id)
+);
+
+CREATE INDEX idx_notification_targets_form ON form_notification_targets(form_id);
+CREATE INDEX idx_notification_deliveries_submission ON notification_deliveries(submission_id);
+
+-- Existing single webhook URLs become the first target of their form
+INSERT INTO form_notification_targets (id, form_id, channel, destination)
+SELECT uuid_generate_v4(), id, 'webhook', webhook_url
+FROM form_schemas
+WHERE webhook_url IS NOT NULL;
+
+ALTER TABLE form_schemas DROP COLUMN webhook_url;
+
+COMMENT ON TABLE form_notification_targets IS 'Destinations notified about new form submissions';
+COMMENT ON COLUMN form_notification_targets.destination IS 'Webhook URL or email address, depending on channel';
+COMMENT ON COLUMN form_notification_targets.status_filter IS 'Submission statuses that trigger this target (empty = all)';
+COMMENT ON TABLE notification_deliveries IS 'Delivery attempts of a submission to a notification target';
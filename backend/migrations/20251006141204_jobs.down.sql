@@ -0,0 +1,10 @@
+-- FormVault Database Down Migration Script
+-- Version: 004_create_jobs (DOWN)
+-- Description: Rollback the background job queue
+
+DROP INDEX IF EXISTS idx_jobs_dead;
+DROP INDEX IF EXISTS idx_jobs_runnable;
+
+DROP TABLE IF EXISTS jobs;
+
+DROP TYPE IF EXISTS job_status;
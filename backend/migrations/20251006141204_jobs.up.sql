@@ -0,0 +1,33 @@
+-- FormVault Database Migration Script
+-- Version: 004_create_jobs
+-- Description: Background job queue claimed with FOR UPDATE SKIP LOCKED
+
+CREATE TYPE job_status AS ENUM (
+    'queued',
+    'running',
+    'completed',
+    'dead'
+);
+
+CREATE TABLE jobs (
+    id UUID PRIMARY KEY,
+    queue TEXT NOT NULL DEFAULT 'default',
+    payload JSONB NOT NULL,
+    status job_status NOT NULL DEFAULT 'queued',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    completed_at TIMESTAMPTZ
+);
+
+-- Workers only ever look for runnable jobs of their queue
+CREATE INDEX idx_jobs_runnable ON jobs(queue, run_at) WHERE status IN ('queued', 'running');
+CREATE INDEX idx_jobs_dead ON jobs(queue) WHERE status = 'dead';
+
+COMMENT ON TABLE jobs IS 'Background work executed outside the request path';
+COMMENT ON COLUMN jobs.payload IS 'Typed job payload (see jobs::Job)';
+COMMENT ON COLUMN jobs.run_at IS 'Earliest time the job may run; pushed back on retry';
+COMMENT ON COLUMN jobs.locked_at IS 'When a worker claimed the job; stale locks are reclaimed';
@@ -0,0 +1,12 @@
+-- FormVault Database Down Migration Script
+-- Version: 005_create_audit_events (DOWN)
+-- Description: Rollback the audit log
+
+DROP TRIGGER IF EXISTS audit_events_no_truncate ON audit_events;
+DROP TRIGGER IF EXISTS audit_events_no_update ON audit_events;
+DROP FUNCTION IF EXISTS audit_events_append_only();
+
+DROP INDEX IF EXISTS idx_audit_events_action;
+DROP INDEX IF EXISTS idx_audit_events_developer;
+
+DROP TABLE IF EXISTS audit_events;
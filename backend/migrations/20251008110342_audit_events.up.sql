@@ -0,0 +1,42 @@
+-- FormVault Database Migration Script
+-- Version: 005_create_audit_events
+-- Description: Append-only, hash-chained audit log
+
+CREATE TABLE audit_events (
+    seq BIGSERIAL PRIMARY KEY,
+    id UUID NOT NULL UNIQUE,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    actor_type TEXT NOT NULL,
+    actor_id UUID,
+    developer_id UUID,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id UUID,
+    ip_address TEXT,
+    details JSONB NOT NULL DEFAULT '{}',
+    prev_hash TEXT NOT NULL,
+    hash TEXT NOT NULL UNIQUE
+);
+
+CREATE INDEX idx_audit_events_developer ON audit_events(developer_id, seq);
+CREATE INDEX idx_audit_events_action ON audit_events(action);
+
+-- Audit events are never changed once written
+CREATE FUNCTION audit_events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_no_update
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
+
+CREATE TRIGGER audit_events_no_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_events_append_only();
+
+COMMENT ON TABLE audit_events IS 'Append-only log of developer and submission access events';
+COMMENT ON COLUMN audit_events.developer_id IS 'Account the event belongs to';
+COMMENT ON COLUMN audit_events.prev_hash IS 'Hash of the previous event (all zeros for the first one)';
+COMMENT ON COLUMN audit_events.hash IS 'SHA-256 over prev_hash and the event fields';
@@ -0,0 +1,16 @@
+-- FormVault Database Down Migration Script
+-- Version: 006_create_api_keys (DOWN)
+-- Description: Rollback hashed API keys
+--
+-- Plaintext keys cannot be recovered from their hashes, so every developer
+-- gets a freshly generated key that has to be handed out again.
+
+ALTER TABLE developers ADD COLUMN api_key TEXT;
+UPDATE developers SET api_key = 'fv_' || replace(uuid_generate_v4()::TEXT, '-', '');
+ALTER TABLE developers ALTER COLUMN api_key SET NOT NULL;
+ALTER TABLE developers ADD CONSTRAINT developers_api_key_key UNIQUE (api_key);
+CREATE INDEX idx_developers_api_key ON developers(api_key);
+
+DROP INDEX IF EXISTS idx_api_keys_developer;
+DROP TABLE IF EXISTS api_keys;
+DROP TYPE IF EXISTS api_key_scope;
@@ -0,0 +1,38 @@
+-- FormVault Database Migration Script
+-- Version: 006_create_api_keys
+-- Description: Multiple named, scoped API keys per developer, stored hashed
+
+CREATE TYPE api_key_scope AS ENUM (
+    'read_submissions',
+    'manage_forms',
+    'admin'
+);
+
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY,
+    developer_id UUID NOT NULL REFERENCES developers(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL UNIQUE,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes api_key_scope[] NOT NULL,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_api_keys_developer ON api_keys(developer_id);
+
+-- Existing plaintext keys become hashed admin keys named "default"
+INSERT INTO api_keys (id, developer_id, name, prefix, key_hash, scopes, created_at)
+SELECT uuid_generate_v4(), id, 'default', LEFT(api_key, 11),
+       encode(sha256(convert_to(api_key, 'UTF8')), 'hex'), '{admin}', created_at
+FROM developers;
+
+DROP INDEX IF EXISTS idx_developers_api_key;
+ALTER TABLE developers DROP COLUMN api_key;
+
+COMMENT ON TABLE api_keys IS 'API keys of developers; only a SHA-256 hash of each key is stored';
+COMMENT ON COLUMN api_keys.prefix IS 'Leading characters of the key, shown to identify it';
+COMMENT ON COLUMN api_keys.key_hash IS 'Hex SHA-256 of the full key';
+COMMENT ON COLUMN api_keys.scopes IS 'Permissions granted to the key (admin implies all)';
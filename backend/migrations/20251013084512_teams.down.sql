@@ -0,0 +1,11 @@
+-- FormVault Database Down Migration Script
+-- Version: 007_create_teams (DOWN)
+-- Description: Rollback teams; forms fall back to their creating developer
+
+DROP INDEX IF EXISTS idx_form_schemas_team;
+ALTER TABLE form_schemas DROP COLUMN team_id;
+
+DROP INDEX IF EXISTS idx_team_members_developer;
+DROP TABLE IF EXISTS team_members;
+DROP TABLE IF EXISTS teams;
+DROP TYPE IF EXISTS team_role;
@@ -0,0 +1,62 @@
+-- FormVault Database Migration Script
+-- Version: 007_create_teams
+-- Description: Teams with role-based membership; forms are owned by a team
+
+CREATE TYPE team_role AS ENUM (
+    'viewer',
+    'editor',
+    'admin',
+    'owner'
+);
+
+CREATE TABLE teams (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE team_members (
+    team_id UUID NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    developer_id UUID NOT NULL REFERENCES developers(id) ON DELETE CASCADE,
+    role team_role NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (team_id, developer_id)
+);
+
+CREATE INDEX idx_team_members_developer ON team_members(developer_id);
+
+-- Every existing developer gets a personal team they own
+CREATE TEMPORARY TABLE personal_teams AS
+SELECT id AS developer_id, uuid_generate_v4() AS team_id, name || '''s team' AS team_name, created_at
+FROM developers;
+
+-- Forms whose developer no longer exists still need an owning team
+INSERT INTO personal_teams (developer_id, team_id, team_name, created_at)
+SELECT DISTINCT ON (f.developer_id) f.developer_id, uuid_generate_v4(), 'Imported forms', NOW()
+FROM form_schemas f
+WHERE NOT EXISTS (SELECT 1 FROM developers d WHERE d.id = f.developer_id);
+
+INSERT INTO teams (id, name, created_at)
+SELECT team_id, team_name, created_at FROM personal_teams;
+
+INSERT INTO team_members (team_id, developer_id, role, created_at)
+SELECT p.team_id, p.developer_id, 'owner', p.created_at
+FROM personal_teams p
+JOIN developers d ON d.id = p.developer_id;
+
+ALTER TABLE form_schemas ADD COLUMN team_id UUID REFERENCES teams(id) ON DELETE CASCADE;
+
+UPDATE form_schemas f
+SET team_id = p.team_id
+FROM personal_teams p
+WHERE p.developer_id = f.developer_id;
+
+ALTER TABLE form_schemas ALTER COLUMN team_id SET NOT NULL;
+CREATE INDEX idx_form_schemas_team ON form_schemas(team_id);
+
+DROP TABLE personal_teams;
+
+COMMENT ON TABLE teams IS 'Groups of developers sharing forms';
+COMMENT ON TABLE team_members IS 'Developers of a team and their role';
+COMMENT ON COLUMN form_schemas.team_id IS 'Team owning the form';
+COMMENT ON COLUMN form_schemas.developer_id IS 'Developer who created the form';
@@ -0,0 +1,8 @@
+-- FormVault Database Down Migration Script
+-- Version: 008_key_algorithms (DOWN)
+-- Description: Rollback key algorithm columns
+
+ALTER TABLE form_schemas DROP COLUMN key_algorithm;
+ALTER TABLE developers DROP COLUMN public_key_algorithm;
+
+DROP TYPE IF EXISTS key_algorithm;
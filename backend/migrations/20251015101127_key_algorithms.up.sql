@@ -0,0 +1,16 @@
+-- FormVault Database Migration Script
+-- Version: 008_key_algorithms
+-- Description: Record the algorithm of every public key (RSA-OAEP or X25519)
+
+CREATE TYPE key_algorithm AS ENUM (
+    'rsa',
+    'x25519'
+);
+
+-- Keys stored before validation existed were meant to be RSA; ones that do
+-- not parse are rejected when a submission is encrypted to them
+ALTER TABLE developers ADD COLUMN public_key_algorithm key_algorithm NOT NULL DEFAULT 'rsa';
+ALTER TABLE developers ALTER COLUMN public_key_algorithm DROP DEFAULT;
+
+ALTER TABLE form_schemas ADD COLUMN key_algorithm key_algorithm NOT NULL DEFAULT 'rsa';
+ALTER TABLE form_schemas ALTER COLUMN key_algorithm DROP DEFAULT;
@@ -0,0 +1,30 @@
+-- FormVault Database Down Migration Script
+-- Version: 009_public_key_versions (DOWN)
+-- Description: Rollback versioned public keys; forms get a copy of their
+--              effective key back
+
+ALTER TABLE form_schemas ADD COLUMN public_key TEXT;
+ALTER TABLE form_schemas ADD COLUMN key_algorithm key_algorithm;
+
+UPDATE form_schemas f
+SET public_key = k.public_key, key_algorithm = k.algorithm
+FROM public_keys k
+WHERE k.id = f.key_id;
+
+UPDATE form_schemas f
+SET public_key = d.public_key, key_algorithm = d.public_key_algorithm
+FROM developers d
+WHERE f.public_key IS NULL AND d.id = f.developer_id;
+
+-- Forms of deleted developers have no key left to copy
+UPDATE form_schemas SET public_key = '', key_algorithm = 'rsa' WHERE public_key IS NULL;
+
+ALTER TABLE form_schemas ALTER COLUMN public_key SET NOT NULL;
+ALTER TABLE form_schemas ALTER COLUMN key_algorithm SET NOT NULL;
+ALTER TABLE form_schemas DROP COLUMN key_id;
+
+DROP INDEX IF EXISTS idx_form_submissions_key;
+ALTER TABLE form_submissions DROP COLUMN key_id;
+
+DROP INDEX IF EXISTS idx_public_keys_current;
+DROP TABLE IF EXISTS public_keys;
@@ -0,0 +1,79 @@
+-- FormVault Database Migration Script
+-- Version: 009_public_key_versions
+-- Description: Versioned public keys with fingerprints; forms may pin a key
+--              version and every submission records the key it was encrypted to
+
+CREATE TABLE public_keys (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    developer_id UUID NOT NULL REFERENCES developers(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL CHECK (version > 0),
+    algorithm key_algorithm NOT NULL,
+    public_key TEXT NOT NULL,
+    -- Lowercase hex SHA-256 of the DER encoded SubjectPublicKeyInfo
+    fingerprint TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    retired_at TIMESTAMPTZ,
+    UNIQUE (developer_id, version),
+    UNIQUE (developer_id, fingerprint)
+);
+
+-- At most one current key per developer
+CREATE UNIQUE INDEX idx_public_keys_current ON public_keys(developer_id) WHERE retired_at IS NULL;
+
+-- Keys stored before validation may not be valid base64; fingerprint the
+-- text itself in that case so the backfill never fails
+CREATE FUNCTION pg_temp.pem_fingerprint(pem TEXT) RETURNS TEXT AS $$
+BEGIN
+    RETURN encode(sha256(decode(regexp_replace(pem, '-----[^-]+-----|\s', '', 'g'), 'base64')), 'hex');
+EXCEPTION WHEN OTHERS THEN
+    RETURN encode(sha256(convert_to(pem, 'UTF8')), 'hex');
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- The developer's current key becomes version 1
+INSERT INTO public_keys (developer_id, version, algorithm, public_key, fingerprint, created_at)
+SELECT id, 1, public_key_algorithm, public_key, pg_temp.pem_fingerprint(public_key), created_at
+FROM developers;
+
+-- Forms copied the developer key when they were created; any copy that no
+-- longer matches becomes a retired version of its developer's key
+INSERT INTO public_keys (developer_id, version, algorithm, public_key, fingerprint, created_at, retired_at)
+SELECT developer_id,
+       1 + ROW_NUMBER() OVER (PARTITION BY developer_id ORDER BY first_used),
+       key_algorithm, public_key, fingerprint, first_used, NOW()
+FROM (
+    SELECT DISTINCT ON (f.developer_id, pg_temp.pem_fingerprint(f.public_key))
+           f.developer_id, f.key_algorithm, f.public_key,
+           pg_temp.pem_fingerprint(f.public_key) AS fingerprint, f.created_at AS first_used
+    FROM form_schemas f
+    JOIN developers d ON d.id = f.developer_id
+    WHERE pg_temp.pem_fingerprint(f.public_key) <> pg_temp.pem_fingerprint(d.public_key)
+    ORDER BY f.developer_id, pg_temp.pem_fingerprint(f.public_key), f.created_at
+) legacy;
+
+-- Submissions were encrypted to the form's copy of the key
+ALTER TABLE form_submissions ADD COLUMN key_id UUID REFERENCES public_keys(id);
+
+UPDATE form_submissions s
+SET key_id = k.id
+FROM form_schemas f
+JOIN public_keys k
+  ON k.developer_id = f.developer_id
+ AND k.fingerprint = pg_temp.pem_fingerprint(f.public_key)
+WHERE s.form_schema_id = f.id;
+
+CREATE INDEX idx_form_submissions_key ON form_submissions(key_id);
+
+-- NULL follows the developer's current key; forms whose copy is outdated
+-- stay pinned to it so nothing changes for them
+ALTER TABLE form_schemas ADD COLUMN key_id UUID REFERENCES public_keys(id);
+
+UPDATE form_schemas f
+SET key_id = k.id
+FROM public_keys k
+WHERE k.developer_id = f.developer_id
+  AND k.fingerprint = pg_temp.pem_fingerprint(f.public_key)
+  AND k.retired_at IS NOT NULL;
+
+ALTER TABLE form_schemas DROP COLUMN public_key;
+ALTER TABLE form_schemas DROP COLUMN key_algorithm;
@@ -0,0 +1,45 @@
+-- FormVault Database Down Migration Script
+-- Version: 010_form_public_keys (DOWN)
+-- Description: Rollback form-owned public keys; forms using one fall back to
+--              their developer's current key
+
+UPDATE form_schemas f
+SET key_id = NULL
+FROM public_keys k
+WHERE k.id = f.key_id AND k.form_id IS NOT NULL;
+
+DROP INDEX IF EXISTS idx_public_keys_form_current;
+DROP INDEX IF EXISTS idx_public_keys_developer_current;
+DROP INDEX IF EXISTS idx_public_keys_form_fingerprint;
+DROP INDEX IF EXISTS idx_public_keys_developer_fingerprint;
+DROP INDEX IF EXISTS idx_public_keys_form_version;
+DROP INDEX IF EXISTS idx_public_keys_developer_version;
+
+-- Submissions keep pointing at the keys they were encrypted to, so form
+-- keys become retired versions of the developer's key, numbered after them
+UPDATE public_keys k
+SET version = v.version, retired_at = COALESCE(k.retired_at, NOW())
+FROM (
+    SELECT id,
+           (SELECT COALESCE(MAX(version), 0) FROM public_keys d
+            WHERE d.developer_id = p.developer_id AND d.form_id IS NULL)
+           + ROW_NUMBER() OVER (PARTITION BY developer_id ORDER BY created_at, id) AS version
+    FROM public_keys p
+    WHERE form_id IS NOT NULL
+) v
+WHERE k.id = v.id;
+
+-- Duplicate fingerprints can't survive the old per-developer constraint
+DELETE FROM public_keys k
+WHERE k.form_id IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM form_submissions s WHERE s.key_id = k.id)
+  AND EXISTS (
+      SELECT 1 FROM public_keys o
+      WHERE o.developer_id = k.developer_id AND o.fingerprint = k.fingerprint AND o.id <> k.id
+  );
+
+ALTER TABLE public_keys DROP COLUMN form_id;
+
+ALTER TABLE public_keys ADD CONSTRAINT public_keys_developer_id_version_key UNIQUE (developer_id, version);
+ALTER TABLE public_keys ADD CONSTRAINT public_keys_developer_id_fingerprint_key UNIQUE (developer_id, fingerprint);
+CREATE UNIQUE INDEX idx_public_keys_current ON public_keys(developer_id) WHERE retired_at IS NULL;
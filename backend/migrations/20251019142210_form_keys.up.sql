@@ -0,0 +1,27 @@
+-- FormVault Database Migration Script
+-- Version: 010_form_public_keys
+-- Description: Forms can own public keys instead of inheriting the
+--              developer's; versions and fingerprints are scoped per owner
+
+ALTER TABLE public_keys
+    ADD COLUMN form_id UUID REFERENCES form_schemas(id) ON DELETE CASCADE;
+
+ALTER TABLE public_keys DROP CONSTRAINT public_keys_developer_id_version_key;
+ALTER TABLE public_keys DROP CONSTRAINT public_keys_developer_id_fingerprint_key;
+DROP INDEX idx_public_keys_current;
+
+-- Developer keys (form_id IS NULL) and each form's keys are versioned separately
+CREATE UNIQUE INDEX idx_public_keys_developer_version
+    ON public_keys(developer_id, version) WHERE form_id IS NULL;
+CREATE UNIQUE INDEX idx_public_keys_form_version
+    ON public_keys(form_id, version) WHERE form_id IS NOT NULL;
+
+CREATE UNIQUE INDEX idx_public_keys_developer_fingerprint
+    ON public_keys(developer_id, fingerprint) WHERE form_id IS NULL;
+CREATE UNIQUE INDEX idx_public_keys_form_fingerprint
+    ON public_keys(form_id, fingerprint) WHERE form_id IS NOT NULL;
+
+CREATE UNIQUE INDEX idx_public_keys_developer_current
+    ON public_keys(developer_id) WHERE retired_at IS NULL AND form_id IS NULL;
+CREATE UNIQUE INDEX idx_public_keys_form_current
+    ON public_keys(form_id) WHERE retired_at IS NULL AND form_id IS NOT NULL;
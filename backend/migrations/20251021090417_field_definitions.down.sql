@@ -0,0 +1,18 @@
+-- FormVault Database Down Migration Script
+-- Version: 011_field_definitions (DOWN)
+-- Description: Rollback field ordering and JSON validation rules; rules other
+--              than the pattern and its message are lost
+
+DROP INDEX IF EXISTS idx_field_definitions_form_name;
+
+ALTER TABLE field_definitions
+    ADD COLUMN validation_regex TEXT,
+    ADD COLUMN custom_error_message TEXT;
+
+UPDATE field_definitions
+SET validation_regex = validation_rules->>'pattern',
+    custom_error_message = validation_rules->>'message';
+
+ALTER TABLE field_definitions
+    DROP COLUMN validation_rules,
+    DROP COLUMN position;
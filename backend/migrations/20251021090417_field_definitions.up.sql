@@ -0,0 +1,28 @@
+-- FormVault Database Migration Script
+-- Version: 011_field_definitions
+-- Description: Order form fields and keep their validation rules as JSON,
+--              folding the old regex and error message columns into them
+
+ALTER TABLE field_definitions
+    ADD COLUMN position INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN validation_rules JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+UPDATE field_definitions
+SET validation_rules = jsonb_strip_nulls(jsonb_build_object(
+    'pattern', validation_regex,
+    'message', custom_error_message
+));
+
+UPDATE field_definitions f
+SET position = n.position
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY form_id ORDER BY name, id) - 1 AS position
+    FROM field_definitions
+) n
+WHERE f.id = n.id;
+
+ALTER TABLE field_definitions
+    DROP COLUMN validation_regex,
+    DROP COLUMN custom_error_message;
+
+CREATE UNIQUE INDEX idx_field_definitions_form_name ON field_definitions(form_id, name);
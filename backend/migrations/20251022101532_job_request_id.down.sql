@@ -0,0 +1,5 @@
+-- FormVault Database Down Migration Script
+-- Version: 012_job_request_id (DOWN)
+-- Description: Forget which request enqueued each job
+
+ALTER TABLE jobs DROP COLUMN IF EXISTS request_id;
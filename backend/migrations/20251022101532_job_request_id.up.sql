@@ -0,0 +1,8 @@
+-- FormVault Database Migration Script
+-- Version: 012_job_request_id
+-- Description: Remember which HTTP request enqueued a job, so its logs and
+--              failures can be correlated with that request
+
+ALTER TABLE jobs ADD COLUMN request_id TEXT;
+
+COMMENT ON COLUMN jobs.request_id IS 'X-Request-Id of the request that enqueued the job, if any';
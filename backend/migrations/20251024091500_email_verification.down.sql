@@ -0,0 +1,10 @@
+-- FormVault Database Down Migration Script
+-- Version: 013_email_verification (DOWN)
+-- Description: Forget email verification, email tokens and sessions
+
+DROP INDEX IF EXISTS idx_sessions_developer;
+DROP TABLE IF EXISTS sessions;
+DROP INDEX IF EXISTS idx_email_tokens_developer;
+DROP TABLE IF EXISTS email_tokens;
+DROP TYPE IF EXISTS email_token_purpose;
+ALTER TABLE developers DROP COLUMN IF EXISTS email_verified_at;
@@ -0,0 +1,41 @@
+-- FormVault Database Migration Script
+-- Version: 013_email_verification
+-- Description: Verified email addresses, single-use email tokens for
+--              verification and login links, and dashboard sessions
+
+ALTER TABLE developers ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+-- Accounts that predate verification keep accepting submissions
+UPDATE developers SET email_verified_at = created_at;
+
+CREATE TYPE email_token_purpose AS ENUM (
+    'verify_email',
+    'login'
+);
+
+CREATE TABLE email_tokens (
+    id UUID PRIMARY KEY,
+    developer_id UUID NOT NULL REFERENCES developers(id) ON DELETE CASCADE,
+    purpose email_token_purpose NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_tokens_developer ON email_tokens(developer_id);
+
+CREATE TABLE sessions (
+    id UUID PRIMARY KEY,
+    developer_id UUID NOT NULL REFERENCES developers(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_sessions_developer ON sessions(developer_id);
+
+COMMENT ON COLUMN developers.email_verified_at IS 'When the developer proved they own the email; forms only accept submissions once set';
+COMMENT ON TABLE email_tokens IS 'Single-use tokens mailed in verification and login links; only a SHA-256 hash is stored';
+COMMENT ON TABLE sessions IS 'Dashboard sessions started from a login link; only a SHA-256 hash of the token is stored';
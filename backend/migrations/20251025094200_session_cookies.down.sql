@@ -0,0 +1,8 @@
+-- FormVault Database Down Migration Script
+-- Version: 014_session_cookies (DOWN)
+-- Description: Drop the CSRF token and details of sessions
+
+ALTER TABLE sessions DROP COLUMN IF EXISTS last_seen_at;
+ALTER TABLE sessions DROP COLUMN IF EXISTS user_agent;
+ALTER TABLE sessions DROP COLUMN IF EXISTS ip_address;
+ALTER TABLE sessions DROP COLUMN IF EXISTS csrf_token_hash;
@@ -0,0 +1,17 @@
+-- FormVault Database Migration Script
+-- Version: 014_session_cookies
+-- Description: Dashboard sessions move to cookies, with a CSRF token per
+--              session and enough detail to tell sessions apart when
+--              revoking them
+
+-- Sessions started so far were handed out in response bodies and have no
+-- CSRF token, so they are ended rather than carried over
+DELETE FROM sessions;
+
+ALTER TABLE sessions ADD COLUMN csrf_token_hash TEXT NOT NULL;
+ALTER TABLE sessions ADD COLUMN ip_address TEXT;
+ALTER TABLE sessions ADD COLUMN user_agent TEXT;
+ALTER TABLE sessions ADD COLUMN last_seen_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN sessions.csrf_token_hash IS 'Hex SHA-256 of the token mutating requests must echo in X-CSRF-Token';
+COMMENT ON COLUMN sessions.last_seen_at IS 'When the session last authenticated a request';
@@ -0,0 +1,6 @@
+-- FormVault Database Down Migration Script
+-- Version: 015_two_factor (DOWN)
+-- Description: Drop two-factor authentication
+
+DROP TABLE IF EXISTS recovery_codes;
+DROP TABLE IF EXISTS totp_credentials;
@@ -0,0 +1,30 @@
+-- FormVault Database Migration Script
+-- Version: 015_two_factor
+-- Description: Optional TOTP two-factor authentication, with single-use
+--              recovery codes for a lost authenticator
+
+CREATE TABLE totp_credentials (
+    developer_id UUID PRIMARY KEY REFERENCES developers(id) ON DELETE CASCADE,
+    secret TEXT NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    last_used_step BIGINT,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE recovery_codes (
+    id UUID PRIMARY KEY,
+    developer_id UUID NOT NULL REFERENCES developers(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL UNIQUE,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_recovery_codes_developer ON recovery_codes(developer_id);
+
+COMMENT ON TABLE totp_credentials IS 'Authenticator app secrets; two-factor authentication is enabled once confirmed_at is set';
+COMMENT ON COLUMN totp_credentials.secret IS 'Base32 shared secret, as shown to the authenticator app';
+COMMENT ON COLUMN totp_credentials.last_used_step IS 'Time step of the last accepted code, so a code only works once';
+COMMENT ON COLUMN totp_credentials.locked_until IS 'Codes are refused until then after too many wrong ones';
+COMMENT ON TABLE recovery_codes IS 'Single-use codes standing in for the authenticator; only a SHA-256 hash is stored';
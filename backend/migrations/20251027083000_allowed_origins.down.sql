@@ -0,0 +1,5 @@
+-- FormVault Database Down Migration Script
+-- Version: 016_allowed_origins (DOWN)
+-- Description: Drop the allowed origins of forms
+
+ALTER TABLE form_schemas DROP COLUMN IF EXISTS allowed_origins;
@@ -0,0 +1,7 @@
+-- FormVault Database Migration Script
+-- Version: 016_allowed_origins
+-- Description: Origins each form accepts submissions from
+
+ALTER TABLE form_schemas ADD COLUMN allowed_origins TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN form_schemas.allowed_origins IS 'Origin patterns such as https://*.example.com that may submit to the form; empty accepts every origin';
@@ -0,0 +1,6 @@
+-- FormVault Database Down Migration Script
+-- Version: 017_redirect_urls (DOWN)
+-- Description: Drop the redirect URLs of forms
+
+ALTER TABLE form_schemas DROP COLUMN IF EXISTS error_url;
+ALTER TABLE form_schemas DROP COLUMN IF EXISTS success_url;
@@ -0,0 +1,9 @@
+-- FormVault Database Migration Script
+-- Version: 017_redirect_urls
+-- Description: Pages HTML form posts are redirected to after a submission
+
+ALTER TABLE form_schemas ADD COLUMN success_url TEXT;
+ALTER TABLE form_schemas ADD COLUMN error_url TEXT;
+
+COMMENT ON COLUMN form_schemas.success_url IS 'Where browsers go after a successful HTML form post; NULL shows the hosted thank-you page';
+COMMENT ON COLUMN form_schemas.error_url IS 'Where browsers go after a failed HTML form post, with ?error=CODE; NULL shows the hosted error page';
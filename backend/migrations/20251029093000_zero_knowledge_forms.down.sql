@@ -0,0 +1,5 @@
+-- FormVault Database Down Migration Script
+-- Version: 018_zero_knowledge_forms (DOWN)
+-- Description: Drop the zero-knowledge flag of forms
+
+ALTER TABLE form_schemas DROP COLUMN IF EXISTS zero_knowledge;
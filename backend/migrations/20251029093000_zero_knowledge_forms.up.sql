@@ -0,0 +1,7 @@
+-- FormVault Database Migration Script
+-- Version: 018_zero_knowledge_forms
+-- Description: Forms that only accept submissions encrypted by the submitter
+
+ALTER TABLE form_schemas ADD COLUMN zero_knowledge BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN form_schemas.zero_knowledge IS 'Refuse plaintext fields; submissions must arrive as envelopes encrypted before they are sent';
@@ -0,0 +1,5 @@
+-- FormVault Database Down Migration Script
+-- Version: 019_pin_team_form_keys (DOWN)
+-- Description: Pinned forms keep their key version; there is nothing to undo
+
+COMMENT ON COLUMN form_schemas.key_id IS NULL;
@@ -0,0 +1,15 @@
+-- FormVault Database Migration Script
+-- Version: 019_pin_team_form_keys
+-- Description: Pin team forms to their creator's current key unless the creator manages the team's keys
+
+UPDATE form_schemas f SET key_id = k.id
+FROM public_keys k
+WHERE f.key_id IS NULL
+  AND k.developer_id = f.developer_id AND k.form_id IS NULL AND k.retired_at IS NULL
+  AND NOT EXISTS (
+      SELECT 1 FROM team_members m
+      WHERE m.team_id = f.team_id AND m.developer_id = f.developer_id
+        AND m.role IN ('admin', 'owner')
+  );
+
+COMMENT ON COLUMN form_schemas.key_id IS 'Key version the form is pinned to; NULL follows the creator''s current key, only while they are an admin or owner of the team';
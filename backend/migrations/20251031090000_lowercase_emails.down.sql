@@ -0,0 +1,5 @@
+-- FormVault Database Down Migration Script
+-- Version: 020_lowercase_emails (DOWN)
+-- Description: The original case of addresses is not kept; there is nothing to undo
+
+SELECT 1;
@@ -0,0 +1,12 @@
+-- FormVault Database Migration Script
+-- Version: 020_lowercase_emails
+-- Description: Store email addresses in lowercase, as they are now looked up
+
+-- Addresses that only differ in case from another account are left as they
+-- are, for an operator to merge
+UPDATE developers d SET email = lower(d.email)
+WHERE d.email <> lower(d.email)
+  AND NOT EXISTS (
+      SELECT 1 FROM developers o
+      WHERE o.id <> d.id AND lower(o.email) = lower(d.email)
+  );
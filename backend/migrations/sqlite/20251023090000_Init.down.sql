@@ -0,0 +1,7 @@
+-- FormVault SQLite Down Migration Script
+-- Version: 001_init (DOWN)
+-- Description: Drop the SQLite storage backend tables
+
+DROP TABLE IF EXISTS form_submissions;
+DROP TABLE IF EXISTS form_schemas;
+DROP TABLE IF EXISTS developers;
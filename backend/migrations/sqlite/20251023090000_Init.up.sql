@@ -0,0 +1,43 @@
+-- FormVault SQLite Migration Script
+-- Version: 001_init
+-- Description: Developers, forms and submissions for the SQLite storage
+--              backend, mirroring the PostgreSQL columns. UUIDs are stored
+--              as 16-byte blobs, timestamps as RFC 3339 text and enums as
+--              their names.
+
+CREATE TABLE developers (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    public_key TEXT NOT NULL,
+    public_key_algorithm TEXT NOT NULL CHECK (public_key_algorithm IN ('rsa', 'x25519')),
+    created_at TEXT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT 1
+);
+
+CREATE TABLE form_schemas (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    developer_id BLOB NOT NULL,
+    team_id BLOB NOT NULL,
+    key_id BLOB,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_form_schemas_developer_id ON form_schemas(developer_id);
+
+CREATE TABLE form_submissions (
+    id BLOB PRIMARY KEY NOT NULL,
+    form_schema_id BLOB NOT NULL REFERENCES form_schemas(id) ON DELETE CASCADE,
+    encrypted_data TEXT NOT NULL,
+    encrypted_key TEXT NOT NULL,
+    key_id BLOB,
+    metadata TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    status TEXT NOT NULL
+        CHECK (status IN ('New', 'Processing', 'Delivered', 'Failed', 'Archived')),
+    failure_reason TEXT
+);
+
+CREATE INDEX idx_form_submissions_form_created
+    ON form_submissions(form_schema_id, created_at DESC);
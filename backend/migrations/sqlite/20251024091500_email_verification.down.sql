@@ -0,0 +1,5 @@
+-- FormVault SQLite Down Migration Script
+-- Version: 002_email_verification (DOWN)
+-- Description: Forget email verification
+
+ALTER TABLE developers DROP COLUMN email_verified_at;
@@ -0,0 +1,7 @@
+-- FormVault SQLite Migration Script
+-- Version: 002_email_verification
+-- Description: When a developer verified their email, mirroring PostgreSQL
+
+ALTER TABLE developers ADD COLUMN email_verified_at TEXT;
+
+UPDATE developers SET email_verified_at = created_at;
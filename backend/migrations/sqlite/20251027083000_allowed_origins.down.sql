@@ -0,0 +1,5 @@
+-- FormVault SQLite Down Migration Script
+-- Version: 003_allowed_origins (DOWN)
+-- Description: Drop the allowed origins of forms
+
+ALTER TABLE form_schemas DROP COLUMN allowed_origins;
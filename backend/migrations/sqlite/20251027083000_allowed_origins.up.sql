@@ -0,0 +1,5 @@
+-- FormVault SQLite Migration Script
+-- Version: 003_allowed_origins
+-- Description: Origins each form accepts submissions from, as a JSON array
+
+ALTER TABLE form_schemas ADD COLUMN allowed_origins TEXT NOT NULL DEFAULT '[]';
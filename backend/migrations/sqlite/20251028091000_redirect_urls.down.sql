@@ -0,0 +1,6 @@
+-- FormVault SQLite Down Migration Script
+-- Version: 004_redirect_urls (DOWN)
+-- Description: Drop the redirect URLs of forms
+
+ALTER TABLE form_schemas DROP COLUMN error_url;
+ALTER TABLE form_schemas DROP COLUMN success_url;
@@ -0,0 +1,6 @@
+-- FormVault SQLite Migration Script
+-- Version: 004_redirect_urls
+-- Description: Pages HTML form posts are redirected to after a submission
+
+ALTER TABLE form_schemas ADD COLUMN success_url TEXT;
+ALTER TABLE form_schemas ADD COLUMN error_url TEXT;
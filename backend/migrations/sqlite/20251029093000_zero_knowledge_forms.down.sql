@@ -0,0 +1,5 @@
+-- FormVault SQLite Down Migration Script
+-- Version: 005_zero_knowledge_forms (DOWN)
+-- Description: Drop the zero-knowledge flag of forms
+
+ALTER TABLE form_schemas DROP COLUMN zero_knowledge;
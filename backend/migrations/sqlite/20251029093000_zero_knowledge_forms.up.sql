@@ -0,0 +1,5 @@
+-- FormVault SQLite Migration Script
+-- Version: 005_zero_knowledge_forms
+-- Description: Forms that only accept submissions encrypted by the submitter
+
+ALTER TABLE form_schemas ADD COLUMN zero_knowledge INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+-- FormVault SQLite Down Migration Script
+-- Version: 006_lowercase_emails (DOWN)
+-- Description: The original case of addresses is not kept; there is nothing to undo
+
+SELECT 1;
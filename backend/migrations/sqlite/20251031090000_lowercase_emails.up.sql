@@ -0,0 +1,12 @@
+-- FormVault SQLite Migration Script
+-- Version: 006_lowercase_emails
+-- Description: Store email addresses in lowercase, as they are now looked up
+
+-- Addresses that only differ in case from another account are left as they
+-- are, for an operator to merge
+UPDATE developers SET email = lower(email)
+WHERE email <> lower(email)
+  AND NOT EXISTS (
+      SELECT 1 FROM developers o
+      WHERE o.id <> developers.id AND lower(o.email) = lower(developers.email)
+  );
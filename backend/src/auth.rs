@@ -0,0 +1,279 @@
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+
+use actix_web::http::Method;
+use actix_web::{FromRequest, HttpRequest, dev::Payload, web};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::audit::Actor;
+use crate::models::users::api_key::{ApiKey, ApiKeyScope};
+use crate::models::users::developer::Developer;
+use crate::models::users::session::Session;
+use crate::models::users::team::{Permission, TeamRole};
+use crate::models::users::two_factor::verify_second_factor;
+use crate::repositories::teams::find_role;
+use crate::settings::TrustedProxies;
+use crate::storage::Storage;
+
+/// Cookie holding the dashboard's session token; HTTP-only
+pub const SESSION_COOKIE: &str = "formvault_session";
+
+/// Cookie holding the session's CSRF token, readable by the dashboard
+pub const CSRF_COOKIE: &str = "formvault_csrf";
+
+/// Header mutating requests of a session echo the CSRF token in
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Header sensitive requests carry the current two-factor code (or a
+/// recovery code) in
+pub const TOTP_HEADER: &str = "X-TOTP-Code";
+
+/// How the caller of a request authenticated.
+pub enum Principal {
+    /// A service, with an API key from `Authorization: Bearer <key>` or
+    /// `X-API-Key`
+    ApiKey(ApiKey),
+    /// A person in the dashboard, with the session cookie
+    Session(Session),
+}
+
+/// The developer behind the current request.
+///
+/// An API key is used when one is sent; otherwise the session cookie is.
+/// Requests without either are rejected with 401, as are unknown, revoked
+/// and expired keys and sessions and those of deactivated accounts.
+/// Session requests other than `GET`, `HEAD` and `OPTIONS` must also send
+/// the session's CSRF token in [`CSRF_HEADER`], or are rejected with 403;
+/// API keys are never sent by browsers on their own, so they need none.
+pub struct AuthenticatedDeveloper {
+    pub developer: Developer,
+    pub principal: Principal,
+}
+
+impl AuthenticatedDeveloper {
+    /// The API key of the request, unless it came from the dashboard
+    pub fn api_key(&self) -> Option<&ApiKey> {
+        match &self.principal {
+            Principal::ApiKey(key) => Some(key),
+            Principal::Session(_) => None,
+        }
+    }
+
+    /// The session of the request, if it came from the dashboard
+    pub fn session(&self) -> Option<&Session> {
+        match &self.principal {
+            Principal::Session(session) => Some(session),
+            Principal::ApiKey(_) => None,
+        }
+    }
+
+    /// Fail with 403 unless the key grants `scope`
+    pub fn require(&self, scope: ApiKeyScope) -> FormVaultResult<()> {
+        self.require_any(&[scope])
+    }
+
+    /// Fail with 403 unless the key grants at least one of `scopes`.
+    ///
+    /// Sessions are the developer acting in person and pass every scope.
+    pub fn require_any(&self, scopes: &[ApiKeyScope]) -> FormVaultResult<()> {
+        let granted = match &self.principal {
+            Principal::ApiKey(key) => scopes.iter().any(|scope| key.has_scope(*scope)),
+            Principal::Session(_) => true,
+        };
+        if granted {
+            Ok(())
+        } else {
+            Err(FormVaultError::Forbidden(format!(
+                "API key lacks the required scope ({:?})",
+                scopes
+            )))
+        }
+    }
+
+    /// Role of the developer in `team_id`.
+    ///
+    /// Non-members get `missing` (so other teams' resources look like they
+    /// don't exist), members whose role lacks `permission` get 403.
+    pub async fn require_permission(
+        &self,
+        pool: &PgPool,
+        team_id: Uuid,
+        permission: Permission,
+        missing: FormVaultError,
+    ) -> FormVaultResult<TeamRole> {
+        let role = find_role(pool, team_id, self.developer.id())
+            .await?
+            .ok_or(missing)?;
+
+        if role.can(permission) {
+            Ok(role)
+        } else {
+            Err(FormVaultError::Forbidden(format!(
+                "the {:?} role cannot {:?}",
+                role, permission
+            )))
+        }
+    }
+
+    /// Fail unless the request carries a valid code in [`TOTP_HEADER`],
+    /// when the developer enabled two-factor authentication.
+    ///
+    /// For actions that would let a stolen key or session lock the owner
+    /// out of the account or its data.
+    pub async fn require_second_factor(
+        &self,
+        req: &HttpRequest,
+        pool: &PgPool,
+    ) -> FormVaultResult<()> {
+        verify_second_factor(
+            pool,
+            self.developer.id(),
+            header(req, TOTP_HEADER).as_deref(),
+        )
+        .await
+    }
+
+    /// How this request shows up in the audit log
+    pub fn actor(&self) -> Actor {
+        match &self.principal {
+            Principal::ApiKey(key) => Actor::ApiKey(key.id),
+            Principal::Session(session) => Actor::Session(session.id),
+        }
+    }
+}
+
+impl FromRequest for AuthenticatedDeveloper {
+    type Error = FormVaultError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+        let storage = req.app_data::<web::Data<Storage>>().cloned();
+        let api_key = api_key_from(req);
+        let session = req
+            .cookie(SESSION_COOKIE)
+            .map(|cookie| cookie.value().to_string());
+        let csrf_token = needs_csrf_token(req.method()).then(|| header(req, CSRF_HEADER));
+
+        Box::pin(async move {
+            let (pool, storage) = pool.zip(storage).ok_or(FormVaultError::Unauthorized)?;
+
+            if let Some(api_key) = api_key {
+                let (developer, api_key) =
+                    Developer::authenticate(&api_key, &pool, &storage).await?;
+                return Ok(AuthenticatedDeveloper {
+                    developer,
+                    principal: Principal::ApiKey(api_key),
+                });
+            }
+
+            let session = session.ok_or(FormVaultError::Unauthorized)?;
+            let (developer, session) =
+                Developer::authenticate_session(&session, &pool, &storage).await?;
+            if let Some(csrf_token) = csrf_token
+                && !csrf_token.is_some_and(|token| session.accepts_csrf_token(&token))
+            {
+                return Err(FormVaultError::Forbidden(format!(
+                    "missing or invalid {} header",
+                    CSRF_HEADER
+                )));
+            }
+            Ok(AuthenticatedDeveloper {
+                developer,
+                principal: Principal::Session(session),
+            })
+        })
+    }
+}
+
+/// Whether a request changes something, and so has to prove it was sent
+/// by the dashboard rather than by another site through the browser
+fn needs_csrf_token(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn header(req: &HttpRequest, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+fn api_key_from(req: &HttpRequest) -> Option<String> {
+    let headers = req.headers();
+    headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("X-API-Key").and_then(|v| v.to_str().ok()))
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// Client address for audit records.
+///
+/// That is the connection's peer, unless the peer is a trusted proxy: then
+/// the forwarding headers are walked from the nearest hop back, and the
+/// first address that is not a trusted proxy is the client. Addresses
+/// further back were supplied by the client and prove nothing.
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    let Some(proxies) = req.app_data::<web::Data<TrustedProxies>>() else {
+        return Some(peer.to_string());
+    };
+    if !proxies.contains(peer) {
+        return Some(peer.to_string());
+    }
+
+    let mut client = peer.to_string();
+    for hop in forwarded_for(req).iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) if proxies.contains(ip) => client = hop.clone(),
+            _ => return Some(hop.clone()),
+        }
+    }
+    Some(client)
+}
+
+/// The addresses in `Forwarded`, or else `X-Forwarded-For`, client first
+fn forwarded_for(req: &HttpRequest) -> Vec<String> {
+    let headers = req.headers();
+    let forwarded: Vec<String> = headers
+        .get_all("Forwarded")
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.trim().split_once('=')?;
+                name.eq_ignore_ascii_case("for")
+                    .then(|| node_address(value.trim().trim_matches('"')))
+            })
+        })
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+
+    headers
+        .get_all("X-Forwarded-For")
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|hop| node_address(hop.trim()))
+        .filter(|hop| !hop.is_empty())
+        .collect()
+}
+
+/// The address of a `Forwarded` node, without its port or IPv6 brackets
+fn node_address(node: &str) -> String {
+    if let Some(v6) = node.strip_prefix('[') {
+        return v6.split(']').next().unwrap_or_default().to_string();
+    }
+    match node.split_once(':') {
+        // One colon is an IPv4 address and a port; more are IPv6
+        Some((address, port)) if !port.contains(':') => address.to_string(),
+        _ => node.to_string(),
+    }
+}
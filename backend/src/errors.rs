@@ -1,5 +1,10 @@
-use serde::Serialize;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use utoipa::ToSchema;
+
+use crate::telemetry::current_request_id;
 
 // Single unified error type for the entire application
 #[derive(Debug)]
@@ -16,6 +21,10 @@ pub enum FormVaultError {
     // Authentication/Authorization
     Unauthorized,
     InvalidApiKey,
+    Forbidden(String),
+    TwoFactorRequired,
+    TooManyAttempts,
+    OriginNotAllowed(String),
 
     // Validation errors
     ValidationFailed(Vec<String>),
@@ -25,12 +34,15 @@ pub enum FormVaultError {
     DuplicateEmail,
 
     // Encryption errors
+    EncryptionRequired,
+    KeyRevoked,
     EncryptionError(String),
     DecryptionError(String),
 
-    // Webhook errors
+    // Webhook and notification errors
     WebhookFailed(String),
     WebhookTimeout,
+    NotificationFailed(String),
 
     // Network errors
     NetworkError(String),
@@ -39,6 +51,7 @@ pub enum FormVaultError {
     FormLimitExceeded,
     SubmissionLimitExceeded,
     InactiveAccount,
+    EmailNotVerified,
 }
 
 impl fmt::Display for FormVaultError {
@@ -65,6 +78,18 @@ impl fmt::Display for FormVaultError {
             FormVaultError::InvalidApiKey => {
                 write!(f, "Invalid or expired API key")
             }
+            FormVaultError::Forbidden(msg) => {
+                write!(f, "Forbidden: {}", msg)
+            }
+            FormVaultError::TwoFactorRequired => {
+                write!(f, "A valid two-factor authentication code is required")
+            }
+            FormVaultError::TooManyAttempts => {
+                write!(f, "Too many wrong codes; try again later")
+            }
+            FormVaultError::OriginNotAllowed(origin) => {
+                write!(f, "This form does not accept submissions from {}", origin)
+            }
             FormVaultError::ValidationFailed(errors) => {
                 write!(f, "Validation failed: {}", errors.join(", "))
             }
@@ -77,6 +102,18 @@ impl fmt::Display for FormVaultError {
             FormVaultError::DuplicateEmail => {
                 write!(f, "Email address already registered")
             }
+            FormVaultError::EncryptionRequired => {
+                write!(
+                    f,
+                    "This form only accepts submissions encrypted before they are sent"
+                )
+            }
+            FormVaultError::KeyRevoked => {
+                write!(
+                    f,
+                    "This form's key belongs to someone no longer in its team; it needs a new key"
+                )
+            }
             FormVaultError::EncryptionError(msg) => {
                 write!(f, "Encryption error: {}", msg)
             }
@@ -89,6 +126,9 @@ impl fmt::Display for FormVaultError {
             FormVaultError::WebhookTimeout => {
                 write!(f, "Webhook request timed out")
             }
+            FormVaultError::NotificationFailed(msg) => {
+                write!(f, "Notification delivery failed: {}", msg)
+            }
             FormVaultError::NetworkError(msg) => {
                 write!(f, "Network error: {}", msg)
             }
@@ -101,6 +141,9 @@ impl fmt::Display for FormVaultError {
             FormVaultError::InactiveAccount => {
                 write!(f, "Account is inactive or suspended")
             }
+            FormVaultError::EmailNotVerified => {
+                write!(f, "The account's email address is not verified yet")
+            }
             FormVaultError::ValidationError(e) => {
                 write!(f, "Validation Failed {}", e)
             }
@@ -134,52 +177,57 @@ impl From<std::io::Error> for FormVaultError {
 pub type FormVaultResult<T> = std::result::Result<T, FormVaultError>;
 
 // Error response for API endpoints
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
     pub code: String,
     pub details: Option<Vec<String>>,
+    /// ID of the failed request, also sent as `X-Request-Id`; quote it
+    /// when reporting a problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl FormVaultError {
     /// Convert error to HTTP response format
     pub fn to_response(&self) -> ErrorResponse {
-        match self {
+        let (error, code, details) = match self {
             FormVaultError::NotFound
             | FormVaultError::DeveloperNotFound
             | FormVaultError::FormNotFound
-            | FormVaultError::SubmissionNotFound => ErrorResponse {
-                error: self.to_string(),
-                code: "NOT_FOUND".to_string(),
-                details: None,
-            },
-            FormVaultError::Unauthorized | FormVaultError::InvalidApiKey => ErrorResponse {
-                error: "Access denied".to_string(),
-                code: "UNAUTHORIZED".to_string(),
-                details: None,
-            },
-            FormVaultError::ValidationFailed(errors) => ErrorResponse {
-                error: "Validation failed".to_string(),
-                code: "VALIDATION_ERROR".to_string(),
-                details: Some(errors.clone()),
-            },
-            FormVaultError::DuplicateEmail => ErrorResponse {
-                error: self.to_string(),
-                code: "DUPLICATE_EMAIL".to_string(),
-                details: None,
-            },
+            | FormVaultError::SubmissionNotFound => (self.to_string(), "NOT_FOUND", None),
+            FormVaultError::Unauthorized | FormVaultError::InvalidApiKey => {
+                ("Access denied".to_string(), "UNAUTHORIZED", None)
+            }
+            FormVaultError::ValidationFailed(errors) => (
+                "Validation failed".to_string(),
+                "VALIDATION_ERROR",
+                Some(errors.clone()),
+            ),
+            FormVaultError::InvalidEmail | FormVaultError::InvalidPublicKey => {
+                (self.to_string(), "INVALID_INPUT", None)
+            }
+            FormVaultError::InactiveAccount | FormVaultError::Forbidden(_) => {
+                (self.to_string(), "FORBIDDEN", None)
+            }
+            FormVaultError::DuplicateEmail => (self.to_string(), "DUPLICATE_EMAIL", None),
+            FormVaultError::EmailNotVerified => (self.to_string(), "EMAIL_NOT_VERIFIED", None),
+            FormVaultError::TwoFactorRequired => (self.to_string(), "TWO_FACTOR_REQUIRED", None),
+            FormVaultError::TooManyAttempts => (self.to_string(), "TOO_MANY_ATTEMPTS", None),
+            FormVaultError::OriginNotAllowed(_) => (self.to_string(), "ORIGIN_NOT_ALLOWED", None),
+            FormVaultError::EncryptionRequired => (self.to_string(), "ENCRYPTION_REQUIRED", None),
+            FormVaultError::KeyRevoked => (self.to_string(), "KEY_REVOKED", None),
             FormVaultError::FormLimitExceeded | FormVaultError::SubmissionLimitExceeded => {
-                ErrorResponse {
-                    error: self.to_string(),
-                    code: "LIMIT_EXCEEDED".to_string(),
-                    details: None,
-                }
-            }
-            _ => ErrorResponse {
-                error: "Internal server error".to_string(),
-                code: "INTERNAL_ERROR".to_string(),
-                details: None,
-            },
+                (self.to_string(), "LIMIT_EXCEEDED", None)
+            }
+            _ => ("Internal server error".to_string(), "INTERNAL_ERROR", None),
+        };
+
+        ErrorResponse {
+            error,
+            code: code.to_string(),
+            details,
+            request_id: current_request_id(),
         }
     }
 
@@ -196,13 +244,37 @@ impl FormVaultError {
             FormVaultError::ValidationFailed(_)
             | FormVaultError::InvalidEmail
             | FormVaultError::InvalidPublicKey
-            | FormVaultError::DuplicateEmail => 400,
+            | FormVaultError::DuplicateEmail
+            | FormVaultError::EncryptionRequired => 400,
+
+            FormVaultError::FormLimitExceeded
+            | FormVaultError::SubmissionLimitExceeded
+            | FormVaultError::TooManyAttempts => 429,
 
-            FormVaultError::FormLimitExceeded | FormVaultError::SubmissionLimitExceeded => 429,
+            FormVaultError::InactiveAccount
+            | FormVaultError::Forbidden(_)
+            | FormVaultError::EmailNotVerified
+            | FormVaultError::TwoFactorRequired
+            | FormVaultError::OriginNotAllowed(_) => 403,
 
-            FormVaultError::InactiveAccount => 403,
+            FormVaultError::KeyRevoked => 409,
 
             _ => 500,
         }
     }
 }
+
+// Lets handlers return `FormVaultResult` directly
+impl ResponseError for FormVaultError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(FormVaultError::status_code(self))
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        if FormVaultError::status_code(self) >= 500 {
+            tracing::error!(error = %self, "request failed");
+        }
+        HttpResponse::build(ResponseError::status_code(self)).json(self.to_response())
+    }
+}
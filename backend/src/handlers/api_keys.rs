@@ -0,0 +1,140 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::users::api_key::{ApiKey, ApiKeyScope};
+use crate::repositories::api_keys::{create_api_key, find_api_keys_by_developer, revoke_api_key};
+use crate::repositories::audit::chain_event;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKey {
+    name: String,
+    scopes: Vec<ApiKeyScope>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CreatedApiKey {
+    #[serde(flatten)]
+    key: ApiKey,
+    /// Only ever returned here
+    api_key: String,
+}
+
+/// Keys of the calling developer; secrets are never included
+#[utoipa::path(
+    get,
+    path = "/api_keys",
+    tag = "api_keys",
+    responses((status = 200, body = [ApiKey]))
+)]
+pub async fn list_api_keys(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let keys = find_api_keys_by_developer(&pool, auth.developer.id()).await?;
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+/// Create a key, usually with narrower scopes or an expiry
+#[utoipa::path(
+    post,
+    path = "/api_keys",
+    tag = "api_keys",
+    operation_id = "create_api_key",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 201, body = CreatedApiKey))
+)]
+pub async fn create(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    body: web::Json<CreateApiKey>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    auth.require_second_factor(&req, &pool).await?;
+    let body = body.into_inner();
+
+    let mut errors = Vec::new();
+    if body.name.trim().is_empty() {
+        errors.push("name must not be empty".to_string());
+    }
+    if body.scopes.is_empty() {
+        errors.push("at least one scope is required".to_string());
+    }
+    if body.expires_at.is_some_and(|expires| expires <= Utc::now()) {
+        errors.push("expires_at must be in the future".to_string());
+    }
+    if !errors.is_empty() {
+        return Err(FormVaultError::ValidationFailed(errors));
+    }
+
+    let (key, api_key) = ApiKey::generate(
+        auth.developer.id(),
+        body.name.trim().to_string(),
+        body.scopes,
+        body.expires_at,
+    );
+    let mut tx = pool.begin().await?;
+    create_api_key(&mut *tx, &key).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::ApiKeyCreated,
+        )
+        .target("api_key", key.id)
+        .ip_address(client_ip(&req)),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(CreatedApiKey { key, api_key }))
+}
+
+/// Revoke a key; requests using it fail from then on
+#[utoipa::path(
+    delete,
+    path = "/api_keys/{key_id}",
+    tag = "api_keys",
+    operation_id = "revoke_api_key",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 204))
+)]
+pub async fn revoke(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    auth.require_second_factor(&req, &pool).await?;
+    let key_id = path.into_inner();
+
+    let mut tx = pool.begin().await?;
+    if !revoke_api_key(&mut *tx, auth.developer.id(), key_id).await? {
+        return Err(FormVaultError::NotFound);
+    }
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::ApiKeyRevoked,
+        )
+        .target("api_key", key_id)
+        .ip_address(client_ip(&req)),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,42 @@
+use actix_web::{HttpResponse, web};
+use sqlx::PgPool;
+
+use crate::auth::AuthenticatedDeveloper;
+use crate::errors::FormVaultResult;
+use crate::models::audit::AuditEvent;
+use crate::models::users::api_key::ApiKeyScope;
+use crate::repositories::audit::{AuditQuery, ChainReport, find_events, verify_audit_chain};
+
+/// Audit events of the developer's account, newest first
+#[utoipa::path(
+    get,
+    path = "/audit",
+    tag = "audit",
+    params(AuditQuery),
+    responses((status = 200, body = [AuditEvent]))
+)]
+pub async fn list_events(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    query: web::Query<AuditQuery>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let events = find_events(&pool, auth.developer.id(), &query).await?;
+    Ok(HttpResponse::Ok().json(events))
+}
+
+/// Check that the audit log's hash chain is unbroken
+#[utoipa::path(
+    get,
+    path = "/audit/verify",
+    tag = "audit",
+    responses((status = 200, body = ChainReport))
+)]
+pub async fn verify(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let report = verify_audit_chain(&pool).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
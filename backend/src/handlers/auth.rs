@@ -0,0 +1,313 @@
+use actix_web::cookie::time::Duration as CookieDuration;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::Utc;
+use log::warn;
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::{AuthenticatedDeveloper, CSRF_COOKIE, SESSION_COOKIE, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::mail::AccountMail;
+use crate::models::audit::{Actor, AuditAction, NewAuditEvent};
+use crate::models::users::api_key::ApiKeyScope;
+use crate::models::users::developer::{DeveloperProfile, validate_email};
+use crate::models::users::email_token::{EmailToken, EmailTokenPurpose};
+use crate::models::users::session::{NewSession, SESSION_TTL, Session};
+use crate::models::users::two_factor::verify_second_factor;
+use crate::repositories::audit::append_event;
+use crate::repositories::email_tokens::{consume_email_token, create_email_token};
+use crate::repositories::sessions::{create_session, find_active_sessions, revoke_session};
+use crate::storage::Storage;
+
+/// The token from a verification or login link
+#[derive(Deserialize, ToSchema)]
+pub struct LinkToken {
+    token: String,
+}
+
+/// The token from a login link, and the second factor if the account has one
+#[derive(Deserialize, ToSchema)]
+pub struct StartSession {
+    token: String,
+    /// Current code from the authenticator app, or an unused recovery code
+    code: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginRequest {
+    email: String,
+}
+
+/// Confirm the account's email with the token from a verification link
+#[utoipa::path(
+    post,
+    path = "/auth/verify",
+    tag = "auth",
+    security(()),
+    responses((status = 200, body = DeveloperProfile))
+)]
+pub async fn verify_email(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    body: web::Json<LinkToken>,
+) -> FormVaultResult<HttpResponse> {
+    let token = consume_email_token(
+        &**pool,
+        &EmailToken::hash(&body.token),
+        EmailTokenPurpose::VerifyEmail,
+    )
+    .await?
+    .ok_or_else(|| {
+        FormVaultError::ValidationFailed(vec![
+            "this link is invalid, expired or was already used".to_string(),
+        ])
+    })?;
+
+    storage
+        .developers()
+        .mark_verified(token.developer_id, Utc::now())
+        .await?;
+    let developer = storage
+        .developers()
+        .find_by_id(token.developer_id)
+        .await?
+        .ok_or(FormVaultError::DeveloperNotFound)?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            Actor::Developer(developer.id()),
+            developer.id(),
+            AuditAction::EmailVerified,
+        )
+        .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(DeveloperProfile::from(&developer)))
+}
+
+/// Email a login link for the dashboard
+///
+/// The response is the same whether or not the address has an account.
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    security(()),
+    responses((status = 202))
+)]
+pub async fn request_login(
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    mail: web::Data<AccountMail>,
+    body: web::Json<LoginRequest>,
+) -> FormVaultResult<HttpResponse> {
+    let email = validate_email(&body.email)?;
+    let developer = storage
+        .developers()
+        .find_by_email(&email)
+        .await?
+        .filter(|developer| developer.is_active());
+
+    if let Some(developer) = developer {
+        let (token, secret) = EmailToken::generate(developer.id(), EmailTokenPurpose::Login);
+        create_email_token(&**pool, &token).await?;
+        if let Err(e) = mail.send_login_link(&developer, &secret).await {
+            warn!(
+                "Login link for developer {} not sent: {}",
+                developer.id(),
+                e
+            );
+        }
+    }
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Start a dashboard session with the token from a login link
+///
+/// The session token is set as an HTTP-only cookie. The CSRF token in the
+/// response, also set as a readable cookie, has to be sent as
+/// `X-CSRF-Token` with every mutating request of the session. Following a
+/// login link proves the email as well, so it also verifies the account.
+///
+/// Accounts with two-factor authentication also need `code`; without a
+/// valid one the request fails with 403 `TWO_FACTOR_REQUIRED` and the link
+/// can be used again with the code.
+#[utoipa::path(
+    post,
+    path = "/auth/session",
+    tag = "auth",
+    security(()),
+    responses((status = 201, body = NewSession))
+)]
+pub async fn create_session_from_link(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    body: web::Json<StartSession>,
+) -> FormVaultResult<HttpResponse> {
+    let mut tx = pool.begin().await?;
+    let token = consume_email_token(
+        &mut *tx,
+        &EmailToken::hash(&body.token),
+        EmailTokenPurpose::Login,
+    )
+    .await?
+    .ok_or(FormVaultError::Unauthorized)?;
+
+    let developer = storage
+        .developers()
+        .find_by_id(token.developer_id)
+        .await?
+        .filter(|developer| developer.is_active())
+        .ok_or(FormVaultError::Unauthorized)?;
+    // Failing here rolls the transaction back, which leaves the link unused
+    verify_second_factor(&pool, developer.id(), body.code.as_deref()).await?;
+
+    let (session, tokens) = Session::generate(
+        developer.id(),
+        client_ip(&req),
+        req.headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+    );
+    create_session(&mut *tx, &session).await?;
+    tx.commit().await?;
+
+    if !developer.is_verified() {
+        storage
+            .developers()
+            .mark_verified(developer.id(), Utc::now())
+            .await?;
+    }
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            Actor::Developer(developer.id()),
+            developer.id(),
+            AuditAction::SessionStarted,
+        )
+        .target("session", session.id)
+        .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::Created()
+        .cookie(session_cookie(SESSION_COOKIE, tokens.token, true))
+        .cookie(session_cookie(
+            CSRF_COOKIE,
+            tokens.csrf_token.clone(),
+            false,
+        ))
+        .json(NewSession {
+            id: session.id,
+            csrf_token: tokens.csrf_token,
+            expires_at: session.expires_at,
+        }))
+}
+
+/// A cookie for the whole API that lasts as long as a session, and is only
+/// sent over HTTPS and never with requests from other sites
+fn session_cookie(name: &'static str, value: String, http_only: bool) -> Cookie<'static> {
+    Cookie::build(name, value)
+        .path("/")
+        .secure(true)
+        .http_only(http_only)
+        .same_site(SameSite::Strict)
+        .max_age(CookieDuration::seconds(SESSION_TTL.num_seconds()))
+        .finish()
+}
+
+/// End the session of this request and clear its cookies
+#[utoipa::path(
+    delete,
+    path = "/auth/session",
+    tag = "auth",
+    responses((status = 204))
+)]
+pub async fn end_session(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    let session = auth.session().ok_or_else(|| {
+        FormVaultError::Forbidden("only a dashboard session can be signed out".to_string())
+    })?;
+    revoke(&req, &pool, &auth, session.id).await?;
+
+    let mut response = HttpResponse::NoContent();
+    for name in [SESSION_COOKIE, CSRF_COOKIE] {
+        let mut cookie = session_cookie(name, String::new(), true);
+        cookie.make_removal();
+        response.cookie(cookie);
+    }
+    Ok(response.finish())
+}
+
+/// Dashboard sessions of the developer that are still active
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    tag = "auth",
+    responses((status = 200, body = [Session]))
+)]
+pub async fn list_sessions(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let sessions = find_active_sessions(&pool, auth.developer.id()).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+/// Sign a dashboard session out, such as one on a lost device
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{session_id}",
+    tag = "auth",
+    responses((status = 204))
+)]
+pub async fn revoke_session_by_id(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    revoke(&req, &pool, &auth, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn revoke(
+    req: &HttpRequest,
+    pool: &PgPool,
+    auth: &AuthenticatedDeveloper,
+    session_id: Uuid,
+) -> FormVaultResult<()> {
+    if !revoke_session(pool, auth.developer.id(), session_id).await? {
+        return Err(FormVaultError::NotFound);
+    }
+
+    append_event(
+        pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::SessionRevoked,
+        )
+        .target("session", session_id)
+        .ip_address(client_ip(req)),
+    )
+    .await?;
+
+    Ok(())
+}
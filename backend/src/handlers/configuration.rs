@@ -1,13 +1,37 @@
-use actix_web::{HttpRequest, HttpResponse, Responder};
+use std::time::{Duration, Instant};
+
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
 use chrono::prelude::*;
+use log::warn;
 use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use crate::errors::{ErrorResponse, FormVaultError, FormVaultResult};
+use crate::jobs::WorkerHealth;
+use crate::metrics::{self, ScrapeToken};
+use crate::models::health::{DatabaseCheck, JobsCheck, PoolStats, Readiness, ReadinessStatus};
+use crate::openapi;
+use crate::repositories::health::{latest_migration, ping};
+use crate::repositories::jobs::find_queue_backlog;
 
-#[derive(Serialize)]
+/// How long `/readyz` waits for the database before reporting it down
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, ToSchema)]
 struct HealthResponse {
     status: &'static str,
     timestamp: DateTime<Utc>,
 }
 
+/// Same as `/livez`, kept for existing monitors
+#[utoipa::path(
+    get,
+    path = "/health_check",
+    tag = "meta",
+    security(()),
+    responses((status = 200, body = HealthResponse))
+)]
 pub async fn health_check() -> impl Responder {
     let resp = HealthResponse {
         status: "ok",
@@ -16,29 +40,199 @@ pub async fn health_check() -> impl Responder {
     HttpResponse::Ok().json(resp)
 }
 
-#[derive(Serialize)]
+/// Liveness: the process is up and serving requests
+///
+/// Dependencies are not checked, so a database outage does not get the
+/// instance restarted; use `/readyz` to decide where traffic goes.
+#[utoipa::path(
+    get,
+    path = "/livez",
+    tag = "meta",
+    security(()),
+    responses((status = 200, body = HealthResponse))
+)]
+pub async fn livez() -> impl Responder {
+    health_check().await
+}
+
+/// Readiness: the database answers and the job workers are running
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "meta",
+    security(()),
+    responses(
+        (status = 200, body = Readiness),
+        (status = 503, body = Readiness, description = "A dependency is unhealthy")
+    )
+)]
+pub async fn readyz(pool: web::Data<PgPool>, workers: web::Data<WorkerHealth>) -> impl Responder {
+    let started = Instant::now();
+    let checks = async {
+        ping(&pool).await?;
+        let latency = started.elapsed();
+        let migration = latest_migration(&pool).await?;
+        let backlog = find_queue_backlog(&pool, &workers.queue).await?;
+        Ok::<_, FormVaultError>((latency, migration, backlog))
+    };
+    let checks = match tokio::time::timeout(READINESS_TIMEOUT, checks).await {
+        Ok(checks) => checks.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("no answer within {:?}", READINESS_TIMEOUT)),
+    }
+    .inspect_err(|error| warn!("Not ready, the database check failed: {}", error));
+
+    let now = Utc::now();
+    let (database, backlog) = match checks {
+        Ok((latency, migration_version, backlog)) => (
+            DatabaseCheck {
+                healthy: true,
+                latency_ms: latency.as_millis() as u64,
+                migration_version,
+            },
+            Some(backlog),
+        ),
+        Err(_) => (
+            DatabaseCheck {
+                healthy: false,
+                latency_ms: started.elapsed().as_millis() as u64,
+                migration_version: None,
+            },
+            None,
+        ),
+    };
+
+    let max_connections = pool.options().get_max_connections();
+    let size = pool.size();
+    let idle = (pool.num_idle() as u32).min(size);
+    let pool = PoolStats {
+        size,
+        idle,
+        in_use: size - idle,
+        max_connections,
+        utilization: f64::from(size - idle) / f64::from(max_connections.max(1)),
+    };
+
+    let workers_alive = workers.alive();
+    let jobs = JobsCheck {
+        healthy: workers.concurrency == 0 || workers_alive > 0,
+        queue: workers.queue.clone(),
+        workers: workers.concurrency,
+        workers_alive,
+        due: backlog.map(|backlog| backlog.due),
+        lag_seconds: backlog.map(|backlog| {
+            backlog.oldest_due.map_or(0.0, |oldest| {
+                (now - oldest).num_milliseconds().max(0) as f64 / 1000.0
+            })
+        }),
+    };
+
+    let status = if database.healthy && jobs.healthy {
+        ReadinessStatus::Ready
+    } else {
+        ReadinessStatus::Unavailable
+    };
+    let readiness = Readiness {
+        status,
+        timestamp: now,
+        database,
+        pool,
+        jobs,
+    };
+
+    match status {
+        ReadinessStatus::Ready => HttpResponse::Ok().json(readiness),
+        ReadinessStatus::Unavailable => HttpResponse::ServiceUnavailable().json(readiness),
+    }
+}
+
+/// Prometheus metrics in the text exposition format
+///
+/// Needs `Authorization: Bearer <METRICS_TOKEN>`; without a `METRICS_TOKEN`
+/// the metrics are not served. Pool and job queue gauges are refreshed on
+/// every scrape; the queue is left as last seen when the database does not
+/// answer in time.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "meta",
+    security(("metrics_token" = [])),
+    responses(
+        (status = 200, description = "Prometheus text format", content_type = "text/plain"),
+        (status = 401, body = ErrorResponse, description = "The metrics token was not given"),
+        (status = 404, body = ErrorResponse, description = "No metrics token is configured")
+    )
+)]
+pub async fn metrics(
+    req: HttpRequest,
+    token: web::Data<ScrapeToken>,
+    pool: web::Data<PgPool>,
+    workers: web::Data<WorkerHealth>,
+) -> FormVaultResult<HttpResponse> {
+    if !token.is_set() {
+        return Err(FormVaultError::NotFound);
+    }
+    if !token.allows(&req) {
+        return Err(FormVaultError::Unauthorized);
+    }
+
+    let metrics = metrics::metrics();
+    metrics.set_pool(
+        pool.size(),
+        pool.num_idle() as u32,
+        pool.options().get_max_connections(),
+    );
+
+    let backlog = find_queue_backlog(&pool, &workers.queue);
+    if let Ok(Ok(backlog)) = tokio::time::timeout(READINESS_TIMEOUT, backlog).await {
+        let lag = backlog.oldest_due.map_or(Duration::ZERO, |oldest| {
+            (Utc::now() - oldest).to_std().unwrap_or_default()
+        });
+        metrics.set_job_queue(&workers.queue, backlog.due, lag);
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(metrics::CONTENT_TYPE)
+        .body(metrics.render()))
+}
+
+#[derive(Serialize, ToSchema)]
 struct ApiRoute {
     method: &'static str,
     url: String,
-    description: Option<&'static str>,
+    description: Option<String>,
 }
 
+/// Every route of the API, from the OpenAPI document
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "meta",
+    security(()),
+    responses((status = 200, body = [ApiRoute]))
+)]
 pub async fn get_api_routes(req: HttpRequest) -> impl Responder {
     let scheme = req.connection_info().scheme().to_string();
     let host = req.connection_info().host().to_string();
 
-    let routes = vec![
-        ApiRoute {
-            method: "GET",
-            url: format!("{}://{}/health_check", scheme, host),
-            description: Some("Health check endpoint"),
-        },
-        ApiRoute {
-            method: "GET",
-            url: format!("{}://{}/", scheme, host),
-            description: Some("API root"),
-        },
-    ];
+    let routes: Vec<ApiRoute> = openapi::operations(openapi::spec())
+        .map(|(method, path, operation)| ApiRoute {
+            method,
+            url: format!("{}://{}{}", scheme, host, path),
+            description: operation.summary.clone(),
+        })
+        .collect();
 
     HttpResponse::Ok().json(routes)
 }
+
+/// This API's OpenAPI 3 document
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "meta",
+    security(()),
+    responses((status = 200, description = "An OpenAPI 3.1 document", content_type = "application/json"))
+)]
+pub async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok().json(openapi::spec())
+}
@@ -0,0 +1,301 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::mail::AccountMail;
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::public_key::PublicKeyVersion;
+use crate::models::users::api_key::{ApiKey, ApiKeyScope};
+use crate::models::users::developer::{Developer, DeveloperProfile};
+use crate::models::users::email_token::{EmailToken, EmailTokenPurpose};
+use crate::models::users::registration::Registration;
+use crate::repositories::api_keys::{create_api_key, revoke_api_key};
+use crate::repositories::audit::chain_event;
+use crate::repositories::email_tokens::create_email_token;
+use crate::repositories::public_keys::{
+    KeySummary, RetiredKeySubmission, find_key_summaries, find_submissions_on_retired_keys,
+};
+use crate::storage::Storage;
+
+#[derive(Deserialize, ToSchema)]
+pub struct RegisterDeveloper {
+    name: String,
+    email: String,
+    public_key: String,
+}
+
+/// Returned once, when the key is created; it cannot be read back later
+#[derive(Serialize, ToSchema)]
+struct ApiKeyResponse {
+    api_key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RegisteredDeveloper {
+    #[serde(flatten)]
+    profile: DeveloperProfile,
+    api_key: String,
+}
+
+/// Issue a verification token and mail the link with it
+async fn send_verification(
+    pool: &PgPool,
+    mail: &AccountMail,
+    developer: &Developer,
+) -> FormVaultResult<()> {
+    let (token, secret) = EmailToken::generate(developer.id(), EmailTokenPurpose::VerifyEmail);
+    create_email_token(pool, &token).await?;
+    mail.send_verification(developer, &secret).await
+}
+
+/// Sign up; the response holds the account's first API key
+///
+/// A verification link is mailed to the address. Forms accept submissions
+/// once it has been followed.
+#[utoipa::path(
+    post,
+    path = "/developers",
+    tag = "developers",
+    security(()),
+    responses((status = 201, body = RegisteredDeveloper))
+)]
+pub async fn register(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    mail: web::Data<AccountMail>,
+    body: web::Json<RegisterDeveloper>,
+) -> FormVaultResult<HttpResponse> {
+    let body = body.into_inner();
+    // The personal team new forms go to and the first API key are created
+    // with the account, so a failed signup leaves nothing behind
+    let (registration, api_key) =
+        Registration::new(body.name, body.email, body.public_key, client_ip(&req))?;
+    storage.developers().register(&registration).await?;
+    let developer = registration.developer;
+
+    // The account exists either way; the email can be sent again later
+    if let Err(e) = send_verification(&pool, &mail, &developer).await {
+        warn!(
+            "Verification email for developer {} not sent: {}",
+            developer.id(),
+            e
+        );
+    }
+
+    Ok(HttpResponse::Created().json(RegisteredDeveloper {
+        profile: DeveloperProfile::from(&developer),
+        api_key,
+    }))
+}
+
+/// The developer the API key belongs to
+#[utoipa::path(
+    get,
+    path = "/developers/me",
+    tag = "developers",
+    responses((status = 200, body = DeveloperProfile))
+)]
+pub async fn me(auth: AuthenticatedDeveloper) -> HttpResponse {
+    HttpResponse::Ok().json(DeveloperProfile::from(&auth.developer))
+}
+
+/// Mail a new verification link
+#[utoipa::path(
+    post,
+    path = "/developers/me/verification",
+    tag = "developers",
+    responses((status = 202))
+)]
+pub async fn resend_verification(
+    pool: web::Data<PgPool>,
+    mail: web::Data<AccountMail>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    if auth.developer.is_verified() {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "the email address is already verified".to_string(),
+        ]));
+    }
+
+    send_verification(&pool, &mail, &auth.developer).await?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Replace the API key used for this request
+///
+/// The new key has the same name, scopes and expiry; the old one is revoked.
+#[utoipa::path(
+    post,
+    path = "/developers/me/api_key",
+    tag = "developers",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 200, body = ApiKeyResponse))
+)]
+pub async fn rotate_api_key(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let current = auth.api_key().ok_or_else(|| {
+        FormVaultError::Forbidden(
+            "a session has no API key to rotate; create one with POST /api_keys".to_string(),
+        )
+    })?;
+    auth.require_second_factor(&req, &pool).await?;
+
+    let (key, api_key) = ApiKey::generate(
+        current.developer_id,
+        current.name.clone(),
+        current.scopes.clone(),
+        current.expires_at,
+    );
+    // The old key must not outlive the rotation, so both happen or neither
+    let mut tx = pool.begin().await?;
+    create_api_key(&mut *tx, &key).await?;
+    if !revoke_api_key(&mut *tx, current.developer_id, current.id).await? {
+        return Err(FormVaultError::InvalidApiKey);
+    }
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::ApiKeyRotated,
+        )
+        .target("api_key", key.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "revoked": current.id })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(ApiKeyResponse { api_key }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RotatePublicKey {
+    public_key: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Page {
+    /// Defaults to 100, at most 1000
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Switch to a new public key; the previous version is retired but kept
+#[utoipa::path(
+    put,
+    path = "/developers/me/public_key",
+    tag = "developers",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 200, body = PublicKeyVersion))
+)]
+pub async fn rotate_public_key(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    body: web::Json<RotatePublicKey>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    auth.require_second_factor(&req, &pool).await?;
+    let actor = auth.actor();
+    let mut developer = auth.developer;
+    let mut tx = pool.begin().await?;
+    let (key, retired) = developer
+        .update_public_key(body.into_inner().public_key, &mut tx)
+        .await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(actor, developer.id(), AuditAction::PublicKeyRotated)
+            .target("public_key", key.id)
+            .ip_address(client_ip(&req))
+            .details(json!({
+                "version": key.version,
+                "fingerprint": key.fingerprint,
+                "retired": retired,
+            })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(key))
+}
+
+/// Every key version with the number of submissions encrypted to it
+#[utoipa::path(
+    get,
+    path = "/developers/me/public_keys",
+    tag = "developers",
+    responses((status = 200, body = [KeySummary]))
+)]
+pub async fn list_public_keys(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let keys = find_key_summaries(&pool, auth.developer.id()).await?;
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+/// Submissions that still need a retired private key to be read
+#[utoipa::path(
+    get,
+    path = "/developers/me/public_keys/retired/submissions",
+    tag = "developers",
+    params(Page),
+    responses((status = 200, body = [RetiredKeySubmission]))
+)]
+pub async fn retired_key_submissions(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    page: web::Query<Page>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ReadSubmissions)?;
+    let limit = page.limit.unwrap_or(100).clamp(1, 1000);
+    let offset = page.offset.unwrap_or(0).max(0);
+
+    let submissions =
+        find_submissions_on_retired_keys(&pool, auth.developer.id(), limit, offset).await?;
+    Ok(HttpResponse::Ok().json(submissions))
+}
+
+/// Deactivate the account; its API keys stop working
+#[utoipa::path(
+    delete,
+    path = "/developers/me",
+    tag = "developers",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 204))
+)]
+pub async fn deactivate(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    auth.require_second_factor(&req, &pool).await?;
+    let actor = auth.actor();
+    let mut developer = auth.developer;
+    let mut tx = pool.begin().await?;
+    developer.deactivate(&mut *tx).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(actor, developer.id(), AuditAction::AccountDeactivated)
+            .ip_address(client_ip(&req)),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,147 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::handlers::forms::authorized_form;
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::forms::field_definition::{FieldDefinition, FieldType};
+use crate::models::users::api_key::ApiKeyScope;
+use crate::models::users::team::Permission;
+use crate::repositories::audit::append_event;
+use crate::repositories::fields::{create_field, delete_field, find_fields_by_form};
+use crate::storage::Storage;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateField {
+    name: String,
+    field_type: FieldType,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    validation_rules: serde_json::Value,
+}
+
+/// Fields of a form in display order
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}/fields",
+    tag = "fields",
+    responses((status = 200, body = [FieldDefinition]))
+)]
+pub async fn list_fields(
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ReadSubmissions,
+    )
+    .await?;
+    let fields = find_fields_by_form(&pool, form.id).await?;
+    Ok(HttpResponse::Ok().json(fields))
+}
+
+/// Add a field; it is placed after the existing ones
+#[utoipa::path(
+    post,
+    path = "/forms/{form_id}/fields",
+    tag = "fields",
+    operation_id = "create_field",
+    responses((status = 201, body = FieldDefinition))
+)]
+pub async fn create(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    body: web::Json<CreateField>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ManageForms)?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ManageForms,
+    )
+    .await?;
+    let body = body.into_inner();
+
+    let validation_rules = match body.validation_rules {
+        serde_json::Value::Null => json!({}),
+        rules => rules,
+    };
+    let mut field = FieldDefinition::new(
+        form.id,
+        body.name.trim().to_string(),
+        body.field_type,
+        body.required,
+        validation_rules,
+    )?;
+    create_field(&pool, &mut field).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::FormFieldAdded,
+        )
+        .target("form", form.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "field_id": field.id, "name": field.name })),
+    )
+    .await?;
+
+    Ok(HttpResponse::Created().json(field))
+}
+
+/// Remove a field; existing submissions are unchanged
+#[utoipa::path(
+    delete,
+    path = "/forms/{form_id}/fields/{field_id}",
+    tag = "fields",
+    operation_id = "delete_field",
+    responses((status = 204))
+)]
+pub async fn delete(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<(Uuid, Uuid)>,
+) -> FormVaultResult<HttpResponse> {
+    let (form_id, field_id) = path.into_inner();
+    auth.require(ApiKeyScope::ManageForms)?;
+    let form = authorized_form(&pool, &storage, &auth, form_id, Permission::ManageForms).await?;
+    if !delete_field(&pool, form.id, field_id).await? {
+        return Err(FormVaultError::NotFound);
+    }
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::FormFieldRemoved,
+        )
+        .target("form", form.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "field_id": field_id })),
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
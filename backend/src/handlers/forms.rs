@@ -0,0 +1,539 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::forms::FormSubmission;
+use crate::models::forms::form_schema::{EffectiveKey, FormSchema, KeySource};
+use crate::models::users::api_key::ApiKeyScope;
+use crate::models::users::team::{Permission, TeamRole};
+use crate::repositories::audit::{append_event, chain_event};
+use crate::repositories::form::update_form_key;
+use crate::repositories::public_keys::find_public_key_by_id;
+use crate::repositories::teams::{find_memberships, find_role};
+use crate::storage::Storage;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateForm {
+    name: String,
+    /// Defaults to the oldest team the developer owns
+    team_id: Option<Uuid>,
+    /// A key for this form only; without one the form inherits the
+    /// developer's current key
+    public_key: Option<String>,
+    /// Origins submissions are accepted from, such as
+    /// `https://*.example.com`; empty or missing accepts every origin
+    #[serde(default)]
+    allowed_origins: Vec<String>,
+    /// Where browsers go after a successful HTML form post; without one they
+    /// see the hosted thank-you page
+    success_url: Option<String>,
+    /// Where browsers go after a failed HTML form post, with the error code
+    /// in `?error=`; without one they see the hosted error page
+    error_url: Option<String>,
+    /// Refuse plaintext fields; submissions must be encrypted by the
+    /// submitter, as the hosted form does in the browser
+    #[serde(default)]
+    zero_knowledge: bool,
+}
+
+/// Settings to change; missing ones are kept
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateForm {
+    /// Replaces the form's allowed origins; empty accepts every origin
+    allowed_origins: Option<Vec<String>>,
+    /// An empty string goes back to the hosted thank-you page
+    success_url: Option<String>,
+    /// An empty string goes back to the hosted error page
+    error_url: Option<String>,
+    zero_knowledge: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RekeyForm {
+    public_key: String,
+}
+
+/// A form together with the key its submissions are encrypted to
+#[derive(Serialize, ToSchema)]
+struct FormWithKey {
+    #[serde(flatten)]
+    form: FormSchema,
+    key: EffectiveKey,
+}
+
+impl FormWithKey {
+    async fn load(pool: &PgPool, form: FormSchema) -> FormVaultResult<Self> {
+        let key = form.effective_key(pool).await?;
+        Ok(Self { form, key })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PinKey {
+    /// `null` unpins the form so it follows the current key again, which
+    /// only works while its creator may manage the team's keys
+    key_id: Option<Uuid>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Page {
+    /// Defaults to 50, at most 500
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Load a form if the developer's role in its team grants `permission`.
+///
+/// Forms of teams the developer is not in look missing.
+pub(crate) async fn authorized_form(
+    pool: &PgPool,
+    storage: &Storage,
+    auth: &AuthenticatedDeveloper,
+    id: Uuid,
+    permission: Permission,
+) -> FormVaultResult<FormSchema> {
+    let form = storage
+        .forms()
+        .find_by_id(id)
+        .await?
+        .ok_or(FormVaultError::FormNotFound)?;
+    auth.require_permission(pool, form.team_id, permission, FormVaultError::FormNotFound)
+        .await?;
+    Ok(form)
+}
+
+/// Create a form in one of the developer's teams
+#[utoipa::path(
+    post,
+    path = "/forms",
+    tag = "forms",
+    responses((status = 201, body = FormWithKey))
+)]
+pub async fn create_form(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    body: web::Json<CreateForm>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ManageForms)?;
+    let developer = &auth.developer;
+    let body = body.into_inner();
+    let name = body.name.trim().to_string();
+    if name.is_empty() {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "name must not be empty".to_string(),
+        ]));
+    }
+
+    let team_id = match body.team_id {
+        Some(team_id) => team_id,
+        None => find_memberships(&pool, developer.id())
+            .await?
+            .into_iter()
+            .find(|team| team.role == TeamRole::Owner)
+            .map(|team| team.id)
+            .ok_or(FormVaultError::NotFound)?,
+    };
+    let role = auth
+        .require_permission(
+            &pool,
+            team_id,
+            Permission::ManageForms,
+            FormVaultError::NotFound,
+        )
+        .await?;
+
+    let mut form = FormSchema::new(name, developer.id(), team_id);
+    form.set_allowed_origins(body.allowed_origins)?;
+    form.set_redirect_urls(body.success_url, body.error_url)?;
+    form.zero_knowledge = body.zero_knowledge;
+    form.create(&pool, body.public_key, role).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(auth.actor(), auth.developer.id(), AuditAction::FormCreated)
+            .target("form", form.id)
+            .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::Created().json(FormWithKey::load(&pool, form).await?))
+}
+
+/// Forms of every team the developer is in
+#[utoipa::path(
+    get,
+    path = "/forms",
+    tag = "forms",
+    responses((status = 200, body = [FormSchema]))
+)]
+pub async fn list_forms(
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let forms = storage.forms().find_by_member(auth.developer.id()).await?;
+    Ok(HttpResponse::Ok().json(forms))
+}
+
+/// A form with the key its submissions are encrypted to
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}",
+    tag = "forms",
+    responses((status = 200, body = FormWithKey))
+)]
+pub async fn get_form(
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ReadSubmissions,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(FormWithKey::load(&pool, form).await?))
+}
+
+/// Change the settings of a form
+#[utoipa::path(
+    patch,
+    path = "/forms/{form_id}",
+    tag = "forms",
+    responses((status = 200, body = FormWithKey))
+)]
+pub async fn update_form(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    body: web::Json<UpdateForm>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ManageForms)?;
+    let mut form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ManageForms,
+    )
+    .await?;
+    let body = body.into_inner();
+    if let Some(allowed_origins) = body.allowed_origins {
+        form.set_allowed_origins(allowed_origins)?;
+    }
+    form.set_redirect_urls(body.success_url, body.error_url)?;
+    if let Some(zero_knowledge) = body.zero_knowledge {
+        form.zero_knowledge = zero_knowledge;
+    }
+    storage.forms().update_settings(&form).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(auth.actor(), auth.developer.id(), AuditAction::FormUpdated)
+            .target("form", form.id)
+            .ip_address(client_ip(&req))
+            .details(json!({
+                "allowed_origins": form.allowed_origins,
+                "success_url": form.success_url,
+                "error_url": form.error_url,
+                "zero_knowledge": form.zero_knowledge,
+            })),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(FormWithKey::load(&pool, form).await?))
+}
+
+/// Encrypted submissions of a form, newest first
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}/submissions",
+    tag = "forms",
+    params(Page),
+    responses((status = 200, body = [FormSubmission]))
+)]
+pub async fn list_submissions(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    page: web::Query<Page>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ReadSubmissions)?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ReadSubmissions,
+    )
+    .await?;
+    let limit = page.limit.unwrap_or(50).clamp(1, 500);
+    let offset = page.offset.unwrap_or(0).max(0);
+
+    let submissions = storage
+        .submissions()
+        .find_by_form(form.id, Some(limit), offset)
+        .await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::SubmissionRead,
+        )
+        .target("form", form.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "count": submissions.len(), "offset": offset })),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(submissions))
+}
+
+/// One encrypted submission
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}/submissions/{submission_id}",
+    tag = "forms",
+    responses((status = 200, body = FormSubmission))
+)]
+pub async fn get_submission(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<(Uuid, Uuid)>,
+) -> FormVaultResult<HttpResponse> {
+    let (form_id, submission_id) = path.into_inner();
+    auth.require(ApiKeyScope::ReadSubmissions)?;
+    let form =
+        authorized_form(&pool, &storage, &auth, form_id, Permission::ReadSubmissions).await?;
+
+    let submission = storage
+        .submissions()
+        .find_by_id(submission_id)
+        .await?
+        .filter(|s| s.form_schema_id == form.id)
+        .ok_or(FormVaultError::SubmissionNotFound)?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::SubmissionRead,
+        )
+        .target("submission", submission.id)
+        .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(submission))
+}
+
+/// Download every submission of a form
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}/export",
+    tag = "forms",
+    responses((status = 200, body = [FormSubmission], description = "Every submission, for `formvault decrypt`"))
+)]
+pub async fn export_submissions(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ReadSubmissions)?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ReadSubmissions,
+    )
+    .await?;
+    let submissions = storage.submissions().find_by_form(form.id, None, 0).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(auth.actor(), auth.developer.id(), AuditAction::ExportRun)
+            .target("form", form.id)
+            .ip_address(client_ip(&req))
+            .details(json!({ "count": submissions.len() })),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"submissions-{}.json\"", form.id),
+        ))
+        .json(submissions))
+}
+
+/// Pin the form to one version of its developer's key, or unpin it
+#[utoipa::path(
+    put,
+    path = "/forms/{form_id}/key",
+    tag = "forms",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 200, body = FormWithKey))
+)]
+pub async fn set_form_key(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    body: web::Json<PinKey>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ManageForms)?;
+    auth.require_second_factor(&req, &pool).await?;
+    let mut form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ManageKeys,
+    )
+    .await?;
+    let key_id = body.into_inner().key_id;
+
+    if let Some(key_id) = key_id {
+        let key = find_public_key_by_id(&pool, key_id)
+            .await?
+            .filter(|key| {
+                key.developer_id == form.developer_id
+                    && key.form_id.is_none_or(|form_id| form_id == form.id)
+            })
+            .ok_or(FormVaultError::NotFound)?;
+        if key.retired_at.is_some() {
+            return Err(FormVaultError::ValidationFailed(vec![
+                "this key version was retired and can't be pinned".to_string(),
+            ]));
+        }
+    } else if !find_role(&pool, form.team_id, form.developer_id)
+        .await?
+        .is_some_and(|role| role.can(Permission::ManageKeys))
+    {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "only forms of developers who manage the team's keys can follow their current key"
+                .to_string(),
+        ]));
+    }
+    form.key_id = key_id;
+    let key = form.effective_key(&pool).await?;
+    let mut tx = pool.begin().await?;
+    update_form_key(&mut *tx, &form).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::FormKeyChanged,
+        )
+        .target("form", form.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "key_id": key.key.id, "source": key.source })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(FormWithKey { form, key }))
+}
+
+/// The key submissions to the form are encrypted to, with its fingerprint
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}/key",
+    tag = "forms",
+    responses((status = 200, body = EffectiveKey))
+)]
+pub async fn get_form_key(
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ReadSubmissions,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(form.effective_key(&pool).await?))
+}
+
+/// Give the form a new key of its own, retiring its previous one
+#[utoipa::path(
+    put,
+    path = "/forms/{form_id}/public_key",
+    tag = "forms",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 200, body = FormWithKey))
+)]
+pub async fn rekey_form(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    body: web::Json<RekeyForm>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ManageForms)?;
+    auth.require_second_factor(&req, &pool).await?;
+    let mut form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ManageKeys,
+    )
+    .await?;
+    let mut tx = pool.begin().await?;
+    let (key, retired) = form.rekey(&mut tx, body.into_inner().public_key).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::FormKeyChanged,
+        )
+        .target("form", form.id)
+        .ip_address(client_ip(&req))
+        .details(json!({
+            "key_id": key.id,
+            "source": KeySource::Own,
+            "version": key.version,
+            "fingerprint": key.fingerprint,
+            "retired": retired,
+        })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(FormWithKey::load(&pool, form).await?))
+}
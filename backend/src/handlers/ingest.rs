@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use actix_web::http::StatusCode;
+use actix_web::http::header::{self, Accept, Header, HeaderValue};
+use actix_web::{Either, HttpRequest, HttpResponse, ResponseError, web};
+use reqwest::Url;
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::client_ip;
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::metrics::metrics;
+use crate::models::forms::SubmissionMetadata;
+use crate::models::forms::field_definition::validate_submission;
+use crate::models::forms::form_schema::{FormSchema, SubmissionKey};
+use crate::models::forms::origin::origin_of;
+use crate::models::forms::submission::{EncryptedPayload, FormSubmission, SubmissionReceipt};
+use crate::notifications::Notifier;
+use crate::pages;
+use crate::repositories::fields::find_fields_by_form;
+use crate::shutdown::InFlight;
+use crate::storage::Storage;
+
+/// How long browsers may cache a preflight, in seconds
+const PREFLIGHT_MAX_AGE: &str = "600";
+
+/// Either an envelope encrypted by the submitter or plain fields for the
+/// server to encrypt
+#[derive(Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum SubmissionBody {
+    Encrypted(EncryptedPayload),
+    Fields(HashMap<String, String>),
+}
+
+async fn public_form(storage: &Storage, form_id: Uuid) -> FormVaultResult<FormSchema> {
+    storage
+        .forms()
+        .find_by_id(form_id)
+        .await?
+        .ok_or(FormVaultError::FormNotFound)
+}
+
+fn text(req: &HttpRequest, name: header::HeaderName) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+fn metadata(req: &HttpRequest) -> SubmissionMetadata {
+    SubmissionMetadata {
+        ip_address: client_ip(req),
+        user_agent: text(req, header::USER_AGENT),
+        referrer: text(req, header::REFERER),
+        country: None,
+    }
+}
+
+/// The origin of the page a request was sent from: its `Origin`, or failing
+/// that the origin of its `Referer`
+fn request_origin(req: &HttpRequest) -> Option<String> {
+    text(req, header::ORIGIN).or_else(|| origin_of(&text(req, header::REFERER)?))
+}
+
+/// The origin this server is reached at, which its hosted forms post from
+fn own_origin(req: &HttpRequest) -> String {
+    let info = req.connection_info();
+    format!("{}://{}", info.scheme(), info.host())
+}
+
+/// Fail with 403 unless the form accepts submissions from the request's
+/// origin; the form's own hosted page is always accepted
+fn check_origin(form: &FormSchema, req: &HttpRequest) -> FormVaultResult<()> {
+    let origin = request_origin(req);
+    let hosted = origin
+        .as_deref()
+        .is_some_and(|origin| origin.eq_ignore_ascii_case(&own_origin(req)));
+    if hosted || form.accepts_origin(origin.as_deref()) {
+        Ok(())
+    } else {
+        Err(FormVaultError::OriginNotAllowed(origin.unwrap_or_default()))
+    }
+}
+
+/// Let the page that sent the request read the response.
+///
+/// Pages on origins the form refuses never get this far, so the header can
+/// simply echo theirs.
+fn allow_origin(req: &HttpRequest, response: &mut HttpResponse) {
+    if let Some(origin) = req
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|origin| HeaderValue::from_bytes(origin.as_bytes()).ok())
+    {
+        let headers = response.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+    }
+}
+
+/// Public endpoint submitters post to; no API key needed
+///
+/// Takes JSON or, from plain HTML forms, `application/x-www-form-urlencoded`
+/// fields. Requests that prefer `text/html` in `Accept`, as browsers
+/// following a form post do, are answered with a 303 to the form's success
+/// or error URL, or to the hosted pages when it has none; everything else
+/// gets JSON.
+///
+/// Refused with 403 until the form's owner has verified their email, and
+/// with 403 `ORIGIN_NOT_ALLOWED` when the form has allowed origins and the
+/// `Origin` (or, without one, the `Referer`) matches none of them.
+#[utoipa::path(
+    post,
+    path = "/f/{form_id}",
+    tag = "ingest",
+    security(()),
+    request_body(content(
+        (SubmissionBody = "application/json"),
+        (SubmissionBody = "application/x-www-form-urlencoded")
+    )),
+    responses(
+        (status = 201, body = SubmissionReceipt),
+        (status = 303, description = "An HTML form post, redirected to the success or error page",
+            headers(("Location" = String)))
+    )
+)]
+pub async fn submit(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    notifier: web::Data<Notifier>,
+    in_flight: web::Data<InFlight>,
+    path: web::Path<Uuid>,
+    body: Either<web::Json<SubmissionBody>, web::Form<SubmissionBody>>,
+) -> FormVaultResult<HttpResponse> {
+    // A shutdown waits for this submission to be stored
+    let _in_flight = in_flight.enter();
+    let form_id = path.into_inner();
+    let body = match body {
+        Either::Left(body) => body.into_inner(),
+        Either::Right(body) => body.into_inner(),
+    };
+    let client_encrypted = matches!(body, SubmissionBody::Encrypted(_));
+
+    let (form, stored) = match public_form(&storage, form_id).await {
+        Ok(form) => {
+            let stored = store(&req, &pool, &storage, &notifier, &form, body).await;
+            (Some(form), stored)
+        }
+        Err(error) => (None, Err(error)),
+    };
+    match &stored {
+        Ok(_) => metrics().submission_accepted(form_id, client_encrypted),
+        Err(error) => metrics().submission_rejected(form_id, error),
+    }
+
+    if wants_html(&req) {
+        return Ok(after_post(&req, form_id, form.as_ref(), stored));
+    }
+    let mut response = match stored {
+        Ok(submission) => HttpResponse::Created().json(SubmissionReceipt::from(&submission)),
+        Err(error @ FormVaultError::OriginNotAllowed(_)) => return Err(error),
+        Err(error) => error.error_response(),
+    };
+    allow_origin(&req, &mut response);
+    Ok(response)
+}
+
+/// Whether the client ranks HTML above JSON, as browsers navigating do;
+/// `fetch` and API clients send `*/*` or ask for JSON
+fn wants_html(req: &HttpRequest) -> bool {
+    let Ok(accept) = Accept::parse(req) else {
+        return false;
+    };
+    accept
+        .ranked()
+        .iter()
+        .find_map(|mime| match mime.essence_str() {
+            "text/html" => Some(true),
+            "application/json" => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// Send a browser on after an HTML form post: to the form's success or
+/// error URL if it has one, to the hosted thank-you page, or show the hosted
+/// error page in place
+fn after_post(
+    req: &HttpRequest,
+    form_id: Uuid,
+    form: Option<&FormSchema>,
+    stored: FormVaultResult<FormSubmission>,
+) -> HttpResponse {
+    let see_other = |location: &str| {
+        HttpResponse::SeeOther()
+            .insert_header((header::LOCATION, location))
+            .finish()
+    };
+    match stored {
+        Ok(_) => match form.and_then(|form| form.success_url.as_deref()) {
+            Some(success_url) => see_other(success_url),
+            None => see_other(&format!("/f/{}/thanks", form_id)),
+        },
+        Err(error) => {
+            let response = error.to_response();
+            match form.and_then(|form| form.error_url.as_deref()) {
+                Some(error_url) => match Url::parse(error_url) {
+                    Ok(mut location) => {
+                        location
+                            .query_pairs_mut()
+                            .append_pair("error", &response.code);
+                        see_other(location.as_str())
+                    }
+                    Err(_) => see_other(error_url),
+                },
+                None => {
+                    let back =
+                        text(req, header::REFERER).filter(|referer| origin_of(referer).is_some());
+                    pages::html(
+                        ResponseError::status_code(&error),
+                        pages::submission_failed(&response, back.as_deref()),
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// The form as a page to fill in, for sharing a link instead of building one
+///
+/// Fields are rendered in order with their labels, types, required flags,
+/// rules and select options. Zero-knowledge forms come with a script that
+/// encrypts the entries in the browser before they are posted.
+#[utoipa::path(
+    get,
+    path = "/f/{form_id}",
+    tag = "ingest",
+    security(()),
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+pub async fn hosted_form(
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    let Some(form) = storage.forms().find_by_id(path.into_inner()).await? else {
+        return Ok(pages::html(StatusCode::NOT_FOUND, pages::form_not_found()));
+    };
+    let fields = find_fields_by_form(&pool, form.id).await?;
+    let key = if form.zero_knowledge {
+        Some(SubmissionKey::new(
+            form.id,
+            &form.submission_key(&pool).await?,
+        ))
+    } else {
+        None
+    };
+    Ok(pages::hosted_form(&form, &fields, key.as_ref()))
+}
+
+/// Hosted page browsers are sent to after a successful HTML form post when
+/// the form has no success URL
+#[utoipa::path(
+    get,
+    path = "/f/{form_id}/thanks",
+    tag = "ingest",
+    security(()),
+    responses((status = 200, content_type = "text/html", body = String))
+)]
+pub async fn thank_you(
+    storage: web::Data<Storage>,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    match storage.forms().find_by_id(path.into_inner()).await? {
+        Some(form) => Ok(pages::html(StatusCode::OK, pages::thank_you(&form.name))),
+        None => Ok(pages::html(StatusCode::NOT_FOUND, pages::form_not_found())),
+    }
+}
+
+/// CORS preflight for submitting from a page on another origin
+///
+/// Answered with 204 and the CORS headers when the form accepts the
+/// page's origin, and 403 `ORIGIN_NOT_ALLOWED` otherwise.
+#[utoipa::path(
+    options,
+    path = "/f/{form_id}",
+    tag = "ingest",
+    security(()),
+    responses((status = 204))
+)]
+pub async fn preflight(
+    req: HttpRequest,
+    storage: web::Data<Storage>,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    let form = public_form(&storage, path.into_inner()).await?;
+    check_origin(&form, &req)?;
+
+    let mut response = HttpResponse::NoContent()
+        .insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, "POST, OPTIONS"))
+        .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type, Accept"))
+        .insert_header((header::ACCESS_CONTROL_MAX_AGE, PREFLIGHT_MAX_AGE))
+        .finish();
+    allow_origin(&req, &mut response);
+    Ok(response)
+}
+
+async fn store(
+    req: &HttpRequest,
+    pool: &PgPool,
+    storage: &Storage,
+    notifier: &Notifier,
+    form: &FormSchema,
+    body: SubmissionBody,
+) -> FormVaultResult<FormSubmission> {
+    check_origin(form, req)?;
+    // Forms only go live once their owner has verified their email
+    let owner = storage.developers().find_by_id(form.developer_id).await?;
+    if !owner.is_some_and(|owner| owner.is_verified()) {
+        return Err(FormVaultError::EmailNotVerified);
+    }
+
+    match body {
+        SubmissionBody::Encrypted(payload) => {
+            form.accept_encrypted(pool, notifier, payload, metadata(req))
+                .await
+        }
+        SubmissionBody::Fields(_) if form.zero_knowledge => Err(FormVaultError::EncryptionRequired),
+        SubmissionBody::Fields(data) => {
+            let fields = find_fields_by_form(pool, form.id).await?;
+            validate_submission(&fields, &data)?;
+            form.process_submission(pool, notifier, data, metadata(req))
+                .await
+        }
+    }
+}
+
+/// The key to encrypt submissions to before posting them
+#[utoipa::path(
+    get,
+    path = "/f/{form_id}/key",
+    tag = "ingest",
+    security(()),
+    responses((status = 200, body = SubmissionKey))
+)]
+pub async fn submission_key(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    let form = public_form(&storage, path.into_inner()).await?;
+    check_origin(&form, &req)?;
+    let key = form.submission_key(&pool).await?;
+    let mut response = HttpResponse::Ok().json(SubmissionKey::new(form.id, &key));
+    allow_origin(&req, &mut response);
+    Ok(response)
+}
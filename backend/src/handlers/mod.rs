@@ -1 +1,12 @@
+pub mod api_keys;
+pub mod audit;
+pub mod auth;
 pub mod configuration;
+pub mod developers;
+pub mod fields;
+pub mod forms;
+pub mod ingest;
+pub mod notifications;
+pub mod sdk;
+pub mod teams;
+pub mod two_factor;
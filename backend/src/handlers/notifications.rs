@@ -0,0 +1,236 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::handlers::forms::authorized_form;
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::forms::notification_target::{ChannelKind, NotificationTarget};
+use crate::models::forms::submission::SubmissionStatus;
+use crate::models::users::api_key::ApiKeyScope;
+use crate::models::users::team::Permission;
+use crate::repositories::audit::append_event;
+use crate::repositories::notification::{
+    create_target, delete_target, find_target_by_id, find_targets_by_form, update_target,
+};
+use crate::storage::Storage;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTarget {
+    channel: ChannelKind,
+    destination: String,
+    #[serde(default)]
+    status_filter: Vec<SubmissionStatus>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateTarget {
+    destination: Option<String>,
+    enabled: Option<bool>,
+    status_filter: Option<Vec<SubmissionStatus>>,
+}
+
+/// HTTP channels need an absolute http(s) URL, email needs an address
+fn validate_destination(channel: ChannelKind, destination: &str) -> FormVaultResult<()> {
+    let valid = match channel {
+        ChannelKind::Email => {
+            let mut parts = destination.splitn(2, '@');
+            matches!(
+                (parts.next(), parts.next()),
+                (Some(local), Some(domain)) if !local.is_empty() && domain.contains('.')
+            )
+        }
+        ChannelKind::Webhook | ChannelKind::Slack | ChannelKind::Discord => {
+            Url::parse(destination).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+        }
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(FormVaultError::ValidationFailed(vec![format!(
+            "destination is not valid for the {:?} channel",
+            channel
+        )]))
+    }
+}
+
+/// Load a target of `form_id`; targets of other forms look missing
+async fn form_target(
+    pool: &PgPool,
+    form_id: Uuid,
+    target_id: Uuid,
+) -> FormVaultResult<NotificationTarget> {
+    find_target_by_id(pool, target_id)
+        .await?
+        .filter(|target| target.form_id == form_id)
+        .ok_or(FormVaultError::NotFound)
+}
+
+/// Where new submissions to a form are announced
+#[utoipa::path(
+    get,
+    path = "/forms/{form_id}/notifications",
+    tag = "notifications",
+    responses((status = 200, body = [NotificationTarget]))
+)]
+pub async fn list_targets(
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ReadSubmissions,
+    )
+    .await?;
+    let targets = find_targets_by_form(&**pool, form.id).await?;
+    Ok(HttpResponse::Ok().json(targets))
+}
+
+/// Notify a webhook, mailbox or chat channel of new submissions
+#[utoipa::path(
+    post,
+    path = "/forms/{form_id}/notifications",
+    tag = "notifications",
+    operation_id = "create_notification_target",
+    responses((status = 201, body = NotificationTarget))
+)]
+pub async fn create(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    body: web::Json<CreateTarget>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::ManageForms)?;
+    let form = authorized_form(
+        &pool,
+        &storage,
+        &auth,
+        path.into_inner(),
+        Permission::ManageForms,
+    )
+    .await?;
+    let body = body.into_inner();
+    let destination = body.destination.trim().to_string();
+    validate_destination(body.channel, &destination)?;
+
+    let target = NotificationTarget::new(form.id, body.channel, destination)
+        .with_status_filter(body.status_filter);
+    create_target(&pool, &target).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::NotificationTargetCreated,
+        )
+        .target("notification_target", target.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "form_id": form.id, "channel": target.channel })),
+    )
+    .await?;
+
+    Ok(HttpResponse::Created().json(target))
+}
+
+/// Change a target's destination, status filter or enable it
+#[utoipa::path(
+    patch,
+    path = "/forms/{form_id}/notifications/{target_id}",
+    tag = "notifications",
+    operation_id = "update_notification_target",
+    responses((status = 200, body = NotificationTarget))
+)]
+pub async fn update(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<(Uuid, Uuid)>,
+    body: web::Json<UpdateTarget>,
+) -> FormVaultResult<HttpResponse> {
+    let (form_id, target_id) = path.into_inner();
+    auth.require(ApiKeyScope::ManageForms)?;
+    let form = authorized_form(&pool, &storage, &auth, form_id, Permission::ManageForms).await?;
+    let mut target = form_target(&pool, form.id, target_id).await?;
+    let body = body.into_inner();
+
+    if let Some(destination) = body.destination {
+        let destination = destination.trim().to_string();
+        validate_destination(target.channel, &destination)?;
+        target.destination = destination;
+    }
+    if let Some(enabled) = body.enabled {
+        target.enabled = enabled;
+    }
+    if let Some(status_filter) = body.status_filter {
+        target.status_filter = status_filter;
+    }
+    update_target(&pool, &target).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::NotificationTargetUpdated,
+        )
+        .target("notification_target", target.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "form_id": form.id, "enabled": target.enabled })),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(target))
+}
+
+/// Stop notifying a target
+#[utoipa::path(
+    delete,
+    path = "/forms/{form_id}/notifications/{target_id}",
+    tag = "notifications",
+    operation_id = "delete_notification_target",
+    responses((status = 204))
+)]
+pub async fn delete(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<(Uuid, Uuid)>,
+) -> FormVaultResult<HttpResponse> {
+    let (form_id, target_id) = path.into_inner();
+    auth.require(ApiKeyScope::ManageForms)?;
+    let form = authorized_form(&pool, &storage, &auth, form_id, Permission::ManageForms).await?;
+    let target = form_target(&pool, form.id, target_id).await?;
+    delete_target(&pool, target.id).await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::NotificationTargetDeleted,
+        )
+        .target("notification_target", target.id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "form_id": form.id })),
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
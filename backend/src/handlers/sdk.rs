@@ -0,0 +1,65 @@
+use actix_web::http::header;
+use actix_web::{HttpResponse, Responder};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::sdk;
+
+/// How long the bundle may be cached: a year, since it never changes
+const BUNDLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// What pages need to load the SDK
+#[derive(Serialize, ToSchema)]
+pub struct SdkRelease {
+    /// Release of the bundle
+    #[schema(example = "1.0.0")]
+    pub version: &'static str,
+    /// Path of the bundle on this server
+    #[schema(example = "/sdk/v1/formvault.js")]
+    pub path: &'static str,
+    /// Value for the script's `integrity` attribute
+    #[schema(example = "sha384-…")]
+    pub integrity: String,
+}
+
+/// The JavaScript SDK
+///
+/// Encrypts submissions in the browser and posts them to `/f/{form_id}`.
+/// Load it with the integrity hash from `/sdk/v1` and
+/// `crossorigin="anonymous"`.
+#[utoipa::path(
+    get,
+    path = "/sdk/v1/formvault.js",
+    tag = "sdk",
+    security(()),
+    responses((status = 200, content_type = "text/javascript", body = String))
+)]
+pub async fn bundle() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/javascript; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, BUNDLE_CACHE_CONTROL))
+        .insert_header((header::ETAG, format!("\"{}\"", sdk::INTEGRITY.as_str())))
+        // Pages on any origin may load it; integrity checks need CORS
+        .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"))
+        .insert_header(("Cross-Origin-Resource-Policy", "cross-origin"))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(sdk::BUNDLE)
+}
+
+/// The SDK's version and integrity hash
+#[utoipa::path(
+    get,
+    path = "/sdk/v1",
+    tag = "sdk",
+    security(()),
+    responses((status = 200, body = SdkRelease))
+)]
+pub async fn release() -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"))
+        .json(SdkRelease {
+            version: sdk::VERSION,
+            path: sdk::PATH,
+            integrity: sdk::INTEGRITY.clone(),
+        })
+}
@@ -0,0 +1,319 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::users::api_key::ApiKeyScope;
+use crate::models::users::team::{Permission, Team, TeamMember, TeamRole};
+use crate::repositories::audit::chain_event;
+use crate::repositories::form::pin_inherited_forms;
+use crate::repositories::teams::{
+    MemberProfile, Membership, count_owners, create_team_with_owner, find_members,
+    find_memberships, find_role, remove_member, upsert_member,
+};
+use crate::storage::Storage;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTeam {
+    name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddMember {
+    email: String,
+    role: TeamRole,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateMember {
+    role: TeamRole,
+}
+
+/// Only owners may hand out or take away ownership
+fn check_owner_change(
+    actor: TeamRole,
+    from: Option<TeamRole>,
+    to: Option<TeamRole>,
+) -> FormVaultResult<()> {
+    let touches_owner = from == Some(TeamRole::Owner) || to == Some(TeamRole::Owner);
+    if touches_owner && actor != TeamRole::Owner {
+        return Err(FormVaultError::Forbidden(
+            "only owners can grant or revoke ownership".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse to leave a team without an owner; run in the transaction that
+/// demotes or removes the owner
+async fn check_last_owner(
+    conn: &mut PgConnection,
+    team_id: Uuid,
+    from: TeamRole,
+) -> FormVaultResult<()> {
+    if from == TeamRole::Owner && count_owners(conn, team_id).await? <= 1 {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "a team must keep at least one owner".to_string(),
+        ]));
+    }
+    Ok(())
+}
+
+/// Create a team owned by the caller
+#[utoipa::path(
+    post,
+    path = "/teams",
+    tag = "teams",
+    responses((status = 201, body = Team))
+)]
+pub async fn create_team(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    body: web::Json<CreateTeam>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let name = body.into_inner().name.trim().to_string();
+    if name.is_empty() {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "name must not be empty".to_string(),
+        ]));
+    }
+
+    let team = Team::new(name);
+    let mut tx = pool.begin().await?;
+    create_team_with_owner(&mut tx, &team, auth.developer.id()).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(auth.actor(), auth.developer.id(), AuditAction::TeamCreated)
+            .target("team", team.id)
+            .ip_address(client_ip(&req)),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(team))
+}
+
+/// Teams the developer is in, with their role
+#[utoipa::path(
+    get,
+    path = "/teams",
+    tag = "teams",
+    responses((status = 200, body = [Membership]))
+)]
+pub async fn list_teams(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let teams = find_memberships(&pool, auth.developer.id()).await?;
+    Ok(HttpResponse::Ok().json(teams))
+}
+
+/// Members of a team with their role
+#[utoipa::path(
+    get,
+    path = "/teams/{team_id}/members",
+    tag = "teams",
+    responses((status = 200, body = [MemberProfile]))
+)]
+pub async fn list_members(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require_any(&[ApiKeyScope::ReadSubmissions, ApiKeyScope::ManageForms])?;
+    let team_id = path.into_inner();
+    auth.require_permission(
+        &pool,
+        team_id,
+        Permission::ReadSubmissions,
+        FormVaultError::NotFound,
+    )
+    .await?;
+
+    let members = find_members(&pool, team_id).await?;
+    Ok(HttpResponse::Ok().json(members))
+}
+
+/// Add an existing developer by email
+#[utoipa::path(
+    post,
+    path = "/teams/{team_id}/members",
+    tag = "teams",
+    responses((status = 201, body = TeamMember))
+)]
+pub async fn add_member(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    storage: web::Data<Storage>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<Uuid>,
+    body: web::Json<AddMember>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let team_id = path.into_inner();
+    let body = body.into_inner();
+    let actor_role = auth
+        .require_permission(
+            &pool,
+            team_id,
+            Permission::ManageMembers,
+            FormVaultError::NotFound,
+        )
+        .await?;
+    check_owner_change(actor_role, None, Some(body.role))?;
+
+    let developer = storage
+        .developers()
+        .find_by_email(&body.email.trim().to_lowercase())
+        .await?
+        .filter(|developer| developer.is_active())
+        .ok_or(FormVaultError::DeveloperNotFound)?;
+    if find_role(&pool, team_id, developer.id()).await?.is_some() {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "developer is already a member of this team".to_string(),
+        ]));
+    }
+
+    let member = TeamMember::new(team_id, developer.id(), body.role);
+    let mut tx = pool.begin().await?;
+    upsert_member(&mut *tx, &member).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::TeamMemberAdded,
+        )
+        .target("team", team_id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "developer_id": developer.id(), "role": body.role })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(member))
+}
+
+/// Change a member's role
+#[utoipa::path(
+    patch,
+    path = "/teams/{team_id}/members/{developer_id}",
+    tag = "teams",
+    responses((status = 200, body = TeamMember))
+)]
+pub async fn update_member(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<(Uuid, Uuid)>,
+    body: web::Json<UpdateMember>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let (team_id, developer_id) = path.into_inner();
+    let role = body.into_inner().role;
+    let actor_role = auth
+        .require_permission(
+            &pool,
+            team_id,
+            Permission::ManageMembers,
+            FormVaultError::NotFound,
+        )
+        .await?;
+
+    let current = find_role(&pool, team_id, developer_id)
+        .await?
+        .ok_or(FormVaultError::DeveloperNotFound)?;
+    check_owner_change(actor_role, Some(current), Some(role))?;
+
+    // Forms following the member's key stay on the version they have now
+    // once the member may no longer manage the team's keys
+    let member = TeamMember::new(team_id, developer_id, role);
+    let mut tx = pool.begin().await?;
+    if role != TeamRole::Owner {
+        check_last_owner(&mut tx, team_id, current).await?;
+    }
+    if !role.can(Permission::ManageKeys) {
+        pin_inherited_forms(&mut *tx, team_id, developer_id).await?;
+    }
+    upsert_member(&mut *tx, &member).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::TeamMemberUpdated,
+        )
+        .target("team", team_id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "developer_id": developer_id, "from": current, "to": role })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(member))
+}
+
+/// Remove a member, or leave the team
+#[utoipa::path(
+    delete,
+    path = "/teams/{team_id}/members/{developer_id}",
+    tag = "teams",
+    operation_id = "remove_member",
+    responses((status = 204))
+)]
+pub async fn remove(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    path: web::Path<(Uuid, Uuid)>,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let (team_id, developer_id) = path.into_inner();
+
+    // Anyone may leave a team; removing others needs ManageMembers
+    let permission = if developer_id == auth.developer.id() {
+        Permission::ReadSubmissions
+    } else {
+        Permission::ManageMembers
+    };
+    let actor_role = auth
+        .require_permission(&pool, team_id, permission, FormVaultError::NotFound)
+        .await?;
+
+    let current = find_role(&pool, team_id, developer_id)
+        .await?
+        .ok_or(FormVaultError::DeveloperNotFound)?;
+    if developer_id != auth.developer.id() {
+        check_owner_change(actor_role, Some(current), None)?;
+    }
+
+    // Their forms keep the key version they have now; submissions stop
+    // until an admin gives them a new key
+    let mut tx = pool.begin().await?;
+    check_last_owner(&mut tx, team_id, current).await?;
+    pin_inherited_forms(&mut *tx, team_id, developer_id).await?;
+    remove_member(&mut *tx, team_id, developer_id).await?;
+    chain_event(
+        &mut tx,
+        NewAuditEvent::new(
+            auth.actor(),
+            auth.developer.id(),
+            AuditAction::TeamMemberRemoved,
+        )
+        .target("team", team_id)
+        .ip_address(client_ip(&req))
+        .details(json!({ "developer_id": developer_id, "role": current })),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
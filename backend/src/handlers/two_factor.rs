@@ -0,0 +1,223 @@
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+
+use crate::auth::{AuthenticatedDeveloper, client_ip};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::audit::{AuditAction, NewAuditEvent};
+use crate::models::users::api_key::ApiKeyScope;
+use crate::models::users::two_factor::{
+    RecoveryCode, RecoveryCodes, TotpCredential, TotpEnrollment, TwoFactorStatus,
+};
+use crate::repositories::audit::append_event;
+use crate::repositories::two_factor::{
+    confirm_totp_credential, count_unused_recovery_codes, delete_two_factor, find_totp_credential,
+    replace_recovery_codes, start_totp_enrollment,
+};
+
+/// A code from the authenticator app
+#[derive(Deserialize, ToSchema)]
+pub struct TotpCode {
+    code: String,
+}
+
+/// Whether two-factor authentication is on, and how many recovery codes are left
+#[utoipa::path(
+    get,
+    path = "/developers/me/two_factor",
+    tag = "auth",
+    responses((status = 200, body = TwoFactorStatus))
+)]
+pub async fn status(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let enabled_at = find_totp_credential(&**pool, auth.developer.id())
+        .await?
+        .and_then(|credential| credential.confirmed_at);
+    let recovery_codes_left = count_unused_recovery_codes(&pool, auth.developer.id()).await?;
+
+    Ok(HttpResponse::Ok().json(TwoFactorStatus {
+        enabled: enabled_at.is_some(),
+        enabled_at,
+        recovery_codes_left,
+    }))
+}
+
+/// Start enrolling an authenticator app
+///
+/// Returns a new secret and the `otpauth://` URI to show as a QR code.
+/// Two-factor authentication is only turned on once a code from the app is
+/// sent to `POST /developers/me/two_factor/confirm`; starting again before
+/// that replaces the secret. Only a dashboard session can enroll.
+#[utoipa::path(
+    post,
+    path = "/developers/me/two_factor",
+    tag = "auth",
+    responses((status = 201, body = TotpEnrollment))
+)]
+pub async fn enroll(
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    in_person(&auth)?;
+    let credential = TotpCredential::generate(auth.developer.id());
+    if !start_totp_enrollment(&pool, &credential).await? {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "two-factor authentication is already enabled".to_string(),
+        ]));
+    }
+
+    Ok(HttpResponse::Created().json(TotpEnrollment {
+        provisioning_uri: credential.provisioning_uri(auth.developer.email())?,
+        secret: credential.secret,
+    }))
+}
+
+/// Turn two-factor authentication on with a code from the enrolled app
+///
+/// The response holds the recovery codes, which are not shown again.
+#[utoipa::path(
+    post,
+    path = "/developers/me/two_factor/confirm",
+    tag = "auth",
+    responses((status = 200, body = RecoveryCodes))
+)]
+pub async fn confirm(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+    body: web::Json<TotpCode>,
+) -> FormVaultResult<HttpResponse> {
+    in_person(&auth)?;
+    let developer_id = auth.developer.id();
+    let credential = find_totp_credential(&**pool, developer_id)
+        .await?
+        .filter(|credential| !credential.is_enabled())
+        .ok_or_else(|| {
+            FormVaultError::ValidationFailed(vec![
+                "start enrolling with POST /developers/me/two_factor first".to_string(),
+            ])
+        })?;
+    let step = credential
+        .matching_step(&body.code, Utc::now())
+        .ok_or_else(|| {
+            FormVaultError::ValidationFailed(vec![
+                "the code does not match; check the authenticator's clock".to_string(),
+            ])
+        })?;
+
+    let (codes, recovery_codes) = RecoveryCode::generate_set(developer_id);
+    let mut tx = pool.begin().await?;
+    if !confirm_totp_credential(&mut *tx, developer_id, step).await? {
+        return Err(FormVaultError::ValidationFailed(vec![
+            "two-factor authentication is already enabled".to_string(),
+        ]));
+    }
+    replace_recovery_codes(&mut tx, developer_id, &codes).await?;
+    tx.commit().await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(auth.actor(), developer_id, AuditAction::TwoFactorEnabled)
+            .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodes { recovery_codes }))
+}
+
+/// Replace the recovery codes; needs a current code in `X-TOTP-Code`
+#[utoipa::path(
+    post,
+    path = "/developers/me/two_factor/recovery_codes",
+    tag = "auth",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 200, body = RecoveryCodes))
+)]
+pub async fn regenerate_recovery_codes(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let developer_id = auth.developer.id();
+    enabled(&pool, &auth).await?;
+    auth.require_second_factor(&req, &pool).await?;
+
+    let (codes, recovery_codes) = RecoveryCode::generate_set(developer_id);
+    let mut tx = pool.begin().await?;
+    replace_recovery_codes(&mut tx, developer_id, &codes).await?;
+    tx.commit().await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(
+            auth.actor(),
+            developer_id,
+            AuditAction::RecoveryCodesRegenerated,
+        )
+        .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(RecoveryCodes { recovery_codes }))
+}
+
+/// Turn two-factor authentication off; needs a current code in `X-TOTP-Code`
+#[utoipa::path(
+    delete,
+    path = "/developers/me/two_factor",
+    tag = "auth",
+    params(("X-TOTP-Code" = Option<String>, Header, description = "Current two-factor code or an unused recovery code; required once two-factor authentication is enabled")),
+    responses((status = 204))
+)]
+pub async fn disable(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    auth: AuthenticatedDeveloper,
+) -> FormVaultResult<HttpResponse> {
+    auth.require(ApiKeyScope::Admin)?;
+    let developer_id = auth.developer.id();
+    enabled(&pool, &auth).await?;
+    auth.require_second_factor(&req, &pool).await?;
+
+    let mut tx = pool.begin().await?;
+    delete_two_factor(&mut tx, developer_id).await?;
+    tx.commit().await?;
+
+    append_event(
+        &pool,
+        NewAuditEvent::new(auth.actor(), developer_id, AuditAction::TwoFactorDisabled)
+            .ip_address(client_ip(&req)),
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Fail with 403 unless the request comes from a dashboard session, so that
+/// a leaked API key can't put its own authenticator in front of the account
+fn in_person(auth: &AuthenticatedDeveloper) -> FormVaultResult<()> {
+    auth.session().map(|_| ()).ok_or_else(|| {
+        FormVaultError::Forbidden(
+            "two-factor authentication is set up from a dashboard session".to_string(),
+        )
+    })
+}
+
+/// Fail with 400 unless two-factor authentication is on
+async fn enabled(pool: &PgPool, auth: &AuthenticatedDeveloper) -> FormVaultResult<()> {
+    find_totp_credential(pool, auth.developer.id())
+        .await?
+        .filter(TotpCredential::is_enabled)
+        .map(|_| ())
+        .ok_or_else(|| {
+            FormVaultError::ValidationFailed(vec![
+                "two-factor authentication is not enabled".to_string(),
+            ])
+        })
+}
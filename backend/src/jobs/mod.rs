@@ -0,0 +1,117 @@
+//! Background jobs.
+//!
+//! Work that talks to downstream services (webhooks, email, exports) is
+//! written to the `jobs` table as a typed [`Job`] and executed by
+//! [`worker::Workers`], so request latency never depends on those services.
+//! Failed jobs are retried with exponential backoff until they run out of
+//! attempts and land in the dead-letter state.
+
+pub mod worker;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::repositories::jobs::insert_job;
+use crate::telemetry::current_request_id;
+
+pub use worker::{JobContext, WorkerConfig, WorkerHealth, Workers};
+
+/// Queue used when nothing else is configured
+pub const DEFAULT_QUEUE: &str = "default";
+
+/// Typed job payloads, stored as JSON in `jobs.payload`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Job {
+    /// Send one submission to one notification target
+    DeliverNotification {
+        target_id: Uuid,
+        submission_id: Uuid,
+    },
+}
+
+impl Job {
+    /// How many times the job runs before it is declared dead
+    pub fn max_attempts(&self) -> i32 {
+        match self {
+            Job::DeliverNotification { .. } => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Dead,
+}
+
+/// A row of the `jobs` table.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct JobRecord {
+    pub id: Uuid,
+    pub queue: String,
+    pub payload: serde_json::Value,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Request that enqueued the job, for correlating logs
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl JobRecord {
+    pub fn new(queue: &str, job: &Job) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            queue: queue.to_string(),
+            payload: serde_json::to_value(job).expect("Job payloads always serialize"),
+            status: JobStatus::Queued,
+            attempts: 0,
+            max_attempts: job.max_attempts(),
+            run_at: now,
+            locked_at: None,
+            last_error: None,
+            request_id: None,
+            created_at: now,
+            completed_at: None,
+        }
+    }
+
+    /// Decode the typed payload
+    pub fn job(&self) -> Result<Job, serde_json::Error> {
+        serde_json::from_value(self.payload.clone())
+    }
+
+    /// Whether another failure should send the job to the dead-letter state
+    pub fn is_last_attempt(&self) -> bool {
+        self.attempts >= self.max_attempts
+    }
+}
+
+/// Add `job` to `queue`, returning the new job ID.
+///
+/// Jobs enqueued while handling a request remember its ID.
+pub async fn enqueue<'e>(
+    executor: impl PgExecutor<'e>,
+    queue: &str,
+    job: &Job,
+) -> FormVaultResult<Uuid> {
+    let record = JobRecord {
+        request_id: current_request_id(),
+        ..JobRecord::new(queue, job)
+    };
+    insert_job(executor, &record).await?;
+    Ok(record.id)
+}
@@ -0,0 +1,308 @@
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use chrono::Utc;
+use log::{error, info, warn};
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info_span};
+
+use super::{DEFAULT_QUEUE, Job, JobRecord};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::notifications::Notifier;
+use crate::repositories::form::{find_form_by_id, find_submission_by_id};
+use crate::repositories::jobs::{bury_job, claim_next_job, complete_job, retry_job};
+use crate::repositories::notification::find_target_by_id;
+
+/// Tuning knobs for the worker pool.
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    /// Queue the workers consume
+    pub queue: String,
+    /// Number of concurrent workers; 0 disables background processing
+    pub concurrency: usize,
+    /// How long an idle worker waits before polling again
+    pub poll_interval: Duration,
+    /// Running jobs locked for longer than this are assumed abandoned
+    pub stale_after: Duration,
+    /// Delay before the first retry; doubles with every attempt
+    pub base_backoff: Duration,
+    /// Upper bound for the retry delay
+    pub max_backoff: Duration,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            queue: DEFAULT_QUEUE.to_string(),
+            concurrency: 4,
+            poll_interval: Duration::from_secs(1),
+            stale_after: Duration::from_secs(5 * 60),
+            base_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl WorkerConfig {
+    /// Defaults overridden by `JOB_WORKERS` and `JOB_POLL_INTERVAL_MS`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(workers) = env::var("JOB_WORKERS").ok().and_then(|v| v.parse().ok()) {
+            config.concurrency = workers;
+        }
+        if let Some(ms) = env::var("JOB_POLL_INTERVAL_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            config.poll_interval = Duration::from_millis(ms);
+        }
+        config
+    }
+
+    /// Delay before retrying a job that failed its `attempt`-th run
+    pub fn backoff(&self, attempt: i32) -> Duration {
+        let exponent = attempt.saturating_sub(1).clamp(0, 20) as u32;
+        self.base_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+/// Everything a job needs to run.
+#[derive(Clone)]
+pub struct JobContext {
+    pub pool: PgPool,
+    pub notifier: Notifier,
+}
+
+impl JobContext {
+    pub fn new(pool: PgPool, notifier: Notifier) -> Self {
+        Self { pool, notifier }
+    }
+
+    async fn execute(&self, job: &Job) -> FormVaultResult<()> {
+        match job {
+            Job::DeliverNotification {
+                target_id,
+                submission_id,
+            } => {
+                // The target may have been deleted since the job was queued
+                let Some(target) = find_target_by_id(&self.pool, *target_id).await? else {
+                    return Ok(());
+                };
+                let submission = find_submission_by_id(&self.pool, *submission_id)
+                    .await?
+                    .ok_or(FormVaultError::SubmissionNotFound)?;
+                let form = find_form_by_id(&self.pool, submission.form_schema_id)
+                    .await?
+                    .ok_or(FormVaultError::FormNotFound)?;
+
+                self.notifier
+                    .deliver(&self.pool, &target, &form, &submission)
+                    .await
+            }
+        }
+    }
+
+    /// Called once when `job` is declared dead
+    async fn on_dead(&self, job: &Job, error: &str) -> FormVaultResult<()> {
+        match job {
+            Job::DeliverNotification { submission_id, .. } => {
+                if let Some(mut submission) =
+                    find_submission_by_id(&self.pool, *submission_id).await?
+                {
+                    submission
+                        .mark_failed(&self.pool, error.to_string())
+                        .await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Claim and run a single job from the configured queue.
+///
+/// Returns `false` when the queue had nothing runnable.
+pub async fn run_once(ctx: &JobContext, config: &WorkerConfig) -> FormVaultResult<bool> {
+    let stale_before = Utc::now() - config.stale_after;
+    let Some(record) = claim_next_job(&ctx.pool, &config.queue, stale_before).await? else {
+        return Ok(false);
+    };
+
+    let span = info_span!(
+        "job",
+        job_id = %record.id,
+        queue = %record.queue,
+        attempt = record.attempts,
+        request_id = record.request_id.as_deref(),
+    );
+    run(ctx, config, &record).instrument(span).await?;
+
+    Ok(true)
+}
+
+async fn run(ctx: &JobContext, config: &WorkerConfig, record: &JobRecord) -> FormVaultResult<()> {
+    let job = match record.job() {
+        Ok(job) => job,
+        Err(e) => {
+            // Nothing will ever be able to run an unreadable payload
+            error!("Job {} has an invalid payload: {}", record.id, e);
+            return bury_job(&ctx.pool, record.id, &e.to_string()).await;
+        }
+    };
+
+    match ctx.execute(&job).await {
+        Ok(()) => complete_job(&ctx.pool, record.id).await,
+        Err(e) => handle_failure(ctx, config, record, &job, e).await,
+    }
+}
+
+async fn handle_failure(
+    ctx: &JobContext,
+    config: &WorkerConfig,
+    record: &JobRecord,
+    job: &Job,
+    error: FormVaultError,
+) -> FormVaultResult<()> {
+    let message = error.to_string();
+
+    if record.is_last_attempt() {
+        error!(
+            "Job {} is dead after {} attempts: {}",
+            record.id, record.attempts, message
+        );
+        bury_job(&ctx.pool, record.id, &message).await?;
+        ctx.on_dead(job, &message).await
+    } else {
+        let delay = config.backoff(record.attempts);
+        warn!(
+            "Job {} failed (attempt {}/{}), retrying in {:?}: {}",
+            record.id, record.attempts, record.max_attempts, delay, message
+        );
+        let run_at = Utc::now() + delay;
+        retry_job(&ctx.pool, record.id, &message, run_at).await
+    }
+}
+
+/// A pool of background workers polling one queue.
+pub struct Workers {
+    token: CancellationToken,
+    handles: Vec<JoinHandle<()>>,
+    health: WorkerHealth,
+}
+
+/// How many of a [`Workers`] pool are still running, for readiness checks.
+#[derive(Debug, Clone)]
+pub struct WorkerHealth {
+    pub queue: String,
+    /// Workers that were started
+    pub concurrency: usize,
+    alive: Arc<AtomicUsize>,
+}
+
+impl WorkerHealth {
+    /// Workers whose loop has not exited, by shutdown or by panicking
+    pub fn alive(&self) -> usize {
+        self.alive.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts a worker as alive until its task ends, however it ends
+struct AliveGuard(Arc<AtomicUsize>);
+
+impl Drop for AliveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Workers {
+    /// Start `config.concurrency` workers on the current Tokio runtime
+    pub fn spawn(config: WorkerConfig, ctx: JobContext) -> Self {
+        let token = CancellationToken::new();
+        let alive = Arc::new(AtomicUsize::new(config.concurrency));
+        let handles = (0..config.concurrency)
+            .map(|id| {
+                let token = token.clone();
+                let config = config.clone();
+                let ctx = ctx.clone();
+                let guard = AliveGuard(alive.clone());
+                tokio::spawn(async move {
+                    let _guard = guard;
+                    work(id, config, ctx, token).await
+                })
+            })
+            .collect();
+
+        info!(
+            "Started {} job workers on queue '{}'",
+            config.concurrency, config.queue
+        );
+        let health = WorkerHealth {
+            queue: config.queue,
+            concurrency: config.concurrency,
+            alive,
+        };
+        Self {
+            token,
+            handles,
+            health,
+        }
+    }
+
+    pub fn health(&self) -> WorkerHealth {
+        self.health.clone()
+    }
+
+    /// Stop claiming new jobs and wait for the ones in flight to finish
+    pub async fn shutdown(self) {
+        self.token.cancel();
+        for handle in self.handles {
+            if let Err(e) = handle.await {
+                error!("Job worker panicked: {}", e);
+            }
+        }
+        info!("Job workers stopped");
+    }
+
+    /// Like [`Workers::shutdown`], but gives up on workers still busy at
+    /// `deadline` and returns how many there were. Their jobs stay locked
+    /// and run again once the lock goes stale.
+    pub async fn shutdown_until(self, deadline: tokio::time::Instant) -> usize {
+        self.token.cancel();
+        let mut abandoned = 0;
+        for mut handle in self.handles {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Job worker panicked: {}", e),
+                Err(_) => {
+                    handle.abort();
+                    abandoned += 1;
+                }
+            }
+        }
+        info!("Job workers stopped");
+        abandoned
+    }
+}
+
+async fn work(id: usize, config: WorkerConfig, ctx: JobContext, token: CancellationToken) {
+    while !token.is_cancelled() {
+        match run_once(&ctx, &config).await {
+            // Keep draining while there is work
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => error!("Job worker {} failed to process a job: {}", id, e),
+        }
+
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = tokio::time::sleep(config.poll_interval) => {}
+        }
+    }
+}
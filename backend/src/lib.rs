@@ -5,16 +5,31 @@ This library exposes the main entry points for running the FormVault
 application. It provides:
 
 - [`run`] — starts the application using configuration from environment variables.
-- [`spawn_app`] — utility function for starting the server inside integration tests.
+- [`run_with`] — starts the application with explicit [`Settings`].
+- `testing::TestApp` — a test server on a database of its own, behind the
+  `testing` feature.
 
 ## Modules
 
 - `errors` — application error definitions
+- `auth` — API key authentication for handlers
+- `decrypt` — offline decryption of submissions with a private key
 - `handlers` — request handlers
+- `jobs` — Postgres-backed background job queue and workers
+- `mail` — verification and login link emails
+- `metrics` — Prometheus metrics served at `/metrics`
 - `models` — database and domain models
 - `repositories` — database repository logic
+- `notifications` — submission notification channels (webhooks, email, chat)
+- `openapi` — the OpenAPI document generated from the handlers
+- `pages` — hosted HTML pages shown after plain HTML form posts
 - `routes` — route configuration
-- `webhook` — webhook handling
+- `sdk` — the browser SDK served at `/sdk/v1/formvault.js`
+- `settings` — the configuration the server is started with
+- `shutdown` — coordinated shutdown draining submissions and jobs
+- `storage` — developer, form and submission repositories on PostgreSQL, SQLite or memory
+- `telemetry` — structured logging, request IDs and trace export
+- `testing` — hermetic test servers with a migrated database each (`testing` feature)
 
 ## Quick Start
 
@@ -29,19 +44,33 @@ async fn main() -> Result<(), std::io::Error> {
 ```
 */
 
-mod errors;
+mod auth;
+pub mod decrypt;
+pub mod errors;
 mod handlers;
-mod models;
+pub mod jobs;
+pub mod mail;
+pub mod metrics;
+pub mod models;
+pub mod notifications;
+pub mod openapi;
+pub mod pages;
 pub mod repositories;
 mod routes;
-mod webhook;
+pub mod sdk;
+pub mod settings;
+pub mod shutdown;
+pub mod storage;
+pub mod telemetry;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 use actix_web::dev::Server;
 use dotenv::dotenv;
 use log::{error, info};
 use models::formvault::FormVault;
+pub use settings::Settings;
 use sqlx::postgres::PgPoolOptions;
-use std::env;
 use std::net::{SocketAddr, TcpListener};
 
 /**
@@ -49,12 +78,9 @@ Starts the FormVault application.
 
 This function:
 1. Loads environment variables from .env (if present).
-2. Fetches the `DATABASE_URL` from the environment.
-3. Connects to the PostgreSQL database using [`sqlx`].
-4. Reads the `PORT` environment variable (default: 8080).
-5. Binds a TCP listener on the specified address.
-6. Initializes [`FormVault`] with the database pool and TCP listener.
-7. Starts the Actix-web server.
+2. Reads the [`Settings`] from the environment.
+3. Hands them to [`run_with`], which connects to the database, binds the
+   listener and starts the Actix-web server and the background job workers.
 
 # Returns
 
@@ -68,11 +94,25 @@ On failure, returns a [`std::io::Error`] describing the cause.
 
 - `DATABASE_URL` — PostgreSQL connection string (required).
 - `PORT` — TCP port to bind the server to (default: 0 for random available port).
+- `JOB_WORKERS` — number of background job workers (default: 4).
+- `JOB_POLL_INTERVAL_MS` — idle polling interval of the job workers (default: 1000).
+- `SHUTDOWN_TIMEOUT_SECS` — how long a shutdown may drain in-flight work (default: 30).
+- `SMTP_URL`, `SMTP_FROM` — outgoing mail server for email notifications and
+  account emails (optional).
+- `DASHBOARD_URL` — where verification and login links point (default: `http://localhost:5173`).
+- `METRICS_TOKEN` — bearer token required at `/metrics`, which is not served
+  without one.
+- `TRUSTED_PROXIES` — comma-separated addresses of the reverse proxies whose
+  `Forwarded`/`X-Forwarded-For` headers give the client's address.
+
+Logging is set up by the caller; see [`telemetry::TelemetryConfig::from_env`].
 
 # Errors
 
 This function will return an error if:
 - `DATABASE_URL` environment variable is missing ([`ErrorKind::NotFound`]).
+- `PORT` is not a port number ([`ErrorKind::InvalidInput`]).
+- `TRUSTED_PROXIES` is not a list of IP addresses ([`ErrorKind::InvalidInput`]).
 - Database connection fails ([`ErrorKind::Other`]).
 - Port binding fails ([`ErrorKind::Other`]).
 
@@ -89,53 +129,50 @@ async fn main() -> Result<(), std::io::Error> {
 ```
 
 [`ErrorKind::NotFound`]: std::io::ErrorKind::NotFound
+[`ErrorKind::InvalidInput`]: std::io::ErrorKind::InvalidInput
 [`ErrorKind::Other`]: std::io::ErrorKind::Other
-[`FormVault`]: models::formvault::FormVault
 */
 pub async fn run() -> Result<(SocketAddr, Server), std::io::Error> {
     // Load .env file if available
     dotenv().ok();
 
-    // Load database URL
-    info!("Getting DATABASE_URL...");
-    let database_url = env::var("DATABASE_URL").map_err(|e| {
-        error!("DATABASE_URL not set in current environment: {}", e);
-        std::io::Error::new(std::io::ErrorKind::NotFound, "DATABASE_URL missing")
+    info!("Reading settings from the environment...");
+    let settings = Settings::from_env().inspect_err(|e| {
+        error!("Invalid configuration: {}", e);
     })?;
 
+    run_with(settings).await
+}
+
+/// Starts FormVault with explicit [`Settings`], without looking at the
+/// environment.
+///
+/// Returns the bound address and the running server, and fails like
+/// [`run`] when the database cannot be reached or the port cannot be bound.
+pub async fn run_with(settings: Settings) -> Result<(SocketAddr, Server), std::io::Error> {
     // Initialize database pool
     let database_pool = PgPoolOptions::new()
-        .max_connections(5) // Consider making this configurable
-        .connect(&database_url)
+        .max_connections(settings.max_connections)
+        .connect(&settings.database_url)
         .await
         .map_err(|e| {
             error!("Failed to connect to database: {}", e);
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Database connection failed: {e}"),
-            )
+            std::io::Error::other(format!("Database connection failed: {e}"))
         })?;
 
     info!("Successfully connected to database");
 
-    // Read bind address - use 0 for random port if none specified
-    let port = env::var("PORT").unwrap_or_else(|_| "0".to_string());
-    let bind_addr = format!("0.0.0.0:{}", port);
-
     // Bind TCP listener
+    let bind_addr = settings.bind_address();
     let listener = TcpListener::bind(&bind_addr).map_err(|e| {
         error!("Could not bind listener to {}: {}", bind_addr, e);
-        std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Port binding failed: {e}"),
-        )
+        std::io::Error::other(format!("Port binding failed: {e}"))
     })?;
 
     let port_addr = listener.local_addr()?;
 
     // Initialize and start server
-    let formvault = FormVault::new(database_pool, listener);
-    let server = formvault.start()?;
+    let server = FormVault::from_settings(database_pool, listener, settings).start()?;
     info!(
         "Server successfully started on {} (actual port: {})",
         bind_addr,
@@ -144,48 +181,3 @@ pub async fn run() -> Result<(SocketAddr, Server), std::io::Error> {
 
     Ok((port_addr, server))
 }
-
-/// Spawns the FormVault server for use in integration tests.
-///
-/// This helper function:
-/// - Initializes the logger in test mode (quiet output).
-/// - Calls [`run`] to start the application.
-/// - Spawns the Actix server in a background Tokio task.
-/// - Returns the bound [`SocketAddr`] so tests can make HTTP requests.
-///
-/// # Returns
-///
-/// The [`SocketAddr`] that the test server is bound to.
-///
-/// # Panics
-///
-/// Panics if the application fails to start.
-///
-/// # Examples
-///
-/// ```rust,no_run
-/// #[tokio::test]
-/// async fn test_health_endpoint() {
-///     let addr = formvault::spawn_app().await;
-///     let url = format!("http://{}/health", addr);
-///     
-///     let response = reqwest::get(&url).await.unwrap();
-///     assert!(response.status().is_success());
-/// }
-/// ```
-pub async fn spawn_app() -> SocketAddr {
-    use env_logger::Env;
-
-    // Initialize logging for test context
-    let _ = env_logger::Builder::from_env(Env::default().default_filter_or("info"))
-        .is_test(true)
-        .try_init();
-
-    // Start application
-    let (addr, server) = run().await.expect("Failed to start app");
-
-    // Run server in background
-    tokio::spawn(server);
-
-    addr
-}
@@ -0,0 +1,166 @@
+//! Emails about the account itself: verification and login links.
+//!
+//! They go out through the SMTP server email notifications use. Links point
+//! into the dashboard, which posts the token back to the API; following a
+//! link never changes anything on its own, so mail scanners that prefetch
+//! links cannot use them up.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::users::developer::Developer;
+use crate::models::users::email_token::{LOGIN_TTL, VERIFY_EMAIL_TTL};
+use crate::notifications::SmtpSettings;
+use crate::notifications::email::send_mail;
+
+/// Dashboard links point to when `DASHBOARD_URL` is not set
+pub const DEFAULT_DASHBOARD_URL: &str = "http://localhost:5173";
+
+/// A plain-text email to one developer
+#[derive(Debug, Clone)]
+pub struct AccountEmail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl AccountEmail {
+    /// The token of the link in the body, if there is one
+    pub fn token(&self) -> Option<&str> {
+        let start = self.body.find("token=")? + "token=".len();
+        self.body[start..].split_whitespace().next()
+    }
+}
+
+/// Delivers account emails.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: &AccountEmail) -> FormVaultResult<()>;
+}
+
+/// Sends over SMTP, failing when no server is configured
+pub struct SmtpMailer {
+    smtp: Option<SmtpSettings>,
+}
+
+impl SmtpMailer {
+    pub fn new(smtp: Option<SmtpSettings>) -> Self {
+        Self { smtp }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: &AccountEmail) -> FormVaultResult<()> {
+        let smtp = self.smtp.as_ref().ok_or_else(|| {
+            FormVaultError::NotificationFailed("SMTP is not configured".to_string())
+        })?;
+        send_mail(smtp, &email.to, email.subject.clone(), email.body.clone()).await
+    }
+}
+
+/// Keeps every email instead of sending it, for tests
+#[derive(Default)]
+pub struct Outbox {
+    sent: Mutex<Vec<AccountEmail>>,
+}
+
+impl Outbox {
+    /// Everything sent so far, oldest first
+    pub fn sent(&self) -> Vec<AccountEmail> {
+        self.sent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// The most recent email to `to`
+    pub fn last_to(&self, to: &str) -> Option<AccountEmail> {
+        self.sent().into_iter().rev().find(|email| email.to == to)
+    }
+}
+
+#[async_trait]
+impl Mailer for Outbox {
+    async fn send(&self, email: &AccountEmail) -> FormVaultResult<()> {
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(email.clone());
+        Ok(())
+    }
+}
+
+/// Writes the account emails and hands them to a [`Mailer`].
+#[derive(Clone)]
+pub struct AccountMail {
+    mailer: Arc<dyn Mailer>,
+    dashboard_url: String,
+}
+
+impl AccountMail {
+    pub fn new(mailer: Arc<dyn Mailer>, dashboard_url: impl Into<String>) -> Self {
+        Self {
+            mailer,
+            dashboard_url: dashboard_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Deliver through `mailer` instead
+    pub fn with_mailer(mut self, mailer: Arc<dyn Mailer>) -> Self {
+        self.mailer = mailer;
+        self
+    }
+
+    /// Ask the developer to confirm their address with `token`
+    pub async fn send_verification(
+        &self,
+        developer: &Developer,
+        token: &str,
+    ) -> FormVaultResult<()> {
+        let body = format!(
+            "Hi {},\n\n\
+             Confirm this is your email address to let your forms accept submissions:\n\n\
+             {}/verify-email?token={}\n\n\
+             The link works once and expires in {} hours. If you did not sign up \
+             for FormVault, ignore this email.\n",
+            developer.name(),
+            self.dashboard_url,
+            token,
+            VERIFY_EMAIL_TTL.num_hours()
+        );
+        self.send(developer, "Verify your FormVault email address", body)
+            .await
+    }
+
+    /// Send a link that starts a dashboard session with `token`
+    pub async fn send_login_link(&self, developer: &Developer, token: &str) -> FormVaultResult<()> {
+        let body = format!(
+            "Hi {},\n\n\
+             Sign in to the FormVault dashboard:\n\n\
+             {}/login?token={}\n\n\
+             The link works once and expires in {} minutes. If you did not ask \
+             to sign in, ignore this email.\n",
+            developer.name(),
+            self.dashboard_url,
+            token,
+            LOGIN_TTL.num_minutes()
+        );
+        self.send(developer, "Your FormVault sign-in link", body)
+            .await
+    }
+
+    async fn send(
+        &self,
+        developer: &Developer,
+        subject: &str,
+        body: String,
+    ) -> FormVaultResult<()> {
+        self.mailer
+            .send(&AccountEmail {
+                to: developer.email().to_string(),
+                subject: subject.to_string(),
+                body,
+            })
+            .await
+    }
+}
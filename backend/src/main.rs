@@ -1,25 +1,175 @@
-use env_logger::Env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use formvault::decrypt::{self, EncryptedSubmission, PrivateKey};
+use formvault::errors::{FormVaultError, FormVaultResult};
+use formvault::telemetry::{self, TelemetryConfig};
 use log::error;
 
-mod errors;
-mod handlers;
-mod models;
-mod repositories;
-mod routes;
-mod webhook;
+/// Secure form backend. Starts the server when no command is given.
+#[derive(Parser)]
+#[command(name = "formvault", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP server and background workers
+    Serve,
+    /// Decrypt exported submissions offline with your private key
+    Decrypt(DecryptArgs),
+    /// Print the OpenAPI document of the HTTP API, as served at /openapi.json
+    Openapi,
+}
+
+#[derive(Args)]
+struct DecryptArgs {
+    /// PEM private key; repeat for exports spanning a key rotation
+    #[arg(short, long = "key", value_name = "FILE", required = true)]
+    keys: Vec<PathBuf>,
+
+    /// Export from `GET /forms/{id}/export`, or a single submission as JSON
+    /// (reads stdin when omitted or `-`)
+    #[arg(value_name = "INPUT", conflicts_with = "data")]
+    input: Option<PathBuf>,
+
+    /// A single envelope's `encrypted_data`, instead of INPUT
+    #[arg(long, value_name = "ENCRYPTED_DATA", requires = "encrypted_key")]
+    data: Option<String>,
+
+    /// The `encrypted_key` belonging to --data
+    #[arg(long, value_name = "ENCRYPTED_KEY", requires = "data")]
+    encrypted_key: Option<String>,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+
+    /// Write to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
 
 #[tokio::main]
-async fn main() {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+async fn main() -> ExitCode {
+    match Cli::parse().command {
+        None | Some(Command::Serve) => serve().await,
+        Some(Command::Decrypt(args)) => match decrypt(args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("formvault decrypt: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Some(Command::Openapi) => match formvault::openapi::spec().to_pretty_json() {
+            Ok(spec) => {
+                println!("{}", spec);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("formvault openapi: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+async fn serve() -> ExitCode {
+    let telemetry = match telemetry::init(&TelemetryConfig::from_env()) {
+        Ok(telemetry) => telemetry,
+        Err(e) => {
+            eprintln!("formvault: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
 
-    match formvault::run().await {
+    let code = match formvault::run().await {
         Ok((_addr, server)) => {
             if let Err(e) = server.await {
                 error!("Server failed while running: {}", e);
+                return ExitCode::FAILURE;
             }
+            ExitCode::SUCCESS
         }
         Err(e) => {
             error!("Server failed to start: {}", e);
+            ExitCode::FAILURE
+        }
+    };
+
+    telemetry.shutdown(Duration::from_secs(5));
+    code
+}
+
+fn read(path: &PathBuf) -> FormVaultResult<String> {
+    fs::read_to_string(path).map_err(|e| {
+        FormVaultError::DecryptionError(format!("cannot read {}: {}", path.display(), e))
+    })
+}
+
+fn decrypt(args: DecryptArgs) -> FormVaultResult<()> {
+    let keys = args
+        .keys
+        .iter()
+        .map(|path| PrivateKey::from_pem(&read(path)?))
+        .collect::<FormVaultResult<Vec<_>>>()?;
+
+    let submissions = match (args.data, args.encrypted_key) {
+        (Some(encrypted_data), Some(encrypted_key)) => vec![EncryptedSubmission {
+            id: None,
+            form_id: None,
+            key_id: None,
+            created_at: None,
+            encrypted_data,
+            encrypted_key,
+        }],
+        _ => {
+            let input = match args.input {
+                Some(path) if path.as_os_str() != "-" => read(&path)?,
+                _ => {
+                    let mut input = String::new();
+                    io::stdin().read_to_string(&mut input).map_err(|e| {
+                        FormVaultError::DecryptionError(format!("cannot read stdin: {}", e))
+                    })?;
+                    input
+                }
+            };
+            decrypt::parse_submissions(&input)?
+        }
+    };
+
+    let decrypted = submissions
+        .iter()
+        .map(|submission| submission.decrypt(&keys))
+        .collect::<FormVaultResult<Vec<_>>>()?;
+
+    let mut output = match args.format {
+        Format::Json => serde_json::to_string_pretty(&decrypted)
+            .map_err(|e| FormVaultError::DecryptionError(e.to_string()))?,
+        Format::Csv => decrypt::to_csv(&decrypted)?,
+    };
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+
+    match args.output {
+        Some(path) => fs::write(&path, output).map_err(|e| {
+            FormVaultError::DecryptionError(format!("cannot write {}: {}", path.display(), e))
+        }),
+        None => {
+            print!("{}", output);
+            Ok(())
         }
     }
 }
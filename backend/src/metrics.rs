@@ -0,0 +1,336 @@
+//! Prometheus metrics, served at `/metrics`.
+//!
+//! Counters and histograms are recorded where things happen (the HTTP
+//! middleware, ingestion, encryption, notification delivery) into one
+//! process-wide registry. Gauges describing shared state, the job queue
+//! and the database pool, are refreshed on every scrape instead.
+//!
+//! Label values are bounded: routes are the matched patterns rather than
+//! raw paths, and submissions to forms that do not exist are counted under
+//! `form_id="unknown"`.
+//!
+//! Form IDs are labels, so the metrics are only served to scrapers
+//! presenting the [`ScrapeToken`], and not at all without one.
+
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use actix_web::HttpRequest;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::errors::FormVaultError;
+use crate::models::forms::notification_target::ChannelKind;
+use crate::models::public_key::KeyAlgorithm;
+
+/// Content type of the text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// The bearer token scrapers must present at `/metrics`, from
+/// `METRICS_TOKEN`.
+///
+/// Only its hash is kept, and presented tokens are compared by hash so the
+/// comparison takes the same time however much of the token is right.
+#[derive(Clone, Default)]
+pub struct ScrapeToken(Option<[u8; 32]>);
+
+impl ScrapeToken {
+    pub fn new(token: Option<&str>) -> Self {
+        Self(token.map(|token| Sha256::digest(token).into()))
+    }
+
+    /// Whether metrics are served at all
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether `req` carries the token as `Authorization: Bearer <token>`
+    pub fn allows(&self, req: &HttpRequest) -> bool {
+        let presented = req
+            .headers()
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|token| <[u8; 32]>::from(Sha256::digest(token.trim())));
+        self.0.is_some() && presented == self.0
+    }
+}
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    submissions_accepted: IntCounterVec,
+    submissions_rejected: IntCounterVec,
+    encryption_duration: HistogramVec,
+    notification_attempts: IntCounterVec,
+    notification_duration: HistogramVec,
+    job_queue_depth: IntGaugeVec,
+    job_queue_lag: GaugeVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+}
+
+/// The process-wide metrics
+pub fn metrics() -> &'static Metrics {
+    static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+    &METRICS
+}
+
+/// Middleware counting and timing every request by its matched route
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let response = next.call(req).await?;
+
+    let route = response.request().match_pattern();
+    metrics().observe_request(
+        &method,
+        route.as_deref().unwrap_or("unmatched"),
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    Ok(response)
+}
+
+/// Register `metric` with `registry`, returning a handle to it
+fn register<M: Collector + Clone + 'static>(
+    registry: &Registry,
+    metric: prometheus::Result<M>,
+) -> M {
+    let metric = metric.expect("valid metric");
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric registered once");
+    metric
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let r = &registry;
+        // Wrapping a content key takes well under a millisecond
+        let encryption_buckets =
+            prometheus::exponential_buckets(0.0001, 2.0, 12).expect("valid buckets");
+
+        Self {
+            http_requests: register(
+                r,
+                IntCounterVec::new(
+                    Opts::new(
+                        "formvault_http_requests_total",
+                        "HTTP requests by route and status",
+                    ),
+                    &["method", "route", "status"],
+                ),
+            ),
+            http_duration: register(
+                r,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "formvault_http_request_duration_seconds",
+                        "Time to produce a response, by route",
+                    ),
+                    &["method", "route"],
+                ),
+            ),
+            submissions_accepted: register(
+                r,
+                IntCounterVec::new(
+                    Opts::new(
+                        "formvault_submissions_accepted_total",
+                        "Stored submissions by form and by who encrypted them",
+                    ),
+                    &["form_id", "encrypted_by"],
+                ),
+            ),
+            submissions_rejected: register(
+                r,
+                IntCounterVec::new(
+                    Opts::new(
+                        "formvault_submissions_rejected_total",
+                        "Refused submissions by form and reason",
+                    ),
+                    &["form_id", "reason"],
+                ),
+            ),
+            encryption_duration: register(
+                r,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "formvault_encryption_duration_seconds",
+                        "Time to encrypt a submission on the server",
+                    )
+                    .buckets(encryption_buckets),
+                    &["algorithm"],
+                ),
+            ),
+            notification_attempts: register(
+                r,
+                IntCounterVec::new(
+                    Opts::new(
+                        "formvault_notification_attempts_total",
+                        "Notification delivery attempts by channel and outcome",
+                    ),
+                    &["channel", "outcome"],
+                ),
+            ),
+            notification_duration: register(
+                r,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "formvault_notification_duration_seconds",
+                        "Time a notification delivery attempt took",
+                    ),
+                    &["channel"],
+                ),
+            ),
+            job_queue_depth: register(
+                r,
+                IntGaugeVec::new(
+                    Opts::new(
+                        "formvault_job_queue_depth",
+                        "Jobs that are due but not yet claimed",
+                    ),
+                    &["queue"],
+                ),
+            ),
+            job_queue_lag: register(
+                r,
+                GaugeVec::new(
+                    Opts::new(
+                        "formvault_job_queue_lag_seconds",
+                        "How long the oldest due job has been waiting",
+                    ),
+                    &["queue"],
+                ),
+            ),
+            pool_connections: register(
+                r,
+                IntGaugeVec::new(
+                    Opts::new(
+                        "formvault_db_pool_connections",
+                        "Open database connections by state",
+                    ),
+                    &["state"],
+                ),
+            ),
+            pool_max_connections: register(
+                r,
+                IntGauge::new(
+                    "formvault_db_pool_max_connections",
+                    "Connections the database pool may open",
+                ),
+            ),
+            registry,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// `client_encrypted` tells whether the submitter sent an envelope
+    pub fn submission_accepted(&self, form_id: Uuid, client_encrypted: bool) {
+        let encrypted_by = if client_encrypted { "client" } else { "server" };
+        self.submissions_accepted
+            .with_label_values(&[&form_id.to_string(), encrypted_by])
+            .inc();
+    }
+
+    pub fn submission_rejected(&self, form_id: Uuid, error: &FormVaultError) {
+        let form_id = match error {
+            FormVaultError::FormNotFound => "unknown".to_string(),
+            _ => form_id.to_string(),
+        };
+        self.submissions_rejected
+            .with_label_values(&[&form_id, rejection_reason(error)])
+            .inc();
+    }
+
+    pub fn observe_encryption(&self, algorithm: KeyAlgorithm, elapsed: Duration) {
+        self.encryption_duration
+            .with_label_values(&[algorithm.as_str()])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_notification(
+        &self,
+        channel: ChannelKind,
+        result: Result<(), &FormVaultError>,
+        elapsed: Duration,
+    ) {
+        let channel = channel.as_str();
+        let outcome = match result {
+            Ok(()) => "success",
+            Err(FormVaultError::WebhookTimeout) => "timeout",
+            Err(_) => "failure",
+        };
+        self.notification_attempts
+            .with_label_values(&[channel, outcome])
+            .inc();
+        self.notification_duration
+            .with_label_values(&[channel])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn set_job_queue(&self, queue: &str, depth: i64, lag: Duration) {
+        self.job_queue_depth.with_label_values(&[queue]).set(depth);
+        self.job_queue_lag
+            .with_label_values(&[queue])
+            .set(lag.as_secs_f64());
+    }
+
+    pub fn set_pool(&self, size: u32, idle: u32, max_connections: u32) {
+        let idle = idle.min(size);
+        self.pool_connections
+            .with_label_values(&["idle"])
+            .set(i64::from(idle));
+        self.pool_connections
+            .with_label_values(&["in_use"])
+            .set(i64::from(size - idle));
+        self.pool_max_connections.set(i64::from(max_connections));
+    }
+
+    /// Everything recorded so far, in the text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics encode as text");
+        String::from_utf8(buffer).expect("metrics are UTF-8")
+    }
+}
+
+/// Short, stable label for why a submission was refused
+fn rejection_reason(error: &FormVaultError) -> &'static str {
+    match error {
+        FormVaultError::FormNotFound => "form_not_found",
+        FormVaultError::ValidationFailed(_) | FormVaultError::InvalidEmail => "invalid",
+        FormVaultError::InvalidPublicKey => "no_public_key",
+        FormVaultError::KeyRevoked => "key_revoked",
+        FormVaultError::FormLimitExceeded | FormVaultError::SubmissionLimitExceeded => "limit",
+        FormVaultError::EncryptionError(_) => "encryption_failed",
+        FormVaultError::EmailNotVerified => "unverified",
+        FormVaultError::OriginNotAllowed(_) => "origin",
+        FormVaultError::EncryptionRequired => "plaintext",
+        _ => "internal_error",
+    }
+}
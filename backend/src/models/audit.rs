@@ -0,0 +1,276 @@
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// `prev_hash` of the very first event in the chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Something that happened to an account or its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditAction {
+    #[serde(rename = "developer.registered")]
+    DeveloperRegistered,
+    #[serde(rename = "account.deactivated")]
+    AccountDeactivated,
+    #[serde(rename = "developer.email_verified")]
+    EmailVerified,
+    #[serde(rename = "session.started")]
+    SessionStarted,
+    #[serde(rename = "session.revoked")]
+    SessionRevoked,
+    #[serde(rename = "two_factor.enabled")]
+    TwoFactorEnabled,
+    #[serde(rename = "two_factor.disabled")]
+    TwoFactorDisabled,
+    #[serde(rename = "two_factor.recovery_codes_regenerated")]
+    RecoveryCodesRegenerated,
+    #[serde(rename = "api_key.created")]
+    ApiKeyCreated,
+    #[serde(rename = "api_key.rotated")]
+    ApiKeyRotated,
+    #[serde(rename = "api_key.revoked")]
+    ApiKeyRevoked,
+    #[serde(rename = "public_key.rotated")]
+    PublicKeyRotated,
+    #[serde(rename = "form.created")]
+    FormCreated,
+    #[serde(rename = "form.updated")]
+    FormUpdated,
+    #[serde(rename = "form.key_changed")]
+    FormKeyChanged,
+    #[serde(rename = "form.field_added")]
+    FormFieldAdded,
+    #[serde(rename = "form.field_removed")]
+    FormFieldRemoved,
+    #[serde(rename = "submission.read")]
+    SubmissionRead,
+    #[serde(rename = "export.run")]
+    ExportRun,
+    #[serde(rename = "team.created")]
+    TeamCreated,
+    #[serde(rename = "team.member_added")]
+    TeamMemberAdded,
+    #[serde(rename = "team.member_updated")]
+    TeamMemberUpdated,
+    #[serde(rename = "team.member_removed")]
+    TeamMemberRemoved,
+    #[serde(rename = "notification_target.created")]
+    NotificationTargetCreated,
+    #[serde(rename = "notification_target.updated")]
+    NotificationTargetUpdated,
+    #[serde(rename = "notification_target.deleted")]
+    NotificationTargetDeleted,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::DeveloperRegistered => "developer.registered",
+            AuditAction::AccountDeactivated => "account.deactivated",
+            AuditAction::EmailVerified => "developer.email_verified",
+            AuditAction::SessionStarted => "session.started",
+            AuditAction::SessionRevoked => "session.revoked",
+            AuditAction::TwoFactorEnabled => "two_factor.enabled",
+            AuditAction::TwoFactorDisabled => "two_factor.disabled",
+            AuditAction::RecoveryCodesRegenerated => "two_factor.recovery_codes_regenerated",
+            AuditAction::ApiKeyCreated => "api_key.created",
+            AuditAction::ApiKeyRotated => "api_key.rotated",
+            AuditAction::ApiKeyRevoked => "api_key.revoked",
+            AuditAction::PublicKeyRotated => "public_key.rotated",
+            AuditAction::FormCreated => "form.created",
+            AuditAction::FormUpdated => "form.updated",
+            AuditAction::FormKeyChanged => "form.key_changed",
+            AuditAction::FormFieldAdded => "form.field_added",
+            AuditAction::FormFieldRemoved => "form.field_removed",
+            AuditAction::SubmissionRead => "submission.read",
+            AuditAction::ExportRun => "export.run",
+            AuditAction::TeamCreated => "team.created",
+            AuditAction::TeamMemberAdded => "team.member_added",
+            AuditAction::TeamMemberUpdated => "team.member_updated",
+            AuditAction::TeamMemberRemoved => "team.member_removed",
+            AuditAction::NotificationTargetCreated => "notification_target.created",
+            AuditAction::NotificationTargetUpdated => "notification_target.updated",
+            AuditAction::NotificationTargetDeleted => "notification_target.deleted",
+        }
+    }
+}
+
+/// Who performed an audited action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Actor {
+    /// A developer acting without an API key (e.g. signing up)
+    Developer(Uuid),
+    /// A request authenticated with the API key of this ID
+    ApiKey(Uuid),
+    /// A request from the dashboard session of this ID
+    Session(Uuid),
+    /// FormVault itself (background jobs, migrations)
+    System,
+}
+
+impl Actor {
+    fn kind(&self) -> &'static str {
+        match self {
+            Actor::Developer(_) => "developer",
+            Actor::ApiKey(_) => "api_key",
+            Actor::Session(_) => "session",
+            Actor::System => "system",
+        }
+    }
+
+    fn id(&self) -> Option<Uuid> {
+        match self {
+            Actor::Developer(id) | Actor::ApiKey(id) | Actor::Session(id) => Some(*id),
+            Actor::System => None,
+        }
+    }
+}
+
+/// One entry of the append-only audit log.
+///
+/// Every event stores the hash of its predecessor, so editing or removing
+/// an event breaks the chain from that point on.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct AuditEvent {
+    pub seq: i64,
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_type: String,
+    pub actor_id: Option<Uuid>,
+    pub developer_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub details: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// An audit event before it is chained and written.
+#[derive(Debug, Clone)]
+pub struct NewAuditEvent {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub actor: Actor,
+    pub developer_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_type: Option<&'static str>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub details: serde_json::Value,
+}
+
+impl NewAuditEvent {
+    pub fn new(actor: Actor, developer_id: Uuid, action: AuditAction) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            // Postgres keeps microseconds; hash exactly what gets stored
+            occurred_at: Utc::now().trunc_subsecs(6),
+            actor,
+            developer_id: Some(developer_id),
+            action,
+            target_type: None,
+            target_id: None,
+            ip_address: None,
+            details: serde_json::json!({}),
+        }
+    }
+
+    pub fn target(mut self, target_type: &'static str, target_id: Uuid) -> Self {
+        self.target_type = Some(target_type);
+        self.target_id = Some(target_id);
+        self
+    }
+
+    pub fn ip_address(mut self, ip_address: Option<String>) -> Self {
+        self.ip_address = ip_address;
+        self
+    }
+
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Link the event to its predecessor
+    pub fn chain(self, seq: i64, prev_hash: String) -> AuditEvent {
+        let mut event = AuditEvent {
+            seq,
+            id: self.id,
+            occurred_at: self.occurred_at,
+            actor_type: self.actor.kind().to_string(),
+            actor_id: self.actor.id(),
+            developer_id: self.developer_id,
+            action: self.action.as_str().to_string(),
+            target_type: self.target_type.map(str::to_string),
+            target_id: self.target_id,
+            ip_address: self.ip_address,
+            details: self.details,
+            prev_hash,
+            hash: String::new(),
+        };
+        event.hash = event.compute_hash();
+        event
+    }
+}
+
+impl AuditEvent {
+    /// SHA-256 over the previous hash and every stored field but `seq`
+    pub fn compute_hash(&self) -> String {
+        fn opt<T: ToString>(value: &Option<T>) -> String {
+            value.as_ref().map(T::to_string).unwrap_or_default()
+        }
+
+        let material = [
+            self.prev_hash.clone(),
+            self.id.to_string(),
+            self.occurred_at.timestamp_micros().to_string(),
+            self.actor_type.clone(),
+            opt(&self.actor_id),
+            opt(&self.developer_id),
+            self.action.clone(),
+            opt(&self.target_type),
+            opt(&self.target_id),
+            opt(&self.ip_address),
+            self.details.to_string(),
+        ]
+        .join("\n");
+
+        hex::encode(Sha256::digest(material.as_bytes()))
+    }
+}
+
+/// Where the audit chain stopped being consistent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ChainBreak {
+    pub seq: i64,
+    pub id: Uuid,
+    pub reason: &'static str,
+}
+
+/// Check that `events` (ordered by `seq`) form an unbroken chain starting
+/// from `prev_hash`, returning the hash of the last event.
+pub fn verify_chain(prev_hash: &str, events: &[AuditEvent]) -> Result<String, ChainBreak> {
+    let mut expected_prev = prev_hash.to_string();
+
+    for event in events {
+        let broken = |reason| ChainBreak {
+            seq: event.seq,
+            id: event.id,
+            reason,
+        };
+
+        if event.prev_hash != expected_prev {
+            return Err(broken("previous hash does not match"));
+        }
+        if event.compute_hash() != event.hash {
+            return Err(broken("event contents do not match its hash"));
+        }
+        expected_prev = event.hash.clone();
+    }
+
+    Ok(expected_prev)
+}
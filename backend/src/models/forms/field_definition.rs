@@ -1,18 +1,29 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+use crate::errors::{FormVaultError, FormVaultResult};
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct FieldDefinition {
     pub id: Uuid,
     pub form_id: Uuid,
     pub name: String,
     pub field_type: FieldType,
     pub required: bool,
+    /// Display order within the form, starting at 0
+    pub position: i32,
+    /// See [`ValidationRules`]
     pub validation_rules: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "form_field_type")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "form_field_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum FieldType {
     Text,
     Email,
@@ -23,3 +34,163 @@ pub enum FieldType {
     Checkbox,
     File,
 }
+
+impl FieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::Text => "text",
+            FieldType::Email => "email",
+            FieldType::Phone => "phone",
+            FieldType::Number => "number",
+            FieldType::Date => "date",
+            FieldType::Select => "select",
+            FieldType::Checkbox => "checkbox",
+            FieldType::File => "file",
+        }
+    }
+}
+
+/// The rules a field's `validation_rules` may contain; all are optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ValidationRules {
+    /// Regular expression the whole value must match
+    pub pattern: Option<String>,
+    /// Shown instead of the default error when the value is rejected
+    pub message: Option<String>,
+    /// Allowed values of a select field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+    pub max_length: Option<usize>,
+}
+
+impl FieldDefinition {
+    pub fn new(
+        form_id: Uuid,
+        name: String,
+        field_type: FieldType,
+        required: bool,
+        validation_rules: serde_json::Value,
+    ) -> FormVaultResult<Self> {
+        let field = Self {
+            id: Uuid::new_v4(),
+            form_id,
+            name,
+            field_type,
+            required,
+            position: 0,
+            validation_rules,
+        };
+
+        let mut errors = Vec::new();
+        if field.name.trim().is_empty() {
+            errors.push("name must not be empty".to_string());
+        }
+        match field.rules() {
+            Ok(rules) => {
+                if let Some(pattern) = &rules.pattern
+                    && Regex::new(pattern).is_err()
+                {
+                    errors.push(format!("pattern of {} is not a valid regex", field.name));
+                }
+                if field.field_type == FieldType::Select && rules.options.is_empty() {
+                    errors.push(format!("select field {} needs options", field.name));
+                }
+            }
+            Err(FormVaultError::ValidationFailed(mut rule_errors)) => {
+                errors.append(&mut rule_errors)
+            }
+            Err(e) => return Err(e),
+        }
+
+        if errors.is_empty() {
+            Ok(field)
+        } else {
+            Err(FormVaultError::ValidationFailed(errors))
+        }
+    }
+
+    pub fn rules(&self) -> FormVaultResult<ValidationRules> {
+        if self.validation_rules.is_null() {
+            return Ok(ValidationRules::default());
+        }
+        serde_json::from_value(self.validation_rules.clone()).map_err(|e| {
+            FormVaultError::ValidationFailed(vec![format!(
+                "validation rules of {}: {}",
+                self.name, e
+            )])
+        })
+    }
+
+    /// Check one submitted value, returning the reason it is rejected
+    pub fn check(&self, value: Option<&str>) -> Result<(), String> {
+        let value = value.map(str::trim).filter(|value| !value.is_empty());
+        let Some(value) = value else {
+            return if self.required {
+                Err(format!("{} is required", self.name))
+            } else {
+                Ok(())
+            };
+        };
+
+        let rules = self.rules().unwrap_or_default();
+        let reject = |default: String| Err(rules.message.clone().unwrap_or(default));
+
+        let valid_type = match self.field_type {
+            FieldType::Email => value.split_once('@').is_some_and(|(local, domain)| {
+                !local.is_empty() && domain.contains('.') && !value.contains(char::is_whitespace)
+            }),
+            FieldType::Phone => {
+                value.chars().filter(char::is_ascii_digit).count() >= 5
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+            }
+            FieldType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            FieldType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+            FieldType::Select => rules.options.iter().any(|option| option == value),
+            FieldType::Text | FieldType::Checkbox | FieldType::File => true,
+        };
+        if !valid_type {
+            return reject(format!(
+                "{} is not a valid {}",
+                self.name,
+                self.field_type.as_str()
+            ));
+        }
+
+        if rules
+            .max_length
+            .is_some_and(|max| value.chars().count() > max)
+        {
+            return reject(format!("{} is too long", self.name));
+        }
+        if let Some(pattern) = &rules.pattern
+            && !Regex::new(&format!("^(?:{})$", pattern)).is_ok_and(|re| re.is_match(value))
+        {
+            return reject(format!("{} has an invalid format", self.name));
+        }
+
+        Ok(())
+    }
+}
+
+/// Check plaintext submission data against a form's fields.
+///
+/// Fields that aren't defined are accepted as they are, so forms without
+/// field definitions take any data.
+pub fn validate_submission(
+    fields: &[FieldDefinition],
+    data: &HashMap<String, String>,
+) -> FormVaultResult<()> {
+    let errors: Vec<String> = fields
+        .iter()
+        .filter_map(|field| field.check(data.get(&field.name).map(String::as_str)).err())
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(FormVaultError::ValidationFailed(errors))
+    }
+}
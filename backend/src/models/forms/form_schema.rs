@@ -1,53 +1,382 @@
 use chrono::{DateTime, Utc};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+use super::origin::{MAX_ALLOWED_ORIGINS, normalize_pattern, origin_matches};
+use super::submission::EncryptedPayload;
+use super::submission::SubmissionStatus;
 use super::{FormSubmission, SubmissionMetadata};
-use crate::errors::FormVaultError;
-use crate::repositories::{encryption::encrypt_form_data, form::save_submission};
-use crate::webhook::send_webhook;
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::public_key::{KeyAlgorithm, PublicKeyVersion};
+use crate::models::users::team::{Permission, TeamRole};
+use crate::notifications::Notifier;
+use crate::repositories::encryption::{Envelope, encrypt_form_data};
+use crate::repositories::form::{
+    save_form, save_submission, update_form_key, update_submission_status,
+};
+use crate::repositories::public_keys::{
+    create_public_key, find_current_key, find_public_key_by_fingerprint, find_public_key_by_id,
+    next_key_version, retire_current_key,
+};
+use crate::repositories::teams::find_role;
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+/// Longest success or error URL a form can have
+pub const MAX_REDIRECT_URL_LENGTH: usize = 2048;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct FormSchema {
     pub id: Uuid,
     pub name: String,
     pub developer_id: Uuid,
-    pub public_key: String,
+    pub team_id: Uuid,
+    /// Key version the form is pinned to; `None` follows the creating
+    /// developer's current key, which is only allowed while they may manage
+    /// the team's keys
+    pub key_id: Option<Uuid>,
+    /// Origins submissions are accepted from, such as
+    /// `https://*.example.com`; empty accepts every origin
+    #[sqlx(json)]
+    pub allowed_origins: Vec<String>,
+    /// Where browsers go after a successful HTML form post; `None` shows
+    /// the hosted thank-you page
+    pub success_url: Option<String>,
+    /// Where browsers go after a failed HTML form post, with the error code
+    /// in `?error=`; `None` shows the hosted error page
+    pub error_url: Option<String>,
+    /// Refuse plaintext fields, so submissions can only arrive encrypted by
+    /// the submitter and the server never sees their content
+    pub zero_knowledge: bool,
     pub created_at: DateTime<Utc>,
-    pub webhook_url: Option<String>,
+}
+
+/// Where a form's effective key comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    /// The current key of the developer who created the form, while they
+    /// may manage the team's keys
+    Inherited,
+    /// A specific version of that developer's key
+    Pinned,
+    /// A key that belongs to this form only
+    Own,
+}
+
+/// The key submissions to a form are encrypted to, and why.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EffectiveKey {
+    pub source: KeySource,
+    #[serde(flatten)]
+    pub key: PublicKeyVersion,
+}
+
+/// A form's effective key as published to submitters, for encrypting in
+/// the browser or another service
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubmissionKey {
+    pub form_id: Uuid,
+    pub key_id: Uuid,
+    pub algorithm: KeyAlgorithm,
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+impl SubmissionKey {
+    pub fn new(form_id: Uuid, effective: &EffectiveKey) -> Self {
+        let key = &effective.key;
+        Self {
+            form_id,
+            key_id: key.id,
+            algorithm: key.algorithm,
+            public_key: key.public_key.clone(),
+            fingerprint: key.fingerprint.clone(),
+        }
+    }
 }
 
 impl FormSchema {
-    pub fn new(name: String, developer_id: Uuid, public_key: String) -> Self {
+    pub fn new(name: String, developer_id: Uuid, team_id: Uuid) -> Self {
         Self {
             id: Uuid::new_v4(),
             name,
             developer_id,
-            public_key,
+            team_id,
+            key_id: None,
+            allowed_origins: Vec::new(),
+            success_url: None,
+            error_url: None,
+            zero_knowledge: false,
             created_at: Utc::now(),
-            webhook_url: None,
         }
     }
 
+    /// Replace the origins the form accepts submissions from, normalizing
+    /// each pattern; see [`origin`](super::origin) for their syntax
+    pub fn set_allowed_origins(&mut self, patterns: Vec<String>) -> FormVaultResult<()> {
+        if patterns.len() > MAX_ALLOWED_ORIGINS {
+            return Err(FormVaultError::ValidationFailed(vec![format!(
+                "a form can have at most {} allowed origins",
+                MAX_ALLOWED_ORIGINS
+            )]));
+        }
+
+        let mut allowed_origins = Vec::with_capacity(patterns.len());
+        let mut errors = Vec::new();
+        for pattern in &patterns {
+            match normalize_pattern(pattern) {
+                Ok(pattern) if !allowed_origins.contains(&pattern) => allowed_origins.push(pattern),
+                Ok(_) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(FormVaultError::ValidationFailed(errors));
+        }
+
+        self.allowed_origins = allowed_origins;
+        Ok(())
+    }
+
+    /// Change where HTML form posts are redirected to. `None` keeps a URL
+    /// and an empty string removes it, so the hosted page is shown instead.
+    pub fn set_redirect_urls(
+        &mut self,
+        success_url: Option<String>,
+        error_url: Option<String>,
+    ) -> FormVaultResult<()> {
+        let mut errors = Vec::new();
+        let mut check = |name: &str, url: Option<String>, current: &Option<String>| match url {
+            None => current.clone(),
+            Some(url) if url.trim().is_empty() => None,
+            Some(url) => match redirect_url(&url) {
+                Ok(url) => Some(url),
+                Err(reason) => {
+                    errors.push(format!("{} {}", name, reason));
+                    None
+                }
+            },
+        };
+        let success_url = check("success_url", success_url, &self.success_url);
+        let error_url = check("error_url", error_url, &self.error_url);
+        if !errors.is_empty() {
+            return Err(FormVaultError::ValidationFailed(errors));
+        }
+
+        self.success_url = success_url;
+        self.error_url = error_url;
+        Ok(())
+    }
+
+    /// Whether a submission from `origin` is accepted. Requests without an
+    /// origin don't come from a browser page, which is all the check can
+    /// stop, so they are accepted too.
+    pub fn accepts_origin(&self, origin: Option<&str>) -> bool {
+        match origin {
+            _ if self.allowed_origins.is_empty() => true,
+            Some(origin) => self
+                .allowed_origins
+                .iter()
+                .any(|pattern| origin_matches(pattern, origin)),
+            None => true,
+        }
+    }
+
+    /// The key the form is set up to encrypt to.
+    ///
+    /// A form with a key of its own uses it; otherwise it uses the developer
+    /// key version it is pinned to, or failing that, the current key of the
+    /// developer who created it.
+    pub async fn effective_key(&self, pool: &PgPool) -> FormVaultResult<EffectiveKey> {
+        let key = match self.key_id {
+            Some(key_id) => find_public_key_by_id(pool, key_id).await?,
+            None => find_current_key(pool, self.developer_id).await?,
+        }
+        .ok_or(FormVaultError::InvalidPublicKey)?;
+
+        let source = match (self.key_id, key.form_id) {
+            (Some(_), Some(_)) => KeySource::Own,
+            (Some(_), None) => KeySource::Pinned,
+            (None, _) => KeySource::Inherited,
+        };
+        Ok(EffectiveKey { source, key })
+    }
+
+    /// The key new submissions are encrypted to: the
+    /// [`effective_key`](Self::effective_key), unless that is a developer key
+    /// of someone no longer in the form's team. Such forms take no
+    /// submissions until someone who may manage the team's keys gives them a
+    /// new one.
+    pub async fn submission_key(&self, pool: &PgPool) -> FormVaultResult<EffectiveKey> {
+        let key = self.effective_key(pool).await?;
+        if key.key.form_id.is_none()
+            && find_role(pool, self.team_id, key.key.developer_id)
+                .await?
+                .is_none()
+        {
+            return Err(FormVaultError::KeyRevoked);
+        }
+        Ok(key)
+    }
+
+    /// Save a new form, with its own key if `public_key` is given.
+    ///
+    /// Otherwise the form only follows its creator's key when `creator_role`
+    /// may manage the team's keys. Anyone else's form is pinned to the key
+    /// version they have now, so rotating their own key cannot re-key the
+    /// team's forms.
+    pub async fn create(
+        &mut self,
+        pool: &PgPool,
+        public_key: Option<String>,
+        creator_role: TeamRole,
+    ) -> FormVaultResult<()> {
+        let key = public_key
+            .map(|pem| PublicKeyVersion::new(self.developer_id, 1, pem))
+            .transpose()?
+            .map(|key| key.for_form(self.id));
+
+        // The form and its key reference each other, so the key is attached
+        // once both rows exist
+        let mut tx = pool.begin().await?;
+        save_form(&mut *tx, self).await?;
+        if let Some(key) = key {
+            create_public_key(&mut *tx, &key).await?;
+            self.key_id = Some(key.id);
+            update_form_key(&mut *tx, self).await?;
+        } else if !creator_role.can(Permission::ManageKeys) {
+            let key = find_current_key(&mut *tx, self.developer_id)
+                .await?
+                .ok_or(FormVaultError::InvalidPublicKey)?;
+            self.key_id = Some(key.id);
+            update_form_key(&mut *tx, self).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Switch the form to a new key of its own.
+    ///
+    /// The form's previous own key is retired, like a developer key on
+    /// rotation. Returns the new version and the ID of the retired one.
+    /// Runs in the transaction on `conn`, which the caller commits.
+    pub async fn rekey(
+        &mut self,
+        conn: &mut PgConnection,
+        public_key: String,
+    ) -> FormVaultResult<(PublicKeyVersion, Option<Uuid>)> {
+        let mut key = PublicKeyVersion::new(self.developer_id, 0, public_key)?.for_form(self.id);
+        if find_public_key_by_fingerprint(
+            &mut *conn,
+            self.developer_id,
+            Some(self.id),
+            &key.fingerprint,
+        )
+        .await?
+        .is_some()
+        {
+            return Err(FormVaultError::ValidationFailed(vec![
+                "this public key was already used for this form; generate a new key pair"
+                    .to_string(),
+            ]));
+        }
+
+        key.version = next_key_version(&mut *conn, self.developer_id, Some(self.id)).await?;
+        let retired = retire_current_key(&mut *conn, self.developer_id, Some(self.id)).await?;
+        create_public_key(&mut *conn, &key).await?;
+        self.key_id = Some(key.id);
+        update_form_key(&mut *conn, self).await?;
+
+        Ok((key, retired))
+    }
+
     pub async fn process_submission(
         &self,
+        pool: &PgPool,
+        notifier: &Notifier,
         raw_data: std::collections::HashMap<String, String>,
         metadata: SubmissionMetadata,
     ) -> Result<FormSubmission, FormVaultError> {
-        let (encrypted_data, encrypted_key) =
-            encrypt_form_data(&raw_data, &self.public_key).await?;
+        let EffectiveKey { key, .. } = self.submission_key(pool).await?;
+        let (encrypted_data, encrypted_key) = encrypt_form_data(&raw_data, &key.public_key).await?;
 
-        let mut submission = FormSubmission::new(self.id, encrypted_data, encrypted_key, metadata);
+        let submission = FormSubmission::new(self.id, encrypted_data, encrypted_key, metadata)
+            .encrypted_with(key.id);
+        self.store(pool, notifier, submission).await
+    }
+
+    /// Accept a submission the submitter already encrypted.
+    ///
+    /// The envelope must be well formed and wrapped for the form's current
+    /// effective key; the server cannot check anything inside it.
+    pub async fn accept_encrypted(
+        &self,
+        pool: &PgPool,
+        notifier: &Notifier,
+        payload: EncryptedPayload,
+        metadata: SubmissionMetadata,
+    ) -> FormVaultResult<FormSubmission> {
+        let EffectiveKey { key, .. } = self.submission_key(pool).await?;
+        if payload.key_id != key.id {
+            return Err(FormVaultError::ValidationFailed(vec![
+                "the form's key has changed; fetch it again and re-encrypt".to_string(),
+            ]));
+        }
+        let envelope = Envelope::decode(&payload.encrypted_data, &payload.encrypted_key)
+            .map_err(|e| FormVaultError::ValidationFailed(vec![e.to_string()]))?;
+        if envelope.algorithm != key.algorithm {
+            return Err(FormVaultError::ValidationFailed(vec![format!(
+                "the form's key is {}, not {}",
+                key.algorithm.as_str(),
+                envelope.algorithm.as_str()
+            )]));
+        }
 
-        save_submission(&submission).await?;
+        // Store the canonical encoding rather than whatever whitespace came in
+        let (encrypted_data, encrypted_key) = envelope.encode();
+        let submission = FormSubmission::new(self.id, encrypted_data, encrypted_key, metadata)
+            .encrypted_with(key.id);
+        self.store(pool, notifier, submission).await
+    }
 
-        if let Some(url) = &self.webhook_url {
-            match send_webhook(&submission, url).await {
-                Ok(_) => submission.mark_delivered().await?,
-                Err(e) => submission.mark_failed(e.to_string()).await?,
-            }
+    async fn store(
+        &self,
+        pool: &PgPool,
+        notifier: &Notifier,
+        mut submission: FormSubmission,
+    ) -> FormVaultResult<FormSubmission> {
+        // The submission and its deliveries are stored together, so a
+        // failed request leaves nothing behind for a retry to duplicate
+        let mut tx = pool.begin().await?;
+        save_submission(&mut *tx, &submission).await?;
+
+        // Deliveries run in the background; the submission stays in
+        // Processing until the job workers report back
+        if notifier.schedule(&mut tx, self, &submission).await? > 0 {
+            submission.status = SubmissionStatus::Processing;
+            update_submission_status(&mut *tx, &submission).await?;
         }
+        tx.commit().await?;
 
         Ok(submission)
     }
 }
+
+/// Check a redirect URL, or explain what is wrong with it
+fn redirect_url(url: &str) -> Result<String, String> {
+    if url.len() > MAX_REDIRECT_URL_LENGTH {
+        return Err(format!(
+            "must be at most {} characters",
+            MAX_REDIRECT_URL_LENGTH
+        ));
+    }
+    match Url::parse(url.trim()) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => {
+            Ok(parsed.to_string())
+        }
+        _ => Err("must be an absolute http or https URL".to_string()),
+    }
+}
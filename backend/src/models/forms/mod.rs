@@ -1,5 +1,8 @@
 pub mod field_definition;
 pub mod form_schema;
+pub mod notification_target;
+pub mod origin;
 pub mod submission;
 
+pub use notification_target::{ChannelKind, NotificationTarget};
 pub use submission::{FormSubmission, SubmissionMetadata};
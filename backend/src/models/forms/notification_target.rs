@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::submission::SubmissionStatus;
+
+/// A destination that gets notified about submissions to a form.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct NotificationTarget {
+    pub id: Uuid,
+    pub form_id: Uuid,
+    pub channel: ChannelKind,
+    /// Webhook URL for HTTP channels, email address for [`ChannelKind::Email`]
+    pub destination: String,
+    pub enabled: bool,
+    /// Submission statuses that trigger this target; empty means all
+    pub status_filter: Vec<SubmissionStatus>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "notification_channel", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelKind {
+    /// Generic JSON webhook carrying the full (encrypted) submission
+    Webhook,
+    /// Plain-text email sent over SMTP
+    Email,
+    /// Slack-compatible incoming webhook
+    Slack,
+    /// Discord-style webhook with an embed
+    Discord,
+}
+
+impl ChannelKind {
+    /// Name used in the database and in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelKind::Webhook => "webhook",
+            ChannelKind::Email => "email",
+            ChannelKind::Slack => "slack",
+            ChannelKind::Discord => "discord",
+        }
+    }
+}
+
+/// One submission delivered (or attempted) to one target.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NotificationDelivery {
+    pub id: Uuid,
+    pub target_id: Uuid,
+    pub submission_id: Uuid,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl NotificationTarget {
+    pub fn new(form_id: Uuid, channel: ChannelKind, destination: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            form_id,
+            channel,
+            destination,
+            enabled: true,
+            status_filter: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Only notify for submissions in one of `statuses`
+    pub fn with_status_filter(mut self, statuses: Vec<SubmissionStatus>) -> Self {
+        self.status_filter = statuses;
+        self
+    }
+
+    /// Whether a submission in `status` should be sent to this target
+    pub fn accepts(&self, status: SubmissionStatus) -> bool {
+        self.enabled && (self.status_filter.is_empty() || self.status_filter.contains(&status))
+    }
+}
+
+impl NotificationDelivery {
+    pub fn new(target_id: Uuid, submission_id: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            target_id,
+            submission_id,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Record the outcome of one delivery attempt
+    pub fn record_attempt(&mut self, result: Result<(), String>) {
+        self.attempts += 1;
+        match result {
+            Ok(()) => {
+                self.status = DeliveryStatus::Delivered;
+                self.last_error = None;
+                self.delivered_at = Some(Utc::now());
+            }
+            Err(e) => {
+                self.status = DeliveryStatus::Failed;
+                self.last_error = Some(e);
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Origins a form accepts submissions from.
+//!
+//! A pattern is a scheme, a host and an optional port, such as
+//! `https://example.com` or `http://localhost:3000`. The leftmost label of
+//! the host may be `*`, so `https://*.example.com` matches every subdomain
+//! of `example.com` (but not `example.com` itself). Origins are compared
+//! the way browsers send them in the `Origin` header: lowercase, without a
+//! path and without the port when it is the scheme's default.
+
+use reqwest::Url;
+
+/// Most patterns a form can have
+pub const MAX_ALLOWED_ORIGINS: usize = 50;
+
+/// The parts of an origin or pattern
+struct Parts<'a> {
+    scheme: &'a str,
+    host: &'a str,
+    port: Option<&'a str>,
+}
+
+fn split(origin: &str) -> Option<Parts<'_>> {
+    let (scheme, authority) = origin.split_once("://")?;
+    let (host, port) = match authority.rsplit_once(':') {
+        // IPv6 literals have colons of their own
+        Some((host, port)) if !port.contains(']') => (host, Some(port)),
+        _ => (authority, None),
+    };
+    Some(Parts { scheme, host, port })
+}
+
+/// Normalize an allowed-origin pattern, or explain what is wrong with it
+pub fn normalize_pattern(pattern: &str) -> Result<String, String> {
+    let normalized = pattern.trim().trim_end_matches('/').to_ascii_lowercase();
+    let invalid = |reason: &str| Err(format!("{:?} is not a valid origin: {}", pattern, reason));
+    if normalized == "*" {
+        return invalid("leave allowed_origins empty to accept every origin");
+    }
+
+    let Some(Parts { scheme, host, port }) = split(&normalized) else {
+        return invalid("expected scheme://host, such as https://example.com");
+    };
+    if scheme != "http" && scheme != "https" {
+        return invalid("the scheme must be http or https");
+    }
+    if let Some(port) = port
+        && !port.parse::<u16>().is_ok_and(|port| port != 0)
+    {
+        return invalid("the port must be a number from 1 to 65535");
+    }
+
+    let (wildcard, domain) = match host.strip_prefix("*.") {
+        Some(domain) => (true, domain),
+        None => (false, host),
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    let valid_label = |label: &&str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    };
+    if !labels.iter().all(valid_label) {
+        return invalid("it can't have a path, and only the leftmost label of the host can be *");
+    }
+    if wildcard && labels.len() < 2 {
+        return invalid("a wildcard needs a domain below it, such as *.example.com");
+    }
+
+    // Browsers leave the scheme's default port out of `Origin`
+    let default_port = if scheme == "https" { "443" } else { "80" };
+    Ok(match port {
+        Some(port) if port == default_port => format!("{}://{}", scheme, host),
+        _ => normalized,
+    })
+}
+
+/// Whether `origin`, as sent by a browser, matches the normalized `pattern`
+pub fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let origin = origin.to_ascii_lowercase();
+    let (Some(pattern), Some(origin)) = (split(pattern), split(&origin)) else {
+        return false;
+    };
+    if pattern.scheme != origin.scheme || pattern.port != origin.port {
+        return false;
+    }
+    match pattern.host.strip_prefix("*.") {
+        Some(domain) => origin
+            .host
+            .strip_suffix(domain)
+            .and_then(|subdomain| subdomain.strip_suffix('.'))
+            .is_some_and(|subdomain| !subdomain.is_empty()),
+        None => pattern.host == origin.host,
+    }
+}
+
+/// The origin of a page URL, as its `Referer` would be reduced to an
+/// `Origin`; `None` for URLs without one
+pub fn origin_of(url: &str) -> Option<String> {
+    let origin = Url::parse(url).ok()?.origin();
+    origin.is_tuple().then(|| origin.ascii_serialization())
+}
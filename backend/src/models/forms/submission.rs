@@ -1,21 +1,25 @@
 use crate::repositories::form::update_submission_status;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct FormSubmission {
     pub id: Uuid,
     pub form_schema_id: Uuid,
     pub encrypted_data: String,
     pub encrypted_key: String,
+    /// Public key version the submission was encrypted to
+    pub key_id: Option<Uuid>,
     pub metadata: SubmissionMetadata,
     pub created_at: DateTime<Utc>,
     pub status: SubmissionStatus,
     pub failure_reason: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "submission_status")]
 pub enum SubmissionStatus {
     New,
@@ -25,7 +29,40 @@ pub enum SubmissionStatus {
     Archived,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A submission encrypted by the submitter, so the server never sees the
+/// plaintext. The envelope is the one produced by
+/// [`crate::repositories::encryption`].
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EncryptedPayload {
+    pub encrypted_data: String,
+    pub encrypted_key: String,
+    /// The key the content key was wrapped for, from `GET /f/{form_id}/key`
+    pub key_id: Uuid,
+}
+
+/// What the ingestion endpoint tells the submitter about an accepted submission
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubmissionReceipt {
+    pub id: Uuid,
+    pub form_id: Uuid,
+    pub key_id: Option<Uuid>,
+    pub status: SubmissionStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&FormSubmission> for SubmissionReceipt {
+    fn from(submission: &FormSubmission) -> Self {
+        Self {
+            id: submission.id,
+            form_id: submission.form_schema_id,
+            key_id: submission.key_id,
+            status: submission.status,
+            created_at: submission.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SubmissionMetadata {
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
@@ -45,6 +82,7 @@ impl FormSubmission {
             form_schema_id,
             encrypted_data,
             encrypted_key,
+            key_id: None,
             metadata,
             created_at: Utc::now(),
             status: SubmissionStatus::New,
@@ -52,18 +90,28 @@ impl FormSubmission {
         }
     }
 
-    pub async fn mark_delivered(&mut self) -> Result<(), crate::errors::FormVaultError> {
+    /// Record the public key version the data was encrypted to
+    pub fn encrypted_with(mut self, key_id: Uuid) -> Self {
+        self.key_id = Some(key_id);
+        self
+    }
+
+    pub async fn mark_delivered(
+        &mut self,
+        pool: &PgPool,
+    ) -> Result<(), crate::errors::FormVaultError> {
         self.status = SubmissionStatus::Delivered;
         self.failure_reason = None;
-        update_submission_status(self).await
+        update_submission_status(pool, self).await
     }
 
     pub async fn mark_failed(
         &mut self,
+        pool: &PgPool,
         reason: String,
     ) -> Result<(), crate::errors::FormVaultError> {
         self.status = SubmissionStatus::Failed;
         self.failure_reason = Some(reason);
-        update_submission_status(self).await
+        update_submission_status(pool, self).await
     }
 }
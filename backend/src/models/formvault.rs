@@ -1,38 +1,180 @@
+use crate::jobs::{JobContext, WorkerConfig, Workers};
+use crate::mail::{self, AccountMail, Mailer, SmtpMailer};
+use crate::metrics::{self, ScrapeToken};
+use crate::notifications::Notifier;
 use crate::routes;
-use actix_web::{App, HttpServer, dev::Server, middleware::Logger};
+use crate::settings::{Settings, TrustedProxies};
+use crate::shutdown::{self, InFlight};
+use crate::storage::Storage;
+use crate::telemetry;
+use actix_web::middleware::from_fn;
+use actix_web::{App, HttpServer, dev::Server, web};
 use log::info;
 use sqlx::PgPool;
 use std::net::TcpListener;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub struct FormVault {
     database_pool: PgPool,
     listener: TcpListener,
+    notifier: Notifier,
+    account_mail: AccountMail,
+    scrape_token: ScrapeToken,
+    trusted_proxies: TrustedProxies,
+    workers: WorkerConfig,
+    shutdown_timeout: Duration,
+    shutdown_signal: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
 }
 impl FormVault {
-    pub fn new(pool: PgPool, listener: TcpListener) -> Self {
+    pub fn new(
+        pool: PgPool,
+        listener: TcpListener,
+        notifier: Notifier,
+        workers: WorkerConfig,
+    ) -> Self {
         Self {
             database_pool: pool,
             listener,
+            notifier,
+            account_mail: AccountMail::new(
+                Arc::new(SmtpMailer::new(None)),
+                mail::DEFAULT_DASHBOARD_URL,
+            ),
+            scrape_token: ScrapeToken::default(),
+            trusted_proxies: TrustedProxies::default(),
+            workers,
+            shutdown_timeout: shutdown::DEFAULT_TIMEOUT,
+            shutdown_signal: None,
         }
     }
 
+    /// A server configured by `settings`, on an already connected pool
+    pub fn from_settings(pool: PgPool, listener: TcpListener, settings: Settings) -> Self {
+        let account_mail = AccountMail::new(
+            Arc::new(SmtpMailer::new(settings.smtp.clone())),
+            settings.dashboard_url,
+        );
+        let mut server = Self::new(
+            pool,
+            listener,
+            Notifier::new(settings.smtp),
+            settings.workers,
+        )
+        .with_shutdown_timeout(settings.shutdown_timeout)
+        .with_metrics_token(settings.metrics_token.as_deref())
+        .with_trusted_proxies(settings.trusted_proxies);
+        server.account_mail = account_mail;
+        server
+    }
+
+    /// Deliver verification and login emails through `mailer`
+    pub fn with_mailer(mut self, mailer: Arc<dyn Mailer>) -> Self {
+        self.account_mail = self.account_mail.with_mailer(mailer);
+        self
+    }
+
+    /// Serve `/metrics` to scrapers presenting `token`, or to nobody
+    pub fn with_metrics_token(mut self, token: Option<&str>) -> Self {
+        self.scrape_token = ScrapeToken::new(token);
+        self
+    }
+
+    /// Believe the client addresses `proxies` forward
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
+    /// How long shutting down may take in total
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Shut down when `signal` resolves instead of on SIGINT/SIGTERM
+    pub fn with_shutdown_signal(
+        mut self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Self {
+        self.shutdown_signal = Some(Box::pin(signal));
+        self
+    }
+
+    /// Start the HTTP server and the background job workers.
+    ///
+    /// Must be called from within a Tokio runtime. On SIGINT/SIGTERM the
+    /// server shuts down as described in [`crate::shutdown`]; the returned
+    /// future resolves once it has.
     pub fn start(self) -> std::io::Result<Server> {
         // Remove async here
-        let pool = self.database_pool.clone();
+        let pool = web::Data::new(self.database_pool.clone());
+        let storage = web::Data::new(Storage::postgres(self.database_pool.clone()));
+        let notifier = web::Data::new(self.notifier.clone());
+        let account_mail = web::Data::new(self.account_mail);
+        let scrape_token = web::Data::new(self.scrape_token);
+        let trusted_proxies = web::Data::new(self.trusted_proxies);
         let addr = self.listener.local_addr().unwrap();
+
+        let workers = Workers::spawn(
+            self.workers,
+            JobContext::new(self.database_pool.clone(), self.notifier.clone()),
+        );
+
+        let worker_health = web::Data::new(workers.health());
+        let submissions = InFlight::default();
+        let in_flight = web::Data::new(submissions.clone());
+
         info!("Starting HTTP server on {}", addr);
         let server = HttpServer::new(move || {
             App::new()
-                // enable Actix built-in request logging
-                .wrap(Logger::default())
+                .wrap(from_fn(metrics::track_requests))
+                // outermost, so everything below runs with the request ID
+                .wrap(from_fn(telemetry::trace_requests))
                 // make DB pool available to handlers
                 .app_data(pool.clone())
+                .app_data(storage.clone())
+                .app_data(notifier.clone())
+                .app_data(account_mail.clone())
+                .app_data(scrape_token.clone())
+                .app_data(trusted_proxies.clone())
+                .app_data(worker_health.clone())
+                .app_data(in_flight.clone())
                 // configure routes
                 .configure(routes::configuration::health_check)
+                .configure(routes::configuration::probes)
+                .configure(routes::configuration::metrics)
                 .configure(routes::configuration::api_routes)
+                .configure(routes::configuration::openapi)
+                .configure(routes::developers::developers)
+                .configure(routes::auth::auth)
+                .configure(routes::two_factor::two_factor)
+                .configure(routes::api_keys::api_keys)
+                .configure(routes::forms::forms)
+                .configure(routes::fields::fields)
+                .configure(routes::ingest::ingest)
+                .configure(routes::sdk::sdk)
+                .configure(routes::notifications::notifications)
+                .configure(routes::teams::teams)
+                .configure(routes::audit::audit)
         })
+        // Signals are handled below so the shutdown can be coordinated
+        .disable_signals()
         .listen(self.listener)?
         .run();
+
+        let handle = server.handle();
+        let pool = self.database_pool;
+        let timeout = self.shutdown_timeout;
+        let signal = self
+            .shutdown_signal
+            .unwrap_or_else(|| Box::pin(shutdown::signal()));
+        tokio::spawn(async move {
+            signal.await;
+            shutdown::drain(handle, submissions, workers, pool, timeout).await;
+        });
+
         Ok(server)
     }
 }
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Answer of `/readyz`; served with 503 unless `status` is `ready`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Readiness {
+    pub status: ReadinessStatus,
+    pub timestamp: DateTime<Utc>,
+    pub database: DatabaseCheck,
+    pub pool: PoolStats,
+    pub jobs: JobsCheck,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadinessStatus {
+    Ready,
+    Unavailable,
+}
+
+/// Why the database is unhealthy is only logged, never served
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DatabaseCheck {
+    pub healthy: bool,
+    /// Round trip of the ping, including waiting for a connection
+    pub latency_ms: u64,
+    /// Newest migration recorded in `_sqlx_migrations`, if any
+    pub migration_version: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolStats {
+    /// Open connections, idle or in use
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+    /// `in_use / max_connections`, from 0 to 1
+    pub utilization: f64,
+}
+
+/// The background job queue this instance works on.
+///
+/// Lag is reported but does not make the instance unready: the queue is
+/// shared, so a backlog would take every instance out of rotation at once.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobsCheck {
+    /// False when workers were started and none of them is still running
+    pub healthy: bool,
+    pub queue: String,
+    pub workers: usize,
+    pub workers_alive: usize,
+    /// Jobs that are due but not claimed yet; `None` without a database
+    pub due: Option<i64>,
+    /// How long the oldest due job has been waiting
+    pub lag_seconds: Option<f64>,
+}
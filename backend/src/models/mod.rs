@@ -1,3 +1,6 @@
+pub mod audit;
 pub mod forms;
 pub mod formvault;
-mod users;
+pub mod health;
+pub mod public_key;
+pub mod users;
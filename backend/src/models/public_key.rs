@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use rsa::RsaPublicKey;
+use rsa::pkcs8::der::EncodePem;
+use rsa::pkcs8::der::asn1::BitStringRef;
+use rsa::pkcs8::der::pem::{self, LineEnding};
+use rsa::pkcs8::spki::{AlgorithmIdentifierRef, ObjectIdentifier, SubjectPublicKeyInfoRef};
+use rsa::traits::PublicKeyParts;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::errors::{FormVaultError, FormVaultResult};
+
+/// `rsaEncryption` from PKCS #1
+const RSA_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+/// `id-X25519` from RFC 8410
+const X25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.110");
+
+/// Smallest RSA modulus we accept, in bits
+pub const MIN_RSA_BITS: usize = 2048;
+
+/// How the per-submission content key is wrapped for the key owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "key_algorithm", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAlgorithm {
+    /// RSA-OAEP with SHA-256
+    Rsa,
+    /// Ephemeral-static X25519, sealed-box style
+    X25519,
+}
+
+impl KeyAlgorithm {
+    /// Name used in the database and in envelopes
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyAlgorithm::Rsa => "rsa",
+            KeyAlgorithm::X25519 => "x25519",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "rsa" => Some(KeyAlgorithm::Rsa),
+            "x25519" => Some(KeyAlgorithm::X25519),
+            _ => None,
+        }
+    }
+}
+
+/// One version of a developer's or a form's public key.
+///
+/// Rotating the key retires the current version instead of replacing it, so
+/// every submission can point at the exact key it was encrypted to.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct PublicKeyVersion {
+    pub id: Uuid,
+    pub developer_id: Uuid,
+    /// Set for keys owned by a single form; versions count per owner
+    pub form_id: Option<Uuid>,
+    /// 1 for the owner's first key, incremented by each rotation
+    pub version: i32,
+    pub algorithm: KeyAlgorithm,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub created_at: DateTime<Utc>,
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+impl PublicKeyVersion {
+    /// Validate `pem_key` and wrap it as version `version`
+    pub fn new(developer_id: Uuid, version: i32, pem_key: String) -> FormVaultResult<Self> {
+        let algorithm = PublicKey::from_pem(&pem_key)?.algorithm();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            developer_id,
+            form_id: None,
+            version,
+            algorithm,
+            fingerprint: fingerprint(&pem_key)?,
+            public_key: pem_key,
+            created_at: Utc::now(),
+            retired_at: None,
+        })
+    }
+
+    /// Make this a key of `form_id` rather than of the developer
+    pub fn for_form(mut self, form_id: Uuid) -> Self {
+        self.form_id = Some(form_id);
+        self
+    }
+
+    pub fn is_retired(&self) -> bool {
+        self.retired_at.is_some()
+    }
+}
+
+/// Lowercase hex SHA-256 of the DER encoded key, as printed by
+/// `openssl pkey -pubin -outform DER | sha256sum`
+pub fn fingerprint(pem_key: &str) -> FormVaultResult<String> {
+    let (_, der) =
+        pem::decode_vec(pem_key.trim().as_bytes()).map_err(|_| FormVaultError::InvalidPublicKey)?;
+    Ok(hex::encode(Sha256::digest(der)))
+}
+
+/// A validated public key that submissions can be encrypted to.
+#[derive(Debug, Clone)]
+pub enum PublicKey {
+    Rsa(RsaPublicKey),
+    X25519([u8; 32]),
+}
+
+impl PublicKey {
+    /// Parse a PEM encoded SubjectPublicKeyInfo (`-----BEGIN PUBLIC KEY-----`).
+    ///
+    /// Only RSA keys of at least [`MIN_RSA_BITS`] and X25519 keys are
+    /// accepted; anything else is [`FormVaultError::InvalidPublicKey`].
+    pub fn from_pem(pem_key: &str) -> FormVaultResult<Self> {
+        let (label, der) = pem::decode_vec(pem_key.trim().as_bytes())
+            .map_err(|_| FormVaultError::InvalidPublicKey)?;
+        if label != "PUBLIC KEY" {
+            return Err(FormVaultError::InvalidPublicKey);
+        }
+
+        let spki = SubjectPublicKeyInfoRef::try_from(der.as_slice())
+            .map_err(|_| FormVaultError::InvalidPublicKey)?;
+
+        match spki.algorithm.oid {
+            RSA_OID => {
+                let key =
+                    RsaPublicKey::try_from(spki).map_err(|_| FormVaultError::InvalidPublicKey)?;
+                if key.n().bits() < MIN_RSA_BITS {
+                    return Err(FormVaultError::InvalidPublicKey);
+                }
+                Ok(PublicKey::Rsa(key))
+            }
+            X25519_OID => {
+                let bytes = spki
+                    .subject_public_key
+                    .as_bytes()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .ok_or(FormVaultError::InvalidPublicKey)?;
+                Ok(PublicKey::X25519(bytes))
+            }
+            _ => Err(FormVaultError::InvalidPublicKey),
+        }
+    }
+
+    /// PEM for a raw X25519 public key, e.g. one generated client-side
+    pub fn x25519_pem(bytes: &[u8; 32]) -> String {
+        let spki = SubjectPublicKeyInfoRef {
+            algorithm: AlgorithmIdentifierRef {
+                oid: X25519_OID,
+                parameters: None,
+            },
+            subject_public_key: BitStringRef::from_bytes(bytes)
+                .expect("32 bytes always fit in a bit string"),
+        };
+        spki.to_pem(LineEnding::LF)
+            .expect("X25519 SubjectPublicKeyInfo always encodes")
+    }
+
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        match self {
+            PublicKey::Rsa(_) => KeyAlgorithm::Rsa,
+            PublicKey::X25519(_) => KeyAlgorithm::X25519,
+        }
+    }
+}
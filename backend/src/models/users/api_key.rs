@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Length of the visible part of a key (`fv_` plus eight characters)
+const PREFIX_LEN: usize = 11;
+
+/// What an API key is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "api_key_scope", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    /// List forms and read or export their submissions
+    ReadSubmissions,
+    /// Create and configure forms
+    ManageForms,
+    /// Everything, including account and key management
+    Admin,
+}
+
+/// A developer's API key.
+///
+/// Only a SHA-256 hash of the key is stored; the plaintext is returned once
+/// by [`ApiKey::generate`] and never persisted.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub developer_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// Create a new key, returning it together with its plaintext secret
+    pub fn generate(
+        developer_id: Uuid,
+        name: String,
+        scopes: Vec<ApiKeyScope>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> (Self, String) {
+        let secret = format!("fv_{}", Uuid::new_v4().simple());
+        let key = Self {
+            id: Uuid::new_v4(),
+            developer_id,
+            name,
+            prefix: secret[..PREFIX_LEN].to_string(),
+            key_hash: Self::hash(&secret),
+            scopes,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: Utc::now(),
+        };
+        (key, secret)
+    }
+
+    /// Hex SHA-256 of a plaintext key, as stored in `api_keys.key_hash`
+    pub fn hash(secret: &str) -> String {
+        hex::encode(Sha256::digest(secret.as_bytes()))
+    }
+
+    /// Whether the key grants `scope`; admin keys grant everything
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes
+            .iter()
+            .any(|s| *s == scope || *s == ApiKeyScope::Admin)
+    }
+
+    /// Whether the key can still be used at `now`
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires| expires > now)
+    }
+}
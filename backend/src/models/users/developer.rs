@@ -1,260 +1,206 @@
 use crate::errors::{FormVaultError, FormVaultResult};
-use crate::models::forms::form_schema::FormSchema;
+use crate::models::public_key::{KeyAlgorithm, PublicKey, PublicKeyVersion};
+use crate::models::users::api_key::ApiKey;
+use crate::models::users::session::Session;
+use crate::repositories::api_keys::{find_api_key_by_hash, touch_api_key};
+use crate::repositories::developers_repository::{
+    deactivate_developer, update_developer_public_key,
+};
+use crate::repositories::public_keys::{
+    create_public_key, find_public_key_by_fingerprint, next_key_version, retire_current_key,
+};
+use crate::repositories::sessions::{find_session_by_hash, touch_session};
+use crate::storage::Storage;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Developer {
-    name: String,
-    email: String,
-    public_key: String,
-    id: Uuid,
-    created_at: DateTime<Utc>,
-    api_key: String,
-    is_active: bool,
+    pub(crate) name: String,
+    pub(crate) email: String,
+    pub(crate) public_key: String,
+    pub(crate) public_key_algorithm: KeyAlgorithm,
+    pub(crate) id: Uuid,
+    pub(crate) created_at: DateTime<Utc>,
+    pub(crate) is_active: bool,
+    pub(crate) email_verified_at: Option<DateTime<Utc>>,
+}
+
+/// What a developer sees of their own account
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeveloperProfile {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub public_key: String,
+    pub public_key_algorithm: KeyAlgorithm,
+    pub is_active: bool,
+    /// Forms only accept submissions once the email is verified
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&Developer> for DeveloperProfile {
+    fn from(developer: &Developer) -> Self {
+        Self {
+            id: developer.id(),
+            name: developer.name().to_string(),
+            email: developer.email().to_string(),
+            public_key: developer.public_key().to_string(),
+            public_key_algorithm: developer.public_key_algorithm(),
+            is_active: developer.is_active(),
+            email_verified_at: developer.email_verified_at(),
+            created_at: developer.created_at(),
+        }
+    }
 }
 
 impl Developer {
     /// Create a new developer
-    pub fn new(name: String, email: String, public_key: String) -> Self {
+    pub fn new(
+        name: String,
+        email: String,
+        public_key: String,
+        public_key_algorithm: KeyAlgorithm,
+    ) -> Self {
         Developer {
             name,
             email,
             public_key,
+            public_key_algorithm,
             id: Uuid::new_v4(),
             created_at: Utc::now(),
-            api_key: Self::generate_api_key(),
             is_active: true,
+            email_verified_at: None,
         }
     }
 
-    /// Generate a secure API key for the developer
-    fn generate_api_key() -> String {
-        format!("fv_{}", Uuid::new_v4().simple())
-    }
-
-    /// Save developer to database
-    pub async fn save(&self, pool: &PgPool) -> FormVaultResult<()> {
-        sqlx::query!(
-            r#"
-            INSERT INTO developers (id, name, email, public_key, created_at, api_key, is_active)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            "#,
-            self.id,
-            self.name,
-            self.email,
-            self.public_key,
-            self.created_at,
-            self.api_key,
-            self.is_active
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    /// Find developer by ID
-    pub async fn find_by_id(id: Uuid, pool: &PgPool) -> FormVaultResult<Option<Self>> {
-        let developer = sqlx::query_as!(
-            Developer,
-            "SELECT id, name, email, public_key, created_at, api_key, is_active FROM developers WHERE id = $1",
-            id
-        )
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(developer)
-    }
-
-    /// Find developer by email
-    pub async fn find_by_email(email: &str, pool: &PgPool) -> FormVaultResult<Option<Self>> {
-        let developer = sqlx::query_as!(
-            Developer,
-            "SELECT id, name, email, public_key, created_at, api_key, is_active FROM developers WHERE email = $1",
-            email
-        )
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(developer)
-    }
-
-    /// Find developer by API key (for authentication)
-    pub async fn find_by_api_key(api_key: &str, pool: &PgPool) -> FormVaultResult<Option<Self>> {
-        let developer = sqlx::query_as!(
-            Developer,
-            "SELECT id, name, email, public_key, created_at, api_key, is_active FROM developers WHERE api_key = $1 AND is_active = true",
-            api_key
-        )
-        .fetch_optional(pool)
-        .await?;
-
-        Ok(developer)
-    }
-
-    /// Update developer information
-    pub async fn update(&mut self, pool: &PgPool) -> FormVaultResult<()> {
-        sqlx::query!(
-            r#"
-            UPDATE developers 
-            SET name = $1, email = $2, public_key = $3
-            WHERE id = $4
-            "#,
-            self.name,
-            self.email,
-            self.public_key,
-            self.id
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
-    }
-
-    /// Regenerate API key (in case of compromise)
-    pub async fn regenerate_api_key(&mut self, pool: &PgPool) -> FormVaultResult<String> {
-        let new_api_key = Self::generate_api_key();
-
-        sqlx::query!(
-            "UPDATE developers SET api_key = $1 WHERE id = $2",
-            new_api_key,
-            self.id
-        )
-        .execute(pool)
-        .await?;
-
-        self.api_key = new_api_key.clone();
-        Ok(new_api_key)
+    /// A new account and the first version of its public key, to be stored
+    /// together as part of a
+    /// [`Registration`](crate::models::users::registration::Registration)
+    ///
+    /// Fails with [`FormVaultError::InvalidEmail`] when `email` is not an
+    /// email address, and when `public_key` is not a supported public key.
+    pub fn register(
+        name: String,
+        email: String,
+        public_key: String,
+    ) -> FormVaultResult<(Self, PublicKeyVersion)> {
+        let email = validate_email(&email)?;
+        let algorithm = PublicKey::from_pem(&public_key)?.algorithm();
+        let developer = Self::new(name, email, public_key.clone(), algorithm);
+        let key = PublicKeyVersion::new(developer.id, 1, public_key)?;
+        Ok((developer, key))
     }
 
     /// Deactivate developer account
-    pub async fn deactivate(&mut self, pool: &PgPool) -> FormVaultResult<()> {
-        self.is_active = false;
-
-        sqlx::query!(
-            "UPDATE developers SET is_active = false WHERE id = $1",
-            self.id
-        )
-        .execute(pool)
-        .await?;
-
+    pub async fn deactivate<'e>(&mut self, executor: impl PgExecutor<'e>) -> FormVaultResult<()> {
+        deactivate_developer(executor, self.id).await?;
+        self.mark_inactive();
         Ok(())
     }
 
-    /// Get all forms belonging to this developer
-    pub async fn get_forms(&self, pool: &PgPool) -> FormVaultResult<Vec<FormSchema>> {
-        let forms = sqlx::query_as!(
-            FormSchema,
-            r#"
-            SELECT id, name, developer_id, public_key, created_at, webhook_url
-            FROM form_schemas 
-            WHERE developer_id = $1 
-            ORDER BY created_at DESC
-            "#,
-            self.id
-        )
-        .fetch_all(pool)
-        .await?;
-
-        Ok(forms)
-    }
-
-    /// Get submission count across all developer's forms
-    // pub async fn get_total_submissions(&self, pool: &PgPool) -> FormVaultResult<i64> {
-    //     let count = sqlx::query!(
-    //         r#"
-    //         SELECT COUNT(*) as count
-    //         FROM form_submissions fs
-    //         JOIN form_schemas sc ON fs.form_schema_id = sc.id
-    //         WHERE sc.developer_id = $1
-    //         "#,
-    //         self.id
-    //     )
-    //     .fetch_one(pool)
-    //     .await?
-    //     .count;
-
-    //     Ok(count)
-    // }
-
-    // /// Validate API key for requests
-    pub async fn authenticate(api_key: &str, pool: &PgPool) -> FormVaultResult<Self> {
-        match Self::find_by_api_key(api_key, pool).await? {
-            Some(developer) => Ok(developer),
-            None => Err(FormVaultError::Unauthorized),
-        }
-    }
-
-    /// Update public key (when developer rotates keys)
+    /// Validate API key for requests
+    ///
+    /// Returns the developer together with the key that was presented.
+    /// Unknown, revoked and expired keys, and keys of deactivated accounts,
+    /// are all rejected the same way.
+    pub async fn authenticate(
+        api_key: &str,
+        pool: &PgPool,
+        storage: &Storage,
+    ) -> FormVaultResult<(Self, ApiKey)> {
+        let key = find_api_key_by_hash(pool, &ApiKey::hash(api_key))
+            .await?
+            .filter(|key| key.is_usable(Utc::now()))
+            .ok_or(FormVaultError::InvalidApiKey)?;
+
+        let developer = storage
+            .developers()
+            .find_by_id(key.developer_id)
+            .await?
+            .filter(|developer| developer.is_active)
+            .ok_or(FormVaultError::InvalidApiKey)?;
+
+        touch_api_key(pool, key.id).await?;
+        Ok((developer, key))
+    }
+
+    /// Validate a session token from the dashboard's cookie
+    ///
+    /// Returns the developer together with their session. Unknown, revoked
+    /// and expired sessions, and sessions of deactivated accounts, are all
+    /// rejected the same way.
+    pub async fn authenticate_session(
+        token: &str,
+        pool: &PgPool,
+        storage: &Storage,
+    ) -> FormVaultResult<(Self, Session)> {
+        let session = find_session_by_hash(pool, &Session::hash(token))
+            .await?
+            .filter(|session| session.is_usable(Utc::now()))
+            .ok_or(FormVaultError::Unauthorized)?;
+
+        let developer = storage
+            .developers()
+            .find_by_id(session.developer_id)
+            .await?
+            .filter(|developer| developer.is_active)
+            .ok_or(FormVaultError::Unauthorized)?;
+
+        touch_session(pool, session.id).await?;
+        Ok((developer, session))
+    }
+
+    /// Rotate to a new public key.
+    ///
+    /// The current key version is retired rather than replaced, so
+    /// submissions encrypted to it can still be traced back to it. Returns
+    /// the new version and the ID of the retired one. Runs in the
+    /// transaction on `conn`, which the caller commits.
     pub async fn update_public_key(
         &mut self,
         new_public_key: String,
-        pool: &PgPool,
-    ) -> FormVaultResult<()> {
-        // TODO: Validate the public key format
-        if !Self::is_valid_public_key(&new_public_key) {
-            return Err(FormVaultError::InvalidPublicKey);
+        conn: &mut PgConnection,
+    ) -> FormVaultResult<(PublicKeyVersion, Option<Uuid>)> {
+        let mut key = PublicKeyVersion::new(self.id, 0, new_public_key)?;
+        if find_public_key_by_fingerprint(&mut *conn, self.id, None, &key.fingerprint)
+            .await?
+            .is_some()
+        {
+            return Err(FormVaultError::ValidationFailed(vec![
+                "this public key was already used; generate a new key pair".to_string(),
+            ]));
         }
 
-        self.public_key = new_public_key;
+        key.version = next_key_version(&mut *conn, self.id, None).await?;
+        let retired = retire_current_key(&mut *conn, self.id, None).await?;
+        create_public_key(&mut *conn, &key).await?;
+        update_developer_public_key(&mut *conn, self.id, &key).await?;
 
-        sqlx::query!(
-            "UPDATE developers SET public_key = $1 WHERE id = $2",
-            self.public_key,
-            self.id
-        )
-        .execute(pool)
-        .await?;
-
-        Ok(())
+        self.public_key = key.public_key.clone();
+        self.public_key_algorithm = key.algorithm;
+        Ok((key, retired))
     }
 
-    /// Validate public key format (placeholder - implement actual validation)
-    fn is_valid_public_key(key: &str) -> bool {
-        // TODO: Implement proper RSA public key validation
-        !key.is_empty() && key.len() > 100
+    /// Change the name and email; persisted by
+    /// [`update_developer`](crate::repositories::developers_repository::update_developer)
+    pub fn set_contact(&mut self, name: String, email: String) {
+        self.name = name;
+        self.email = email;
     }
 
-    /// Check if email is already taken
-    pub async fn email_exists(email: &str, pool: &PgPool) -> FormVaultResult<bool> {
-        let record = sqlx::query!(
-            "SELECT COUNT(*) as count FROM developers WHERE email = $1",
-            email
-        )
-        .fetch_one(pool)
-        .await?;
-
-        if let Some(count) = record.count {
-            Ok(count > 0)
-        } else {
-            Ok(false)
-        }
+    pub(crate) fn mark_inactive(&mut self) {
+        self.is_active = false;
     }
 
-    /// Create developer with email uniqueness check
-    pub async fn create_unique(
-        name: String,
-        email: String,
-        public_key: String,
-        pool: &PgPool,
-    ) -> FormVaultResult<Self> {
-        // Check if email already exists
-        if Self::email_exists(&email, pool).await? {
-            return Err(FormVaultError::DuplicateEmail);
-        }
-
-        // Validate public key
-        if !Self::is_valid_public_key(&public_key) {
-            return Err(FormVaultError::InvalidPublicKey);
-        }
-
-        let developer = Self::new(name, email, public_key);
-        developer.save(pool).await?;
-
-        Ok(developer)
+    /// Record that the developer proved they own their email; persisted by
+    /// [`mark_email_verified`](crate::repositories::developers_repository::mark_email_verified)
+    pub(crate) fn mark_verified(&mut self, at: DateTime<Utc>) {
+        self.email_verified_at.get_or_insert(at);
     }
 
     // Getters for private fields
@@ -274,8 +220,8 @@ impl Developer {
         &self.public_key
     }
 
-    pub fn api_key(&self) -> &str {
-        &self.api_key
+    pub fn public_key_algorithm(&self) -> KeyAlgorithm {
+        self.public_key_algorithm
     }
 
     pub fn is_active(&self) -> bool {
@@ -285,4 +231,24 @@ impl Developer {
     pub fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
+
+    pub fn email_verified_at(&self) -> Option<DateTime<Utc>> {
+        self.email_verified_at
+    }
+
+    /// Whether the email is verified, which forms need to go live
+    pub fn is_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+}
+
+/// The address in `email` without surrounding whitespace and in lowercase,
+/// as it is stored and looked up, or [`FormVaultError::InvalidEmail`] when
+/// it is not an email address
+pub fn validate_email(email: &str) -> FormVaultResult<String> {
+    let email = email.trim().to_lowercase();
+    email
+        .parse::<lettre::Address>()
+        .map_err(|_| FormVaultError::InvalidEmail)?;
+    Ok(email)
 }
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::users::api_key::ApiKey;
+
+/// How long a verification link can be used
+pub const VERIFY_EMAIL_TTL: Duration = Duration::hours(24);
+
+/// How long a login link can be used
+pub const LOGIN_TTL: Duration = Duration::minutes(15);
+
+/// What an emailed link lets its holder do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "email_token_purpose", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EmailTokenPurpose {
+    /// Prove ownership of the account's email
+    VerifyEmail,
+    /// Start a dashboard session
+    Login,
+}
+
+impl EmailTokenPurpose {
+    fn ttl(self) -> Duration {
+        match self {
+            EmailTokenPurpose::VerifyEmail => VERIFY_EMAIL_TTL,
+            EmailTokenPurpose::Login => LOGIN_TTL,
+        }
+    }
+}
+
+/// A single-use token mailed to a developer.
+///
+/// Like API keys, only a SHA-256 hash is stored; the plaintext only ever
+/// appears in the email.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EmailToken {
+    pub id: Uuid,
+    pub developer_id: Uuid,
+    pub purpose: EmailTokenPurpose,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EmailToken {
+    /// Create a new token, returning it together with its plaintext
+    pub fn generate(developer_id: Uuid, purpose: EmailTokenPurpose) -> (Self, String) {
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let now = Utc::now();
+        let token = Self {
+            id: Uuid::new_v4(),
+            developer_id,
+            purpose,
+            token_hash: Self::hash(&secret),
+            expires_at: now + purpose.ttl(),
+            used_at: None,
+            created_at: now,
+        };
+        (token, secret)
+    }
+
+    /// Hex SHA-256 of a plaintext token, as stored in `email_tokens.token_hash`
+    pub fn hash(secret: &str) -> String {
+        ApiKey::hash(secret)
+    }
+}
@@ -1 +1,7 @@
-mod developer;
+pub mod api_key;
+pub mod developer;
+pub mod email_token;
+pub mod registration;
+pub mod session;
+pub mod team;
+pub mod two_factor;
@@ -0,0 +1,57 @@
+use crate::errors::FormVaultResult;
+use crate::models::audit::{Actor, AuditAction, NewAuditEvent};
+use crate::models::public_key::PublicKeyVersion;
+use crate::models::users::api_key::{ApiKey, ApiKeyScope};
+use crate::models::users::developer::Developer;
+use crate::models::users::team::Team;
+
+/// Everything signing up creates, stored all together or not at all by
+/// [`DeveloperRepository::register`](crate::storage::DeveloperRepository::register).
+#[derive(Debug, Clone)]
+pub struct Registration {
+    pub developer: Developer,
+    /// First version of the developer's public key
+    pub key: PublicKeyVersion,
+    /// Personal team new forms go to, owned by the developer
+    pub team: Team,
+    /// First API key; it can do everything, and narrower keys are created
+    /// with it
+    pub api_key: ApiKey,
+    pub event: NewAuditEvent,
+}
+
+impl Registration {
+    /// A new account, returned with the plaintext of its first API key.
+    ///
+    /// Fails like [`Developer::register`].
+    pub fn new(
+        name: String,
+        email: String,
+        public_key: String,
+        ip_address: Option<String>,
+    ) -> FormVaultResult<(Self, String)> {
+        let (developer, key) = Developer::register(name, email, public_key)?;
+        let team = Team::new(format!("{}'s team", developer.name()));
+        let (api_key, plaintext) = ApiKey::generate(
+            developer.id(),
+            "default".to_string(),
+            vec![ApiKeyScope::Admin],
+            None,
+        );
+        let event = NewAuditEvent::new(
+            Actor::Developer(developer.id()),
+            developer.id(),
+            AuditAction::DeveloperRegistered,
+        )
+        .ip_address(ip_address);
+
+        let registration = Self {
+            developer,
+            key,
+            team,
+            api_key,
+            event,
+        };
+        Ok((registration, plaintext))
+    }
+}
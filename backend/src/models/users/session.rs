@@ -0,0 +1,92 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::users::api_key::ApiKey;
+
+/// How long a dashboard session lasts
+pub const SESSION_TTL: Duration = Duration::hours(8);
+
+/// A dashboard session, started by following a login link.
+///
+/// The browser holds the session token in an HTTP-only cookie and echoes
+/// the CSRF token in a header on every mutating request. Only SHA-256
+/// hashes of both are stored.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Session {
+    pub id: Uuid,
+    pub developer_id: Uuid,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    #[serde(skip_serializing)]
+    pub csrf_token_hash: String,
+    /// Where the session was started from
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The plaintext secrets of a new session, never stored
+pub struct SessionTokens {
+    /// Goes into the session cookie
+    pub token: String,
+    /// Goes into the CSRF cookie, for the dashboard to echo
+    pub csrf_token: String,
+}
+
+/// Returned once, when the session starts; the session token itself is
+/// only set as a cookie
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewSession {
+    pub id: Uuid,
+    /// To send as `X-CSRF-Token` with every mutating request
+    pub csrf_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Session {
+    /// Start a new session, returning it together with its plaintext tokens
+    pub fn generate(
+        developer_id: Uuid,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> (Self, SessionTokens) {
+        let tokens = SessionTokens {
+            token: format!("fvs_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            csrf_token: Uuid::new_v4().simple().to_string(),
+        };
+        let now = Utc::now();
+        let session = Self {
+            id: Uuid::new_v4(),
+            developer_id,
+            token_hash: Self::hash(&tokens.token),
+            csrf_token_hash: Self::hash(&tokens.csrf_token),
+            ip_address,
+            user_agent,
+            expires_at: now + SESSION_TTL,
+            last_seen_at: None,
+            revoked_at: None,
+            created_at: now,
+        };
+        (session, tokens)
+    }
+
+    /// Hex SHA-256 of a plaintext token, as stored in `sessions`
+    pub fn hash(secret: &str) -> String {
+        ApiKey::hash(secret)
+    }
+
+    /// Whether the session can still be used at `now`
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+
+    /// Whether `csrf_token` is the one issued with this session
+    pub fn accepts_csrf_token(&self, csrf_token: &str) -> bool {
+        Self::hash(csrf_token) == self.csrf_token_hash
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A group of developers sharing forms.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Team {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A developer's membership in a team.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct TeamMember {
+    pub team_id: Uuid,
+    pub developer_id: Uuid,
+    pub role: TeamRole,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Roles ordered from least to most privileged.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "team_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TeamRole {
+    Viewer,
+    Editor,
+    Admin,
+    Owner,
+}
+
+/// Actions on a team's forms and membership.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// See forms and read or export their submissions
+    ReadSubmissions,
+    /// Create forms and change their notification targets
+    ManageForms,
+    /// Change the encryption keys of the team's forms
+    ManageKeys,
+    /// Invite, remove and change the role of members
+    ManageMembers,
+}
+
+impl Permission {
+    /// Least privileged role holding this permission
+    pub fn min_role(&self) -> TeamRole {
+        match self {
+            Permission::ReadSubmissions => TeamRole::Viewer,
+            Permission::ManageForms => TeamRole::Editor,
+            Permission::ManageKeys | Permission::ManageMembers => TeamRole::Admin,
+        }
+    }
+}
+
+impl TeamRole {
+    pub fn can(&self, permission: Permission) -> bool {
+        *self >= permission.min_role()
+    }
+}
+
+impl Team {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl TeamMember {
+    pub fn new(team_id: Uuid, developer_id: Uuid, role: TeamRole) -> Self {
+        Self {
+            team_id,
+            developer_id,
+            role,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::users::api_key::ApiKey;
+use crate::repositories::two_factor::{
+    consume_recovery_code, find_totp_credential, record_failed_attempt, record_totp_step,
+    reset_failed_attempts,
+};
+
+/// Name authenticator apps list the account under
+pub const TOTP_ISSUER: &str = "FormVault";
+
+/// How many recovery codes are handed out at a time
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Wrong codes in a row before codes are refused for a while
+pub const MAX_FAILED_ATTEMPTS: i32 = 5;
+
+/// How long codes are refused after too many wrong ones
+pub const LOCKOUT: Duration = Duration::minutes(15);
+
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+
+/// The authenticator app of a developer.
+///
+/// A credential is created unconfirmed when enrollment starts, and
+/// two-factor authentication is only enforced once a code from the app has
+/// confirmed it.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TotpCredential {
+    pub developer_id: Uuid,
+    /// Base32 shared secret
+    pub secret: String,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// Time step of the last accepted code
+    pub last_used_step: Option<i64>,
+    pub failed_attempts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Everything an authenticator app needs; returned once, when enrollment starts
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpEnrollment {
+    /// Base32 secret, for apps the URI can't be handed to
+    pub secret: String,
+    /// `otpauth://` URI to show as a QR code
+    pub provisioning_uri: String,
+}
+
+/// Whether two-factor authentication is on for the account
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub enabled_at: Option<DateTime<Utc>>,
+    /// Recovery codes that have not been used yet
+    pub recovery_codes_left: i64,
+}
+
+/// Returned once, when the codes are generated; only hashes are kept
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+impl TotpCredential {
+    /// Start enrolling an authenticator with a new random secret
+    pub fn generate(developer_id: Uuid) -> Self {
+        Self {
+            developer_id,
+            secret: Secret::generate_secret().to_encoded().to_string(),
+            confirmed_at: None,
+            last_used_step: None,
+            failed_attempts: 0,
+            locked_until: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether two-factor authentication is enforced with this credential
+    pub fn is_enabled(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+
+    fn totp(&self, account: &str) -> FormVaultResult<TOTP> {
+        let secret = Secret::Encoded(self.secret.clone())
+            .to_bytes()
+            .map_err(|e| FormVaultError::EncryptionError(format!("bad TOTP secret: {:?}", e)))?;
+        // Unchecked, as `new` refuses account names with a colon
+        Ok(TOTP::new_unchecked(
+            Algorithm::SHA1,
+            DIGITS,
+            1,
+            STEP_SECONDS,
+            secret,
+            Some(TOTP_ISSUER.to_string()),
+            account.to_string(),
+        ))
+    }
+
+    /// The `otpauth://` URI for the developer's authenticator app
+    pub fn provisioning_uri(&self, email: &str) -> FormVaultResult<String> {
+        Ok(self.totp(email)?.get_url())
+    }
+
+    /// Time step `code` is valid for at `now`, allowing one step of clock
+    /// drift either way. Steps up to the last accepted one are refused, so
+    /// a code seen by someone else can't be replayed.
+    pub fn matching_step(&self, code: &str, now: DateTime<Utc>) -> Option<i64> {
+        let code = code.trim();
+        if code.len() != DIGITS || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let totp = self.totp("").ok()?;
+        let current = now.timestamp() / STEP_SECONDS as i64;
+        (current - 1..=current + 1)
+            .filter(|step| self.last_used_step.is_none_or(|last| *step > last))
+            .find(|step| totp.generate(*step as u64 * STEP_SECONDS) == code)
+    }
+
+    /// Whether too many wrong codes were tried recently
+    pub fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+/// A single-use stand-in for the authenticator.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RecoveryCode {
+    pub id: Uuid,
+    pub developer_id: Uuid,
+    pub code_hash: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecoveryCode {
+    /// A fresh set of codes, returned together with their plaintexts
+    pub fn generate_set(developer_id: Uuid) -> (Vec<Self>, Vec<String>) {
+        (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                // 64 random bits, in groups of four to be easy to type
+                let mut bytes = [0u8; 8];
+                OsRng.fill_bytes(&mut bytes);
+                let hex = hex::encode(bytes);
+                let code = [&hex[0..4], &hex[4..8], &hex[8..12], &hex[12..16]].join("-");
+                let stored = Self {
+                    id: Uuid::new_v4(),
+                    developer_id,
+                    code_hash: Self::hash(&code),
+                    used_at: None,
+                    created_at: Utc::now(),
+                };
+                (stored, code)
+            })
+            .unzip()
+    }
+
+    /// Hex SHA-256 of a code, ignoring case, dashes and spaces
+    pub fn hash(code: &str) -> String {
+        let normalized: String = code
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        ApiKey::hash(&normalized)
+    }
+}
+
+/// Check the second factor of `developer_id`, if they enabled one.
+///
+/// `code` is either the current code from the authenticator or an unused
+/// recovery code, which is then used up. Accounts without two-factor
+/// authentication pass whatever the code. A missing or wrong code fails
+/// with [`FormVaultError::TwoFactorRequired`]; after
+/// [`MAX_FAILED_ATTEMPTS`] wrong ones in a row, every code is refused for
+/// [`LOCKOUT`] with [`FormVaultError::TooManyAttempts`].
+pub async fn verify_second_factor(
+    pool: &PgPool,
+    developer_id: Uuid,
+    code: Option<&str>,
+) -> FormVaultResult<()> {
+    let Some(credential) = find_totp_credential(pool, developer_id)
+        .await?
+        .filter(TotpCredential::is_enabled)
+    else {
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    if credential.is_locked(now) {
+        return Err(FormVaultError::TooManyAttempts);
+    }
+    let code = code
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .ok_or(FormVaultError::TwoFactorRequired)?;
+
+    if let Some(step) = credential.matching_step(code, now)
+        && record_totp_step(pool, developer_id, step).await?
+    {
+        return Ok(());
+    }
+    if consume_recovery_code(pool, developer_id, &RecoveryCode::hash(code)).await? {
+        reset_failed_attempts(pool, developer_id).await?;
+        return Ok(());
+    }
+
+    record_failed_attempt(pool, developer_id, MAX_FAILED_ATTEMPTS, now + LOCKOUT).await?;
+    Err(FormVaultError::TwoFactorRequired)
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{NotificationChannel, post_json};
+use crate::errors::FormVaultResult;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::forms::submission::FormSubmission;
+
+/// Discord-style webhook with a single embed.
+pub struct DiscordChannel {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl DiscordChannel {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for DiscordChannel {
+    async fn send(&self, form: &FormSchema, submission: &FormSubmission) -> FormVaultResult<()> {
+        let payload = json!({
+            "username": "FormVault",
+            "embeds": [{
+                "title": format!("New submission for {}", form.name),
+                "fields": [
+                    { "name": "Form", "value": form.id.to_string(), "inline": true },
+                    { "name": "Submission", "value": submission.id.to_string(), "inline": true },
+                ],
+                "timestamp": submission.created_at.to_rfc3339(),
+            }],
+        });
+        post_json(&self.client, &self.url, &payload).await
+    }
+}
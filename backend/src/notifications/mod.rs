@@ -0,0 +1,220 @@
+//! Submission notifications.
+//!
+//! Every form can fan out to any number of [`NotificationTarget`]s. Each
+//! target is turned into a [`NotificationChannel`] that knows how to talk to
+//! its destination; new kinds of destinations only need a new channel.
+
+pub mod discord;
+pub mod email;
+pub mod slack;
+pub mod webhook;
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use log::warn;
+use sqlx::{PgConnection, PgPool};
+use tracing::field::Empty;
+use tracing::{Span, instrument};
+
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::jobs::{self, Job};
+use crate::metrics::metrics;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::forms::notification_target::{
+    ChannelKind, NotificationDelivery, NotificationTarget,
+};
+use crate::models::forms::submission::FormSubmission;
+use crate::repositories::form::find_submission_by_id;
+use crate::repositories::notification::{
+    count_undelivered, create_delivery, find_delivery, find_targets_by_form, update_delivery,
+};
+
+pub use discord::DiscordChannel;
+pub use email::{EmailChannel, SmtpSettings};
+pub use slack::SlackChannel;
+pub use webhook::WebhookChannel;
+
+/// Timeout applied to every outgoing HTTP notification
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A destination that can be told about a form submission.
+///
+/// Channels never see plaintext: they only get the form and the encrypted
+/// submission, and most of them only forward metadata.
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Deliver one notification about `submission` to `form`
+    async fn send(&self, form: &FormSchema, submission: &FormSubmission) -> FormVaultResult<()>;
+}
+
+/// Builds channels for targets and records their deliveries.
+#[derive(Clone)]
+pub struct Notifier {
+    client: reqwest::Client,
+    smtp: Option<SmtpSettings>,
+    queue: String,
+}
+
+impl Notifier {
+    pub fn new(smtp: Option<SmtpSettings>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .user_agent(concat!("FormVault/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self {
+            client,
+            smtp,
+            queue: jobs::DEFAULT_QUEUE.to_string(),
+        }
+    }
+
+    /// Schedule deliveries on `queue` instead of the default one
+    pub fn with_queue(mut self, queue: impl Into<String>) -> Self {
+        self.queue = queue.into();
+        self
+    }
+
+    /// Notifier configured from the `SMTP_*` environment variables
+    pub fn from_env() -> Self {
+        Self::new(SmtpSettings::from_env())
+    }
+
+    /// The channel responsible for delivering to `target`
+    pub fn channel_for(&self, target: &NotificationTarget) -> Box<dyn NotificationChannel> {
+        let url = target.destination.clone();
+        match target.channel {
+            ChannelKind::Webhook => Box::new(WebhookChannel::new(self.client.clone(), url)),
+            ChannelKind::Slack => Box::new(SlackChannel::new(self.client.clone(), url)),
+            ChannelKind::Discord => Box::new(DiscordChannel::new(self.client.clone(), url)),
+            ChannelKind::Email => Box::new(EmailChannel::new(self.smtp.clone(), url)),
+        }
+    }
+
+    /// Schedule `submission` for every enabled target of `form` whose
+    /// status filter matches.
+    ///
+    /// Each target gets a pending delivery and a [`Job::DeliverNotification`],
+    /// written in the transaction on `conn` so they are committed together
+    /// with the submission. Returns the number of deliveries scheduled.
+    pub async fn schedule(
+        &self,
+        conn: &mut PgConnection,
+        form: &FormSchema,
+        submission: &FormSubmission,
+    ) -> FormVaultResult<usize> {
+        let targets = find_targets_by_form(&mut *conn, form.id).await?;
+        let mut scheduled = 0;
+
+        for target in targets.iter().filter(|t| t.accepts(submission.status)) {
+            let delivery = NotificationDelivery::new(target.id, submission.id);
+            create_delivery(&mut *conn, &delivery).await?;
+
+            let job = Job::DeliverNotification {
+                target_id: target.id,
+                submission_id: submission.id,
+            };
+            jobs::enqueue(&mut *conn, &self.queue, &job).await?;
+            scheduled += 1;
+        }
+
+        Ok(scheduled)
+    }
+
+    /// Send `submission` to `target` right now and record the attempt.
+    ///
+    /// Once every delivery of the submission succeeded it is marked
+    /// delivered. Errors are returned so the job can be retried.
+    #[instrument(
+        skip_all,
+        fields(
+            channel = target.channel.as_str(),
+            target_id = %target.id,
+            submission_id = %submission.id,
+        )
+    )]
+    pub async fn deliver(
+        &self,
+        pool: &PgPool,
+        target: &NotificationTarget,
+        form: &FormSchema,
+        submission: &FormSubmission,
+    ) -> FormVaultResult<()> {
+        let mut delivery = find_delivery(pool, target.id, submission.id)
+            .await?
+            .unwrap_or_else(|| NotificationDelivery::new(target.id, submission.id));
+
+        let result = if target.enabled {
+            let started = Instant::now();
+            let result = self.channel_for(target).send(form, submission).await;
+            metrics().observe_notification(
+                target.channel,
+                result.as_ref().copied(),
+                started.elapsed(),
+            );
+            result
+        } else {
+            // Disabled after scheduling: nothing to send, nothing to retry
+            Ok(())
+        };
+
+        if let Err(e) = &result {
+            warn!(
+                "Notification {:?} for submission {} failed: {}",
+                target.channel, submission.id, e
+            );
+        }
+
+        delivery.record_attempt(result.as_ref().map_err(|e| e.to_string()).copied());
+        update_delivery(pool, &delivery).await?;
+        result?;
+
+        if count_undelivered(pool, submission.id).await? == 0 {
+            let mut submission = find_submission_by_id(pool, submission.id)
+                .await?
+                .ok_or(FormVaultError::SubmissionNotFound)?;
+            submission.mark_delivered(pool).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One-line human readable summary shared by the chat and email channels
+pub(crate) fn summary(form: &FormSchema, submission: &FormSubmission) -> String {
+    format!(
+        "New submission {} for form \"{}\" at {}",
+        submission.id,
+        form.name,
+        submission.created_at.to_rfc3339()
+    )
+}
+
+/// POST `body` as JSON and turn non-2xx responses and timeouts into errors
+#[instrument(name = "webhook", skip(client, body), fields(status = Empty))]
+pub(crate) async fn post_json<T: serde::Serialize + ?Sized>(
+    client: &reqwest::Client,
+    url: &str,
+    body: &T,
+) -> FormVaultResult<()> {
+    let response = client.post(url).json(body).send().await.map_err(|e| {
+        if e.is_timeout() {
+            FormVaultError::WebhookTimeout
+        } else {
+            FormVaultError::WebhookFailed(e.to_string())
+        }
+    })?;
+
+    let status = response.status();
+    Span::current().record("status", status.as_u16());
+    if !status.is_success() {
+        return Err(FormVaultError::WebhookFailed(format!(
+            "{} responded with {}",
+            url, status
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{NotificationChannel, post_json, summary};
+use crate::errors::FormVaultResult;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::forms::submission::FormSubmission;
+
+/// Slack-compatible incoming webhook (`{"text": ...}`).
+pub struct SlackChannel {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl SlackChannel {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SlackChannel {
+    async fn send(&self, form: &FormSchema, submission: &FormSubmission) -> FormVaultResult<()> {
+        let payload = json!({ "text": summary(form, submission) });
+        post_json(&self.client, &self.url, &payload).await
+    }
+}
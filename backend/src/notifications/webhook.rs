@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+use serde::Serialize;
+
+use super::{NotificationChannel, post_json};
+use crate::errors::FormVaultResult;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::forms::submission::FormSubmission;
+
+/// Generic webhook: POSTs the full encrypted submission as JSON.
+pub struct WebhookChannel {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    event: &'static str,
+    form_id: uuid::Uuid,
+    form_name: &'a str,
+    submission: &'a FormSubmission,
+}
+
+impl WebhookChannel {
+    pub fn new(client: reqwest::Client, url: String) -> Self {
+        Self { client, url }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    async fn send(&self, form: &FormSchema, submission: &FormSubmission) -> FormVaultResult<()> {
+        let payload = WebhookPayload {
+            event: "submission.created",
+            form_id: form.id,
+            form_name: &form.name,
+            submission,
+        };
+        post_json(&self.client, &self.url, &payload).await
+    }
+}
@@ -0,0 +1,237 @@
+//! OpenAPI 3 description of the HTTP API.
+//!
+//! Generated from the `#[utoipa::path]` annotations on the handlers and the
+//! `ToSchema` derives on the types they accept and return, so it changes
+//! with the code. It is served at `/openapi.json`, printed by
+//! `formvault openapi`, and the `/` index is built from it.
+//!
+//! Handlers only document their success response; the error responses
+//! every route shares are added by [`ErrorResponses`].
+
+use std::sync::LazyLock;
+
+use utoipa::openapi::path::{Operation, ParameterIn};
+use utoipa::openapi::security::{
+    ApiKey, ApiKeyValue, Http, HttpAuthScheme, HttpBuilder, SecurityScheme,
+};
+use utoipa::openapi::{
+    ContentBuilder, OpenApi as OpenApiDoc, PathItem, Ref, RefOr, ResponseBuilder,
+};
+use utoipa::{Modify, OpenApi};
+
+use crate::auth::{SESSION_COOKIE, TOTP_HEADER};
+use crate::errors::ErrorResponse;
+use crate::handlers;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "FormVault API"),
+    paths(
+        handlers::configuration::get_api_routes,
+        handlers::configuration::health_check,
+        handlers::configuration::livez,
+        handlers::configuration::readyz,
+        handlers::configuration::metrics,
+        handlers::configuration::openapi_json,
+        handlers::developers::register,
+        handlers::developers::me,
+        handlers::developers::deactivate,
+        handlers::developers::rotate_api_key,
+        handlers::developers::rotate_public_key,
+        handlers::developers::list_public_keys,
+        handlers::developers::retired_key_submissions,
+        handlers::developers::resend_verification,
+        handlers::auth::verify_email,
+        handlers::auth::request_login,
+        handlers::auth::create_session_from_link,
+        handlers::auth::end_session,
+        handlers::auth::list_sessions,
+        handlers::auth::revoke_session_by_id,
+        handlers::two_factor::status,
+        handlers::two_factor::enroll,
+        handlers::two_factor::confirm,
+        handlers::two_factor::regenerate_recovery_codes,
+        handlers::two_factor::disable,
+        handlers::api_keys::list_api_keys,
+        handlers::api_keys::create,
+        handlers::api_keys::revoke,
+        handlers::forms::list_forms,
+        handlers::forms::create_form,
+        handlers::forms::get_form,
+        handlers::forms::update_form,
+        handlers::forms::get_form_key,
+        handlers::forms::set_form_key,
+        handlers::forms::rekey_form,
+        handlers::forms::list_submissions,
+        handlers::forms::get_submission,
+        handlers::forms::export_submissions,
+        handlers::fields::list_fields,
+        handlers::fields::create,
+        handlers::fields::delete,
+        handlers::ingest::hosted_form,
+        handlers::ingest::submit,
+        handlers::ingest::preflight,
+        handlers::ingest::thank_you,
+        handlers::ingest::submission_key,
+        handlers::sdk::release,
+        handlers::sdk::bundle,
+        handlers::notifications::list_targets,
+        handlers::notifications::create,
+        handlers::notifications::update,
+        handlers::notifications::delete,
+        handlers::teams::list_teams,
+        handlers::teams::create_team,
+        handlers::teams::list_members,
+        handlers::teams::add_member,
+        handlers::teams::update_member,
+        handlers::teams::remove,
+        handlers::audit::list_events,
+        handlers::audit::verify,
+    ),
+    components(schemas(ErrorResponse)),
+    security(("api_key" = []), ("session" = [])),
+    tags(
+        (name = "meta", description = "Service information"),
+        (name = "developers", description = "Accounts and their encryption keys"),
+        (name = "auth", description = "Email verification, dashboard sign-in and two-factor authentication"),
+        (name = "api_keys", description = "Scoped API keys"),
+        (name = "forms", description = "Forms, their keys and their submissions"),
+        (name = "fields", description = "Field definitions used to validate plaintext submissions"),
+        (name = "ingest", description = "Public endpoints submitters post to"),
+        (name = "sdk", description = "The JavaScript SDK that encrypts submissions in the browser"),
+        (name = "notifications", description = "Where new submissions are announced"),
+        (name = "teams", description = "Teams sharing forms"),
+        (name = "audit", description = "The tamper-evident audit log"),
+    ),
+    modifiers(&ApiKeyAuth, &ErrorResponses)
+)]
+pub struct ApiDoc;
+
+/// The generated document, built on first use
+pub fn spec() -> &'static OpenApiDoc {
+    static SPEC: LazyLock<OpenApiDoc> = LazyLock::new(ApiDoc::openapi);
+    &SPEC
+}
+
+/// Every `(method, path, operation)` of `spec`, ordered by path
+pub fn operations(spec: &OpenApiDoc) -> impl Iterator<Item = (&'static str, &str, &Operation)> {
+    spec.paths.paths.iter().flat_map(|(path, item)| {
+        path_operations(item).map(move |(method, operation)| (method, path.as_str(), operation))
+    })
+}
+
+fn path_operations(item: &PathItem) -> impl Iterator<Item = (&'static str, &Operation)> {
+    [
+        ("GET", &item.get),
+        ("POST", &item.post),
+        ("PUT", &item.put),
+        ("PATCH", &item.patch),
+        ("DELETE", &item.delete),
+        ("OPTIONS", &item.options),
+    ]
+    .into_iter()
+    .filter_map(|(method, operation)| operation.as_ref().map(|operation| (method, operation)))
+}
+
+/// API keys are sent as `Authorization: Bearer <key>` (or `X-API-Key`);
+/// the dashboard authenticates with its session cookie instead
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                SESSION_COOKIE,
+                "Set by POST /auth/session; mutating requests also need the X-CSRF-Token header",
+            ))),
+        );
+        components.add_security_scheme(
+            "metrics_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("The METRICS_TOKEN the server was started with"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Document the [`ErrorResponse`]s a route can produce.
+///
+/// Routes without their own `security` need an API key, so they can fail
+/// with 401 and 403. Routes with path parameters can 404, and those taking
+/// a body or query parameters can be rejected with 400.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.patch,
+                &mut item.delete,
+                &mut item.options,
+            ];
+            for operation in operations.into_iter().flatten() {
+                let mut errors = Vec::new();
+                if operation.request_body.is_some() || has_query(operation) {
+                    errors.push(("400", "The request was invalid"));
+                }
+                if operation.security.is_none() {
+                    errors.push(("401", "No valid API key or session was given"));
+                    errors.push((
+                        "403",
+                        "The API key, the developer's role, or a missing CSRF token or two-factor code does not allow this",
+                    ));
+                }
+                if has_header(operation, TOTP_HEADER) {
+                    errors.push(("429", "Too many wrong two-factor codes were tried"));
+                }
+                if path.contains('{') {
+                    errors.push(("404", "Not found, or not visible to this developer"));
+                }
+                errors.push(("500", "Internal server error"));
+
+                for (status, description) in errors {
+                    let response = ResponseBuilder::new()
+                        .description(description)
+                        .content(
+                            "application/json",
+                            ContentBuilder::new()
+                                .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                                .build(),
+                        )
+                        .build();
+                    operation
+                        .responses
+                        .responses
+                        .entry(status.to_string())
+                        .or_insert(RefOr::T(response));
+                }
+            }
+        }
+    }
+}
+
+fn has_header(operation: &Operation, name: &str) -> bool {
+    operation.parameters.iter().flatten().any(|parameter| {
+        matches!(parameter.parameter_in, ParameterIn::Header) && parameter.name == name
+    })
+}
+
+fn has_query(operation: &Operation) -> bool {
+    operation
+        .parameters
+        .iter()
+        .flatten()
+        .any(|parameter| matches!(parameter.parameter_in, ParameterIn::Query))
+}
@@ -0,0 +1,304 @@
+//! Pages the server renders itself for people filling in forms.
+//!
+//! `GET /f/{form_id}` renders a form from its field definitions, for sharing
+//! a link instead of building a page. Plain HTML forms, hosted or not, post
+//! straight to the ingestion endpoint, so the browser shows whatever comes
+//! back; forms without their own success or error URL land on the pages
+//! here instead of a JSON body. All of them are self-contained: no external
+//! assets, and a Content-Security-Policy that allows nothing but their
+//! inline styles and, on zero-knowledge forms, the SDK and the script that
+//! hands it the form.
+
+use std::sync::LazyLock;
+
+use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use actix_web::http::header::{self, ContentType};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha2::{Digest, Sha256};
+
+use crate::errors::ErrorResponse;
+use crate::models::forms::field_definition::{FieldDefinition, FieldType};
+use crate::models::forms::form_schema::{FormSchema, SubmissionKey};
+use crate::sdk;
+
+/// Nothing may load but the page's own `<style>`
+const CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; style-src 'unsafe-inline'; frame-ancestors 'none'";
+
+/// Has the SDK encrypt zero-knowledge forms; inlined into their page
+const ENCRYPT_SCRIPT: &str = include_str!("pages/encrypt.js");
+
+/// Hosted forms may also run this server's scripts, which are pinned by
+/// their integrity hash, and the inline one
+static FORM_CONTENT_SECURITY_POLICY: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "default-src 'none'; style-src 'unsafe-inline'; script-src 'sha256-{}' 'self'; frame-ancestors 'none'",
+        STANDARD.encode(Sha256::digest(ENCRYPT_SCRIPT))
+    )
+});
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;line-height:1.5;color:#1f2933;background:#f5f7fa;margin:0}\
+main{max-width:32rem;margin:4rem auto;padding:2rem;background:#fff;border-radius:.5rem;\
+box-shadow:0 1px 3px rgba(0,0,0,.1)}\
+h1{font-size:1.5rem;margin-top:0}\
+.error h1{color:#b42318}\
+a{color:#2563eb}.field{margin:1.25rem 0}label{display:block;font-weight:600;margin-bottom:.25rem}input,select{box-sizing:border-box;width:100%;padding:.5rem;font:inherit;border:1px solid #9aa5b1;border-radius:.25rem}.checkbox{display:flex;gap:.5rem;align-items:baseline}.checkbox input{width:auto}.checkbox label{font-weight:400}.required{color:#b42318}.hint{color:#52606d;font-size:.875rem;margin:.25rem 0 0}button{font:inherit;padding:.5rem 1.25rem;border:0;border-radius:.25rem;background:#2563eb;color:#fff;cursor:pointer}button:disabled{background:#9aa5b1;cursor:default}";
+
+/// Escape text for use in HTML content and quoted attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A complete document around `main`, which must already be escaped
+fn document(title: &str, class: &str, main: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <meta name=\"robots\" content=\"noindex\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <main class=\"{}\">\n{}</main>\n</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        class,
+        main
+    )
+}
+
+/// Respond with a rendered page
+pub fn html(status: StatusCode, page: String) -> HttpResponse {
+    respond(status, page, CONTENT_SECURITY_POLICY)
+}
+
+fn respond(status: StatusCode, page: String, policy: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type(ContentType::html())
+        .insert_header((header::CONTENT_SECURITY_POLICY, policy))
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .body(page)
+}
+
+/// `first_name` as `First name`
+fn label(name: &str) -> String {
+    let words = name.replace(['_', '-'], " ");
+    let words = words.trim();
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name.to_string(),
+    }
+}
+
+/// The control for one field, with its label and hint
+fn field(index: usize, field: &FieldDefinition) -> String {
+    let rules = field.rules().unwrap_or_default();
+    let id = format!("fv-field-{}", index);
+    let hint = match field.field_type {
+        FieldType::File => Some("A link to the file".to_string()),
+        _ => rules.message.clone(),
+    };
+
+    let mut attributes = format!("id=\"{}\" name=\"{}\"", id, escape(&field.name));
+    if field.required {
+        attributes.push_str(" required");
+    }
+    if hint.is_some() {
+        attributes.push_str(&format!(" aria-describedby=\"{}-hint\"", id));
+    }
+    // `extra` holds attributes of the input type, such as `autocomplete`
+    let input = |input_type: &str, extra: &str| {
+        let mut input = format!("<input type=\"{}\" {}{}", input_type, attributes, extra);
+        if let Some(max_length) = rules.max_length {
+            input.push_str(&format!(" maxlength=\"{}\"", max_length));
+        }
+        if let Some(pattern) = &rules.pattern {
+            input.push_str(&format!(" pattern=\"{}\"", escape(pattern)));
+        }
+        input.push('>');
+        input
+    };
+    let control = match field.field_type {
+        FieldType::Text => input("text", ""),
+        FieldType::Email => input("email", " autocomplete=\"email\""),
+        FieldType::Phone => input("tel", " autocomplete=\"tel\""),
+        FieldType::Number => input("number", " step=\"any\""),
+        FieldType::Date => input("date", ""),
+        // Uploads are not supported, so files are shared as links
+        FieldType::File => input("url", ""),
+        FieldType::Select => {
+            let mut select = format!(
+                "<select {}>\n<option value=\"\">Choose…</option>\n",
+                attributes
+            );
+            for option in &rules.options {
+                select.push_str(&format!("<option>{}</option>\n", escape(option)));
+            }
+            select.push_str("</select>");
+            select
+        }
+        FieldType::Checkbox => format!("<input type=\"checkbox\" value=\"yes\" {}>", attributes),
+    };
+
+    let marker = if field.required {
+        "<span class=\"required\" aria-hidden=\"true\"> *</span>"
+    } else {
+        ""
+    };
+    let label = format!(
+        "<label for=\"{}\">{}{}</label>",
+        id,
+        escape(&label(&field.name)),
+        marker
+    );
+    let hint = hint
+        .map(|hint| {
+            format!(
+                "\n<p id=\"{}-hint\" class=\"hint\">{}</p>",
+                id,
+                escape(&hint)
+            )
+        })
+        .unwrap_or_default();
+    match field.field_type {
+        FieldType::Checkbox => {
+            format!(
+                "<div class=\"field checkbox\">\n{}\n{}{}\n</div>\n",
+                control, label, hint
+            )
+        }
+        _ => format!(
+            "<div class=\"field\">\n{}\n{}{}\n</div>\n",
+            label, control, hint
+        ),
+    }
+}
+
+/// A form to fill in, rendered from its fields in order.
+///
+/// Zero-knowledge forms need their submission `key`: the page then carries
+/// it and loads the SDK to encrypt the entries with it, and can't be sent
+/// without JavaScript. Other forms post their fields as they are, so the
+/// server validates them and renders the errors.
+pub fn hosted_form(
+    form: &FormSchema,
+    fields: &[FieldDefinition],
+    key: Option<&SubmissionKey>,
+) -> HttpResponse {
+    let title = escape(&form.name);
+    if fields.is_empty() {
+        let main = format!("<h1>{}</h1>\n<p>This form has no fields yet.</p>\n", title);
+        return html(StatusCode::OK, document(&form.name, "form", &main));
+    }
+
+    let mut main = format!("<h1 id=\"fv-title\">{}</h1>\n", title);
+    if fields.iter().any(|field| field.required) {
+        main.push_str("<p class=\"hint\">Required fields are marked with an asterisk (*).</p>\n");
+    }
+    main.push_str(&format!(
+        "<form method=\"post\" action=\"/f/{}\" accept-charset=\"utf-8\" aria-labelledby=\"fv-title\"",
+        form.id
+    ));
+    if let Some(key) = key {
+        main.push_str(&format!(
+            " data-key-id=\"{}\" data-algorithm=\"{}\" data-public-key=\"{}\"",
+            key.key_id,
+            key.algorithm.as_str(),
+            escape(&key.public_key)
+        ));
+    }
+    main.push_str(">\n");
+    for (index, definition) in fields.iter().enumerate() {
+        main.push_str(&field(index, definition));
+    }
+    match key {
+        Some(_) => main.push_str(
+            "<p class=\"hint\">Your response is encrypted in this browser before it is sent.</p>\n\
+             <noscript><p class=\"hint\">Encrypting your response needs JavaScript.</p></noscript>\n\
+             <button type=\"submit\" disabled>Send</button>\n",
+        ),
+        None => main.push_str("<button type=\"submit\">Send</button>\n"),
+    }
+    main.push_str("<p id=\"fv-status\" class=\"hint\" role=\"status\"></p>\n</form>\n");
+
+    match key {
+        Some(_) => {
+            main.push_str(&format!(
+                "<script src=\"{}\" integrity=\"{}\" crossorigin=\"anonymous\"></script>\n\
+                 <script>{}</script>\n",
+                sdk::PATH,
+                sdk::INTEGRITY.as_str(),
+                ENCRYPT_SCRIPT
+            ));
+            respond(
+                StatusCode::OK,
+                document(&form.name, "form", &main),
+                &FORM_CONTENT_SECURITY_POLICY,
+            )
+        }
+        None => html(StatusCode::OK, document(&form.name, "form", &main)),
+    }
+}
+
+/// Shown after a successful submission to a form without a success URL
+pub fn thank_you(form_name: &str) -> String {
+    document(
+        "Thank you",
+        "success",
+        &format!(
+            "<h1>Thank you!</h1>\n<p>Your response to <strong>{}</strong> has been received.</p>\n",
+            escape(form_name)
+        ),
+    )
+}
+
+/// Shown for links to forms that don't exist
+pub fn form_not_found() -> String {
+    document(
+        "Form not found",
+        "error",
+        "<h1>Form not found</h1>\n<p>This form does not exist or has been removed.</p>\n",
+    )
+}
+
+/// Shown when a submission fails and the form has no error URL.
+///
+/// `back` is the page the form was on, for a link to correct the entries.
+pub fn submission_failed(error: &ErrorResponse, back: Option<&str>) -> String {
+    let mut main = format!(
+        "<h1>Your response was not sent</h1>\n<p role=\"alert\">{}</p>\n",
+        escape(&error.error)
+    );
+    if let Some(details) = error.details.as_ref().filter(|details| !details.is_empty()) {
+        main.push_str("<ul>\n");
+        for detail in details {
+            main.push_str(&format!("<li>{}</li>\n", escape(detail)));
+        }
+        main.push_str("</ul>\n");
+    }
+    if let Some(back) = back {
+        main.push_str(&format!(
+            "<p><a href=\"{}\">Go back to the form</a></p>\n",
+            escape(back)
+        ));
+    }
+    if let Some(request_id) = &error.request_id {
+        main.push_str(&format!(
+            "<p><small>Reference: {}</small></p>\n",
+            escape(request_id)
+        ));
+    }
+    document("Submission failed", "error", &main)
+}
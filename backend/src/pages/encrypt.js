@@ -0,0 +1,37 @@
+// Encrypts a hosted zero-knowledge form in the browser before it is posted.
+//
+// The encryption is the SDK's, loaded from /sdk/v1/formvault.js just before
+// this script; the form carries its key so nothing needs fetching. Only the
+// envelope leaves the page.
+(() => {
+  "use strict";
+
+  const form = document.querySelector("form[data-key-id]");
+  const submit = form.querySelector("button[type=submit]");
+  const status = document.getElementById("fv-status");
+
+  if (!window.FormVault || !window.FormVault.supported) {
+    status.textContent = "This browser cannot encrypt your response, so it cannot be sent.";
+    return;
+  }
+
+  // Registered first, so it runs before the SDK's listener posts the envelope
+  form.addEventListener("submit", () => {
+    submit.disabled = true;
+    status.textContent = "Encrypting your response…";
+  });
+  const { keyId, algorithm, publicKey } = form.dataset;
+  window.FormVault.attach(form, {
+    key: { key_id: keyId, algorithm, public_key: publicKey },
+    onError: () => {
+      status.textContent = "Your response could not be encrypted, so it was not sent.";
+      submit.disabled = false;
+    },
+  });
+
+  // Coming back to the page, from the error page say, starts over
+  window.addEventListener("pageshow", () => {
+    submit.disabled = false;
+  });
+  submit.disabled = false;
+})();
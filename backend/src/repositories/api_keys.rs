@@ -0,0 +1,119 @@
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::models::users::api_key::{ApiKey, ApiKeyScope};
+use tracing::instrument;
+
+/// Insert a new key
+#[instrument(skip_all)]
+pub async fn create_api_key<'e>(
+    executor: impl PgExecutor<'e>,
+    key: &ApiKey,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO api_keys
+            (id, developer_id, name, prefix, key_hash, scopes, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        key.id,
+        key.developer_id,
+        key.name,
+        key.prefix,
+        key.key_hash,
+        &key.scopes as &[ApiKeyScope],
+        key.expires_at,
+        key.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Look a key up by the hash of its plaintext
+#[instrument(skip_all)]
+pub async fn find_api_key_by_hash(
+    pool: &PgPool,
+    key_hash: &str,
+) -> FormVaultResult<Option<ApiKey>> {
+    let key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, developer_id, name, prefix, key_hash,
+               scopes AS "scopes: Vec<ApiKeyScope>", expires_at, last_used_at, revoked_at,
+               created_at
+        FROM api_keys
+        WHERE key_hash = $1
+        "#,
+        key_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(key)
+}
+
+/// All keys of a developer, including revoked ones
+#[instrument(skip_all)]
+pub async fn find_api_keys_by_developer(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> FormVaultResult<Vec<ApiKey>> {
+    let keys = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, developer_id, name, prefix, key_hash,
+               scopes AS "scopes: Vec<ApiKeyScope>", expires_at, last_used_at, revoked_at,
+               created_at
+        FROM api_keys
+        WHERE developer_id = $1
+        ORDER BY created_at
+        "#,
+        developer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(keys)
+}
+
+/// Record that a key was just used; writes at most once a minute per key
+#[instrument(skip_all)]
+pub async fn touch_api_key(pool: &PgPool, id: Uuid) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE api_keys
+        SET last_used_at = NOW()
+        WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Revoke a key of `developer_id`; false if there is no such active key
+#[instrument(skip_all)]
+pub async fn revoke_api_key<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+    id: Uuid,
+) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = NOW()
+        WHERE id = $1 AND developer_id = $2 AND revoked_at IS NULL
+        "#,
+        id,
+        developer_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::models::audit::{AuditEvent, ChainBreak, GENESIS_HASH, NewAuditEvent, verify_chain};
+use tracing::instrument;
+
+/// Advisory lock serializing writers so every event sees its predecessor
+const AUDIT_CHAIN_LOCK: i64 = 0x666f_726d_6175_6474;
+
+/// Events checked per round trip when verifying the chain
+const VERIFY_BATCH: i64 = 1000;
+
+/// Filters for [`find_events`].
+#[derive(Debug, Default, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// e.g. `api_key.created`
+    pub action: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Only events with a smaller `seq` (for paging backwards)
+    pub before: Option<i64>,
+    /// Defaults to 50, at most 500
+    pub limit: Option<i64>,
+}
+
+/// Outcome of verifying the whole chain.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ChainReport {
+    pub valid: bool,
+    pub checked: i64,
+    pub broken_at: Option<ChainBreak>,
+}
+
+/// Chain `event` onto the latest entry and write it
+#[instrument(skip_all)]
+pub async fn append_event(pool: &PgPool, event: NewAuditEvent) -> FormVaultResult<AuditEvent> {
+    let mut tx = pool.begin().await?;
+    let event = chain_event(&mut tx, event).await?;
+    tx.commit().await?;
+    Ok(event)
+}
+
+/// Chain `event` onto the latest entry and write it as part of the
+/// transaction on `conn`, which holds the chain until it ends
+#[instrument(skip_all)]
+pub async fn chain_event(
+    conn: &mut PgConnection,
+    event: NewAuditEvent,
+) -> FormVaultResult<AuditEvent> {
+    sqlx::query!("SELECT pg_advisory_xact_lock($1)", AUDIT_CHAIN_LOCK)
+        .execute(&mut *conn)
+        .await?;
+
+    let prev_hash = sqlx::query_scalar!("SELECT hash FROM audit_events ORDER BY seq DESC LIMIT 1")
+        .fetch_optional(&mut *conn)
+        .await?
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    let mut event = event.chain(0, prev_hash);
+
+    event.seq = sqlx::query_scalar!(
+        r#"
+        INSERT INTO audit_events
+            (id, occurred_at, actor_type, actor_id, developer_id, action, target_type,
+             target_id, ip_address, details, prev_hash, hash)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING seq
+        "#,
+        event.id,
+        event.occurred_at,
+        event.actor_type,
+        event.actor_id,
+        event.developer_id,
+        event.action,
+        event.target_type,
+        event.target_id,
+        event.ip_address,
+        event.details,
+        event.prev_hash,
+        event.hash
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(event)
+}
+
+/// Events of one account, newest first
+#[instrument(skip_all)]
+pub async fn find_events(
+    pool: &PgPool,
+    developer_id: Uuid,
+    query: &AuditQuery,
+) -> FormVaultResult<Vec<AuditEvent>> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+
+    let events = sqlx::query_as!(
+        AuditEvent,
+        r#"
+        SELECT seq, id, occurred_at, actor_type, actor_id, developer_id, action, target_type,
+               target_id, ip_address, details, prev_hash, hash
+        FROM audit_events
+        WHERE developer_id = $1
+          AND ($2::TEXT IS NULL OR action = $2)
+          AND ($3::TIMESTAMPTZ IS NULL OR occurred_at >= $3)
+          AND ($4::TIMESTAMPTZ IS NULL OR occurred_at < $4)
+          AND ($5::BIGINT IS NULL OR seq < $5)
+        ORDER BY seq DESC
+        LIMIT $6
+        "#,
+        developer_id,
+        query.action,
+        query.from,
+        query.to,
+        query.before,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+/// Walk the entire chain from the first event and report the first break
+#[instrument(skip_all)]
+pub async fn verify_audit_chain(pool: &PgPool) -> FormVaultResult<ChainReport> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut after = 0;
+    let mut checked = 0;
+
+    loop {
+        let events = sqlx::query_as!(
+            AuditEvent,
+            r#"
+            SELECT seq, id, occurred_at, actor_type, actor_id, developer_id, action, target_type,
+                   target_id, ip_address, details, prev_hash, hash
+            FROM audit_events
+            WHERE seq > $1
+            ORDER BY seq
+            LIMIT $2
+            "#,
+            after,
+            VERIFY_BATCH
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let Some(last) = events.last() else {
+            break;
+        };
+        after = last.seq;
+
+        match verify_chain(&prev_hash, &events) {
+            Ok(hash) => {
+                prev_hash = hash;
+                checked += events.len() as i64;
+            }
+            Err(broken) => {
+                checked += events.iter().take_while(|e| e.seq < broken.seq).count() as i64;
+                return Ok(ChainReport {
+                    valid: false,
+                    checked,
+                    broken_at: Some(broken),
+                });
+            }
+        }
+    }
+
+    Ok(ChainReport {
+        valid: true,
+        checked,
+        broken_at: None,
+    })
+}
@@ -0,0 +1,196 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::public_key::{KeyAlgorithm, PublicKeyVersion};
+use crate::models::users::developer::Developer;
+use crate::models::users::registration::Registration;
+use crate::models::users::team::{TeamMember, TeamRole};
+use crate::repositories::api_keys::create_api_key;
+use crate::repositories::audit::chain_event;
+use crate::repositories::public_keys::create_public_key;
+use crate::repositories::teams::{create_team, upsert_member};
+
+/// Report a unique constraint violation as a taken email
+pub(crate) fn duplicate_email(error: FormVaultError) -> FormVaultError {
+    match error {
+        FormVaultError::DatabaseError(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            FormVaultError::DuplicateEmail
+        }
+        error => error,
+    }
+}
+
+/// Insert a developer; a taken email fails with [`FormVaultError::DuplicateEmail`]
+#[instrument(skip_all)]
+pub async fn create_developer<'e>(
+    executor: impl PgExecutor<'e>,
+    developer: &Developer,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO developers
+            (id, name, email, public_key, public_key_algorithm, created_at, is_active,
+             email_verified_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        developer.id,
+        developer.name,
+        developer.email,
+        developer.public_key,
+        developer.public_key_algorithm as KeyAlgorithm,
+        developer.created_at,
+        developer.is_active,
+        developer.email_verified_at
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| duplicate_email(e.into()))?;
+
+    Ok(())
+}
+
+/// Store everything signing up creates in one transaction: the developer,
+/// their first key version, their personal team and its membership, the
+/// first API key and the audit event.
+///
+/// Uniqueness of the email is left to the constraint on `developers.email`
+/// instead of being checked first, so of two concurrent signups with the
+/// same email exactly one succeeds and the other fails with
+/// [`FormVaultError::DuplicateEmail`]. Nothing is stored when any insert
+/// fails, so a failed signup can simply be retried.
+#[instrument(skip_all)]
+pub async fn create_registration(
+    pool: &PgPool,
+    registration: &Registration,
+) -> FormVaultResult<()> {
+    let developer_id = registration.developer.id;
+    let mut tx = pool.begin().await?;
+    create_developer(&mut *tx, &registration.developer).await?;
+    create_public_key(&mut *tx, &registration.key).await?;
+    create_team(&mut *tx, &registration.team).await?;
+    upsert_member(
+        &mut *tx,
+        &TeamMember::new(registration.team.id, developer_id, TeamRole::Owner),
+    )
+    .await?;
+    create_api_key(&mut *tx, &registration.api_key).await?;
+    chain_event(&mut tx, registration.event.clone()).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Find a developer by ID
+#[instrument(skip_all)]
+pub async fn find_developer_by_id(pool: &PgPool, id: Uuid) -> FormVaultResult<Option<Developer>> {
+    let developer = sqlx::query_as!(
+        Developer,
+        r#"
+        SELECT id, name, email, public_key,
+               public_key_algorithm AS "public_key_algorithm: KeyAlgorithm",
+               created_at, is_active, email_verified_at
+        FROM developers WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(developer)
+}
+
+/// Find a developer by email
+#[instrument(skip_all)]
+pub async fn find_developer_by_email(
+    pool: &PgPool,
+    email: &str,
+) -> FormVaultResult<Option<Developer>> {
+    let developer = sqlx::query_as!(
+        Developer,
+        r#"
+        SELECT id, name, email, public_key,
+               public_key_algorithm AS "public_key_algorithm: KeyAlgorithm",
+               created_at, is_active, email_verified_at
+        FROM developers WHERE email = $1
+        "#,
+        email
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(developer)
+}
+
+/// Persist name and email; the public key only changes through
+/// [`update_developer_public_key`]
+#[instrument(skip_all)]
+pub async fn update_developer<'e>(
+    executor: impl PgExecutor<'e>,
+    developer: &Developer,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        "UPDATE developers SET name = $1, email = $2 WHERE id = $3",
+        developer.name,
+        developer.email,
+        developer.id
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| duplicate_email(e.into()))?;
+
+    Ok(())
+}
+
+/// Point the developer row at their current key version
+#[instrument(skip_all)]
+pub async fn update_developer_public_key<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+    key: &PublicKeyVersion,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        "UPDATE developers SET public_key = $1, public_key_algorithm = $2 WHERE id = $3",
+        key.public_key,
+        key.algorithm as KeyAlgorithm,
+        developer_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Record when the developer verified their email; an earlier
+/// verification is kept
+#[instrument(skip_all)]
+pub async fn mark_email_verified<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    at: DateTime<Utc>,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        "UPDATE developers SET email_verified_at = COALESCE(email_verified_at, $2) WHERE id = $1",
+        id,
+        at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Deactivate an account
+#[instrument(skip_all)]
+pub async fn deactivate_developer<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> FormVaultResult<()> {
+    sqlx::query!("UPDATE developers SET is_active = false WHERE id = $1", id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
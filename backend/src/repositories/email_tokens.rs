@@ -0,0 +1,56 @@
+use sqlx::PgExecutor;
+use tracing::instrument;
+
+use crate::errors::FormVaultResult;
+use crate::models::users::email_token::{EmailToken, EmailTokenPurpose};
+
+/// Insert a new token
+#[instrument(skip_all)]
+pub async fn create_email_token<'e>(
+    executor: impl PgExecutor<'e>,
+    token: &EmailToken,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_tokens (id, developer_id, purpose, token_hash, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        token.id,
+        token.developer_id,
+        token.purpose as EmailTokenPurpose,
+        token.token_hash,
+        token.expires_at,
+        token.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark an unused, unexpired token for `purpose` as used and return it.
+///
+/// Checking and using the token is one statement, so a link followed twice
+/// at the same time only works once.
+#[instrument(skip_all)]
+pub async fn consume_email_token<'e>(
+    executor: impl PgExecutor<'e>,
+    token_hash: &str,
+    purpose: EmailTokenPurpose,
+) -> FormVaultResult<Option<EmailToken>> {
+    let token = sqlx::query_as!(
+        EmailToken,
+        r#"
+        UPDATE email_tokens SET used_at = NOW()
+        WHERE token_hash = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING id, developer_id, purpose AS "purpose: EmailTokenPurpose", token_hash,
+                  expires_at, used_at, created_at
+        "#,
+        token_hash,
+        purpose as EmailTokenPurpose
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(token)
+}
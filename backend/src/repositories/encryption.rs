@@ -1,12 +1,235 @@
-// Helper functions (to be implemented elsewhere)
-use crate::errors::FormVaultError;
+//! Envelope encryption of submissions.
+//!
+//! Every submission gets a fresh AES-256-GCM content key. The form data is
+//! encrypted with it and the content key is wrapped for the form's public
+//! key, so only the holder of the private key can read submissions. Both
+//! halves are stored as text:
+//!
+//! ```text
+//! encrypted_data = fv1.<algorithm>.<nonce>.<ciphertext + tag>
+//! encrypted_key  = <wrapped content key>
+//! ```
+//!
+//! All binary parts are unpadded URL-safe base64. `fv1.<algorithm>` is also
+//! the AEAD associated data, so the header cannot be altered without the tag
+//! failing. The content key is wrapped with
+//!
+//! - `rsa`: RSA-OAEP with SHA-256 (MGF1 SHA-256, empty label)
+//! - `x25519`: an ephemeral X25519 key agreement; the wrapped key is the
+//!   32-byte ephemeral public key followed by the content key sealed with
+//!   AES-256-GCM under `SHA-256("formvault/x25519/v1" || shared || ephemeral || recipient)`
+//!   and an all-zero nonce (the key is never reused)
+//!
+//! The JavaScript SDK served at `/sdk/v1/formvault.js` builds the same
+//! envelope in the browser; `tests/fixtures/envelope_vectors.json` holds
+//! vectors both implementations are checked against.
 
 use std::collections::HashMap;
+use std::time::Instant;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rsa::Oaep;
+use sha2::{Digest, Sha256};
+use tracing::instrument;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use crate::errors::FormVaultError;
+use crate::metrics::metrics;
+use crate::models::public_key::{KeyAlgorithm, PublicKey};
+
+/// Version tag leading every envelope
+pub const ENVELOPE_VERSION: &str = "fv1";
+
+/// Domain separation for the X25519 key-encryption key
+const X25519_KDF_CONTEXT: &[u8] = b"formvault/x25519/v1";
+
+/// An encrypted submission, split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub algorithm: KeyAlgorithm,
+    pub nonce: [u8; 12],
+    /// Ciphertext with the 16-byte GCM tag appended
+    pub ciphertext: Vec<u8>,
+    pub encrypted_key: Vec<u8>,
+}
+
+impl Envelope {
+    /// `fv1.<algorithm>`, authenticated as associated data
+    pub fn header(algorithm: KeyAlgorithm) -> String {
+        format!("{}.{}", ENVELOPE_VERSION, algorithm.as_str())
+    }
+
+    /// The `(encrypted_data, encrypted_key)` pair stored for a submission
+    pub fn encode(&self) -> (String, String) {
+        let data = format!(
+            "{}.{}.{}",
+            Self::header(self.algorithm),
+            URL_SAFE_NO_PAD.encode(self.nonce),
+            URL_SAFE_NO_PAD.encode(&self.ciphertext)
+        );
+        (data, URL_SAFE_NO_PAD.encode(&self.encrypted_key))
+    }
+
+    /// Inverse of [`Envelope::encode`]; rejects unknown versions
+    pub fn decode(encrypted_data: &str, encrypted_key: &str) -> Result<Self, FormVaultError> {
+        let malformed =
+            |what: &str| FormVaultError::DecryptionError(format!("malformed envelope: {}", what));
+
+        let mut parts = encrypted_data.trim().split('.');
+        let (Some(version), Some(algorithm), Some(nonce), Some(ciphertext), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(malformed("expected four dot-separated parts"));
+        };
+
+        if version != ENVELOPE_VERSION {
+            return Err(FormVaultError::DecryptionError(format!(
+                "unsupported envelope version {:?}",
+                version
+            )));
+        }
+        let algorithm =
+            KeyAlgorithm::parse(algorithm).ok_or_else(|| malformed("unknown algorithm"))?;
+        let nonce = URL_SAFE_NO_PAD
+            .decode(nonce)
+            .ok()
+            .and_then(|nonce| <[u8; 12]>::try_from(nonce).ok())
+            .ok_or_else(|| malformed("bad nonce"))?;
+        let ciphertext = URL_SAFE_NO_PAD
+            .decode(ciphertext)
+            .map_err(|_| malformed("bad ciphertext"))?;
+        let encrypted_key = URL_SAFE_NO_PAD
+            .decode(encrypted_key.trim())
+            .map_err(|_| malformed("bad encrypted key"))?;
+
+        Ok(Self {
+            algorithm,
+            nonce,
+            ciphertext,
+            encrypted_key,
+        })
+    }
+}
+
+/// Key-encryption key for the X25519 wrap
+pub fn x25519_kek(
+    shared_secret: &[u8; 32],
+    ephemeral: &[u8; 32],
+    recipient: &[u8; 32],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(X25519_KDF_CONTEXT);
+    hasher.update(shared_secret);
+    hasher.update(ephemeral);
+    hasher.update(recipient);
+    hasher.finalize().into()
+}
 
-/// TODO: Implement encryption using a library like `ring` or `rsa`
+/// The random values one envelope is made from
+pub struct SealSecrets {
+    pub content_key: [u8; 32],
+    pub nonce: [u8; 12],
+    /// Ephemeral X25519 private key; unused for RSA, whose padding draws
+    /// its own randomness
+    pub ephemeral_secret: [u8; 32],
+}
+
+impl SealSecrets {
+    /// Fresh values from the operating system's generator
+    pub fn generate() -> Self {
+        Self {
+            content_key: Aes256Gcm::generate_key(OsRng).into(),
+            nonce: Aes256Gcm::generate_nonce(OsRng).into(),
+            ephemeral_secret: StaticSecret::random_from_rng(OsRng).to_bytes(),
+        }
+    }
+}
+
+/// Wrap a content key for `public_key`
+fn wrap_key(
+    content_key: &[u8; 32],
+    public_key: &PublicKey,
+    ephemeral_secret: &[u8; 32],
+) -> Result<Vec<u8>, FormVaultError> {
+    match public_key {
+        PublicKey::Rsa(key) => key
+            .encrypt(&mut OsRng, Oaep::new::<Sha256>(), content_key)
+            .map_err(|e| FormVaultError::EncryptionError(e.to_string())),
+        PublicKey::X25519(recipient) => {
+            let secret = StaticSecret::from(*ephemeral_secret);
+            let ephemeral = X25519PublicKey::from(&secret);
+            let shared = secret.diffie_hellman(&X25519PublicKey::from(*recipient));
+            let kek = x25519_kek(shared.as_bytes(), ephemeral.as_bytes(), recipient);
+
+            let sealed = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&kek))
+                .encrypt(Nonce::from_slice(&[0u8; 12]), content_key.as_slice())
+                .map_err(|e| FormVaultError::EncryptionError(e.to_string()))?;
+
+            let mut wrapped = ephemeral.as_bytes().to_vec();
+            wrapped.extend_from_slice(&sealed);
+            Ok(wrapped)
+        }
+    }
+}
+
+/// Encrypt `plaintext` to `public_key` under a fresh content key
+pub fn seal(plaintext: &[u8], public_key: &PublicKey) -> Result<Envelope, FormVaultError> {
+    seal_with(plaintext, public_key, &SealSecrets::generate())
+}
+
+/// [`seal`] with the given random values.
+///
+/// Only for reproducing test vectors: an envelope made from secrets that
+/// were used before is not confidential.
+#[instrument(skip_all, fields(algorithm = public_key.algorithm().as_str()))]
+pub fn seal_with(
+    plaintext: &[u8],
+    public_key: &PublicKey,
+    secrets: &SealSecrets,
+) -> Result<Envelope, FormVaultError> {
+    let algorithm = public_key.algorithm();
+    let header = Envelope::header(algorithm);
+
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&secrets.content_key))
+        .encrypt(
+            Nonce::from_slice(&secrets.nonce),
+            Payload {
+                msg: plaintext,
+                aad: header.as_bytes(),
+            },
+        )
+        .map_err(|e| FormVaultError::EncryptionError(e.to_string()))?;
+
+    Ok(Envelope {
+        algorithm,
+        nonce: secrets.nonce,
+        ciphertext,
+        encrypted_key: wrap_key(&secrets.content_key, public_key, &secrets.ephemeral_secret)?,
+    })
+}
+
+/// Encrypt submitted fields to a PEM public key.
+///
+/// Returns the `(encrypted_data, encrypted_key)` pair described in the
+/// module docs.
 pub async fn encrypt_form_data(
-    _data: &HashMap<String, String>,
-    _public_key: &str,
+    data: &HashMap<String, String>,
+    public_key: &str,
 ) -> Result<(String, String), FormVaultError> {
-    unimplemented!("Encryption logic")
+    let public_key = PublicKey::from_pem(public_key)?;
+    let plaintext =
+        serde_json::to_vec(data).map_err(|e| FormVaultError::EncryptionError(e.to_string()))?;
+
+    let started = Instant::now();
+    let envelope = seal(&plaintext, &public_key)?;
+    metrics().observe_encryption(public_key.algorithm(), started.elapsed());
+
+    Ok(envelope.encode())
 }
@@ -0,0 +1,74 @@
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::forms::field_definition::{FieldDefinition, FieldType};
+use sqlx::PgPool;
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Append a field to its form, after the existing ones
+#[instrument(skip_all)]
+pub async fn create_field(pool: &PgPool, field: &mut FieldDefinition) -> FormVaultResult<()> {
+    let position = sqlx::query_scalar!(
+        r#"
+        INSERT INTO field_definitions
+            (id, form_id, name, field_type, required, position, validation_rules)
+        VALUES ($1, $2, $3, $4, $5,
+                (SELECT COALESCE(MAX(position) + 1, 0) FROM field_definitions WHERE form_id = $2),
+                $6)
+        RETURNING position
+        "#,
+        field.id,
+        field.form_id,
+        field.name,
+        field.field_type as FieldType,
+        field.required,
+        field.validation_rules
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => FormVaultError::ValidationFailed(
+            vec![format!("the form already has a field named {}", field.name)],
+        ),
+        e => e.into(),
+    })?;
+
+    field.position = position;
+    Ok(())
+}
+
+/// The fields of a form in display order
+#[instrument(skip_all)]
+pub async fn find_fields_by_form(
+    pool: &PgPool,
+    form_id: Uuid,
+) -> FormVaultResult<Vec<FieldDefinition>> {
+    let fields = sqlx::query_as!(
+        FieldDefinition,
+        r#"
+        SELECT id, form_id, name, field_type AS "field_type: FieldType", required, position,
+               validation_rules
+        FROM field_definitions
+        WHERE form_id = $1
+        ORDER BY position, name
+        "#,
+        form_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(fields)
+}
+
+/// Remove a field of the form; returns whether it existed
+#[instrument(skip_all)]
+pub async fn delete_field(pool: &PgPool, form_id: Uuid, field_id: Uuid) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM field_definitions WHERE id = $1 AND form_id = $2",
+        field_id,
+        form_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
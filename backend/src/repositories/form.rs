@@ -1,13 +1,304 @@
 use crate::errors::FormVaultError;
 
-use crate::models::forms::submission::FormSubmission;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::forms::submission::{FormSubmission, SubmissionMetadata, SubmissionStatus};
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
 
-/// TODO: Implement database save using `sqlx` or `diesel`
-pub async fn save_submission(submission: &FormSubmission) -> Result<(), FormVaultError> {
-    unimplemented!("Database save")
+/// Find a form by ID
+#[instrument(skip_all)]
+pub async fn find_form_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<FormSchema>, FormVaultError> {
+    let form = sqlx::query_as!(
+        FormSchema,
+        r#"
+        SELECT id, name, developer_id, team_id, key_id, allowed_origins, success_url, error_url,
+               zero_knowledge, created_at
+        FROM form_schemas WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(form)
+}
+
+/// Forms created by a developer, newest first
+#[instrument(skip_all)]
+pub async fn find_forms_by_developer(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> Result<Vec<FormSchema>, FormVaultError> {
+    let forms = sqlx::query_as!(
+        FormSchema,
+        r#"
+        SELECT id, name, developer_id, team_id, key_id, allowed_origins, success_url, error_url,
+               zero_knowledge, created_at
+        FROM form_schemas WHERE developer_id = $1
+        ORDER BY created_at DESC
+        "#,
+        developer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(forms)
+}
+
+/// Forms of every team the developer belongs to, newest first
+#[instrument(skip_all)]
+pub async fn find_forms_by_member(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> Result<Vec<FormSchema>, FormVaultError> {
+    let forms = sqlx::query_as!(
+        FormSchema,
+        r#"
+        SELECT f.id, f.name, f.developer_id, f.team_id, f.key_id, f.allowed_origins,
+               f.success_url, f.error_url, f.zero_knowledge, f.created_at
+        FROM form_schemas f
+        JOIN team_members m ON m.team_id = f.team_id
+        WHERE m.developer_id = $1
+        ORDER BY f.created_at DESC
+        "#,
+        developer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(forms)
+}
+
+/// Insert a new form
+#[instrument(skip_all)]
+pub async fn save_form<'e>(
+    executor: impl PgExecutor<'e>,
+    form: &FormSchema,
+) -> Result<(), FormVaultError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO form_schemas
+            (id, name, developer_id, team_id, key_id, allowed_origins, success_url,
+             error_url, zero_knowledge, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#,
+        form.id,
+        form.name,
+        form.developer_id,
+        form.team_id,
+        form.key_id,
+        &form.allowed_origins,
+        form.success_url,
+        form.error_url,
+        form.zero_knowledge,
+        form.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// `form_submissions` row with the JSON metadata still wrapped
+#[derive(sqlx::FromRow)]
+pub(crate) struct SubmissionRow {
+    id: Uuid,
+    form_schema_id: Uuid,
+    encrypted_data: String,
+    encrypted_key: String,
+    key_id: Option<Uuid>,
+    metadata: Json<SubmissionMetadata>,
+    created_at: DateTime<Utc>,
+    status: SubmissionStatus,
+    failure_reason: Option<String>,
+}
+
+impl From<SubmissionRow> for FormSubmission {
+    fn from(row: SubmissionRow) -> Self {
+        FormSubmission {
+            id: row.id,
+            form_schema_id: row.form_schema_id,
+            encrypted_data: row.encrypted_data,
+            encrypted_key: row.encrypted_key,
+            key_id: row.key_id,
+            metadata: row.metadata.0,
+            created_at: row.created_at,
+            status: row.status,
+            failure_reason: row.failure_reason,
+        }
+    }
+}
+
+/// Persist the key version a form is pinned to
+#[instrument(skip_all)]
+pub async fn update_form_key<'e>(
+    executor: impl PgExecutor<'e>,
+    form: &FormSchema,
+) -> Result<(), FormVaultError> {
+    sqlx::query!(
+        "UPDATE form_schemas SET key_id = $1 WHERE id = $2",
+        form.key_id,
+        form.id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
 }
 
-/// TODO: Implement database update for submission status
-pub async fn update_submission_status(submission: &FormSubmission) -> Result<(), FormVaultError> {
-    unimplemented!("Database update")
+/// Pin a developer's forms in a team that follow their current key to the
+/// version they have now; returns how many forms were pinned
+#[instrument(skip_all)]
+pub async fn pin_inherited_forms<'e>(
+    executor: impl PgExecutor<'e>,
+    team_id: Uuid,
+    developer_id: Uuid,
+) -> Result<u64, FormVaultError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE form_schemas f SET key_id = k.id
+        FROM public_keys k
+        WHERE f.team_id = $1 AND f.developer_id = $2 AND f.key_id IS NULL
+          AND k.developer_id = f.developer_id AND k.form_id IS NULL AND k.retired_at IS NULL
+        "#,
+        team_id,
+        developer_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Persist the settings of a form: its allowed origins, redirect URLs and
+/// whether it is zero-knowledge
+#[instrument(skip_all)]
+pub async fn update_form_settings<'e>(
+    executor: impl PgExecutor<'e>,
+    form: &FormSchema,
+) -> Result<(), FormVaultError> {
+    sqlx::query!(
+        r#"
+        UPDATE form_schemas
+        SET allowed_origins = $1, success_url = $2, error_url = $3, zero_knowledge = $4
+        WHERE id = $5
+        "#,
+        &form.allowed_origins,
+        form.success_url,
+        form.error_url,
+        form.zero_knowledge,
+        form.id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Find a submission by ID
+#[instrument(skip_all)]
+pub async fn find_submission_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<FormSubmission>, FormVaultError> {
+    let row = sqlx::query_as!(
+        SubmissionRow,
+        r#"
+        SELECT id, form_schema_id, encrypted_data, encrypted_key, key_id,
+               metadata AS "metadata: Json<SubmissionMetadata>", created_at,
+               status AS "status: SubmissionStatus", failure_reason
+        FROM form_submissions
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(FormSubmission::from))
+}
+
+/// Submissions of a form, newest first; `limit: None` returns all of them
+#[instrument(skip_all)]
+pub async fn find_submissions_by_form(
+    pool: &PgPool,
+    form_id: Uuid,
+    limit: Option<i64>,
+    offset: i64,
+) -> Result<Vec<FormSubmission>, FormVaultError> {
+    let rows = sqlx::query_as!(
+        SubmissionRow,
+        r#"
+        SELECT id, form_schema_id, encrypted_data, encrypted_key, key_id,
+               metadata AS "metadata: Json<SubmissionMetadata>", created_at,
+               status AS "status: SubmissionStatus", failure_reason
+        FROM form_submissions
+        WHERE form_schema_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        form_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(FormSubmission::from).collect())
+}
+
+/// Insert a new submission row
+#[instrument(skip_all)]
+pub async fn save_submission<'e>(
+    executor: impl PgExecutor<'e>,
+    submission: &FormSubmission,
+) -> Result<(), FormVaultError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO form_submissions
+            (id, form_schema_id, encrypted_data, encrypted_key, key_id, metadata, created_at, status, failure_reason)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        submission.id,
+        submission.form_schema_id,
+        submission.encrypted_data,
+        submission.encrypted_key,
+        submission.key_id,
+        Json(&submission.metadata) as _,
+        submission.created_at,
+        submission.status as SubmissionStatus,
+        submission.failure_reason
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Persist the current status and failure reason of a submission
+#[instrument(skip_all)]
+pub async fn update_submission_status<'e>(
+    executor: impl PgExecutor<'e>,
+    submission: &FormSubmission,
+) -> Result<(), FormVaultError> {
+    let result = sqlx::query!(
+        "UPDATE form_submissions SET status = $1, failure_reason = $2 WHERE id = $3",
+        submission.status as SubmissionStatus,
+        submission.failure_reason,
+        submission.id
+    )
+    .execute(executor)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(FormVaultError::SubmissionNotFound);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,33 @@
+use sqlx::PgPool;
+
+use crate::errors::FormVaultResult;
+use tracing::instrument;
+
+/// Round trip to the database through the pool
+#[instrument(skip_all)]
+pub async fn ping(pool: &PgPool) -> FormVaultResult<()> {
+    sqlx::query!("SELECT 1 AS one").fetch_one(pool).await?;
+    Ok(())
+}
+
+/// Version of the newest migration applied by `sqlx migrate`.
+///
+/// `None` when the schema was created some other way and the
+/// `_sqlx_migrations` table does not exist.
+#[instrument(skip_all)]
+pub async fn latest_migration(pool: &PgPool) -> FormVaultResult<Option<i64>> {
+    let tracked =
+        sqlx::query_scalar!(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "tracked!""#)
+            .fetch_one(pool)
+            .await?;
+    if !tracked {
+        return Ok(None);
+    }
+
+    // Not checked at compile time: the table only exists once migrations ran
+    let version: Option<i64> =
+        sqlx::query_scalar("SELECT max(version) FROM _sqlx_migrations WHERE success")
+            .fetch_one(pool)
+            .await?;
+    Ok(version)
+}
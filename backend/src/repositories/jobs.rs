@@ -0,0 +1,192 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::jobs::{JobRecord, JobStatus};
+use tracing::instrument;
+
+/// Insert a new job; accepts a transaction so jobs commit with the work
+/// that produced them
+#[instrument(skip_all)]
+pub async fn insert_job<'e>(executor: impl PgExecutor<'e>, job: &JobRecord) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO jobs
+            (id, queue, payload, status, attempts, max_attempts, run_at, request_id, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        job.id,
+        job.queue,
+        job.payload,
+        job.status as JobStatus,
+        job.attempts,
+        job.max_attempts,
+        job.run_at,
+        job.request_id,
+        job.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Claim the next runnable job of `queue`.
+///
+/// Runnable means queued and due, or running with a lock older than
+/// `stale_before` (its worker died). `SKIP LOCKED` lets any number of
+/// workers poll the same queue without blocking each other.
+#[instrument(skip_all)]
+pub async fn claim_next_job(
+    pool: &PgPool,
+    queue: &str,
+    stale_before: DateTime<Utc>,
+) -> FormVaultResult<Option<JobRecord>> {
+    let job = sqlx::query_as!(
+        JobRecord,
+        r#"
+        UPDATE jobs
+        SET status = 'running', locked_at = now(), attempts = attempts + 1
+        WHERE id = (
+            SELECT id FROM jobs
+            WHERE queue = $1
+              AND ((status = 'queued' AND run_at <= now())
+                   OR (status = 'running' AND locked_at < $2))
+            ORDER BY run_at
+            FOR UPDATE SKIP LOCKED
+            LIMIT 1
+        )
+        RETURNING id, queue, payload, status AS "status: JobStatus", attempts, max_attempts,
+                  run_at, locked_at, last_error, request_id, created_at, completed_at
+        "#,
+        queue,
+        stale_before
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
+
+/// Mark a job as successfully finished
+#[instrument(skip_all)]
+pub async fn complete_job(pool: &PgPool, id: Uuid) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET status = 'completed', locked_at = NULL, last_error = NULL, completed_at = now()
+        WHERE id = $1
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Put a failed job back in the queue to run again at `run_at`
+#[instrument(skip_all)]
+pub async fn retry_job(
+    pool: &PgPool,
+    id: Uuid,
+    error: &str,
+    run_at: DateTime<Utc>,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET status = 'queued', locked_at = NULL, last_error = $2, run_at = $3
+        WHERE id = $1
+        "#,
+        id,
+        error,
+        run_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Move a job that ran out of attempts to the dead-letter state
+#[instrument(skip_all)]
+pub async fn bury_job(pool: &PgPool, id: Uuid, error: &str) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET status = 'dead', locked_at = NULL, last_error = $2
+        WHERE id = $1
+        "#,
+        id,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Give a dead job a fresh set of attempts
+#[instrument(skip_all)]
+pub async fn requeue_dead_job(pool: &PgPool, id: Uuid) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE jobs
+        SET status = 'queued', attempts = 0, run_at = now()
+        WHERE id = $1 AND status = 'dead'
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Find a job by ID
+#[instrument(skip_all)]
+pub async fn find_job(pool: &PgPool, id: Uuid) -> FormVaultResult<Option<JobRecord>> {
+    let job = sqlx::query_as!(
+        JobRecord,
+        r#"
+        SELECT id, queue, payload, status AS "status: JobStatus", attempts, max_attempts,
+               run_at, locked_at, last_error, request_id, created_at, completed_at
+        FROM jobs
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(job)
+}
+
+/// Backlog of a queue: jobs that are due but not yet claimed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueBacklog {
+    pub due: i64,
+    /// `run_at` of the job that has waited longest
+    pub oldest_due: Option<DateTime<Utc>>,
+}
+
+#[instrument(skip_all)]
+pub async fn find_queue_backlog(pool: &PgPool, queue: &str) -> FormVaultResult<QueueBacklog> {
+    let row = sqlx::query!(
+        r#"
+        SELECT count(*) AS "due!", min(run_at) AS oldest_due
+        FROM jobs
+        WHERE queue = $1 AND status = 'queued' AND run_at <= now()
+        "#,
+        queue
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(QueueBacklog {
+        due: row.due,
+        oldest_due: row.oldest_due,
+    })
+}
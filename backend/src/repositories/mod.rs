@@ -1,2 +1,14 @@
+pub mod api_keys;
+pub mod audit;
+pub mod developers_repository;
+pub mod email_tokens;
 pub mod encryption;
+pub mod fields;
 pub mod form;
+pub mod health;
+pub mod jobs;
+pub mod notification;
+pub mod public_keys;
+pub mod sessions;
+pub mod teams;
+pub mod two_factor;
@@ -0,0 +1,226 @@
+use crate::errors::FormVaultResult;
+use crate::models::forms::notification_target::{
+    ChannelKind, DeliveryStatus, NotificationDelivery, NotificationTarget,
+};
+use crate::models::forms::submission::SubmissionStatus;
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+/// Insert a new notification target
+#[instrument(skip_all)]
+pub async fn create_target(pool: &PgPool, target: &NotificationTarget) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO form_notification_targets
+            (id, form_id, channel, destination, enabled, status_filter, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        target.id,
+        target.form_id,
+        target.channel as ChannelKind,
+        target.destination,
+        target.enabled,
+        &target.status_filter as &[SubmissionStatus],
+        target.created_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// All targets of a form, enabled or not
+#[instrument(skip_all)]
+pub async fn find_targets_by_form<'e>(
+    executor: impl PgExecutor<'e>,
+    form_id: Uuid,
+) -> FormVaultResult<Vec<NotificationTarget>> {
+    let targets = sqlx::query_as!(
+        NotificationTarget,
+        r#"
+        SELECT id, form_id, channel AS "channel: ChannelKind", destination, enabled,
+               status_filter AS "status_filter: Vec<SubmissionStatus>", created_at
+        FROM form_notification_targets
+        WHERE form_id = $1
+        ORDER BY created_at
+        "#,
+        form_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(targets)
+}
+
+/// Find a target by ID
+#[instrument(skip_all)]
+pub async fn find_target_by_id(
+    pool: &PgPool,
+    target_id: Uuid,
+) -> FormVaultResult<Option<NotificationTarget>> {
+    let target = sqlx::query_as!(
+        NotificationTarget,
+        r#"
+        SELECT id, form_id, channel AS "channel: ChannelKind", destination, enabled,
+               status_filter AS "status_filter: Vec<SubmissionStatus>", created_at
+        FROM form_notification_targets
+        WHERE id = $1
+        "#,
+        target_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(target)
+}
+
+/// Update the destination, enable flag and status filter of a target
+#[instrument(skip_all)]
+pub async fn update_target(pool: &PgPool, target: &NotificationTarget) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE form_notification_targets
+        SET destination = $1, enabled = $2, status_filter = $3
+        WHERE id = $4
+        "#,
+        target.destination,
+        target.enabled,
+        &target.status_filter as &[SubmissionStatus],
+        target.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a target; its delivery history goes with it
+#[instrument(skip_all)]
+pub async fn delete_target(pool: &PgPool, target_id: Uuid) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM form_notification_targets WHERE id = $1",
+        target_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Insert a pending delivery; scheduling the same submission for the same
+/// target twice is a no-op
+#[instrument(skip_all)]
+pub async fn create_delivery<'e>(
+    executor: impl PgExecutor<'e>,
+    delivery: &NotificationDelivery,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO notification_deliveries
+            (id, target_id, submission_id, status, attempts, last_error, delivered_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (target_id, submission_id) DO NOTHING
+        "#,
+        delivery.id,
+        delivery.target_id,
+        delivery.submission_id,
+        delivery.status as DeliveryStatus,
+        delivery.attempts,
+        delivery.last_error,
+        delivery.delivered_at,
+        delivery.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// The delivery of `submission_id` to `target_id`, if one was scheduled
+#[instrument(skip_all)]
+pub async fn find_delivery(
+    pool: &PgPool,
+    target_id: Uuid,
+    submission_id: Uuid,
+) -> FormVaultResult<Option<NotificationDelivery>> {
+    let delivery = sqlx::query_as!(
+        NotificationDelivery,
+        r#"
+        SELECT id, target_id, submission_id, status AS "status: DeliveryStatus", attempts,
+               last_error, delivered_at, created_at
+        FROM notification_deliveries
+        WHERE target_id = $1 AND submission_id = $2
+        "#,
+        target_id,
+        submission_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(delivery)
+}
+
+/// Persist the outcome of a delivery attempt
+#[instrument(skip_all)]
+pub async fn update_delivery(
+    pool: &PgPool,
+    delivery: &NotificationDelivery,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE notification_deliveries
+        SET status = $1, attempts = $2, last_error = $3, delivered_at = $4
+        WHERE id = $5
+        "#,
+        delivery.status as DeliveryStatus,
+        delivery.attempts,
+        delivery.last_error,
+        delivery.delivered_at,
+        delivery.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Number of deliveries of a submission that have not succeeded yet
+#[instrument(skip_all)]
+pub async fn count_undelivered(pool: &PgPool, submission_id: Uuid) -> FormVaultResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM notification_deliveries
+        WHERE submission_id = $1 AND status <> 'delivered'
+        "#,
+        submission_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Delivery history of a submission across all targets
+#[instrument(skip_all)]
+pub async fn find_deliveries_by_submission(
+    pool: &PgPool,
+    submission_id: Uuid,
+) -> FormVaultResult<Vec<NotificationDelivery>> {
+    let deliveries = sqlx::query_as!(
+        NotificationDelivery,
+        r#"
+        SELECT id, target_id, submission_id, status AS "status: DeliveryStatus", attempts,
+               last_error, delivered_at, created_at
+        FROM notification_deliveries
+        WHERE submission_id = $1
+        ORDER BY created_at
+        "#,
+        submission_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deliveries)
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::models::public_key::{KeyAlgorithm, PublicKeyVersion};
+use tracing::instrument;
+
+/// A key version with the number of submissions encrypted to it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KeySummary {
+    pub id: Uuid,
+    pub form_id: Option<Uuid>,
+    pub version: i32,
+    pub algorithm: KeyAlgorithm,
+    pub fingerprint: String,
+    pub created_at: DateTime<Utc>,
+    pub retired_at: Option<DateTime<Utc>>,
+    pub submissions: i64,
+}
+
+/// A submission that can only be read with a retired private key.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RetiredKeySubmission {
+    pub submission_id: Uuid,
+    pub form_id: Uuid,
+    pub key_id: Uuid,
+    pub key_version: i32,
+    pub fingerprint: String,
+    pub retired_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Insert a key version
+#[instrument(skip_all)]
+pub async fn create_public_key<'e>(
+    executor: impl PgExecutor<'e>,
+    key: &PublicKeyVersion,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO public_keys
+            (id, developer_id, form_id, version, algorithm, public_key, fingerprint, created_at, retired_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        key.id,
+        key.developer_id,
+        key.form_id,
+        key.version,
+        key.algorithm as KeyAlgorithm,
+        key.public_key,
+        key.fingerprint,
+        key.created_at,
+        key.retired_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Retire the current key of the developer (`form_id: None`) or of one of
+/// their forms; returns its id, if there was one
+#[instrument(skip_all)]
+pub async fn retire_current_key<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+    form_id: Option<Uuid>,
+) -> FormVaultResult<Option<Uuid>> {
+    let id = sqlx::query_scalar!(
+        r#"
+        UPDATE public_keys SET retired_at = NOW()
+        WHERE developer_id = $1 AND form_id IS NOT DISTINCT FROM $2 AND retired_at IS NULL
+        RETURNING id
+        "#,
+        developer_id,
+        form_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(id)
+}
+
+/// Version number the next key of the developer or form gets
+#[instrument(skip_all)]
+pub async fn next_key_version<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+    form_id: Option<Uuid>,
+) -> FormVaultResult<i32> {
+    let version = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(MAX(version), 0) + 1 AS "version!"
+        FROM public_keys
+        WHERE developer_id = $1 AND form_id IS NOT DISTINCT FROM $2
+        "#,
+        developer_id,
+        form_id
+    )
+    .fetch_one(executor)
+    .await?;
+
+    Ok(version)
+}
+
+/// Find a key version by ID
+#[instrument(skip_all)]
+pub async fn find_public_key_by_id(
+    pool: &PgPool,
+    id: Uuid,
+) -> FormVaultResult<Option<PublicKeyVersion>> {
+    let key = sqlx::query_as!(
+        PublicKeyVersion,
+        r#"
+        SELECT id, developer_id, form_id, version, algorithm AS "algorithm: KeyAlgorithm",
+               public_key, fingerprint, created_at, retired_at
+        FROM public_keys
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(key)
+}
+
+/// The developer's (or form's) key version with the given fingerprint
+#[instrument(skip_all)]
+pub async fn find_public_key_by_fingerprint<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+    form_id: Option<Uuid>,
+    fingerprint: &str,
+) -> FormVaultResult<Option<PublicKeyVersion>> {
+    let key = sqlx::query_as!(
+        PublicKeyVersion,
+        r#"
+        SELECT id, developer_id, form_id, version, algorithm AS "algorithm: KeyAlgorithm",
+               public_key, fingerprint, created_at, retired_at
+        FROM public_keys
+        WHERE developer_id = $1 AND form_id IS NOT DISTINCT FROM $2 AND fingerprint = $3
+        "#,
+        developer_id,
+        form_id,
+        fingerprint
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(key)
+}
+
+/// The developer's own key that is not retired
+#[instrument(skip_all)]
+pub async fn find_current_key<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+) -> FormVaultResult<Option<PublicKeyVersion>> {
+    let key = sqlx::query_as!(
+        PublicKeyVersion,
+        r#"
+        SELECT id, developer_id, form_id, version, algorithm AS "algorithm: KeyAlgorithm",
+               public_key, fingerprint, created_at, retired_at
+        FROM public_keys
+        WHERE developer_id = $1 AND form_id IS NULL AND retired_at IS NULL
+        "#,
+        developer_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(key)
+}
+
+/// All key versions of a developer and their forms, developer keys first,
+/// newest first
+#[instrument(skip_all)]
+pub async fn find_key_summaries(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> FormVaultResult<Vec<KeySummary>> {
+    let keys = sqlx::query_as!(
+        KeySummary,
+        r#"
+        SELECT k.id, k.form_id, k.version, k.algorithm AS "algorithm: KeyAlgorithm", k.fingerprint,
+               k.created_at, k.retired_at, COUNT(s.id) AS "submissions!"
+        FROM public_keys k
+        LEFT JOIN form_submissions s ON s.key_id = k.id
+        WHERE k.developer_id = $1
+        GROUP BY k.id
+        ORDER BY k.form_id NULLS FIRST, k.version DESC
+        "#,
+        developer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(keys)
+}
+
+/// Submissions encrypted to one of the developer's retired keys, oldest first
+#[instrument(skip_all)]
+pub async fn find_submissions_on_retired_keys(
+    pool: &PgPool,
+    developer_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> FormVaultResult<Vec<RetiredKeySubmission>> {
+    let submissions = sqlx::query_as!(
+        RetiredKeySubmission,
+        r#"
+        SELECT s.id AS submission_id, s.form_schema_id AS form_id, k.id AS key_id,
+               k.version AS key_version, k.fingerprint,
+               k.retired_at AS "retired_at!", s.created_at
+        FROM form_submissions s
+        JOIN public_keys k ON k.id = s.key_id
+        WHERE k.developer_id = $1 AND k.retired_at IS NOT NULL
+        ORDER BY s.created_at, s.id
+        LIMIT $2 OFFSET $3
+        "#,
+        developer_id,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(submissions)
+}
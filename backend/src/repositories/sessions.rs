@@ -0,0 +1,114 @@
+use sqlx::{PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::models::users::session::Session;
+
+/// Insert a new session
+#[instrument(skip_all)]
+pub async fn create_session<'e>(
+    executor: impl PgExecutor<'e>,
+    session: &Session,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO sessions
+            (id, developer_id, token_hash, csrf_token_hash, ip_address, user_agent, expires_at,
+             created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        session.id,
+        session.developer_id,
+        session.token_hash,
+        session.csrf_token_hash,
+        session.ip_address,
+        session.user_agent,
+        session.expires_at,
+        session.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Look a session up by the hash of its token, whether or not it is still usable
+#[instrument(skip_all)]
+pub async fn find_session_by_hash(
+    pool: &PgPool,
+    token_hash: &str,
+) -> FormVaultResult<Option<Session>> {
+    let session = sqlx::query_as!(
+        Session,
+        r#"
+        SELECT id, developer_id, token_hash, csrf_token_hash, ip_address, user_agent,
+               expires_at, last_seen_at, revoked_at, created_at
+        FROM sessions
+        WHERE token_hash = $1
+        "#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(session)
+}
+
+/// Sessions of a developer that are neither revoked nor expired, newest first
+#[instrument(skip_all)]
+pub async fn find_active_sessions(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> FormVaultResult<Vec<Session>> {
+    let sessions = sqlx::query_as!(
+        Session,
+        r#"
+        SELECT id, developer_id, token_hash, csrf_token_hash, ip_address, user_agent,
+               expires_at, last_seen_at, revoked_at, created_at
+        FROM sessions
+        WHERE developer_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY created_at DESC
+        "#,
+        developer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Record that a session was just used; writes at most once a minute per session
+#[instrument(skip_all)]
+pub async fn touch_session(pool: &PgPool, id: Uuid) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET last_seen_at = NOW()
+        WHERE id = $1 AND (last_seen_at IS NULL OR last_seen_at < NOW() - INTERVAL '1 minute')
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Revoke a session of `developer_id`; false if there is no such active session
+#[instrument(skip_all)]
+pub async fn revoke_session(pool: &PgPool, developer_id: Uuid, id: Uuid) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW()
+        WHERE id = $1 AND developer_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        "#,
+        id,
+        developer_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::models::users::team::{Team, TeamMember, TeamRole};
+use tracing::instrument;
+
+/// A team as seen by one of its members.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Membership {
+    pub id: Uuid,
+    pub name: String,
+    pub role: TeamRole,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A member with the account details needed to show them.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MemberProfile {
+    pub developer_id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub role: TeamRole,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Insert a team
+#[instrument(skip_all)]
+pub async fn create_team<'e>(executor: impl PgExecutor<'e>, team: &Team) -> FormVaultResult<()> {
+    sqlx::query!(
+        "INSERT INTO teams (id, name, created_at) VALUES ($1, $2, $3)",
+        team.id,
+        team.name,
+        team.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Add a member, or change their role if they already belong to the team
+#[instrument(skip_all)]
+pub async fn upsert_member<'e>(
+    executor: impl PgExecutor<'e>,
+    member: &TeamMember,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO team_members (team_id, developer_id, role, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (team_id, developer_id) DO UPDATE SET role = EXCLUDED.role
+        "#,
+        member.team_id,
+        member.developer_id,
+        member.role as TeamRole,
+        member.created_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Create a team owned by `owner_id`, as part of the transaction on `conn`
+#[instrument(skip_all)]
+pub async fn create_team_with_owner(
+    conn: &mut PgConnection,
+    team: &Team,
+    owner_id: Uuid,
+) -> FormVaultResult<()> {
+    create_team(&mut *conn, team).await?;
+    upsert_member(
+        &mut *conn,
+        &TeamMember::new(team.id, owner_id, TeamRole::Owner),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Role of `developer_id` in `team_id`, if they are a member
+#[instrument(skip_all)]
+pub async fn find_role(
+    pool: &PgPool,
+    team_id: Uuid,
+    developer_id: Uuid,
+) -> FormVaultResult<Option<TeamRole>> {
+    let role = sqlx::query_scalar!(
+        r#"
+        SELECT role AS "role: TeamRole"
+        FROM team_members
+        WHERE team_id = $1 AND developer_id = $2
+        "#,
+        team_id,
+        developer_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(role)
+}
+
+/// Teams the developer belongs to, oldest first
+#[instrument(skip_all)]
+pub async fn find_memberships(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> FormVaultResult<Vec<Membership>> {
+    let teams = sqlx::query_as!(
+        Membership,
+        r#"
+        SELECT t.id, t.name, m.role AS "role: TeamRole", t.created_at
+        FROM teams t
+        JOIN team_members m ON m.team_id = t.id
+        WHERE m.developer_id = $1
+        ORDER BY t.created_at, t.id
+        "#,
+        developer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(teams)
+}
+
+/// Members of a team, most privileged first
+#[instrument(skip_all)]
+pub async fn find_members(pool: &PgPool, team_id: Uuid) -> FormVaultResult<Vec<MemberProfile>> {
+    let members = sqlx::query_as!(
+        MemberProfile,
+        r#"
+        SELECT d.id AS developer_id, d.name, d.email, m.role AS "role: TeamRole", m.created_at
+        FROM team_members m
+        JOIN developers d ON d.id = m.developer_id
+        WHERE m.team_id = $1
+        ORDER BY m.role DESC, m.created_at
+        "#,
+        team_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+/// Remove a member; false if they were not in the team
+#[instrument(skip_all)]
+pub async fn remove_member<'e>(
+    executor: impl PgExecutor<'e>,
+    team_id: Uuid,
+    developer_id: Uuid,
+) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM team_members WHERE team_id = $1 AND developer_id = $2",
+        team_id,
+        developer_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Number of owners left in a team. Their rows stay locked until the
+/// transaction ends, so concurrent demotions count one after the other.
+#[instrument(skip_all)]
+pub async fn count_owners(conn: &mut PgConnection, team_id: Uuid) -> FormVaultResult<i64> {
+    let owners = sqlx::query_scalar!(
+        "SELECT developer_id FROM team_members WHERE team_id = $1 AND role = 'owner' FOR UPDATE",
+        team_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(owners.len() as i64)
+}
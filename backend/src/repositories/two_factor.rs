@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::errors::FormVaultResult;
+use crate::models::users::two_factor::{RecoveryCode, TotpCredential};
+
+/// Store a credential being enrolled, replacing an earlier unconfirmed one.
+///
+/// False, and nothing changes, when two-factor authentication is already
+/// enabled.
+#[instrument(skip_all)]
+pub async fn start_totp_enrollment(
+    pool: &PgPool,
+    credential: &TotpCredential,
+) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO totp_credentials (developer_id, secret, created_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (developer_id) DO UPDATE
+        SET secret = EXCLUDED.secret, created_at = EXCLUDED.created_at,
+            last_used_step = NULL, failed_attempts = 0, locked_until = NULL
+        WHERE totp_credentials.confirmed_at IS NULL
+        "#,
+        credential.developer_id,
+        credential.secret,
+        credential.created_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// The credential of a developer, confirmed or not
+#[instrument(skip_all)]
+pub async fn find_totp_credential<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+) -> FormVaultResult<Option<TotpCredential>> {
+    let credential = sqlx::query_as!(
+        TotpCredential,
+        r#"
+        SELECT developer_id, secret, confirmed_at, last_used_step, failed_attempts,
+               locked_until, created_at
+        FROM totp_credentials
+        WHERE developer_id = $1
+        "#,
+        developer_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(credential)
+}
+
+/// Turn two-factor authentication on with the code of time step `step`;
+/// false if it was already on
+#[instrument(skip_all)]
+pub async fn confirm_totp_credential<'e>(
+    executor: impl PgExecutor<'e>,
+    developer_id: Uuid,
+    step: i64,
+) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE totp_credentials
+        SET confirmed_at = NOW(), last_used_step = $2, failed_attempts = 0
+        WHERE developer_id = $1 AND confirmed_at IS NULL
+        "#,
+        developer_id,
+        step
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Accept the code of time step `step`; false if a code of that step or a
+/// later one was accepted already, so that the code is being replayed
+#[instrument(skip_all)]
+pub async fn record_totp_step(
+    pool: &PgPool,
+    developer_id: Uuid,
+    step: i64,
+) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE totp_credentials
+        SET last_used_step = $2, failed_attempts = 0, locked_until = NULL
+        WHERE developer_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+        developer_id,
+        step
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Count a wrong code; the `max_attempts`th in a row locks codes out until
+/// `locked_until` and starts the count over
+#[instrument(skip_all)]
+pub async fn record_failed_attempt(
+    pool: &PgPool,
+    developer_id: Uuid,
+    max_attempts: i32,
+    locked_until: DateTime<Utc>,
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE totp_credentials
+        SET failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0
+                                   ELSE failed_attempts + 1 END,
+            locked_until = CASE WHEN failed_attempts + 1 >= $2 THEN $3
+                                ELSE locked_until END
+        WHERE developer_id = $1
+        "#,
+        developer_id,
+        max_attempts,
+        locked_until
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Forget earlier wrong codes after a right one
+#[instrument(skip_all)]
+pub async fn reset_failed_attempts(pool: &PgPool, developer_id: Uuid) -> FormVaultResult<()> {
+    sqlx::query!(
+        "UPDATE totp_credentials SET failed_attempts = 0, locked_until = NULL WHERE developer_id = $1",
+        developer_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Turn two-factor authentication off, dropping the secret and the
+/// recovery codes
+#[instrument(skip_all)]
+pub async fn delete_two_factor(conn: &mut PgConnection, developer_id: Uuid) -> FormVaultResult<()> {
+    sqlx::query!(
+        "DELETE FROM recovery_codes WHERE developer_id = $1",
+        developer_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM totp_credentials WHERE developer_id = $1",
+        developer_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Replace every recovery code of the developer with `codes`
+#[instrument(skip_all)]
+pub async fn replace_recovery_codes(
+    conn: &mut PgConnection,
+    developer_id: Uuid,
+    codes: &[RecoveryCode],
+) -> FormVaultResult<()> {
+    sqlx::query!(
+        "DELETE FROM recovery_codes WHERE developer_id = $1",
+        developer_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for code in codes {
+        sqlx::query!(
+            r#"
+            INSERT INTO recovery_codes (id, developer_id, code_hash, created_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            code.id,
+            code.developer_id,
+            code.code_hash,
+            code.created_at
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Use up an unused recovery code of the developer; false if there is none
+/// with this hash
+#[instrument(skip_all)]
+pub async fn consume_recovery_code(
+    pool: &PgPool,
+    developer_id: Uuid,
+    code_hash: &str,
+) -> FormVaultResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE recovery_codes SET used_at = NOW()
+        WHERE developer_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+        developer_id,
+        code_hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// How many recovery codes the developer has left
+#[instrument(skip_all)]
+pub async fn count_unused_recovery_codes(
+    pool: &PgPool,
+    developer_id: Uuid,
+) -> FormVaultResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM recovery_codes
+        WHERE developer_id = $1 AND used_at IS NULL
+        "#,
+        developer_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
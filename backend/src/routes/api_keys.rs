@@ -0,0 +1,13 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn api_keys(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/api_keys")
+            .route(web::get().to(handlers::api_keys::list_api_keys))
+            .route(web::post().to(handlers::api_keys::create)),
+    )
+    .service(
+        web::resource("/api_keys/{key_id}").route(web::delete().to(handlers::api_keys::revoke)),
+    );
+}
@@ -0,0 +1,7 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn audit(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/audit").route(web::get().to(handlers::audit::list_events)))
+        .service(web::resource("/audit/verify").route(web::get().to(handlers::audit::verify)));
+}
@@ -0,0 +1,19 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn auth(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/auth/verify").route(web::post().to(handlers::auth::verify_email)))
+        .service(web::resource("/auth/login").route(web::post().to(handlers::auth::request_login)))
+        .service(
+            web::resource("/auth/session")
+                .route(web::post().to(handlers::auth::create_session_from_link))
+                .route(web::delete().to(handlers::auth::end_session)),
+        )
+        .service(
+            web::resource("/auth/sessions").route(web::get().to(handlers::auth::list_sessions)),
+        )
+        .service(
+            web::resource("/auth/sessions/{session_id}")
+                .route(web::delete().to(handlers::auth::revoke_session_by_id)),
+        );
+}
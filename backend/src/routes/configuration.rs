@@ -7,6 +7,22 @@ pub fn health_check(cfg: &mut web::ServiceConfig) {
     );
 }
 
+/// `/livez` and `/readyz` for orchestrators
+pub fn probes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/livez").route(web::get().to(handlers::configuration::livez)))
+        .service(web::resource("/readyz").route(web::get().to(handlers::configuration::readyz)));
+}
+
+pub fn metrics(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/metrics").route(web::get().to(handlers::configuration::metrics)));
+}
+
 pub fn api_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/").route(web::get().to(handlers::configuration::get_api_routes)));
 }
+
+pub fn openapi(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/openapi.json").route(web::get().to(handlers::configuration::openapi_json)),
+    );
+}
@@ -0,0 +1,31 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn developers(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/developers").route(web::post().to(handlers::developers::register)))
+        .service(
+            web::resource("/developers/me")
+                .route(web::get().to(handlers::developers::me))
+                .route(web::delete().to(handlers::developers::deactivate)),
+        )
+        .service(
+            web::resource("/developers/me/verification")
+                .route(web::post().to(handlers::developers::resend_verification)),
+        )
+        .service(
+            web::resource("/developers/me/api_key")
+                .route(web::post().to(handlers::developers::rotate_api_key)),
+        )
+        .service(
+            web::resource("/developers/me/public_key")
+                .route(web::put().to(handlers::developers::rotate_public_key)),
+        )
+        .service(
+            web::resource("/developers/me/public_keys")
+                .route(web::get().to(handlers::developers::list_public_keys)),
+        )
+        .service(
+            web::resource("/developers/me/public_keys/retired/submissions")
+                .route(web::get().to(handlers::developers::retired_key_submissions)),
+        );
+}
@@ -0,0 +1,14 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn fields(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/forms/{form_id}/fields")
+            .route(web::get().to(handlers::fields::list_fields))
+            .route(web::post().to(handlers::fields::create)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/fields/{field_id}")
+            .route(web::delete().to(handlers::fields::delete)),
+    );
+}
@@ -0,0 +1,36 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn forms(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/forms")
+            .route(web::get().to(handlers::forms::list_forms))
+            .route(web::post().to(handlers::forms::create_form)),
+    )
+    .service(
+        web::resource("/forms/{form_id}")
+            .route(web::get().to(handlers::forms::get_form))
+            .route(web::patch().to(handlers::forms::update_form)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/submissions")
+            .route(web::get().to(handlers::forms::list_submissions)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/submissions/{submission_id}")
+            .route(web::get().to(handlers::forms::get_submission)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/key")
+            .route(web::get().to(handlers::forms::get_form_key))
+            .route(web::put().to(handlers::forms::set_form_key)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/public_key")
+            .route(web::put().to(handlers::forms::rekey_form)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/export")
+            .route(web::get().to(handlers::forms::export_submissions)),
+    );
+}
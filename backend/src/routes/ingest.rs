@@ -0,0 +1,16 @@
+use crate::handlers;
+use actix_web::http::Method;
+use actix_web::web;
+
+pub fn ingest(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/f/{form_id}")
+            .route(web::get().to(handlers::ingest::hosted_form))
+            .route(web::post().to(handlers::ingest::submit))
+            .route(web::method(Method::OPTIONS).to(handlers::ingest::preflight)),
+    )
+    .service(web::resource("/f/{form_id}/thanks").route(web::get().to(handlers::ingest::thank_you)))
+    .service(
+        web::resource("/f/{form_id}/key").route(web::get().to(handlers::ingest::submission_key)),
+    );
+}
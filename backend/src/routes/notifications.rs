@@ -0,0 +1,15 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn notifications(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/forms/{form_id}/notifications")
+            .route(web::get().to(handlers::notifications::list_targets))
+            .route(web::post().to(handlers::notifications::create)),
+    )
+    .service(
+        web::resource("/forms/{form_id}/notifications/{target_id}")
+            .route(web::patch().to(handlers::notifications::update))
+            .route(web::delete().to(handlers::notifications::delete)),
+    );
+}
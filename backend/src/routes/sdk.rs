@@ -0,0 +1,7 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn sdk(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/sdk/v1").route(web::get().to(handlers::sdk::release)))
+        .service(web::resource("/sdk/v1/formvault.js").route(web::get().to(handlers::sdk::bundle)));
+}
@@ -0,0 +1,20 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn teams(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/teams")
+            .route(web::get().to(handlers::teams::list_teams))
+            .route(web::post().to(handlers::teams::create_team)),
+    )
+    .service(
+        web::resource("/teams/{team_id}/members")
+            .route(web::get().to(handlers::teams::list_members))
+            .route(web::post().to(handlers::teams::add_member)),
+    )
+    .service(
+        web::resource("/teams/{team_id}/members/{developer_id}")
+            .route(web::patch().to(handlers::teams::update_member))
+            .route(web::delete().to(handlers::teams::remove)),
+    );
+}
@@ -0,0 +1,19 @@
+use crate::handlers;
+use actix_web::web;
+
+pub fn two_factor(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/developers/me/two_factor")
+            .route(web::get().to(handlers::two_factor::status))
+            .route(web::post().to(handlers::two_factor::enroll))
+            .route(web::delete().to(handlers::two_factor::disable)),
+    )
+    .service(
+        web::resource("/developers/me/two_factor/confirm")
+            .route(web::post().to(handlers::two_factor::confirm)),
+    )
+    .service(
+        web::resource("/developers/me/two_factor/recovery_codes")
+            .route(web::post().to(handlers::two_factor::regenerate_recovery_codes)),
+    );
+}
@@ -0,0 +1,26 @@
+//! The JavaScript SDK that encrypts submissions in the browser.
+//!
+//! It is served at `/sdk/v1/formvault.js` and builds the same envelope as
+//! [`crate::repositories::encryption`], so browsers never send plaintext.
+//! Pages load it with Subresource Integrity, so the bytes under a version's
+//! path never change: anything else ships as a new version. `GET /sdk/v1`
+//! publishes the hash to pin.
+
+use std::sync::LazyLock;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha2::{Digest, Sha384};
+
+/// Release of the bundle served under [`PATH`]
+pub const VERSION: &str = "1.0.0";
+
+/// Where the bundle is served
+pub const PATH: &str = "/sdk/v1/formvault.js";
+
+/// The bundle itself
+pub const BUNDLE: &str = include_str!("sdk/formvault.js");
+
+/// `integrity` attribute value of the bundle: `sha384-<base64 digest>`
+pub static INTEGRITY: LazyLock<String> =
+    LazyLock::new(|| format!("sha384-{}", STANDARD.encode(Sha384::digest(BUNDLE))));
@@ -0,0 +1,258 @@
+/*! FormVault SDK v1.0.0 */
+// Encrypts form submissions in the browser and posts them to FormVault.
+//
+// Submissions are sealed into the same envelope as the server's
+// repositories::encryption: the JSON of the fields encrypted with a fresh
+// AES-256-GCM key, which is wrapped for the form's public key with
+// RSA-OAEP/SHA-256 or an ephemeral X25519 agreement. Only the envelope
+// leaves the page.
+//
+//   <script src="https://formvault.example/sdk/v1/formvault.js"
+//           integrity="sha384-…" crossorigin="anonymous"></script>
+//   <form data-formvault="FORM_ID">…</form>
+//
+// Forms marked with data-formvault are encrypted and posted when submitted;
+// FormVault.submit(formId, fields) does the same from code. The bytes served
+// under /sdk/v1 never change, so the integrity hash can be pinned.
+(function (root, factory) {
+  if (typeof module === "object" && module.exports) {
+    module.exports = factory(root);
+  } else {
+    root.FormVault = factory(root);
+  }
+})(typeof window !== "undefined" ? window : globalThis, (root) => {
+  "use strict";
+
+  const VERSION = "1.0.0";
+
+  const subtle = root.crypto && root.crypto.subtle;
+  const encoder = new TextEncoder();
+
+  // Forms post to the server the script was loaded from unless told otherwise
+  const script = typeof document !== "undefined" ? document.currentScript : null;
+  const origin = script && script.src ? new URL(script.src).origin : null;
+
+  // PKCS#8 header of a raw X25519 private key (RFC 8410)
+  const X25519_PKCS8 = Uint8Array.of(
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x04, 0x22, 0x04, 0x20,
+  );
+
+  /** An error from FormVault, with the API's error code when it sent one */
+  class FormVaultError extends Error {
+    constructor(message, code, status, details) {
+      super(message);
+      this.name = "FormVaultError";
+      this.code = code;
+      this.status = status;
+      this.details = details || [];
+    }
+  }
+
+  const concat = (...parts) => {
+    const joined = new Uint8Array(parts.reduce((length, part) => length + part.length, 0));
+    let offset = 0;
+    for (const part of parts) {
+      joined.set(part, offset);
+      offset += part.length;
+    }
+    return joined;
+  };
+
+  const base64url = (bytes) =>
+    btoa(String.fromCharCode(...bytes))
+      .replace(/\+/g, "-")
+      .replace(/\//g, "_")
+      .replace(/=+$/, "");
+
+  // Standard or URL-safe base64, padded or not
+  const fromBase64 = (text) =>
+    Uint8Array.from(atob(text.replace(/-/g, "+").replace(/_/g, "/").replace(/\s+/g, "")), (c) =>
+      c.charCodeAt(0),
+    );
+
+  const spki = (pem) => fromBase64(pem.replace(/-----[^-]+-----/g, ""));
+
+  const randomBytes = (length) => root.crypto.getRandomValues(new Uint8Array(length));
+
+  const aesGcm = async (keyBytes, iv, plaintext, additionalData) => {
+    const key = await subtle.importKey("raw", keyBytes, "AES-GCM", false, ["encrypt"]);
+    const params = additionalData ? { name: "AES-GCM", iv, additionalData } : { name: "AES-GCM", iv };
+    return new Uint8Array(await subtle.encrypt(params, key, plaintext));
+  };
+
+  const ephemeralKey = async (secret) => {
+    if (!secret) {
+      const pair = await subtle.generateKey({ name: "X25519" }, true, ["deriveBits"]);
+      return {
+        privateKey: pair.privateKey,
+        publicBytes: new Uint8Array(await subtle.exportKey("raw", pair.publicKey)),
+      };
+    }
+    const privateKey = await subtle.importKey("pkcs8", concat(X25519_PKCS8, secret), { name: "X25519" }, true, [
+      "deriveBits",
+    ]);
+    // The private JWK carries the public key too
+    const { x } = await subtle.exportKey("jwk", privateKey);
+    return { privateKey, publicBytes: fromBase64(x) };
+  };
+
+  const wrapKey = async (algorithm, publicKey, contentKey, ephemeralSecret) => {
+    if (algorithm === "rsa") {
+      const key = await subtle.importKey("spki", spki(publicKey), { name: "RSA-OAEP", hash: "SHA-256" }, false, [
+        "encrypt",
+      ]);
+      return new Uint8Array(await subtle.encrypt({ name: "RSA-OAEP" }, key, contentKey));
+    }
+
+    const recipient = await subtle.importKey("spki", spki(publicKey), { name: "X25519" }, true, []);
+    const recipientBytes = new Uint8Array(await subtle.exportKey("raw", recipient));
+    const ephemeral = await ephemeralKey(ephemeralSecret);
+    const shared = new Uint8Array(
+      await subtle.deriveBits({ name: "X25519", public: recipient }, ephemeral.privateKey, 256),
+    );
+    const kek = new Uint8Array(
+      await subtle.digest(
+        "SHA-256",
+        concat(encoder.encode("formvault/x25519/v1"), shared, ephemeral.publicBytes, recipientBytes),
+      ),
+    );
+    // The key-encryption key is used once, so the all-zero nonce is safe
+    return concat(ephemeral.publicBytes, await aesGcm(kek, new Uint8Array(12), contentKey));
+  };
+
+  /**
+   * Encrypt raw bytes to a form's key, as returned by `GET /f/{form_id}/key`.
+   *
+   * `secrets` fixes the random values ({ contentKey, nonce, ephemeralSecret })
+   * and only exists to reproduce test vectors; never pass it otherwise.
+   */
+  const envelope = async (plaintext, key, secrets = {}) => {
+    if (!subtle) {
+      throw new FormVaultError("This browser cannot encrypt submissions", "UNSUPPORTED");
+    }
+    if (key.algorithm !== "rsa" && key.algorithm !== "x25519") {
+      throw new FormVaultError(`Unsupported key algorithm ${key.algorithm}`, "UNSUPPORTED");
+    }
+    const header = "fv1." + key.algorithm;
+    const contentKey = secrets.contentKey || randomBytes(32);
+    const nonce = secrets.nonce || randomBytes(12);
+    const ciphertext = await aesGcm(contentKey, nonce, plaintext, encoder.encode(header));
+    const wrapped = await wrapKey(key.algorithm, key.public_key, contentKey, secrets.ephemeralSecret);
+    return {
+      encrypted_data: [header, base64url(nonce), base64url(ciphertext)].join("."),
+      encrypted_key: base64url(wrapped),
+    };
+  };
+
+  // Submissions are string maps; a repeated name keeps its last value
+  const entries = (fields) => {
+    const source =
+      typeof HTMLFormElement !== "undefined" && fields instanceof HTMLFormElement ? new FormData(fields) : fields;
+    const map = {};
+    for (const [name, value] of typeof source.entries === "function" ? source.entries() : Object.entries(source)) {
+      map[name] = String(value);
+    }
+    return map;
+  };
+
+  /** The body to post for `fields` (an object, FormData or form element) */
+  const seal = async (fields, key) => ({
+    ...(await envelope(encoder.encode(JSON.stringify(entries(fields))), key)),
+    key_id: key.key_id,
+  });
+
+  const endpoint = (options) => {
+    const base = options.endpoint || origin;
+    if (!base) {
+      throw new FormVaultError("No FormVault endpoint was given", "NO_ENDPOINT");
+    }
+    return base.replace(/\/+$/, "");
+  };
+
+  const formUrl = (formId, options) => `${endpoint(options)}/f/${encodeURIComponent(formId)}`;
+
+  const request = async (url, init) => {
+    const response = await root.fetch(url, { ...init, credentials: "omit" });
+    const body = await response.json().catch(() => ({}));
+    if (!response.ok) {
+      throw new FormVaultError(body.error || response.statusText, body.code, response.status, body.details);
+    }
+    return body;
+  };
+
+  /** The key submissions to a form are encrypted with */
+  const fetchKey = (formId, options = {}) =>
+    request(`${formUrl(formId, options)}/key`, { headers: { Accept: "application/json" } });
+
+  /** Encrypt `fields` and submit them; resolves to the submission receipt */
+  const submit = async (formId, fields, options = {}) => {
+    const key = options.key || (await fetchKey(formId, options));
+    return request(formUrl(formId, options), {
+      method: "POST",
+      headers: { "Content-Type": "application/json", Accept: "application/json" },
+      body: JSON.stringify(await seal(fields, key)),
+    });
+  };
+
+  // Post as a regular form so the browser follows the redirect to the
+  // success or error page
+  const navigate = (action, body) => {
+    const post = document.createElement("form");
+    post.method = "post";
+    post.action = action;
+    post.hidden = true;
+    for (const [name, value] of Object.entries(body)) {
+      const input = document.createElement("input");
+      input.type = "hidden";
+      input.name = name;
+      input.value = value;
+      post.append(input);
+    }
+    document.body.append(post);
+    post.submit();
+  };
+
+  /**
+   * Encrypt `form` whenever it is submitted.
+   *
+   * Options: `formId` (defaults to the form's data-formvault), `endpoint`,
+   * `key` to skip fetching it, and `onError(error)`.
+   */
+  const attach = (form, options = {}) => {
+    const formId = options.formId || form.dataset.formvault;
+    form.addEventListener("submit", async (event) => {
+      event.preventDefault();
+      try {
+        const action = form.getAttribute("action") ? form.action : formUrl(formId, options);
+        const key = options.key || (await fetchKey(formId, options));
+        navigate(action, await seal(form, key));
+      } catch (error) {
+        if (!options.onError) {
+          throw error;
+        }
+        options.onError(error);
+      }
+    });
+    return form;
+  };
+
+  if (typeof document !== "undefined") {
+    const attachAll = () => document.querySelectorAll("form[data-formvault]").forEach((form) => attach(form));
+    if (document.readyState === "loading") {
+      document.addEventListener("DOMContentLoaded", attachAll);
+    } else {
+      attachAll();
+    }
+  }
+
+  return {
+    version: VERSION,
+    supported: Boolean(subtle),
+    Error: FormVaultError,
+    envelope,
+    seal,
+    fetchKey,
+    submit,
+    attach,
+  };
+});
@@ -0,0 +1,126 @@
+//! Everything needed to start the service, in one value.
+//!
+//! [`Settings::from_env`] is what the binary uses; tests and benches build
+//! [`Settings`] explicitly instead of mutating the process environment.
+
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::jobs::WorkerConfig;
+use crate::mail;
+use crate::notifications::SmtpSettings;
+use crate::shutdown;
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// PostgreSQL connection string
+    pub database_url: String,
+    /// Interface to listen on
+    pub host: String,
+    /// Port to listen on; 0 picks a free one
+    pub port: u16,
+    /// Size of the database pool
+    pub max_connections: u32,
+    pub workers: WorkerConfig,
+    /// Outgoing mail server for email notifications
+    pub smtp: Option<SmtpSettings>,
+    /// How long a shutdown may drain in-flight work
+    pub shutdown_timeout: Duration,
+    /// Base URL of the dashboard, which verification and login links open
+    pub dashboard_url: String,
+    /// Bearer token Prometheus presents at `/metrics`; the metrics are not
+    /// served without one
+    pub metrics_token: Option<String>,
+    /// Reverse proxies allowed to report the client's address
+    pub trusted_proxies: TrustedProxies,
+}
+
+impl Settings {
+    /// Defaults for everything but the database
+    pub fn new(database_url: impl Into<String>) -> Self {
+        Self {
+            database_url: database_url.into(),
+            host: "0.0.0.0".to_string(),
+            port: 0,
+            max_connections: 5,
+            workers: WorkerConfig::default(),
+            smtp: None,
+            shutdown_timeout: shutdown::DEFAULT_TIMEOUT,
+            dashboard_url: mail::DEFAULT_DASHBOARD_URL.to_string(),
+            metrics_token: None,
+            trusted_proxies: TrustedProxies::default(),
+        }
+    }
+
+    /// Read `DATABASE_URL`, `PORT`, `JOB_WORKERS`, `JOB_POLL_INTERVAL_MS`,
+    /// `SMTP_URL`, `SMTP_FROM`, `SHUTDOWN_TIMEOUT_SECS`, `DASHBOARD_URL`,
+    /// `METRICS_TOKEN` and `TRUSTED_PROXIES`.
+    ///
+    /// Fails with [`std::io::ErrorKind::NotFound`] without a `DATABASE_URL`,
+    /// and with [`std::io::ErrorKind::InvalidInput`] for an unusable `PORT`
+    /// or `TRUSTED_PROXIES`.
+    pub fn from_env() -> std::io::Result<Self> {
+        let database_url = env::var("DATABASE_URL").map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "DATABASE_URL missing")
+        })?;
+
+        let mut settings = Self::new(database_url);
+        if let Ok(port) = env::var("PORT") {
+            settings.port = port.parse().map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("PORT is not a port number: {port}"),
+                )
+            })?;
+        }
+        settings.workers = WorkerConfig::from_env();
+        settings.smtp = SmtpSettings::from_env();
+        settings.shutdown_timeout = shutdown::timeout_from_env();
+        if let Ok(url) = env::var("DASHBOARD_URL") {
+            settings.dashboard_url = url;
+        }
+        settings.metrics_token = env::var("METRICS_TOKEN")
+            .ok()
+            .filter(|token| !token.trim().is_empty());
+        if let Ok(proxies) = env::var("TRUSTED_PROXIES") {
+            settings.trusted_proxies = TrustedProxies::parse(&proxies).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("TRUSTED_PROXIES is not a list of IP addresses: {proxies}"),
+                )
+            })?;
+        }
+        Ok(settings)
+    }
+
+    /// Address the listener binds to
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Reverse proxies whose `Forwarded`/`X-Forwarded-For` headers are believed,
+/// from `TRUSTED_PROXIES`; anyone else could put any address in them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Vec<IpAddr>);
+
+impl TrustedProxies {
+    pub fn new(proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        Self(proxies.into_iter().collect())
+    }
+
+    /// Parse a comma-separated list of IP addresses
+    pub fn parse(list: &str) -> Option<Self> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| proxy.parse().ok())
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.contains(&ip)
+    }
+}
@@ -0,0 +1,142 @@
+//! Coordinated shutdown.
+//!
+//! On SIGINT or SIGTERM the server stops accepting connections, lets the
+//! submissions being stored finish, drains the job workers and closes the
+//! database pool, all within one deadline, then stops and logs what it had
+//! to give up on. Queued jobs are durable, so only work in progress when the
+//! deadline hits is at risk: abandoned jobs are reclaimed once their lock
+//! goes stale, abandoned submissions are answered with an error.
+
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use actix_web::dev::ServerHandle;
+use sqlx::PgPool;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::jobs::Workers;
+
+/// How long shutdown may take when `SHUTDOWN_TIMEOUT_SECS` is not set
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The shutdown deadline from `SHUTDOWN_TIMEOUT_SECS`
+pub fn timeout_from_env() -> Duration {
+    env::var("SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map_or(DEFAULT_TIMEOUT, Duration::from_secs)
+}
+
+/// Requests that a shutdown waits for, such as submissions being stored.
+#[derive(Debug, Clone, Default)]
+pub struct InFlight {
+    count: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+/// Marks one request as in flight until dropped
+pub struct InFlightGuard(InFlight);
+
+impl InFlight {
+    pub fn enter(&self) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Wait until nothing is in flight or `deadline` passes, returning how
+    /// many requests are still running
+    pub async fn drained_by(&self, deadline: Instant) -> usize {
+        loop {
+            let idle = self.idle.notified();
+            tokio::pin!(idle);
+            // Register before checking, so a guard dropped in between is seen
+            idle.as_mut().enable();
+            if self.count() == 0 {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                return self.count();
+            }
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix
+pub async fn signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Shut the server down in order, giving up on whatever is left at
+/// `timeout`. The server future resolves once this returns.
+pub async fn drain(
+    server: ServerHandle,
+    submissions: InFlight,
+    workers: Workers,
+    pool: PgPool,
+    timeout: Duration,
+) {
+    let started = Instant::now();
+    let deadline = started + timeout;
+    info!(
+        submissions_in_flight = submissions.count(),
+        timeout_secs = timeout.as_secs_f64(),
+        "Shutting down, no longer accepting connections"
+    );
+
+    server.pause().await;
+    let submissions_abandoned = submissions.drained_by(deadline).await;
+    let jobs_abandoned = workers.shutdown_until(deadline).await;
+    let pool_closed = tokio::time::timeout_at(deadline, pool.close())
+        .await
+        .is_ok();
+
+    let clean = submissions_abandoned == 0 && jobs_abandoned == 0 && pool_closed;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if clean {
+        info!(elapsed_ms, "Shutdown complete, nothing was abandoned");
+    } else {
+        warn!(
+            elapsed_ms,
+            submissions_abandoned, jobs_abandoned, pool_closed, "Shutdown deadline reached"
+        );
+    }
+
+    // Anything still running past the deadline is cut off
+    server.stop(clean).await;
+}
@@ -0,0 +1,190 @@
+//! The in-memory backend, for tests and embedding.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{DeveloperRepository, FormRepository, SubmissionRepository};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::forms::FormSubmission;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::users::developer::Developer;
+use crate::models::users::registration::Registration;
+
+/// Keeps everything in maps behind one lock, enforcing the same unique
+/// emails and form references as the database schemas.
+#[derive(Default)]
+pub struct MemoryStore {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    developers: HashMap<Uuid, Developer>,
+    forms: HashMap<Uuid, FormSchema>,
+    submissions: HashMap<Uuid, FormSubmission>,
+}
+
+impl MemoryStore {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        // A panic while holding the lock leaves the maps consistent, as
+        // every change is applied in one step
+        self.tables.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl DeveloperRepository for MemoryStore {
+    async fn insert(&self, developer: &Developer) -> FormVaultResult<()> {
+        let mut tables = self.tables();
+        if tables
+            .developers
+            .values()
+            .any(|existing| existing.email() == developer.email())
+        {
+            return Err(FormVaultError::DuplicateEmail);
+        }
+        tables.developers.insert(developer.id(), developer.clone());
+        Ok(())
+    }
+
+    async fn register(&self, registration: &Registration) -> FormVaultResult<()> {
+        DeveloperRepository::insert(self, &registration.developer).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<Developer>> {
+        Ok(self.tables().developers.get(&id).cloned())
+    }
+
+    async fn find_by_email(&self, email: &str) -> FormVaultResult<Option<Developer>> {
+        Ok(self
+            .tables()
+            .developers
+            .values()
+            .find(|developer| developer.email() == email)
+            .cloned())
+    }
+
+    async fn update(&self, developer: &Developer) -> FormVaultResult<()> {
+        let mut tables = self.tables();
+        if tables.developers.values().any(|existing| {
+            existing.id() != developer.id() && existing.email() == developer.email()
+        }) {
+            return Err(FormVaultError::DuplicateEmail);
+        }
+        if let Some(stored) = tables.developers.get_mut(&developer.id()) {
+            stored.set_contact(developer.name().to_string(), developer.email().to_string());
+        }
+        Ok(())
+    }
+
+    async fn mark_verified(&self, id: Uuid, at: DateTime<Utc>) -> FormVaultResult<()> {
+        if let Some(developer) = self.tables().developers.get_mut(&id) {
+            developer.mark_verified(at);
+        }
+        Ok(())
+    }
+
+    async fn deactivate(&self, id: Uuid) -> FormVaultResult<()> {
+        if let Some(developer) = self.tables().developers.get_mut(&id) {
+            developer.mark_inactive();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FormRepository for MemoryStore {
+    async fn insert(&self, form: &FormSchema) -> FormVaultResult<()> {
+        self.tables().forms.insert(form.id, form.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSchema>> {
+        Ok(self.tables().forms.get(&id).cloned())
+    }
+
+    async fn find_by_developer(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>> {
+        let mut forms: Vec<_> = self
+            .tables()
+            .forms
+            .values()
+            .filter(|form| form.developer_id == developer_id)
+            .cloned()
+            .collect();
+        forms.sort_by_key(|form| Reverse(form.created_at));
+        Ok(forms)
+    }
+
+    async fn find_by_member(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>> {
+        self.find_by_developer(developer_id).await
+    }
+
+    async fn update_key(&self, form: &FormSchema) -> FormVaultResult<()> {
+        if let Some(stored) = self.tables().forms.get_mut(&form.id) {
+            stored.key_id = form.key_id;
+        }
+        Ok(())
+    }
+
+    async fn update_settings(&self, form: &FormSchema) -> FormVaultResult<()> {
+        if let Some(stored) = self.tables().forms.get_mut(&form.id) {
+            stored.allowed_origins = form.allowed_origins.clone();
+            stored.success_url = form.success_url.clone();
+            stored.error_url = form.error_url.clone();
+            stored.zero_knowledge = form.zero_knowledge;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SubmissionRepository for MemoryStore {
+    async fn insert(&self, submission: &FormSubmission) -> FormVaultResult<()> {
+        let mut tables = self.tables();
+        if !tables.forms.contains_key(&submission.form_schema_id) {
+            return Err(FormVaultError::FormNotFound);
+        }
+        tables.submissions.insert(submission.id, submission.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSubmission>> {
+        Ok(self.tables().submissions.get(&id).cloned())
+    }
+
+    async fn find_by_form(
+        &self,
+        form_id: Uuid,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> FormVaultResult<Vec<FormSubmission>> {
+        let mut submissions: Vec<_> = self
+            .tables()
+            .submissions
+            .values()
+            .filter(|submission| submission.form_schema_id == form_id)
+            .cloned()
+            .collect();
+        submissions.sort_by_key(|submission| Reverse(submission.created_at));
+
+        let offset = usize::try_from(offset).unwrap_or(0);
+        let limit = limit.map_or(usize::MAX, |limit| usize::try_from(limit).unwrap_or(0));
+        Ok(submissions.into_iter().skip(offset).take(limit).collect())
+    }
+
+    async fn update_status(&self, submission: &FormSubmission) -> FormVaultResult<()> {
+        let mut tables = self.tables();
+        let stored = tables
+            .submissions
+            .get_mut(&submission.id)
+            .ok_or(FormVaultError::SubmissionNotFound)?;
+        stored.status = submission.status;
+        stored.failure_reason = submission.failure_reason.clone();
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Storage backends for developers, forms and submissions.
+//!
+//! Handlers reach these records through the [`DeveloperRepository`],
+//! [`FormRepository`] and [`SubmissionRepository`] traits rather than
+//! through SQL, so the same code runs on PostgreSQL, on a single SQLite file
+//! or entirely in memory. [`Storage`] bundles one implementation of each.
+//!
+//! The server itself always runs on [`Storage::postgres`], and the backend is
+//! not a setting: API keys, teams, public key versions, form keys, sessions,
+//! audit events and jobs are not behind these traits and only live in
+//! PostgreSQL. A single-binary SQLite deployment is out of scope until they
+//! are. The SQLite and in-memory stores are complete for the records they
+//! cover, and are there for embedding `formvault::storage` and for tests.
+
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::forms::FormSubmission;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::users::developer::Developer;
+use crate::models::users::registration::Registration;
+
+pub use memory::MemoryStore;
+pub use postgres::PgStore;
+pub use sqlite::SqliteStore;
+
+#[async_trait]
+pub trait DeveloperRepository: Send + Sync {
+    /// Store a new developer; fails with [`FormVaultError::DuplicateEmail`]
+    /// when the email is taken
+    async fn insert(&self, developer: &Developer) -> FormVaultResult<()>;
+    /// Store a signup all at once, or nothing of it; fails like
+    /// [`DeveloperRepository::insert`]. Stores without keys, teams, API
+    /// keys or an audit log keep only the developer.
+    async fn register(&self, registration: &Registration) -> FormVaultResult<()>;
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<Developer>>;
+    async fn find_by_email(&self, email: &str) -> FormVaultResult<Option<Developer>>;
+    /// Persist the name and email
+    async fn update(&self, developer: &Developer) -> FormVaultResult<()>;
+    /// Record when the email was verified, keeping an earlier verification
+    async fn mark_verified(&self, id: Uuid, at: DateTime<Utc>) -> FormVaultResult<()>;
+    async fn deactivate(&self, id: Uuid) -> FormVaultResult<()>;
+}
+
+#[async_trait]
+pub trait FormRepository: Send + Sync {
+    async fn insert(&self, form: &FormSchema) -> FormVaultResult<()>;
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSchema>>;
+    /// Forms created by the developer, newest first
+    async fn find_by_developer(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>>;
+    /// Forms of every team the developer belongs to, newest first; stores
+    /// without teams return the forms the developer created
+    async fn find_by_member(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>>;
+    /// Persist the key version the form is pinned to
+    async fn update_key(&self, form: &FormSchema) -> FormVaultResult<()>;
+    /// Persist the form's settings: its allowed origins, redirect URLs and
+    /// zero-knowledge flag
+    async fn update_settings(&self, form: &FormSchema) -> FormVaultResult<()>;
+}
+
+#[async_trait]
+pub trait SubmissionRepository: Send + Sync {
+    /// Store a new submission; fails with [`FormVaultError::FormNotFound`]
+    /// when its form does not exist
+    async fn insert(&self, submission: &FormSubmission) -> FormVaultResult<()>;
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSubmission>>;
+    /// Submissions of a form, newest first; `limit: None` returns all of them
+    async fn find_by_form(
+        &self,
+        form_id: Uuid,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> FormVaultResult<Vec<FormSubmission>>;
+    /// Persist the status and failure reason; fails with
+    /// [`FormVaultError::SubmissionNotFound`] for unknown submissions
+    async fn update_status(&self, submission: &FormSubmission) -> FormVaultResult<()>;
+}
+
+/// One repository of each kind, cheap to clone and share between workers.
+#[derive(Clone)]
+pub struct Storage {
+    developers: Arc<dyn DeveloperRepository>,
+    forms: Arc<dyn FormRepository>,
+    submissions: Arc<dyn SubmissionRepository>,
+}
+
+impl Storage {
+    /// Use one store for all three repositories
+    pub fn from_store<S>(store: S) -> Self
+    where
+        S: DeveloperRepository + FormRepository + SubmissionRepository + 'static,
+    {
+        let store = Arc::new(store);
+        Self {
+            developers: store.clone(),
+            forms: store.clone(),
+            submissions: store,
+        }
+    }
+
+    pub fn postgres(pool: sqlx::PgPool) -> Self {
+        Self::from_store(PgStore::new(pool))
+    }
+
+    pub fn memory() -> Self {
+        Self::from_store(MemoryStore::default())
+    }
+
+    /// Open the SQLite database at `url`, creating and migrating it as needed
+    pub async fn sqlite(url: &str) -> FormVaultResult<Self> {
+        Ok(Self::from_store(SqliteStore::connect(url).await?))
+    }
+
+    pub fn developers(&self) -> &dyn DeveloperRepository {
+        &*self.developers
+    }
+
+    pub fn forms(&self) -> &dyn FormRepository {
+        &*self.forms
+    }
+
+    pub fn submissions(&self) -> &dyn SubmissionRepository {
+        &*self.submissions
+    }
+}
+
+/// Report a foreign key violation as a missing form
+pub(crate) fn missing_form(error: FormVaultError) -> FormVaultError {
+    match error {
+        FormVaultError::DatabaseError(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            FormVaultError::FormNotFound
+        }
+        error => error,
+    }
+}
@@ -0,0 +1,117 @@
+//! The PostgreSQL backend, the one the server runs on.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{DeveloperRepository, FormRepository, SubmissionRepository, missing_form};
+use crate::errors::FormVaultResult;
+use crate::models::forms::FormSubmission;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::users::developer::Developer;
+use crate::models::users::registration::Registration;
+use crate::repositories::developers_repository::{
+    create_developer, create_registration, deactivate_developer, find_developer_by_email,
+    find_developer_by_id, mark_email_verified, update_developer,
+};
+use crate::repositories::form::{
+    find_form_by_id, find_forms_by_developer, find_forms_by_member, find_submission_by_id,
+    find_submissions_by_form, save_form, save_submission, update_form_key, update_form_settings,
+    update_submission_status,
+};
+
+#[derive(Clone)]
+pub struct PgStore {
+    pool: PgPool,
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DeveloperRepository for PgStore {
+    async fn insert(&self, developer: &Developer) -> FormVaultResult<()> {
+        create_developer(&self.pool, developer).await
+    }
+
+    async fn register(&self, registration: &Registration) -> FormVaultResult<()> {
+        create_registration(&self.pool, registration).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<Developer>> {
+        find_developer_by_id(&self.pool, id).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> FormVaultResult<Option<Developer>> {
+        find_developer_by_email(&self.pool, email).await
+    }
+
+    async fn update(&self, developer: &Developer) -> FormVaultResult<()> {
+        update_developer(&self.pool, developer).await
+    }
+
+    async fn mark_verified(&self, id: Uuid, at: DateTime<Utc>) -> FormVaultResult<()> {
+        mark_email_verified(&self.pool, id, at).await
+    }
+
+    async fn deactivate(&self, id: Uuid) -> FormVaultResult<()> {
+        deactivate_developer(&self.pool, id).await
+    }
+}
+
+#[async_trait]
+impl FormRepository for PgStore {
+    async fn insert(&self, form: &FormSchema) -> FormVaultResult<()> {
+        save_form(&self.pool, form).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSchema>> {
+        find_form_by_id(&self.pool, id).await
+    }
+
+    async fn find_by_developer(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>> {
+        find_forms_by_developer(&self.pool, developer_id).await
+    }
+
+    async fn find_by_member(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>> {
+        find_forms_by_member(&self.pool, developer_id).await
+    }
+
+    async fn update_key(&self, form: &FormSchema) -> FormVaultResult<()> {
+        update_form_key(&self.pool, form).await
+    }
+
+    async fn update_settings(&self, form: &FormSchema) -> FormVaultResult<()> {
+        update_form_settings(&self.pool, form).await
+    }
+}
+
+#[async_trait]
+impl SubmissionRepository for PgStore {
+    async fn insert(&self, submission: &FormSubmission) -> FormVaultResult<()> {
+        save_submission(&self.pool, submission)
+            .await
+            .map_err(missing_form)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSubmission>> {
+        find_submission_by_id(&self.pool, id).await
+    }
+
+    async fn find_by_form(
+        &self,
+        form_id: Uuid,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> FormVaultResult<Vec<FormSubmission>> {
+        find_submissions_by_form(&self.pool, form_id, limit, offset).await
+    }
+
+    async fn update_status(&self, submission: &FormSubmission) -> FormVaultResult<()> {
+        update_submission_status(&self.pool, submission).await
+    }
+}
@@ -0,0 +1,326 @@
+//! The SQLite backend, for embedding and tests; the server does not run on it.
+//!
+//! The schema comes from `migrations/sqlite` and is applied on connect.
+//! Queries are checked at runtime rather than at compile time, as the
+//! compile-time checks only cover the PostgreSQL database.
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use super::{DeveloperRepository, FormRepository, SubmissionRepository, missing_form};
+use crate::errors::{FormVaultError, FormVaultResult};
+use crate::models::forms::FormSubmission;
+use crate::models::forms::form_schema::FormSchema;
+use crate::models::users::developer::Developer;
+use crate::models::users::registration::Registration;
+use crate::repositories::developers_repository::duplicate_email;
+use crate::repositories::form::SubmissionRow;
+
+/// The SQLite schema, embedded at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Open the database at `url` (such as `sqlite://formvault.db?mode=rwc`
+    /// or `sqlite::memory:`) and bring its schema up to date
+    pub async fn connect(url: &str) -> FormVaultResult<Self> {
+        let options = SqliteConnectOptions::from_str(url)?.foreign_keys(true);
+        // Every connection to an in-memory database gets a database of its
+        // own, so those are limited to one
+        let in_memory = options.get_filename().as_os_str() == ":memory:";
+        let pool = SqlitePoolOptions::new()
+            .max_connections(if in_memory { 1 } else { 5 })
+            .connect_with(options)
+            .await?;
+        MIGRATOR
+            .run(&pool)
+            .await
+            .map_err(|e| FormVaultError::DatabaseError(e.into()))?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl DeveloperRepository for SqliteStore {
+    async fn insert(&self, developer: &Developer) -> FormVaultResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO developers
+                (id, name, email, public_key, public_key_algorithm, created_at, is_active,
+                 email_verified_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(developer.id())
+        .bind(developer.name())
+        .bind(developer.email())
+        .bind(developer.public_key())
+        .bind(developer.public_key_algorithm())
+        .bind(developer.created_at())
+        .bind(developer.is_active())
+        .bind(developer.email_verified_at())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| duplicate_email(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn register(&self, registration: &Registration) -> FormVaultResult<()> {
+        DeveloperRepository::insert(self, &registration.developer).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<Developer>> {
+        let developer = sqlx::query_as(
+            r#"
+            SELECT id, name, email, public_key, public_key_algorithm, created_at, is_active,
+                   email_verified_at
+            FROM developers WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(developer)
+    }
+
+    async fn find_by_email(&self, email: &str) -> FormVaultResult<Option<Developer>> {
+        let developer = sqlx::query_as(
+            r#"
+            SELECT id, name, email, public_key, public_key_algorithm, created_at, is_active,
+                   email_verified_at
+            FROM developers WHERE email = ?
+            "#,
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(developer)
+    }
+
+    async fn update(&self, developer: &Developer) -> FormVaultResult<()> {
+        sqlx::query("UPDATE developers SET name = ?, email = ? WHERE id = ?")
+            .bind(developer.name())
+            .bind(developer.email())
+            .bind(developer.id())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| duplicate_email(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn mark_verified(&self, id: Uuid, at: DateTime<Utc>) -> FormVaultResult<()> {
+        sqlx::query(
+            "UPDATE developers SET email_verified_at = COALESCE(email_verified_at, ?) WHERE id = ?",
+        )
+        .bind(at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn deactivate(&self, id: Uuid) -> FormVaultResult<()> {
+        sqlx::query("UPDATE developers SET is_active = 0 WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FormRepository for SqliteStore {
+    async fn insert(&self, form: &FormSchema) -> FormVaultResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO form_schemas
+                (id, name, developer_id, team_id, key_id, allowed_origins, success_url,
+                 error_url, zero_knowledge, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(form.id)
+        .bind(&form.name)
+        .bind(form.developer_id)
+        .bind(form.team_id)
+        .bind(form.key_id)
+        .bind(Json(&form.allowed_origins))
+        .bind(&form.success_url)
+        .bind(&form.error_url)
+        .bind(form.zero_knowledge)
+        .bind(form.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSchema>> {
+        let form = sqlx::query_as(
+            r#"
+            SELECT id, name, developer_id, team_id, key_id, allowed_origins, success_url,
+                   error_url, zero_knowledge, created_at
+            FROM form_schemas WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(form)
+    }
+
+    async fn find_by_developer(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>> {
+        let forms = sqlx::query_as(
+            r#"
+            SELECT id, name, developer_id, team_id, key_id, allowed_origins, success_url,
+                   error_url, zero_knowledge, created_at
+            FROM form_schemas WHERE developer_id = ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(developer_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(forms)
+    }
+
+    async fn find_by_member(&self, developer_id: Uuid) -> FormVaultResult<Vec<FormSchema>> {
+        self.find_by_developer(developer_id).await
+    }
+
+    async fn update_key(&self, form: &FormSchema) -> FormVaultResult<()> {
+        sqlx::query("UPDATE form_schemas SET key_id = ? WHERE id = ?")
+            .bind(form.key_id)
+            .bind(form.id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_settings(&self, form: &FormSchema) -> FormVaultResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE form_schemas
+            SET allowed_origins = ?, success_url = ?, error_url = ?, zero_knowledge = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(Json(&form.allowed_origins))
+        .bind(&form.success_url)
+        .bind(&form.error_url)
+        .bind(form.zero_knowledge)
+        .bind(form.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SubmissionRepository for SqliteStore {
+    async fn insert(&self, submission: &FormSubmission) -> FormVaultResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO form_submissions
+                (id, form_schema_id, encrypted_data, encrypted_key, key_id, metadata, created_at, status, failure_reason)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(submission.id)
+        .bind(submission.form_schema_id)
+        .bind(&submission.encrypted_data)
+        .bind(&submission.encrypted_key)
+        .bind(submission.key_id)
+        .bind(Json(&submission.metadata))
+        .bind(submission.created_at)
+        .bind(submission.status)
+        .bind(&submission.failure_reason)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| missing_form(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> FormVaultResult<Option<FormSubmission>> {
+        let row: Option<SubmissionRow> = sqlx::query_as(
+            r#"
+            SELECT id, form_schema_id, encrypted_data, encrypted_key, key_id,
+                   metadata, created_at, status, failure_reason
+            FROM form_submissions
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(FormSubmission::from))
+    }
+
+    async fn find_by_form(
+        &self,
+        form_id: Uuid,
+        limit: Option<i64>,
+        offset: i64,
+    ) -> FormVaultResult<Vec<FormSubmission>> {
+        // SQLite spells "no limit" as a negative one
+        let rows: Vec<SubmissionRow> = sqlx::query_as(
+            r#"
+            SELECT id, form_schema_id, encrypted_data, encrypted_key, key_id,
+                   metadata, created_at, status, failure_reason
+            FROM form_submissions
+            WHERE form_schema_id = ?
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(form_id)
+        .bind(limit.unwrap_or(-1))
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(FormSubmission::from).collect())
+    }
+
+    async fn update_status(&self, submission: &FormSubmission) -> FormVaultResult<()> {
+        let result =
+            sqlx::query("UPDATE form_submissions SET status = ?, failure_reason = ? WHERE id = ?")
+                .bind(submission.status)
+                .bind(&submission.failure_reason)
+                .bind(submission.id)
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(FormVaultError::SubmissionNotFound);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+//! Structured logging and tracing.
+//!
+//! Everything is logged through `tracing`, records from crates that still
+//! use `log` included, as one JSON object per line by default. Every HTTP
+//! request runs in an `http_request` span carrying its request ID, so the
+//! events and the database, encryption and webhook spans below it can be
+//! told apart.
+//!
+//! The request ID is taken from an incoming `X-Request-Id` header when it
+//! looks sane and generated otherwise. It is echoed on every response,
+//! included in error bodies and stored on the jobs the request enqueues, so
+//! a failed webhook can be traced back to the submission that caused it.
+//!
+//! Spans are also exported over OTLP/HTTP when a collector is configured.
+
+use std::time::{Duration, Instant};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::field::Empty;
+use tracing::{Instrument, info, info_span};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, fmt};
+use uuid::Uuid;
+
+/// Header carrying the request ID, in both directions
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request ID that is kept
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per line, for log shippers
+    Json,
+    /// Human readable lines, for development
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    pub format: LogFormat,
+    /// `EnvFilter` directives, as in `RUST_LOG`
+    pub filter: String,
+    /// Base URL of an OTLP/HTTP collector; spans are only exported when set
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Json,
+            filter: "info".to_string(),
+            otlp_endpoint: None,
+            service_name: "formvault".to_string(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// Read `LOG_FORMAT` (`json` or `text`), `RUST_LOG`,
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` and `OTEL_SERVICE_NAME`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            format: match var("LOG_FORMAT").as_deref() {
+                Some("text") => LogFormat::Text,
+                _ => LogFormat::Json,
+            },
+            filter: var("RUST_LOG").unwrap_or(defaults.filter),
+            otlp_endpoint: var("OTEL_EXPORTER_OTLP_ENDPOINT"),
+            service_name: var("OTEL_SERVICE_NAME").unwrap_or(defaults.service_name),
+        }
+    }
+}
+
+/// Handle on the installed exporter; flushes it on shutdown
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    /// Export the spans still buffered
+    pub fn flush(&self) {
+        if let Some(provider) = &self.provider
+            && let Err(e) = provider.force_flush()
+        {
+            eprintln!("Failed to flush spans: {}", e);
+        }
+    }
+
+    /// Flush and stop the exporter, waiting at most `timeout`
+    pub fn shutdown(self, timeout: Duration) {
+        if let Some(provider) = &self.provider
+            && let Err(e) = provider.shutdown_with_timeout(timeout)
+        {
+            eprintln!("Failed to shut down span exporter: {}", e);
+        }
+    }
+}
+
+/// Install the global subscriber described by `config`.
+///
+/// Fails when the filter or the collector URL is invalid, or when a
+/// subscriber is already installed.
+pub fn init(config: &TelemetryConfig) -> std::io::Result<Telemetry> {
+    let filter = EnvFilter::try_new(&config.filter)
+        .map_err(|e| std::io::Error::other(format!("invalid log filter: {e}")))?;
+
+    let output = match config.format {
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .boxed(),
+        LogFormat::Text => fmt::layer().boxed(),
+    };
+
+    let provider = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| tracer_provider(endpoint, &config.service_name))
+        .transpose()?;
+    let export = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("formvault")));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .with(export)
+        .try_init()
+        .map_err(|e| std::io::Error::other(format!("logging already initialized: {e}")))?;
+
+    Ok(Telemetry { provider })
+}
+
+fn tracer_provider(endpoint: &str, service_name: &str) -> std::io::Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .map_err(|e| std::io::Error::other(format!("invalid OTLP endpoint: {e}")))?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build())
+}
+
+/// The ID of the request being handled, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Accept IDs from proxies and clients as long as they fit in a header and
+/// a log line unchanged
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b))
+}
+
+/// Middleware running each request in an `http_request` span with its ID,
+/// and logging its outcome once it has been handled
+pub async fn trace_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let span = info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+        route = Empty,
+        status = Empty,
+    );
+    let started = Instant::now();
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.call(req))
+        .instrument(span.clone())
+        .await?;
+
+    let status = response.status().as_u16();
+    let route = response.request().match_pattern();
+    span.record("route", route.as_deref().unwrap_or("unmatched"));
+    span.record("status", status);
+    span.in_scope(|| {
+        info!(
+            latency_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "request completed"
+        )
+    });
+
+    response.headers_mut().insert(
+        HeaderName::from_static(REQUEST_ID_HEADER),
+        HeaderValue::from_str(&request_id).expect("request IDs are valid header values"),
+    );
+    Ok(response)
+}
@@ -0,0 +1,230 @@
+//! Hermetic servers for integration tests and benches.
+//!
+//! Every [`TestApp`] gets a database of its own, created from scratch and
+//! migrated, so tests can assert on exact database contents and run in
+//! parallel. The database is dropped with the `TestApp`.
+//!
+//! ```rust,no_run
+//! use formvault::testing::TestApp;
+//!
+//! #[tokio::test]
+//! async fn lists_no_forms_yet() {
+//!     let app = TestApp::spawn().await;
+//!     let response = reqwest::get(app.url("/health_check")).await.unwrap();
+//!     assert!(response.status().is_success());
+//! }
+//! ```
+
+use std::net::{SocketAddr, TcpListener};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, Executor, PgConnection, PgPool};
+use tokio::task::JoinHandle;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+use crate::mail::Outbox;
+use crate::models::formvault::FormVault;
+use crate::settings::Settings;
+
+/// The schema migrations, embedded at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Log to the test harness' captured output, once per process
+pub fn init_logging() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_test_writer()
+        .try_init();
+}
+
+/// A freshly created, migrated database, dropped with this value.
+pub struct TestDatabase {
+    /// Connected to this database
+    pub pool: PgPool,
+    name: String,
+    admin: PgConnectOptions,
+}
+
+impl TestDatabase {
+    /// Create a uniquely named database on the Postgres server
+    /// `database_url` points to, and migrate it.
+    pub async fn create(database_url: &str, max_connections: u32) -> Self {
+        let admin = PgConnectOptions::from_str(database_url)
+            .expect("database_url is a valid connection string");
+        let name = format!("formvault_test_{}", Uuid::new_v4().simple());
+        let mut connection = PgConnection::connect_with(&admin)
+            .await
+            .expect("Failed to connect to Postgres");
+        connection
+            .execute(format!(r#"CREATE DATABASE "{}""#, name).as_str())
+            .await
+            .expect("Failed to create the test database");
+        connection.close().await.ok();
+
+        let database = Self {
+            pool: PgPoolOptions::new()
+                .max_connections(max_connections)
+                .connect_with(admin.clone().database(&name))
+                .await
+                .expect("Failed to connect to the test database"),
+            name,
+            admin,
+        };
+        MIGRATOR
+            .run(&database.pool)
+            .await
+            .expect("Failed to migrate the test database");
+        database
+    }
+
+    /// Create a database on the Postgres server `DATABASE_URL` points to.
+    /// The environment is only read.
+    pub async fn from_env() -> Self {
+        Self::create(&database_url(), 5).await
+    }
+
+    /// A separate pool on this database, for connections [`pool`](Self::pool)
+    /// must not hand out or that must outlive it
+    pub async fn connect(&self) -> PgPool {
+        PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(self.admin.clone().database(&self.name))
+            .await
+            .expect("Failed to connect to the test database")
+    }
+
+    /// Name of this database
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let admin = self.admin.clone();
+        let name = self.name.clone();
+
+        // Drop may run on a single threaded runtime that cannot be blocked
+        // on, so the database is dropped from a thread of its own
+        let dropped = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            runtime.block_on(async {
+                let mut connection = PgConnection::connect_with(&admin).await?;
+                connection
+                    .execute(format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, name).as_str())
+                    .await?;
+                connection.close().await
+            })?;
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+        })
+        .join();
+
+        if !matches!(dropped, Ok(Ok(()))) {
+            eprintln!("Failed to drop test database {}", self.name);
+        }
+    }
+}
+
+fn database_url() -> String {
+    dotenv::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set")
+}
+
+/// A running server on a freshly created, migrated database.
+pub struct TestApp {
+    pub address: SocketAddr,
+    /// `http://<address>`, without a trailing slash
+    pub base_url: String,
+    /// Connected to this app's database
+    pub pool: PgPool,
+    /// Verification and login emails the app sent
+    pub outbox: Arc<Outbox>,
+    server: JoinHandle<std::io::Result<()>>,
+    database: TestDatabase,
+}
+
+impl TestApp {
+    /// The [`Settings`] [`spawn`](Self::spawn) uses: the defaults, on the
+    /// Postgres server `DATABASE_URL` points to, without background job
+    /// workers. The environment is only read.
+    pub fn settings() -> Settings {
+        let mut settings = Settings::new(database_url());
+        settings.workers.concurrency = 0;
+        settings
+    }
+
+    /// Spawn with [`TestApp::settings`]
+    pub async fn spawn() -> Self {
+        Self::spawn_with(Self::settings()).await
+    }
+
+    /// Spawn with explicit `settings`.
+    ///
+    /// `settings.database_url` only names the server and the credentials:
+    /// the app gets a uniquely named database created next to it. The app
+    /// always listens on a free port of the loopback interface.
+    pub async fn spawn_with(settings: Settings) -> Self {
+        init_logging();
+
+        let database = TestDatabase::create(&settings.database_url, settings.max_connections).await;
+        let pool = database.pool.clone();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind a port");
+        let address = listener.local_addr().unwrap();
+        let outbox = Arc::new(Outbox::default());
+        let server = FormVault::from_settings(pool.clone(), listener, settings)
+            .with_mailer(outbox.clone())
+            .start()
+            .expect("Failed to start the server");
+
+        Self {
+            address,
+            base_url: format!("http://{}", address),
+            pool,
+            outbox,
+            server: tokio::spawn(server),
+            database,
+        }
+    }
+
+    /// Absolute URL of `path`, which starts with a slash
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Follow the latest verification link mailed to `email`, as the
+    /// dashboard would
+    pub async fn verify_email(&self, email: &str) {
+        let email = self
+            .outbox
+            .last_to(email)
+            .expect("no email was sent to this address");
+        let token = email.token().expect("the email holds no link");
+        let response = reqwest::Client::new()
+            .post(self.url("/auth/verify"))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .expect("Failed to verify the email");
+        assert_eq!(response.status(), 200, "verification was refused");
+    }
+
+    /// Name of the database created for this app
+    pub fn database_name(&self) -> &str {
+        self.database.name()
+    }
+}
+
+impl Drop for TestApp {
+    // The database is dropped after the server stops
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{Duration, Utc};
+use formvault::models::public_key::PublicKey;
+use formvault::models::users::api_key::{ApiKey, ApiKeyScope};
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// Register a fresh developer and return their admin API key
+async fn register(client: &reqwest::Client, base: &str) -> String {
+    let body: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Grace",
+            "email": format!("grace-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[7; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["api_key"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn read_only_key_can_read_but_not_manage() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let admin_key = register(&client, &base).await;
+
+    let response = client
+        .post(format!("{}/api_keys", base))
+        .bearer_auth(&admin_key)
+        .json(&json!({ "name": "ci", "scopes": ["read_submissions"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let created: Value = response.json().await.unwrap();
+    let ci_key = created["api_key"].as_str().unwrap();
+    assert!(ci_key.starts_with(created["prefix"].as_str().unwrap()));
+    assert!(created.get("key_hash").is_none());
+
+    let forms = client
+        .get(format!("{}/forms", base))
+        .bearer_auth(ci_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forms.status(), 200);
+
+    let create_form = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(ci_key)
+        .json(&json!({ "name": "Nope" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(create_form.status(), 403);
+
+    let audit = client
+        .get(format!("{}/audit", base))
+        .bearer_auth(ci_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(audit.status(), 403);
+}
+
+#[tokio::test]
+async fn keys_are_stored_hashed_and_can_be_revoked() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let admin_key = register(&client, &base).await;
+
+    let created: Value = client
+        .post(format!("{}/api_keys", base))
+        .bearer_auth(&admin_key)
+        .json(&json!({ "name": "temp", "scopes": ["manage_forms"] }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let temp_key = created["api_key"].as_str().unwrap();
+    let temp_id = created["id"].as_str().unwrap();
+
+    let leaked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM api_keys WHERE key_hash = $1 OR prefix = $1 OR name = $1",
+    )
+    .bind(temp_key)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(leaked, 0, "plaintext key must not be stored");
+
+    let revoke = client
+        .delete(format!("{}/api_keys/{}", base, temp_id))
+        .bearer_auth(&admin_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoke.status(), 204);
+
+    let rejected = client
+        .get(format!("{}/developers/me", base))
+        .bearer_auth(temp_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), 401);
+
+    let keys: Vec<Value> = client
+        .get(format!("{}/api_keys", base))
+        .bearer_auth(&admin_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(keys.len(), 2);
+    let default = keys.iter().find(|k| k["name"] == "default").unwrap();
+    assert!(default["last_used_at"].is_string());
+}
+
+#[tokio::test]
+async fn keys_cannot_be_created_already_expired() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let admin_key = register(&client, &base).await;
+
+    let past = client
+        .post(format!("{}/api_keys", base))
+        .bearer_auth(&admin_key)
+        .json(&json!({
+            "name": "old",
+            "scopes": ["admin"],
+            "expires_at": "2000-01-01T00:00:00Z",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(past.status(), 400);
+}
+
+#[test]
+fn expired_and_revoked_keys_are_unusable() {
+    let now = Utc::now();
+    let (mut key, secret) = ApiKey::generate(
+        Uuid::new_v4(),
+        "ci".to_string(),
+        vec![ApiKeyScope::ReadSubmissions],
+        Some(now + Duration::hours(1)),
+    );
+
+    assert_eq!(key.key_hash, ApiKey::hash(&secret));
+    assert!(key.has_scope(ApiKeyScope::ReadSubmissions));
+    assert!(!key.has_scope(ApiKeyScope::ManageForms));
+    assert!(key.is_usable(now));
+    assert!(!key.is_usable(now + Duration::hours(2)));
+
+    key.revoked_at = Some(now);
+    assert!(!key.is_usable(now));
+}
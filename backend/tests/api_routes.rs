@@ -1,15 +1,18 @@
 // api_routes.rs
-use formvault::spawn_app;
-use reqwest;
-use tokio;
+use std::collections::HashSet;
+use std::process::Command;
+
+use formvault::openapi;
+use formvault::testing::TestApp;
+use uuid::Uuid;
 
 #[tokio::test]
 async fn test_get_api_routes() {
     // Start the app
-    let addr = spawn_app().await;
+    let app = TestApp::spawn().await;
 
     // Build URL
-    let url = format!("http://{}/", addr);
+    let url = app.url("/");
 
     // Send GET request
     let response = reqwest::get(&url)
@@ -33,3 +36,144 @@ async fn test_get_api_routes() {
     // Example assertion: body is an array
     assert!(body.is_array(), "Expected JSON array for API map");
 }
+
+#[tokio::test]
+async fn index_lists_every_documented_route() {
+    let app = TestApp::spawn().await;
+    let routes: Vec<serde_json::Value> = reqwest::get(app.url("/"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let listed: HashSet<(String, String)> = routes
+        .iter()
+        .map(|route| {
+            let url = route["url"].as_str().unwrap();
+            let path = url.strip_prefix(&app.base_url).unwrap();
+            (
+                route["method"].as_str().unwrap().to_string(),
+                path.to_string(),
+            )
+        })
+        .collect();
+    let documented: HashSet<(String, String)> = openapi::operations(openapi::spec())
+        .map(|(method, path, _)| (method.to_string(), path.to_string()))
+        .collect();
+
+    assert_eq!(listed, documented);
+    assert_eq!(routes.len(), documented.len());
+    assert!(listed.contains(&("POST".to_string(), "/f/{form_id}".to_string())));
+    assert!(routes.iter().all(|route| route["description"].is_string()));
+}
+
+#[tokio::test]
+async fn openapi_document_is_served() {
+    let app = TestApp::spawn().await;
+    let response = reqwest::get(app.url("/openapi.json")).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let document: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(document["openapi"], "3.1.0");
+    assert_eq!(
+        document,
+        serde_json::to_value(openapi::spec()).unwrap(),
+        "the served document is the generated one"
+    );
+
+    // Types used by handlers are described, errors included
+    let schemas = &document["components"]["schemas"];
+    for schema in [
+        "FormSchema",
+        "SubmissionBody",
+        "ErrorResponse",
+        "CreatedApiKey",
+    ] {
+        assert!(schemas[schema].is_object(), "{} is missing", schema);
+    }
+    let create_form = &document["paths"]["/forms"]["post"];
+    assert_eq!(
+        create_form["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/CreateForm"
+    );
+    assert_eq!(
+        create_form["responses"]["401"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/ErrorResponse"
+    );
+    assert!(
+        document["components"]["securitySchemes"]["api_key"].is_object(),
+        "API key authentication is declared"
+    );
+    // Public routes opt out of authentication
+    assert_eq!(
+        document["paths"]["/f/{form_id}"]["post"]["security"],
+        serde_json::json!([{}])
+    );
+}
+
+#[test]
+fn operation_ids_are_unique() {
+    let mut seen = HashSet::new();
+    for (method, path, operation) in openapi::operations(openapi::spec()) {
+        let id = operation.operation_id.clone().unwrap();
+        assert!(seen.insert(id.clone()), "{} {} reuses {}", method, path, id);
+        assert!(
+            operation.summary.is_some(),
+            "{} {} has no summary",
+            method,
+            path
+        );
+    }
+}
+
+/// Every documented operation must reach a handler: unmatched paths get
+/// an empty 404 from the router and unmatched methods a 405
+#[tokio::test]
+async fn every_documented_route_is_registered() {
+    let app = TestApp::spawn().await;
+    let client = reqwest::Client::new();
+
+    for (method, path, _) in openapi::operations(openapi::spec()) {
+        let mut url = String::new();
+        for segment in path.split('/').skip(1) {
+            url.push('/');
+            if segment.starts_with('{') {
+                url.push_str(&Uuid::new_v4().to_string());
+            } else {
+                url.push_str(segment);
+            }
+        }
+
+        let response = client
+            .request(method.parse().unwrap(), app.url(&url))
+            .header("Content-Type", "application/json")
+            .body("{}")
+            .send()
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.bytes().await.unwrap();
+
+        assert_ne!(status, 405, "{} {} is not routed", method, path);
+        assert!(
+            !(status == 404 && body.is_empty()),
+            "{} {} is not routed",
+            method,
+            path
+        );
+    }
+}
+
+#[test]
+fn cli_prints_the_document() {
+    let output = Command::new(env!("CARGO_BIN_EXE_formvault"))
+        .arg("openapi")
+        .env_remove("DATABASE_URL")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document, serde_json::to_value(openapi::spec()).unwrap());
+}
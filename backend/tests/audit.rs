@@ -0,0 +1,274 @@
+use formvault::models::audit::{Actor, AuditAction, GENESIS_HASH, NewAuditEvent, verify_chain};
+use formvault::models::public_key::PublicKey;
+use formvault::settings::TrustedProxies;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// Register a fresh developer and return their API key
+async fn register(client: &reqwest::Client, base: &str) -> String {
+    let response = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Ada",
+            "email": format!("ada-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[7; 32]),
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+
+    let body: Value = response.json().await.unwrap();
+    body["api_key"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn developer_actions_are_recorded_in_the_audit_log() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let api_key = register(&client, &base).await;
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Contact" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+
+    let export = client
+        .get(format!("{}/forms/{}/export", base, form_id))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap();
+    assert!(export.status().is_success());
+
+    let rotated: Value = client
+        .post(format!("{}/developers/me/api_key", base))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let api_key = rotated["api_key"].as_str().unwrap();
+
+    let events: Vec<Value> = client
+        .get(format!("{}/audit", base))
+        .header("X-API-Key", api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let actions: Vec<&str> = events
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            "api_key.rotated",
+            "export.run",
+            "form.created",
+            "developer.registered"
+        ]
+    );
+    assert_eq!(events[2]["target_id"], form["id"]);
+    assert!(events[0]["ip_address"].as_str().is_some());
+
+    let filtered: Vec<Value> = client
+        .get(format!("{}/audit?action=form.created", base))
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(filtered.len(), 1);
+
+    let report: Value = client
+        .get(format!("{}/audit/verify", base))
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["valid"], true);
+}
+
+#[tokio::test]
+async fn audit_log_requires_an_api_key() {
+    let app = TestApp::spawn().await;
+    let response = reqwest::get(app.url("/audit")).await.unwrap();
+    assert_eq!(response.status(), 401);
+}
+
+#[test]
+fn tampering_breaks_the_hash_chain() {
+    let developer = Uuid::new_v4();
+    let first = NewAuditEvent::new(
+        Actor::Developer(developer),
+        developer,
+        AuditAction::FormCreated,
+    )
+    .chain(1, GENESIS_HASH.to_string());
+    let second = NewAuditEvent::new(Actor::System, developer, AuditAction::ExportRun)
+        .details(json!({ "count": 3 }))
+        .chain(2, first.hash.clone());
+
+    let mut events = vec![first, second];
+    assert_eq!(
+        verify_chain(GENESIS_HASH, &events),
+        Ok(events[1].hash.clone())
+    );
+
+    events[1].details = json!({ "count": 0 });
+    let broken = verify_chain(GENESIS_HASH, &events).unwrap_err();
+    assert_eq!(broken.seq, 2);
+
+    events.remove(0);
+    let broken = verify_chain(GENESIS_HASH, &events).unwrap_err();
+    assert_eq!(broken.reason, "previous hash does not match");
+}
+
+/// The address the registration of a developer was recorded from
+async fn registered_from(app: &TestApp, forwarded: &[(&str, &str)]) -> Value {
+    let mut request = reqwest::Client::new()
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Ada",
+            "email": format!("ada-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[7; 32]),
+        }));
+    for (name, value) in forwarded {
+        request = request.header(*name, *value);
+    }
+    let body: Value = request.send().await.unwrap().json().await.unwrap();
+
+    let events: Vec<Value> = reqwest::Client::new()
+        .get(app.url("/audit"))
+        .bearer_auth(body["api_key"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    events[0]["ip_address"].clone()
+}
+
+#[tokio::test]
+async fn forwarded_addresses_are_only_believed_from_trusted_proxies() {
+    let spoofed = [("X-Forwarded-For", "198.51.100.1")];
+    let app = TestApp::spawn().await;
+    assert_eq!(registered_from(&app, &spoofed).await, "127.0.0.1");
+    assert_eq!(
+        registered_from(&app, &[("Forwarded", "for=198.51.100.1")]).await,
+        "127.0.0.1"
+    );
+
+    let mut settings = TestApp::settings();
+    settings.trusted_proxies = TrustedProxies::parse("127.0.0.1, 192.0.2.10").unwrap();
+    let app = TestApp::spawn_with(settings).await;
+    assert_eq!(registered_from(&app, &spoofed).await, "198.51.100.1");
+    // What the client sent ahead of the proxies' hops is not believed
+    assert_eq!(
+        registered_from(
+            &app,
+            &[("X-Forwarded-For", "203.0.113.66, 198.51.100.1, 192.0.2.10")]
+        )
+        .await,
+        "198.51.100.1"
+    );
+    assert_eq!(
+        registered_from(
+            &app,
+            &[(
+                "Forwarded",
+                r#"for=203.0.113.66, for="[2001:db8::1]:4711";proto=https"#
+            )]
+        )
+        .await,
+        "2001:db8::1"
+    );
+    assert_eq!(registered_from(&app, &[]).await, "127.0.0.1");
+}
+
+#[tokio::test]
+async fn actions_are_undone_when_their_audit_event_is_not_written() {
+    let app = TestApp::spawn().await;
+    let client = reqwest::Client::new();
+    let api_key = register(&client, &app.base_url).await;
+
+    // From now on every audit event fails to be written
+    sqlx::raw_sql(
+        r#"
+        CREATE FUNCTION refuse_audit_events() RETURNS TRIGGER AS $$
+        BEGIN
+            RAISE EXCEPTION 'audit log unavailable';
+        END;
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER refuse_audit_events BEFORE INSERT ON audit_events
+            FOR EACH ROW EXECUTE FUNCTION refuse_audit_events();
+        "#,
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let team = client
+        .post(app.url("/teams"))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Unrecorded" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(team.status(), 500);
+    let teams: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM teams WHERE name = 'Unrecorded'")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(teams, 0);
+
+    let public_key = client
+        .put(app.url("/developers/me/public_key"))
+        .bearer_auth(&api_key)
+        .json(&json!({ "public_key": PublicKey::x25519_pem(&[8; 32]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(public_key.status(), 500);
+    let versions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public_keys")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(versions, 1);
+
+    let rotated = client
+        .post(app.url("/developers/me/api_key"))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rotated.status(), 500);
+    let still_valid = client
+        .get(app.url("/teams"))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(still_valid.status(), 200);
+}
@@ -0,0 +1,335 @@
+use chrono::Utc;
+use formvault::models::forms::origin::{normalize_pattern, origin_matches, origin_of};
+use formvault::models::public_key::PublicKey;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::testing::TestApp;
+use reqwest::{Client, Method, Response};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// A verified developer's API key
+async fn signed_up(client: &Client, app: &TestApp) -> String {
+    let registered: Value = client
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Origins",
+            "email": format!("origins-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[47; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    registered["api_key"].as_str().unwrap().to_string()
+}
+
+async fn error_code(response: Response) -> String {
+    let body: Value = response.json().await.unwrap();
+    body["code"].as_str().unwrap().to_string()
+}
+
+fn allowed_origin(response: &Response) -> Option<&str> {
+    response
+        .headers()
+        .get("access-control-allow-origin")
+        .map(|value| value.to_str().unwrap())
+}
+
+#[test]
+fn patterns_are_normalized_and_matched_like_browser_origins() {
+    assert_eq!(
+        normalize_pattern(" HTTPS://Example.com:443/ ").unwrap(),
+        "https://example.com"
+    );
+    assert_eq!(
+        normalize_pattern("http://localhost:3000").unwrap(),
+        "http://localhost:3000"
+    );
+    for invalid in [
+        "*",
+        "example.com",
+        "ftp://example.com",
+        "https://example.com/contact",
+        "https://*.com",
+        "https://shop.*.example.com",
+        "https://example.com:99999",
+    ] {
+        assert!(
+            normalize_pattern(invalid).is_err(),
+            "{} was accepted",
+            invalid
+        );
+    }
+
+    let wildcard = normalize_pattern("https://*.example.com").unwrap();
+    assert!(origin_matches(&wildcard, "https://shop.example.com"));
+    assert!(origin_matches(&wildcard, "https://a.b.example.com"));
+    assert!(!origin_matches(&wildcard, "https://example.com"));
+    assert!(!origin_matches(&wildcard, "https://badexample.com"));
+    assert!(!origin_matches(&wildcard, "http://shop.example.com"));
+    assert!(!origin_matches(&wildcard, "https://shop.example.com:8443"));
+    assert!(origin_matches(
+        "http://localhost:3000",
+        "http://LOCALHOST:3000"
+    ));
+
+    assert_eq!(
+        origin_of("https://shop.example.com/contact?ref=1").as_deref(),
+        Some("https://shop.example.com")
+    );
+    assert_eq!(origin_of("about:blank"), None);
+}
+
+#[tokio::test]
+async fn forms_only_accept_submissions_from_allowed_origins() {
+    let app = TestApp::spawn().await;
+    let base = &app.base_url;
+    let client = Client::new();
+    let api_key = signed_up(&client, &app).await;
+
+    let invalid = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Contact", "allowed_origins": ["*"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({
+            "name": "Contact",
+            "allowed_origins": ["https://*.example.com", "https://example.com/"],
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        form["allowed_origins"],
+        json!(["https://*.example.com", "https://example.com"])
+    );
+    let form_url = format!("{}/f/{}", base, form["id"].as_str().unwrap());
+
+    // Preflight
+    let preflight = client
+        .request(Method::OPTIONS, &form_url)
+        .header("Origin", "https://shop.example.com")
+        .header("Access-Control-Request-Method", "POST")
+        .header("Access-Control-Request-Headers", "content-type")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(preflight.status(), 204);
+    assert_eq!(allowed_origin(&preflight), Some("https://shop.example.com"));
+    let allowed_methods = preflight.headers()["access-control-allow-methods"]
+        .to_str()
+        .unwrap();
+    assert!(allowed_methods.contains("POST"));
+
+    let refused = client
+        .request(Method::OPTIONS, &form_url)
+        .header("Origin", "https://evil.test")
+        .header("Access-Control-Request-Method", "POST")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(refused.status(), 403);
+    assert_eq!(allowed_origin(&refused), None);
+    assert_eq!(error_code(refused).await, "ORIGIN_NOT_ALLOWED");
+
+    // Submissions
+    let submitted = client
+        .post(&form_url)
+        .header("Origin", "https://example.com")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(submitted.status(), 201);
+    assert_eq!(allowed_origin(&submitted), Some("https://example.com"));
+
+    let refused = client
+        .post(&form_url)
+        .header("Origin", "https://evil.test")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(refused.status(), 403);
+    let body: Value = refused.json().await.unwrap();
+    assert_eq!(body["code"], "ORIGIN_NOT_ALLOWED");
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap()
+            .contains("https://evil.test")
+    );
+
+    // Without an Origin, the Referer's origin is checked
+    let referred = client
+        .post(&form_url)
+        .header("Referer", "https://evil.test/contact")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(referred.status(), 403);
+    let referred = client
+        .post(&form_url)
+        .header("Referer", "https://www.example.com/contact")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(referred.status(), 201);
+
+    // Server-to-server requests carry neither
+    let direct = client
+        .post(&form_url)
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(direct.status(), 201);
+
+    // Errors other than the origin's can still be read by the page
+    let invalid = client
+        .post(&form_url)
+        .header("Origin", "https://example.com")
+        .json(&json!({
+            "encrypted_data": "fv1.x25519.not-base64!.xx",
+            "encrypted_key": "AAAA",
+            "key_id": form["key"]["id"],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+    assert_eq!(allowed_origin(&invalid), Some("https://example.com"));
+
+    let key = client
+        .get(format!("{}/key", form_url))
+        .header("Origin", "https://evil.test")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(key.status(), 403);
+    let key = client
+        .get(format!("{}/key", form_url))
+        .header("Origin", "https://shop.example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(key.status(), 200);
+    assert_eq!(allowed_origin(&key), Some("https://shop.example.com"));
+}
+
+#[tokio::test]
+async fn allowed_origins_can_be_changed_and_cleared() {
+    let app = TestApp::spawn().await;
+    let base = &app.base_url;
+    let client = Client::new();
+    let api_key = signed_up(&client, &app).await;
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Open" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(form["allowed_origins"], json!([]));
+    let form_id = form["id"].as_str().unwrap();
+    let form_url = format!("{}/f/{}", base, form_id);
+
+    // Forms without allowed origins take submissions from anywhere
+    let anywhere = client
+        .post(&form_url)
+        .header("Origin", "https://anywhere.test")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anywhere.status(), 201);
+    assert_eq!(allowed_origin(&anywhere), Some("https://anywhere.test"));
+
+    let invalid = client
+        .patch(format!("{}/forms/{}", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "allowed_origins": ["https://ok.test", "not an origin"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+
+    let updated: Value = client
+        .patch(format!("{}/forms/{}", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "allowed_origins": ["http://localhost:3000"] }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(updated["allowed_origins"], json!(["http://localhost:3000"]));
+
+    let fetched: Value = client
+        .get(format!("{}/forms/{}", base, form_id))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(fetched["allowed_origins"], json!(["http://localhost:3000"]));
+
+    let anywhere = client
+        .post(&form_url)
+        .header("Origin", "https://anywhere.test")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anywhere.status(), 403);
+    let local = client
+        .post(&form_url)
+        .header("Origin", "http://localhost:3000")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(local.status(), 201);
+
+    let cleared = client
+        .patch(format!("{}/forms/{}", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "allowed_origins": [] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(cleared.status(), 200);
+    let anywhere = client
+        .post(&form_url)
+        .header("Origin", "https://anywhere.test")
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anywhere.status(), 201);
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use formvault::decrypt::{PrivateKey, decrypt_form_data, parse_submissions, to_csv};
+use formvault::errors::FormVaultError;
+use formvault::models::forms::SubmissionMetadata;
+use formvault::models::forms::submission::FormSubmission;
+use formvault::models::public_key::KeyAlgorithm;
+use formvault::repositories::encryption::encrypt_form_data;
+use uuid::Uuid;
+
+const RSA_PRIVATE: &str = include_str!("fixtures/rsa2048.pem");
+const RSA_PUBLIC: &str = include_str!("fixtures/rsa2048.pub.pem");
+const X25519_PRIVATE: &str = include_str!("fixtures/x25519.pem");
+const X25519_PUBLIC: &str = include_str!("fixtures/x25519.pub.pem");
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// An export as produced by `GET /forms/{id}/export`
+async fn export(submissions: &[(HashMap<String, String>, &str)]) -> String {
+    let form_id = Uuid::new_v4();
+    let mut exported = Vec::new();
+    for (data, public_key) in submissions {
+        let (encrypted_data, encrypted_key) = encrypt_form_data(data, public_key).await.unwrap();
+        exported.push(FormSubmission::new(
+            form_id,
+            encrypted_data,
+            encrypted_key,
+            SubmissionMetadata {
+                ip_address: None,
+                user_agent: None,
+                referrer: None,
+                country: None,
+            },
+        ));
+    }
+    serde_json::to_string(&exported).unwrap()
+}
+
+fn formvault(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_formvault"))
+        .args(args)
+        .env_remove("DATABASE_URL")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn private_keys_are_parsed_from_pkcs8() {
+    assert_eq!(
+        PrivateKey::from_pem(RSA_PRIVATE).unwrap().algorithm(),
+        KeyAlgorithm::Rsa
+    );
+    assert_eq!(
+        PrivateKey::from_pem(X25519_PRIVATE).unwrap().algorithm(),
+        KeyAlgorithm::X25519
+    );
+    assert!(matches!(
+        PrivateKey::from_pem(X25519_PUBLIC),
+        Err(FormVaultError::DecryptionError(_))
+    ));
+}
+
+#[tokio::test]
+async fn envelopes_decrypt_with_the_matching_key() {
+    let data = fields(&[
+        ("email", "ada@example.com"),
+        ("message", "Hello, \"world\""),
+    ]);
+    let keys = [
+        PrivateKey::from_pem(RSA_PRIVATE).unwrap(),
+        PrivateKey::from_pem(X25519_PRIVATE).unwrap(),
+    ];
+
+    for public_key in [RSA_PUBLIC, X25519_PUBLIC] {
+        let (encrypted_data, encrypted_key) = encrypt_form_data(&data, public_key).await.unwrap();
+        assert_eq!(
+            decrypt_form_data(&encrypted_data, &encrypted_key, &keys).unwrap(),
+            data
+        );
+    }
+
+    // A key of the right type is not enough
+    let other = PrivateKey::X25519(x25519_dalek::StaticSecret::from([1; 32]));
+    let (encrypted_data, encrypted_key) = encrypt_form_data(&data, X25519_PUBLIC).await.unwrap();
+    assert!(matches!(
+        decrypt_form_data(&encrypted_data, &encrypted_key, &[other]),
+        Err(FormVaultError::DecryptionError(_))
+    ));
+}
+
+#[tokio::test]
+async fn tampering_is_detected() {
+    let keys = [PrivateKey::from_pem(X25519_PRIVATE).unwrap()];
+    let (encrypted_data, encrypted_key) =
+        encrypt_form_data(&fields(&[("message", "hi")]), X25519_PUBLIC)
+            .await
+            .unwrap();
+
+    let (header, ciphertext) = encrypted_data.rsplit_once('.').unwrap();
+    let mut bytes = URL_SAFE_NO_PAD.decode(ciphertext).unwrap();
+    bytes[0] ^= 1;
+    let flipped = format!("{}.{}", header, URL_SAFE_NO_PAD.encode(bytes));
+    let Err(FormVaultError::DecryptionError(msg)) =
+        decrypt_form_data(&flipped, &encrypted_key, &keys)
+    else {
+        panic!("a modified ciphertext must not decrypt");
+    };
+    assert!(msg.contains("authentication tag"));
+
+    // The header is authenticated too, and unknown versions are refused
+    for altered in [
+        encrypted_data.replacen("fv1.x25519.", "fv1.rsa.", 1),
+        encrypted_data.replacen("fv1.", "fv2.", 1),
+    ] {
+        assert!(decrypt_form_data(&altered, &encrypted_key, &keys).is_err());
+    }
+}
+
+#[tokio::test]
+async fn exports_convert_to_csv() {
+    let input = export(&[
+        (fields(&[("email", "ada@example.com")]), X25519_PUBLIC),
+        (fields(&[("message", "one, two\nthree")]), RSA_PUBLIC),
+    ])
+    .await;
+    let keys = [
+        PrivateKey::from_pem(X25519_PRIVATE).unwrap(),
+        PrivateKey::from_pem(RSA_PRIVATE).unwrap(),
+    ];
+
+    let decrypted = parse_submissions(&input)
+        .unwrap()
+        .iter()
+        .map(|submission| submission.decrypt(&keys))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(decrypted.iter().all(|submission| submission.id.is_some()));
+
+    let csv = to_csv(&decrypted).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "id,form_id,key_id,created_at,email,message"
+    );
+    assert!(lines.next().unwrap().ends_with(",ada@example.com,"));
+    assert!(csv.ends_with(",\"one, two\nthree\"\n"));
+}
+
+#[tokio::test]
+async fn cli_decrypts_exports_offline() {
+    let input = export(&[(fields(&[("message", "hi")]), X25519_PUBLIC)]).await;
+    let path = std::env::temp_dir().join(format!("formvault-export-{}.json", Uuid::new_v4()));
+    std::fs::write(&path, &input).unwrap();
+    let key = fixture("x25519.pem");
+
+    let output = formvault(&[
+        "decrypt",
+        "--key",
+        key.to_str().unwrap(),
+        path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success());
+    let decrypted: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(decrypted[0]["data"]["message"], "hi");
+
+    // The wrong key fails loudly instead of printing partial output
+    let output = formvault(&[
+        "decrypt",
+        "--key",
+        fixture("rsa2048.pem").to_str().unwrap(),
+        "--format",
+        "csv",
+        path.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("none of the given keys"));
+    std::fs::remove_file(&path).unwrap();
+
+    let (data, encrypted_key) = encrypt_form_data(&fields(&[("message", "single")]), RSA_PUBLIC)
+        .await
+        .unwrap();
+    let output = formvault(&[
+        "decrypt",
+        "--key",
+        fixture("rsa2048.pem").to_str().unwrap(),
+        "--data",
+        &data,
+        "--encrypted-key",
+        &encrypted_key,
+        "--format",
+        "csv",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "id,form_id,key_id,created_at,message\n,,,,single\n"
+    );
+}
@@ -0,0 +1,195 @@
+use formvault::models::public_key::PublicKey;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+
+/// Register `email` and create a form; returns the API key and the form ID
+async fn signup_with_form(app: &TestApp, email: &str) -> (String, String) {
+    let client = reqwest::Client::new();
+    let registered: Value = client
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Verifier",
+            "email": email,
+            "public_key": PublicKey::x25519_pem(&[5; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let api_key = registered["api_key"].as_str().unwrap().to_string();
+    assert!(registered["email_verified_at"].is_null());
+
+    let form: Value = client
+        .post(app.url("/forms"))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Contact" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    (api_key, form["id"].as_str().unwrap().to_string())
+}
+
+async fn submit(app: &TestApp, form_id: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(app.url(&format!("/f/{}", form_id)))
+        .json(&json!({ "message": "hello" }))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn post(app: &TestApp, path: &str, body: Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(app.url(path))
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn forms_go_live_once_the_email_is_verified() {
+    let app = TestApp::spawn().await;
+    let (_, form_id) = signup_with_form(&app, "live@example.com").await;
+
+    let refused = submit(&app, &form_id).await;
+    assert_eq!(refused.status(), 403);
+    let body: Value = refused.json().await.unwrap();
+    assert_eq!(body["code"], "EMAIL_NOT_VERIFIED");
+
+    let email = app.outbox.last_to("live@example.com").unwrap();
+    assert!(email.body.contains("/verify-email?token="));
+    app.verify_email("live@example.com").await;
+
+    assert_eq!(submit(&app, &form_id).await.status(), 201);
+    let verified: bool = sqlx::query_scalar(
+        "SELECT email_verified_at IS NOT NULL FROM developers WHERE email = 'live@example.com'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(verified);
+}
+
+#[tokio::test]
+async fn verification_links_work_once() {
+    let app = TestApp::spawn().await;
+    signup_with_form(&app, "once@example.com").await;
+    let email = app.outbox.last_to("once@example.com").unwrap();
+    let token = email.token().unwrap();
+
+    let verified = post(&app, "/auth/verify", json!({ "token": token })).await;
+    assert_eq!(verified.status(), 200);
+    let profile: Value = verified.json().await.unwrap();
+    assert!(profile["email_verified_at"].is_string());
+
+    let again = post(&app, "/auth/verify", json!({ "token": token })).await;
+    assert_eq!(again.status(), 400);
+    let unknown = post(&app, "/auth/verify", json!({ "token": "not-a-token" })).await;
+    assert_eq!(unknown.status(), 400);
+}
+
+#[tokio::test]
+async fn verification_can_be_resent_until_verified() {
+    let app = TestApp::spawn().await;
+    let (api_key, _) = signup_with_form(&app, "resend@example.com").await;
+    let client = reqwest::Client::new();
+    let resend = || {
+        client
+            .post(app.url("/developers/me/verification"))
+            .bearer_auth(&api_key)
+            .send()
+    };
+
+    assert_eq!(resend().await.unwrap().status(), 202);
+    assert_eq!(app.outbox.sent().len(), 2);
+    // The newest link works even though the first one was never used
+    app.verify_email("resend@example.com").await;
+
+    let refused = resend().await.unwrap();
+    assert_eq!(refused.status(), 400);
+    assert_eq!(app.outbox.sent().len(), 2);
+}
+
+#[tokio::test]
+async fn login_links_start_a_session_and_verify_the_email() {
+    let app = TestApp::spawn().await;
+    let (_, form_id) = signup_with_form(&app, "login@example.com").await;
+
+    let requested = post(&app, "/auth/login", json!({ "email": "login@example.com" })).await;
+    assert_eq!(requested.status(), 202);
+    let email = app.outbox.last_to("login@example.com").unwrap();
+    assert!(email.body.contains("/login?token="));
+    let token = email.token().unwrap();
+
+    let started = post(&app, "/auth/session", json!({ "token": token })).await;
+    assert_eq!(started.status(), 201);
+    let session: Value = started.json().await.unwrap();
+    assert!(session["csrf_token"].is_string());
+
+    // The session is short-lived
+    let hours: f64 = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM expires_at - created_at)::FLOAT8 / 3600 FROM sessions",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(hours <= 8.0);
+
+    let reused = post(&app, "/auth/session", json!({ "token": token })).await;
+    assert_eq!(reused.status(), 401);
+
+    // Following the link proved the address too
+    assert_eq!(submit(&app, &form_id).await.status(), 201);
+}
+
+#[tokio::test]
+async fn login_requests_do_not_reveal_accounts() {
+    let app = TestApp::spawn().await;
+    signup_with_form(&app, "known@example.com").await;
+    let before = app.outbox.sent().len();
+
+    let unknown = post(
+        &app,
+        "/auth/login",
+        json!({ "email": "unknown@example.com" }),
+    )
+    .await;
+    assert_eq!(unknown.status(), 202);
+    assert_eq!(app.outbox.sent().len(), before);
+
+    let malformed = post(&app, "/auth/login", json!({ "email": "not an email" })).await;
+    assert_eq!(malformed.status(), 400);
+    let body: Value = malformed.json().await.unwrap();
+    assert_eq!(body["code"], "INVALID_INPUT");
+}
+
+#[tokio::test]
+async fn login_links_expire() {
+    let app = TestApp::spawn().await;
+    signup_with_form(&app, "expired@example.com").await;
+    post(
+        &app,
+        "/auth/login",
+        json!({ "email": "expired@example.com" }),
+    )
+    .await;
+    let email = app.outbox.last_to("expired@example.com").unwrap();
+
+    sqlx::query("UPDATE email_tokens SET expires_at = NOW() - INTERVAL '1 second'")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let started = post(
+        &app,
+        "/auth/session",
+        json!({ "token": email.token().unwrap() }),
+    )
+    .await;
+    assert_eq!(started.status(), 401);
+}
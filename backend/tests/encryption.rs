@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use formvault::errors::FormVaultError;
+use formvault::models::public_key::{KeyAlgorithm, PublicKey};
+use formvault::repositories::encryption::{Envelope, encrypt_form_data, x25519_kek};
+use formvault::testing::TestApp;
+use rsa::pkcs8::{DecodePrivateKey, PrivateKeyInfo};
+use rsa::{Oaep, RsaPrivateKey};
+use serde_json::json;
+use sha2::Sha256;
+use uuid::Uuid;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+const RSA_PRIVATE: &str = include_str!("fixtures/rsa2048.pem");
+const RSA_PUBLIC: &str = include_str!("fixtures/rsa2048.pub.pem");
+const RSA_1024_PUBLIC: &str = include_str!("fixtures/rsa1024.pub.pem");
+const X25519_PRIVATE: &str = include_str!("fixtures/x25519.pem");
+const X25519_PUBLIC: &str = include_str!("fixtures/x25519.pub.pem");
+const ED25519_PUBLIC: &str = include_str!("fixtures/ed25519.pub.pem");
+
+/// Recover the content key with the matching private key fixture
+fn unwrap_key(envelope: &Envelope) -> Vec<u8> {
+    match envelope.algorithm {
+        KeyAlgorithm::Rsa => RsaPrivateKey::from_pkcs8_pem(RSA_PRIVATE)
+            .unwrap()
+            .decrypt(Oaep::new::<Sha256>(), &envelope.encrypted_key)
+            .unwrap(),
+        KeyAlgorithm::X25519 => {
+            let (_, der) = rsa::pkcs8::der::pem::decode_vec(X25519_PRIVATE.as_bytes()).unwrap();
+            let info = PrivateKeyInfo::try_from(der.as_slice()).unwrap();
+            // The private key is an OCTET STRING wrapping the 32 raw bytes
+            let raw: [u8; 32] = info.private_key[2..].try_into().unwrap();
+            let secret = StaticSecret::from(raw);
+            let recipient = X25519PublicKey::from(&secret);
+
+            let ephemeral: [u8; 32] = envelope.encrypted_key[..32].try_into().unwrap();
+            let shared = secret.diffie_hellman(&X25519PublicKey::from(ephemeral));
+            let kek = x25519_kek(shared.as_bytes(), &ephemeral, recipient.as_bytes());
+            Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&kek))
+                .decrypt(Nonce::from_slice(&[0; 12]), &envelope.encrypted_key[32..])
+                .unwrap()
+        }
+    }
+}
+
+fn open(envelope: &Envelope) -> Result<Vec<u8>, aes_gcm::Error> {
+    let key = unwrap_key(envelope);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)).decrypt(
+        Nonce::from_slice(&envelope.nonce),
+        Payload {
+            msg: &envelope.ciphertext,
+            aad: Envelope::header(envelope.algorithm).as_bytes(),
+        },
+    )
+}
+
+fn sample_data() -> HashMap<String, String> {
+    HashMap::from([
+        ("email".to_string(), "ada@example.com".to_string()),
+        ("message".to_string(), "Hello".to_string()),
+    ])
+}
+
+#[test]
+fn only_strong_rsa_and_x25519_keys_are_accepted() {
+    assert_eq!(
+        PublicKey::from_pem(RSA_PUBLIC).unwrap().algorithm(),
+        KeyAlgorithm::Rsa
+    );
+    assert_eq!(
+        PublicKey::from_pem(X25519_PUBLIC).unwrap().algorithm(),
+        KeyAlgorithm::X25519
+    );
+
+    for rejected in [
+        RSA_1024_PUBLIC,
+        ED25519_PUBLIC,
+        RSA_PRIVATE,
+        &"k".repeat(128),
+    ] {
+        assert!(matches!(
+            PublicKey::from_pem(rejected),
+            Err(FormVaultError::InvalidPublicKey)
+        ));
+    }
+}
+
+#[test]
+fn x25519_pem_round_trips() {
+    let pem = PublicKey::x25519_pem(&[9; 32]);
+    match PublicKey::from_pem(&pem).unwrap() {
+        PublicKey::X25519(bytes) => assert_eq!(bytes, [9; 32]),
+        other => panic!("expected an X25519 key, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn submissions_decrypt_with_the_private_key() {
+    for (public, algorithm) in [
+        (RSA_PUBLIC, KeyAlgorithm::Rsa),
+        (X25519_PUBLIC, KeyAlgorithm::X25519),
+    ] {
+        let (data, key) = encrypt_form_data(&sample_data(), public).await.unwrap();
+        assert!(data.starts_with(&format!("fv1.{}.", algorithm.as_str())));
+
+        let envelope = Envelope::decode(&data, &key).unwrap();
+        assert_eq!(envelope.algorithm, algorithm);
+        assert_eq!(envelope.encode(), (data, key));
+
+        let plaintext = open(&envelope).unwrap();
+        let decrypted: HashMap<String, String> = serde_json::from_slice(&plaintext).unwrap();
+        assert_eq!(decrypted, sample_data());
+    }
+}
+
+#[tokio::test]
+async fn tampered_envelopes_are_rejected() {
+    let (data, key) = encrypt_form_data(&sample_data(), X25519_PUBLIC)
+        .await
+        .unwrap();
+
+    let unknown_version = data.replacen("fv1.", "fv9.", 1);
+    assert!(matches!(
+        Envelope::decode(&unknown_version, &key),
+        Err(FormVaultError::DecryptionError(_))
+    ));
+
+    let mut envelope = Envelope::decode(&data, &key).unwrap();
+    envelope.ciphertext[0] ^= 1;
+    assert!(open(&envelope).is_err());
+}
+
+#[tokio::test]
+async fn registration_rejects_keys_that_cannot_decrypt() {
+    let app = TestApp::spawn().await;
+    let client = reqwest::Client::new();
+
+    let register = |public_key: &str| {
+        client
+            .post(app.url("/developers"))
+            .json(&json!({
+                "name": "Ada",
+                "email": format!("ada-{}@example.com", Uuid::new_v4()),
+                "public_key": public_key,
+            }))
+            .send()
+    };
+
+    let junk = register(&"k".repeat(101)).await.unwrap();
+    assert_eq!(junk.status(), 400);
+
+    let weak = register(RSA_1024_PUBLIC).await.unwrap();
+    assert_eq!(weak.status(), 400);
+
+    let ok = register(RSA_PUBLIC).await.unwrap();
+    assert_eq!(ok.status(), 201);
+    let body: serde_json::Value = ok.json().await.unwrap();
+    assert_eq!(body["public_key_algorithm"], "rsa");
+}
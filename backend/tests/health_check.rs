@@ -1,16 +1,132 @@
-use env_logger::Env;
-use formvault;
-use formvault::spawn_app;
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use chrono::Utc;
+use formvault::jobs::{Job, JobRecord, WorkerConfig};
+use formvault::models::formvault::FormVault;
+use formvault::models::health::{Readiness, ReadinessStatus};
+use formvault::notifications::Notifier;
+use formvault::repositories::jobs::insert_job;
+use formvault::testing::{TestApp, TestDatabase};
 use log::info;
-use std::net::SocketAddr;
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+use uuid::Uuid;
+
 #[tokio::test]
 async fn test_health_check_point_works() {
-    let addr = spawn_app().await;
+    let app = TestApp::spawn().await;
 
-    let url = format!("http://{}/health_check", addr);
-    let response = reqwest::get(&url).await.expect("Failed to send request");
+    let response = reqwest::get(app.url("/health_check"))
+        .await
+        .expect("Failed to send request");
 
     info!("Response: {:?}", response);
 
     assert!(response.status().is_success());
 }
+
+/// Serve `pool` without background workers, on a queue of its own
+fn serve(pool: PgPool, queue: &str) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let workers = WorkerConfig {
+        queue: queue.to_string(),
+        concurrency: 0,
+        ..WorkerConfig::default()
+    };
+    let server = FormVault::new(pool, listener, Notifier::new(None), workers)
+        .start()
+        .unwrap();
+    tokio::spawn(server);
+    addr
+}
+
+async fn readiness(addr: SocketAddr) -> (u16, Readiness) {
+    let response = reqwest::get(format!("http://{}/readyz", addr))
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn liveness_does_not_need_the_database() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://postgres@127.0.0.1:1/formvault")
+        .unwrap();
+    let addr = serve(pool, "unused");
+
+    let response = reqwest::get(format!("http://{}/livez", addr))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn ready_when_the_database_and_workers_are_up() {
+    let mut settings = TestApp::settings();
+    settings.workers = WorkerConfig::default();
+    let app = TestApp::spawn_with(settings).await;
+    let (status, readiness) = readiness(app.address).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(readiness.status, ReadinessStatus::Ready);
+    assert!(readiness.database.healthy);
+    assert_eq!(readiness.pool.max_connections, 5);
+    assert!(readiness.pool.size >= 1, "the ping opened a connection");
+    assert!((0.0..=1.0).contains(&readiness.pool.utilization));
+    assert!(readiness.jobs.healthy);
+    assert_eq!(readiness.jobs.workers_alive, readiness.jobs.workers);
+    assert!(readiness.jobs.due.is_some());
+}
+
+#[tokio::test]
+async fn unavailable_without_a_database() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://postgres@127.0.0.1:1/formvault")
+        .unwrap();
+    let addr = serve(pool, "unused");
+    let (status, readiness) = readiness(addr).await;
+
+    assert_eq!(status, 503);
+    assert_eq!(readiness.status, ReadinessStatus::Unavailable);
+    assert!(!readiness.database.healthy);
+    assert_eq!(readiness.pool.in_use, 0);
+    assert_eq!(readiness.jobs.due, None);
+
+    // The database error is logged, not served
+    let body = reqwest::get(format!("http://{}/readyz", addr))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(!body.contains("error"), "{}", body);
+}
+
+#[tokio::test]
+async fn job_lag_is_reported() {
+    let database = TestDatabase::from_env().await;
+    let queue = format!("test-{}", Uuid::new_v4());
+    let mut job = JobRecord::new(
+        &queue,
+        &Job::DeliverNotification {
+            target_id: Uuid::new_v4(),
+            submission_id: Uuid::new_v4(),
+        },
+    );
+    job.run_at = Utc::now() - chrono::Duration::minutes(5);
+    insert_job(&database.pool, &job).await.unwrap();
+
+    let addr = serve(database.pool.clone(), &queue);
+    let (status, readiness) = readiness(addr).await;
+
+    // A backlog alone does not take the instance out of rotation
+    assert_eq!(status, 200);
+    assert_eq!(readiness.jobs.queue, queue);
+    assert_eq!(readiness.jobs.due, Some(1));
+    assert!(readiness.jobs.lag_seconds.unwrap() >= 300.0);
+}
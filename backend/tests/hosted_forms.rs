@@ -0,0 +1,300 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::Utc;
+use formvault::models::public_key::PublicKey;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::repositories::encryption::encrypt_form_data;
+use formvault::sdk;
+use formvault::testing::TestApp;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Response};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+const BROWSER_ACCEPT: &str = "text/html,application/xhtml+xml,*/*;q=0.8";
+
+struct Owner {
+    app: TestApp,
+    client: Client,
+    api_key: String,
+}
+
+impl Owner {
+    async fn signed_up() -> Self {
+        let app = TestApp::spawn().await;
+        let client = Client::builder().redirect(Policy::none()).build().unwrap();
+        let registered: Value = client
+            .post(app.url("/developers"))
+            .json(&json!({
+                "name": "Hosted",
+                "email": format!("hosted-{}@example.com", Uuid::new_v4()),
+                "public_key": PublicKey::x25519_pem(&[49; 32]),
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+        mark_email_verified(&app.pool, developer_id, Utc::now())
+            .await
+            .unwrap();
+        let api_key = registered["api_key"].as_str().unwrap().to_string();
+        Self {
+            app,
+            client,
+            api_key,
+        }
+    }
+
+    async fn form(&self, settings: Value, fields: &[Value]) -> Value {
+        let form: Value = self
+            .client
+            .post(self.app.url("/forms"))
+            .bearer_auth(&self.api_key)
+            .json(&settings)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        for field in fields {
+            let added = self
+                .client
+                .post(format!(
+                    "{}/forms/{}/fields",
+                    self.app.base_url,
+                    form["id"].as_str().unwrap()
+                ))
+                .bearer_auth(&self.api_key)
+                .json(field)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(added.status(), 201);
+        }
+        form
+    }
+
+    async fn page(&self, form_id: &str) -> Response {
+        self.client
+            .get(format!("{}/f/{}", self.app.base_url, form_id))
+            .send()
+            .await
+            .unwrap()
+    }
+}
+
+fn field(name: &str, field_type: &str, required: bool, rules: Value) -> Value {
+    json!({
+        "name": name,
+        "field_type": field_type,
+        "required": required,
+        "validation_rules": rules,
+    })
+}
+
+fn header<'r>(response: &'r Response, name: &str) -> &'r str {
+    response.headers()[name].to_str().unwrap()
+}
+
+#[tokio::test]
+async fn forms_are_rendered_from_their_fields() {
+    let owner = Owner::signed_up().await;
+    let form = owner
+        .form(
+            json!({ "name": "Contact \"us\"" }),
+            &[
+                field("full_name", "text", true, json!({ "max_length": 80 })),
+                field("email", "email", true, json!({})),
+                field(
+                    "zip",
+                    "text",
+                    false,
+                    json!({ "pattern": "[0-9]{5}", "message": "Five digits, like 12345" }),
+                ),
+                field(
+                    "topic",
+                    "select",
+                    true,
+                    json!({ "options": ["Sales", "Support & <help>"] }),
+                ),
+                field("newsletter", "checkbox", false, json!({})),
+                field("visit_date", "date", false, json!({})),
+            ],
+        )
+        .await;
+    let form_id = form["id"].as_str().unwrap();
+
+    let page = owner.page(form_id).await;
+    assert_eq!(page.status(), 200);
+    assert!(header(&page, "content-type").starts_with("text/html"));
+    let policy = header(&page, "content-security-policy");
+    assert!(!policy.contains("script-src"));
+    assert!(policy.contains("frame-ancestors 'none'"));
+    let html = page.text().await.unwrap();
+
+    assert!(html.contains("<html lang=\"en\">"));
+    assert!(html.contains("<h1 id=\"fv-title\">Contact &quot;us&quot;</h1>"));
+    assert!(html.contains(&format!("action=\"/f/{}\"", form_id)));
+    // Every control has a label
+    for index in 0..6 {
+        assert!(html.contains(&format!("<label for=\"fv-field-{}\"", index)));
+        assert!(html.contains(&format!("id=\"fv-field-{}\"", index)));
+    }
+    assert!(html.contains("Full name"));
+    assert!(html.contains("maxlength=\"80\""));
+    assert!(html.contains("type=\"email\""));
+    assert!(html.contains("pattern=\"[0-9]{5}\""));
+    assert!(html.contains("aria-describedby=\"fv-field-2-hint\""));
+    assert!(html.contains("Five digits, like 12345"));
+    assert!(html.contains("<option>Support &amp; &lt;help&gt;</option>"));
+    assert!(html.contains("type=\"checkbox\""));
+    assert!(html.contains("type=\"date\""));
+    assert_eq!(html.matches(" required").count(), 3);
+    assert!(!html.contains("<script"));
+
+    // What the browser posts is validated like any submission
+    let posted = owner
+        .client
+        .post(format!("{}/f/{}", owner.app.base_url, form_id))
+        .header("Accept", BROWSER_ACCEPT)
+        .form(&[
+            ("full_name", "Ada Lovelace"),
+            ("email", "ada@example.com"),
+            ("topic", "Sales"),
+            ("newsletter", "yes"),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(posted.status(), 303);
+    assert_eq!(
+        header(&posted, "location"),
+        format!("/f/{}/thanks", form_id)
+    );
+
+    let missing = owner.page(&Uuid::new_v4().to_string()).await;
+    assert_eq!(missing.status(), 404);
+    assert!(missing.text().await.unwrap().contains("Form not found"));
+
+    let empty = owner.form(json!({ "name": "Empty" }), &[]).await;
+    let page = owner.page(empty["id"].as_str().unwrap()).await;
+    assert!(page.text().await.unwrap().contains("no fields yet"));
+}
+
+#[tokio::test]
+async fn zero_knowledge_forms_are_encrypted_in_the_browser() {
+    let owner = Owner::signed_up().await;
+    let form = owner
+        .form(
+            json!({
+                "name": "Confidential",
+                "zero_knowledge": true,
+                "allowed_origins": ["https://example.com"],
+            }),
+            &[field("message", "text", true, json!({}))],
+        )
+        .await;
+    assert_eq!(form["zero_knowledge"], true);
+    let form_id = form["id"].as_str().unwrap();
+    let form_url = format!("{}/f/{}", owner.app.base_url, form_id);
+
+    let page = owner.page(form_id).await;
+    assert_eq!(page.status(), 200);
+    let policy = header(&page, "content-security-policy").to_string();
+    let html = page.text().await.unwrap();
+
+    // The page carries the key and a script the policy pins by its hash
+    assert!(html.contains(&format!(
+        "data-key-id=\"{}\"",
+        form["key"]["id"].as_str().unwrap()
+    )));
+    assert!(html.contains("data-algorithm=\"x25519\""));
+    assert!(html.contains("<button type=\"submit\" disabled>"));
+    let script = html
+        .split_once("<script>")
+        .and_then(|(_, rest)| rest.split_once("</script>"))
+        .map(|(script, _)| script)
+        .unwrap();
+    let hash = STANDARD.encode(Sha256::digest(script));
+    assert!(policy.contains(&format!("script-src 'sha256-{}' 'self'", hash)));
+    assert!(policy.contains("frame-ancestors 'none'"));
+    // which has the SDK, pinned by its integrity hash, do the encryption
+    assert!(html.contains(&format!(
+        "<script src=\"/sdk/v1/formvault.js\" integrity=\"{}\" crossorigin=\"anonymous\"></script>",
+        sdk::INTEGRITY.as_str()
+    )));
+
+    // Plaintext never gets in
+    let plain = owner
+        .client
+        .post(&form_url)
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(plain.status(), 400);
+    let error: Value = plain.json().await.unwrap();
+    assert_eq!(error["code"], "ENCRYPTION_REQUIRED");
+
+    // The script posts the envelope as a form from the hosted page, whose
+    // origin is accepted whatever the allowed origins
+    let (encrypted_data, encrypted_key) = encrypt_form_data(
+        &[("message".to_string(), "hi".to_string())].into(),
+        &PublicKey::x25519_pem(&[49; 32]),
+    )
+    .await
+    .unwrap();
+    let posted = owner
+        .client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .header("Origin", &owner.app.base_url)
+        .form(&[
+            ("encrypted_data", encrypted_data.as_str()),
+            ("encrypted_key", encrypted_key.as_str()),
+            ("key_id", form["key"]["id"].as_str().unwrap()),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(posted.status(), 303);
+    assert_eq!(
+        header(&posted, "location"),
+        format!("/f/{}/thanks", form_id)
+    );
+
+    let elsewhere = owner
+        .client
+        .post(&form_url)
+        .header("Origin", "https://evil.test")
+        .json(&json!({
+            "encrypted_data": encrypted_data,
+            "encrypted_key": encrypted_key,
+            "key_id": form["key"]["id"],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(elsewhere.status(), 403);
+
+    // Turning it off brings the plain page back
+    let updated: Value = owner
+        .client
+        .patch(format!("{}/forms/{}", owner.app.base_url, form_id))
+        .bearer_auth(&owner.api_key)
+        .json(&json!({ "zero_knowledge": false }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(updated["zero_knowledge"], false);
+    let html = owner.page(form_id).await.text().await.unwrap();
+    assert!(!html.contains("<script"));
+}
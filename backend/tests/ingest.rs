@@ -0,0 +1,205 @@
+use chrono::Utc;
+use formvault::models::forms::field_definition::{FieldDefinition, FieldType};
+use formvault::models::public_key::PublicKey;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+const RSA_PUBLIC: &str = include_str!("fixtures/rsa2048.pub.pem");
+
+fn field(field_type: FieldType, rules: Value) -> FieldDefinition {
+    FieldDefinition::new(Uuid::new_v4(), "value".to_string(), field_type, true, rules).unwrap()
+}
+
+#[test]
+fn fields_check_types_and_rules() {
+    let email = field(FieldType::Email, json!({}));
+    assert!(email.check(Some("ada@example.com")).is_ok());
+    assert!(email.check(Some("ada@example")).is_err());
+    assert_eq!(email.check(Some("  ")).unwrap_err(), "value is required");
+
+    let zip = field(
+        FieldType::Text,
+        json!({ "pattern": "[0-9]{5}", "message": "five digits please" }),
+    );
+    assert!(zip.check(Some("12345")).is_ok());
+    assert_eq!(zip.check(Some("123456")).unwrap_err(), "five digits please");
+
+    assert!(
+        field(FieldType::Date, json!({}))
+            .check(Some("2025-02-30"))
+            .is_err()
+    );
+    assert!(
+        field(FieldType::Number, json!({}))
+            .check(Some("4.5"))
+            .is_ok()
+    );
+
+    // Rules are checked when the field is defined
+    for (field_type, rules) in [
+        (FieldType::Select, json!({})),
+        (FieldType::Text, json!({ "pattern": "(" })),
+        (FieldType::Text, json!({ "colour": "red" })),
+    ] {
+        assert!(
+            FieldDefinition::new(Uuid::new_v4(), "x".into(), field_type, false, rules).is_err()
+        );
+    }
+}
+
+#[tokio::test]
+async fn malformed_envelopes_are_rejected() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+
+    let missing = client
+        .post(format!("{}/f/{}", base, Uuid::new_v4()))
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+
+    let registered: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Ingest",
+            "email": format!("ingest-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[21; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    // Forms only accept submissions once the account is verified
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(registered["api_key"].as_str().unwrap())
+        .json(&json!({ "name": "Ingest" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+
+    let key: Value = client
+        .get(format!("{}/f/{}/key", base, form_id))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(key["algorithm"], "x25519");
+    assert!(key.get("developer_id").is_none());
+
+    let (rsa_data, rsa_key) = formvault::repositories::encryption::encrypt_form_data(
+        &[("message".to_string(), "hi".to_string())].into(),
+        RSA_PUBLIC,
+    )
+    .await
+    .unwrap();
+    for (encrypted_data, encrypted_key) in [
+        ("fv1.x25519.not-base64!.xx".to_string(), "AAAA".to_string()),
+        (rsa_data, rsa_key),
+    ] {
+        let rejected = client
+            .post(format!("{}/f/{}", base, form_id))
+            .json(&json!({
+                "encrypted_data": encrypted_data,
+                "encrypted_key": encrypted_key,
+                "key_id": key["key_id"],
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(rejected.status(), 400);
+    }
+}
+
+#[tokio::test]
+async fn submissions_are_not_stored_without_their_deliveries() {
+    let app = TestApp::spawn().await;
+    let client = reqwest::Client::new();
+    let registered: Value = client
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Ingest",
+            "email": "deliveries@example.com",
+            "public_key": PublicKey::x25519_pem(&[22; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let api_key = registered["api_key"].as_str().unwrap();
+
+    let form: Value = client
+        .post(app.url("/forms"))
+        .bearer_auth(api_key)
+        .json(&json!({ "name": "Deliveries" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+    let target = client
+        .post(app.url(&format!("/forms/{}/notifications", form_id)))
+        .bearer_auth(api_key)
+        .json(&json!({ "channel": "webhook", "destination": "https://example.com/hook" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(target.status(), 201);
+
+    // Delivery jobs can't be queued from now on
+    sqlx::raw_sql(
+        r#"
+        CREATE FUNCTION refuse_jobs() RETURNS TRIGGER AS $$
+        BEGIN
+            RAISE EXCEPTION 'job queue unavailable';
+        END;
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER refuse_jobs BEFORE INSERT ON jobs
+            FOR EACH ROW EXECUTE FUNCTION refuse_jobs();
+        "#,
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let submitted = client
+        .post(app.url(&format!("/f/{}", form_id)))
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(submitted.status(), 500);
+
+    // Nothing is left for a retry to duplicate
+    let submissions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM form_submissions")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(submissions, 0);
+}
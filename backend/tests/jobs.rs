@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use formvault::jobs::worker::run_once;
+use formvault::jobs::{self, Job, JobContext, JobStatus, WorkerConfig, Workers};
+use formvault::models::forms::form_schema::FormSchema;
+use formvault::models::forms::notification_target::ChannelKind;
+use formvault::models::forms::submission::SubmissionStatus;
+use formvault::models::forms::{FormSubmission, NotificationTarget, SubmissionMetadata};
+use formvault::models::users::team::Team;
+use formvault::notifications::Notifier;
+use formvault::repositories::form::{find_submission_by_id, save_form, save_submission};
+use formvault::repositories::jobs::{find_job, requeue_dead_job};
+use formvault::repositories::notification::create_target;
+use formvault::repositories::teams::create_team;
+use formvault::testing::TestApp;
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A saved form with one webhook target and one submission, plus an
+/// isolated queue so concurrently running tests never steal each other's jobs
+async fn delivery_job(pool: &PgPool, webhook_url: String) -> (Job, Uuid, WorkerConfig) {
+    let team = Team::new("Jobs".to_string());
+    create_team(pool, &team).await.unwrap();
+    let form = FormSchema::new("Jobs".to_string(), Uuid::new_v4(), team.id);
+    save_form(pool, &form).await.unwrap();
+
+    let target = NotificationTarget::new(form.id, ChannelKind::Webhook, webhook_url);
+    create_target(pool, &target).await.unwrap();
+
+    let submission = FormSubmission::new(
+        form.id,
+        "ciphertext".to_string(),
+        "wrapped-key".to_string(),
+        SubmissionMetadata {
+            ip_address: None,
+            user_agent: None,
+            referrer: None,
+            country: None,
+        },
+    );
+    save_submission(pool, &submission).await.unwrap();
+
+    let job = Job::DeliverNotification {
+        target_id: target.id,
+        submission_id: submission.id,
+    };
+    let config = WorkerConfig {
+        queue: format!("test-{}", Uuid::new_v4()),
+        concurrency: 2,
+        poll_interval: Duration::from_millis(20),
+        base_backoff: Duration::ZERO,
+        ..WorkerConfig::default()
+    };
+    (job, submission.id, config)
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let config = WorkerConfig {
+        base_backoff: Duration::from_secs(10),
+        max_backoff: Duration::from_secs(60),
+        ..WorkerConfig::default()
+    };
+
+    assert_eq!(config.backoff(1), Duration::from_secs(10));
+    assert_eq!(config.backoff(2), Duration::from_secs(20));
+    assert_eq!(config.backoff(3), Duration::from_secs(40));
+    assert_eq!(config.backoff(4), Duration::from_secs(60));
+    assert_eq!(config.backoff(100), Duration::from_secs(60));
+}
+
+#[tokio::test]
+async fn failing_job_is_retried_then_dead_lettered() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let (job, submission_id, config) = delivery_job(&pool, server.uri()).await;
+    let job_id = jobs::enqueue(&pool, &config.queue, &job).await.unwrap();
+    let ctx = JobContext::new(pool.clone(), Notifier::new(None));
+
+    while run_once(&ctx, &config).await.unwrap() {}
+
+    let record = find_job(&pool, job_id).await.unwrap().unwrap();
+    assert_eq!(record.status, JobStatus::Dead);
+    assert_eq!(record.attempts, job.max_attempts());
+    assert!(record.last_error.is_some());
+
+    let submission = find_submission_by_id(&pool, submission_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(submission.status, SubmissionStatus::Failed);
+
+    assert!(requeue_dead_job(&pool, job_id).await.unwrap());
+    let record = find_job(&pool, job_id).await.unwrap().unwrap();
+    assert_eq!(record.status, JobStatus::Queued);
+    assert_eq!(record.attempts, 0);
+}
+
+#[tokio::test]
+async fn workers_process_jobs_and_shut_down_gracefully() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (job, submission_id, config) = delivery_job(&pool, server.uri()).await;
+    let workers = Workers::spawn(
+        config.clone(),
+        JobContext::new(pool.clone(), Notifier::new(None)),
+    );
+    let job_id = jobs::enqueue(&pool, &config.queue, &job).await.unwrap();
+
+    let mut status = JobStatus::Queued;
+    for _ in 0..100 {
+        status = find_job(&pool, job_id).await.unwrap().unwrap().status;
+        if status == JobStatus::Completed {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, JobStatus::Completed);
+
+    tokio::time::timeout(Duration::from_secs(5), workers.shutdown())
+        .await
+        .expect("workers did not stop");
+
+    let submission = find_submission_by_id(&pool, submission_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(submission.status, SubmissionStatus::Delivered);
+}
@@ -0,0 +1,45 @@
+// Checks the JavaScript SDK against the envelope test vectors, then prints
+// envelopes it sealed with fresh randomness for the Rust side to open.
+//
+//   node tests/js/envelope_vectors.js tests/fixtures/envelope_vectors.json
+//
+// Run by tests/sdk.rs; exits non-zero when a vector doesn't match.
+"use strict";
+
+const fs = require("fs");
+const path = require("path");
+const FormVault = require("../../src/sdk/formvault.js");
+
+const file = process.argv[2];
+const { vectors } = JSON.parse(fs.readFileSync(file, "utf8"));
+const bytes = (text) => (text ? new Uint8Array(Buffer.from(text, "base64url")) : undefined);
+
+const main = async () => {
+  const sealed = [];
+  for (const vector of vectors) {
+    const key = {
+      key_id: vector.name,
+      algorithm: vector.algorithm,
+      public_key: fs.readFileSync(path.join(path.dirname(file), vector.public_key), "utf8"),
+    };
+    const envelope = await FormVault.envelope(new TextEncoder().encode(vector.plaintext), key, {
+      contentKey: bytes(vector.content_key),
+      nonce: bytes(vector.nonce),
+      ephemeralSecret: bytes(vector.ephemeral_secret),
+    });
+    if (envelope.encrypted_data !== vector.encrypted_data) {
+      throw new Error(`${vector.name}: encrypted_data is ${envelope.encrypted_data}`);
+    }
+    if (vector.encrypted_key && envelope.encrypted_key !== vector.encrypted_key) {
+      throw new Error(`${vector.name}: encrypted_key is ${envelope.encrypted_key}`);
+    }
+
+    sealed.push({ name: vector.name, ...(await FormVault.seal(JSON.parse(vector.plaintext), key)) });
+  }
+  process.stdout.write(JSON.stringify(sealed));
+};
+
+main().catch((error) => {
+  console.error(error);
+  process.exit(1);
+});
@@ -0,0 +1,18 @@
+// Submits fields to a running server with the JavaScript SDK and prints the
+// receipt.
+//
+//   node tests/js/submit.js <endpoint> <form id> <fields as JSON>
+//
+// Run by tests/sdk.rs.
+"use strict";
+
+const FormVault = require("../../src/sdk/formvault.js");
+
+const [endpoint, formId, fields] = process.argv.slice(2);
+
+FormVault.submit(formId, JSON.parse(fields), { endpoint })
+  .then((receipt) => process.stdout.write(JSON.stringify(receipt)))
+  .catch((error) => {
+    console.error(error);
+    process.exit(1);
+  });
@@ -0,0 +1,239 @@
+use chrono::Utc;
+use formvault::metrics::metrics;
+use formvault::models::forms::form_schema::FormSchema;
+use formvault::models::forms::notification_target::ChannelKind;
+use formvault::models::forms::{FormSubmission, NotificationTarget, SubmissionMetadata};
+use formvault::models::public_key::PublicKey;
+use formvault::models::users::team::Team;
+use formvault::notifications::Notifier;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::repositories::form::{save_form, save_submission};
+use formvault::repositories::notification::create_target;
+use formvault::repositories::teams::create_team;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use uuid::Uuid;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Value of the sample `series` (name and labels, as rendered), 0 if absent
+fn sample(text: &str, series: &str) -> f64 {
+    text.lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .map_or(0.0, |value| value.parse().unwrap())
+}
+
+const METRICS_TOKEN: &str = "scrape-me";
+
+/// A server serving its metrics to [`METRICS_TOKEN`]
+async fn spawn() -> TestApp {
+    let mut settings = TestApp::settings();
+    settings.metrics_token = Some(METRICS_TOKEN.to_string());
+    TestApp::spawn_with(settings).await
+}
+
+async fn scrape(base: &str) -> String {
+    let response = reqwest::Client::new()
+        .get(format!("{}/metrics", base))
+        .bearer_auth(METRICS_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+    response.text().await.unwrap()
+}
+
+#[tokio::test]
+async fn submissions_and_requests_are_counted() {
+    let app = spawn().await;
+    let base = &app.base_url;
+    let client = reqwest::Client::new();
+
+    let registered: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Metrics",
+            "email": format!("metrics-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[7; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    // Forms only accept submissions once the account is verified
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(registered["api_key"].as_str().unwrap())
+        .json(&json!({ "name": "Metrics" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+
+    for _ in 0..2 {
+        let accepted = client
+            .post(format!("{}/f/{}", base, form_id))
+            .json(&json!({ "message": "hi" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(accepted.status(), 201);
+    }
+    let rejected = client
+        .post(format!("{}/f/{}", base, form_id))
+        .json(&json!({
+            "encrypted_data": "fv1.x25519.not-base64!.xx",
+            "encrypted_key": "AAAA",
+            "key_id": Uuid::new_v4(),
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), 400);
+
+    let text = scrape(base).await;
+    assert_eq!(
+        sample(
+            &text,
+            &format!(
+                r#"formvault_submissions_accepted_total{{encrypted_by="server",form_id="{}"}}"#,
+                form_id
+            )
+        ),
+        2.0
+    );
+    assert_eq!(
+        sample(
+            &text,
+            &format!(
+                r#"formvault_submissions_rejected_total{{form_id="{}",reason="invalid"}}"#,
+                form_id
+            )
+        ),
+        1.0
+    );
+    assert!(
+        sample(
+            &text,
+            r#"formvault_encryption_duration_seconds_count{algorithm="x25519"}"#
+        ) >= 2.0
+    );
+
+    // Routes are labelled with their pattern, not the requested path
+    assert!(
+        sample(
+            &text,
+            r#"formvault_http_requests_total{method="POST",route="/f/{form_id}",status="201"}"#
+        ) >= 2.0
+    );
+    assert!(!text.contains(&format!(r#"route="/f/{}""#, form_id)));
+    assert!(text.contains("formvault_db_pool_max_connections 5"));
+    assert!(text.contains(r#"formvault_db_pool_connections{state="in_use"}"#));
+    assert!(text.contains("formvault_job_queue_depth{queue="));
+}
+
+#[tokio::test]
+async fn metrics_are_only_served_with_the_token() {
+    let app = spawn().await;
+    let client = reqwest::Client::new();
+    let anonymous = client.get(app.url("/metrics")).send().await.unwrap();
+    assert_eq!(anonymous.status(), 401);
+    let wrong = client
+        .get(app.url("/metrics"))
+        .bearer_auth("scrape-you")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(wrong.status(), 401);
+    scrape(&app.base_url).await;
+
+    // Form IDs are labels, so nobody may scrape a server without a token
+    let app = TestApp::spawn().await;
+    let response = client.get(app.url("/metrics")).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn unknown_forms_share_one_label() {
+    let app = spawn().await;
+    let base = &app.base_url;
+    let series =
+        r#"formvault_submissions_rejected_total{form_id="unknown",reason="form_not_found"}"#;
+    let before = sample(&scrape(base).await, series);
+
+    let missing = reqwest::Client::new()
+        .post(format!("{}/f/{}", base, Uuid::new_v4()))
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+
+    assert!(sample(&scrape(base).await, series) > before);
+}
+
+#[tokio::test]
+async fn notification_outcomes_are_recorded() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(502))
+        .mount(&server)
+        .await;
+
+    let team = Team::new("Metrics".to_string());
+    create_team(&pool, &team).await.unwrap();
+    let form = FormSchema::new("Metrics".to_string(), Uuid::new_v4(), team.id);
+    save_form(&pool, &form).await.unwrap();
+    let target = NotificationTarget::new(form.id, ChannelKind::Webhook, server.uri());
+    create_target(&pool, &target).await.unwrap();
+    let submission = FormSubmission::new(
+        form.id,
+        "ciphertext".to_string(),
+        "wrapped-key".to_string(),
+        SubmissionMetadata {
+            ip_address: None,
+            user_agent: None,
+            referrer: None,
+            country: None,
+        },
+    );
+    save_submission(&pool, &submission).await.unwrap();
+
+    let series = r#"formvault_notification_attempts_total{channel="webhook",outcome="failure"}"#;
+    let before = sample(&metrics().render(), series);
+
+    let notifier = Notifier::new(None);
+    assert!(
+        notifier
+            .deliver(&pool, &target, &form, &submission)
+            .await
+            .is_err()
+    );
+
+    let text = metrics().render();
+    assert!(sample(&text, series) > before);
+    assert!(
+        sample(
+            &text,
+            r#"formvault_notification_duration_seconds_count{channel="webhook"}"#
+        ) > 0.0
+    );
+}
@@ -0,0 +1,162 @@
+use formvault::jobs::worker::run_once;
+use formvault::jobs::{JobContext, WorkerConfig};
+use formvault::models::forms::form_schema::FormSchema;
+use formvault::models::forms::notification_target::{ChannelKind, DeliveryStatus};
+use formvault::models::forms::submission::SubmissionStatus;
+use formvault::models::forms::{FormSubmission, NotificationTarget, SubmissionMetadata};
+use formvault::models::users::team::Team;
+use formvault::notifications::{NotificationChannel, Notifier, SlackChannel, WebhookChannel};
+use formvault::repositories::form::{save_form, save_submission};
+use formvault::repositories::notification::{create_target, find_deliveries_by_submission};
+use formvault::repositories::teams::create_team;
+use formvault::testing::TestApp;
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn sample_form() -> FormSchema {
+    FormSchema::new("Contact".to_string(), Uuid::new_v4(), Uuid::new_v4())
+}
+
+fn sample_submission(form: &FormSchema) -> FormSubmission {
+    FormSubmission::new(
+        form.id,
+        "ciphertext".to_string(),
+        "wrapped-key".to_string(),
+        SubmissionMetadata {
+            ip_address: None,
+            user_agent: None,
+            referrer: None,
+            country: None,
+        },
+    )
+}
+
+#[tokio::test]
+async fn webhook_channel_posts_encrypted_submission() {
+    let server = MockServer::start().await;
+    let form = sample_form();
+    let submission = sample_submission(&form);
+
+    Mock::given(method("POST"))
+        .and(path("/hook"))
+        .and(body_partial_json(json!({
+            "event": "submission.created",
+            "form_id": form.id,
+            "submission": { "id": submission.id, "encrypted_data": "ciphertext" },
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let channel = WebhookChannel::new(reqwest::Client::new(), format!("{}/hook", server.uri()));
+    channel.send(&form, &submission).await.unwrap();
+}
+
+#[tokio::test]
+async fn slack_channel_reports_non_success_status() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let form = sample_form();
+    let submission = sample_submission(&form);
+    let channel = SlackChannel::new(reqwest::Client::new(), server.uri());
+
+    assert!(channel.send(&form, &submission).await.is_err());
+}
+
+#[test]
+fn targets_respect_enable_flag_and_status_filter() {
+    let mut target = NotificationTarget::new(
+        Uuid::new_v4(),
+        ChannelKind::Email,
+        "ops@example.com".to_string(),
+    )
+    .with_status_filter(vec![SubmissionStatus::Failed]);
+
+    assert!(target.accepts(SubmissionStatus::Failed));
+    assert!(!target.accepts(SubmissionStatus::New));
+
+    target.enabled = false;
+    assert!(!target.accepts(SubmissionStatus::Failed));
+}
+
+#[tokio::test]
+async fn scheduled_deliveries_fan_out_and_are_tracked() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/ok"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/broken"))
+        .respond_with(ResponseTemplate::new(502))
+        .mount(&server)
+        .await;
+
+    let team = Team::new("Notifications".to_string());
+    create_team(&pool, &team).await.unwrap();
+    let form = FormSchema {
+        team_id: team.id,
+        ..sample_form()
+    };
+    save_form(&pool, &form).await.unwrap();
+
+    let targets = [
+        NotificationTarget::new(
+            form.id,
+            ChannelKind::Webhook,
+            format!("{}/ok", server.uri()),
+        ),
+        NotificationTarget::new(
+            form.id,
+            ChannelKind::Discord,
+            format!("{}/broken", server.uri()),
+        ),
+        NotificationTarget::new(form.id, ChannelKind::Slack, format!("{}/ok", server.uri()))
+            .with_status_filter(vec![SubmissionStatus::Archived]),
+    ];
+    for target in &targets {
+        create_target(&pool, target).await.unwrap();
+    }
+
+    let submission = sample_submission(&form);
+    let queue = format!("test-{}", Uuid::new_v4());
+    let notifier = Notifier::new(None).with_queue(queue.clone());
+    let mut tx = pool.begin().await.unwrap();
+    save_submission(&mut *tx, &submission).await.unwrap();
+    let scheduled = notifier
+        .schedule(&mut tx, &form, &submission)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    assert_eq!(scheduled, 2, "filtered target must be skipped");
+
+    let ctx = JobContext::new(pool.clone(), notifier);
+    let config = WorkerConfig {
+        queue,
+        ..WorkerConfig::default()
+    };
+    while run_once(&ctx, &config).await.unwrap() {}
+
+    let stored = find_deliveries_by_submission(&pool, submission.id)
+        .await
+        .unwrap();
+    let status_of = |target: &NotificationTarget| {
+        stored
+            .iter()
+            .find(|d| d.target_id == target.id)
+            .map(|d| d.status)
+    };
+    assert_eq!(status_of(&targets[0]), Some(DeliveryStatus::Delivered));
+    assert_eq!(status_of(&targets[1]), Some(DeliveryStatus::Failed));
+    assert_eq!(status_of(&targets[2]), None);
+}
@@ -0,0 +1,50 @@
+//! Its own test binary: it installs the global subscriber.
+use formvault::telemetry::{self, LogFormat, TelemetryConfig};
+use formvault::testing::TestApp;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn spans_are_exported_to_the_collector() {
+    let collector = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/traces"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&collector)
+        .await;
+
+    let telemetry = telemetry::init(&TelemetryConfig {
+        format: LogFormat::Text,
+        otlp_endpoint: Some(collector.uri()),
+        service_name: "formvault-test".to_string(),
+        ..TelemetryConfig::default()
+    })
+    .unwrap();
+
+    let app = TestApp::spawn().await;
+    let response = reqwest::Client::new()
+        .get(app.url("/readyz"))
+        .header("X-Request-Id", "otlp-export-check")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // The exporter posts from its own thread
+    tokio::task::spawn_blocking(move || telemetry.flush())
+        .await
+        .unwrap();
+
+    let exported: String = collector
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| String::from_utf8_lossy(&request.body).into_owned())
+        .collect();
+    assert!(exported.contains("formvault-test"));
+    assert!(exported.contains("http_request"));
+    assert!(exported.contains("otlp-export-check"));
+    // Database spans are children of the request
+    assert!(exported.contains("ping"));
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+
+use formvault::models::forms::SubmissionMetadata;
+use formvault::models::public_key::{PublicKey, fingerprint};
+use formvault::notifications::Notifier;
+use formvault::repositories::form::find_form_by_id;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const X25519_PUBLIC: &str = include_str!("fixtures/x25519.pub.pem");
+
+/// Register a developer with `public_key` and return their admin API key
+async fn register(client: &reqwest::Client, base: &str, public_key: &str) -> String {
+    let body: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Rotator",
+            "email": format!("rotator-{}@example.com", Uuid::new_v4()),
+            "public_key": public_key,
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["api_key"].as_str().unwrap().to_string()
+}
+
+/// Submit to the form directly and return the key the submission was tagged with
+async fn submit(pool: &PgPool, form_id: &str) -> Uuid {
+    let form = find_form_by_id(pool, form_id.parse().unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    let submission = form
+        .process_submission(
+            pool,
+            &Notifier::new(None),
+            HashMap::from([("message".to_string(), "hi".to_string())]),
+            SubmissionMetadata {
+                ip_address: None,
+                user_agent: None,
+                referrer: None,
+                country: None,
+            },
+        )
+        .await
+        .unwrap();
+    submission.key_id.unwrap()
+}
+
+#[test]
+fn fingerprint_matches_openssl() {
+    // openssl pkey -pubin -in x25519.pub.pem -outform DER | sha256sum
+    assert_eq!(
+        fingerprint(X25519_PUBLIC).unwrap(),
+        "aa1587d05db1f77fe11e71fe6cfec50ec602f0e4f04cfcb47de8e929baaf775b"
+    );
+}
+
+#[tokio::test]
+async fn rotation_keeps_track_of_retired_keys() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let first_key = PublicKey::x25519_pem(&[1; 32]);
+    let api_key = register(&client, &base, &first_key).await;
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Rotating" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+    assert!(form["key_id"].is_null());
+
+    let keys: Value = client
+        .get(format!("{}/developers/me/public_keys", base))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let v1 = keys[0]["id"].as_str().unwrap().to_string();
+    assert_eq!(keys[0]["version"], 1);
+    assert_eq!(keys[0]["fingerprint"], fingerprint(&first_key).unwrap());
+
+    let old_submission = submit(&pool, form_id).await;
+    assert_eq!(old_submission.to_string(), v1);
+
+    let rotated = client
+        .put(format!("{}/developers/me/public_key", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "public_key": PublicKey::x25519_pem(&[2; 32]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rotated.status(), 200);
+    let v2: Value = rotated.json().await.unwrap();
+    assert_eq!(v2["version"], 2);
+
+    // Going back to a retired key would make the history ambiguous
+    let reused = client
+        .put(format!("{}/developers/me/public_key", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "public_key": first_key }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reused.status(), 400);
+
+    let new_submission = submit(&pool, form_id).await;
+    assert_eq!(new_submission.to_string(), v2["id"].as_str().unwrap());
+
+    let retired: Value = client
+        .get(format!(
+            "{}/developers/me/public_keys/retired/submissions",
+            base
+        ))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let retired = retired.as_array().unwrap();
+    assert_eq!(retired.len(), 1);
+    assert_eq!(retired[0]["key_id"], v1.as_str());
+    assert_eq!(retired[0]["form_id"], form_id);
+    assert_eq!(retired[0]["key_version"], 1);
+
+    let keys: Value = client
+        .get(format!("{}/developers/me/public_keys", base))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(keys[0]["version"], 2);
+    assert!(keys[0]["retired_at"].is_null());
+    assert_eq!(keys[1]["submissions"], 1);
+    assert!(!keys[1]["retired_at"].is_null());
+}
+
+#[tokio::test]
+async fn forms_can_pin_a_key_version() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let api_key = register(&client, &base, &PublicKey::x25519_pem(&[3; 32])).await;
+    let other_key = register(&client, &base, &PublicKey::x25519_pem(&[3; 32])).await;
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Pinned" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+
+    let keys: Value = client
+        .get(format!("{}/developers/me/public_keys", base))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let v1 = keys[0]["id"].clone();
+
+    let pinned = client
+        .put(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "key_id": v1 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(pinned.status(), 200);
+
+    client
+        .put(format!("{}/developers/me/public_key", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "public_key": PublicKey::x25519_pem(&[4; 32]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        submit(&pool, form_id).await.to_string(),
+        v1.as_str().unwrap()
+    );
+
+    // Once rotated out, a version can't be pinned again
+    let retired = client
+        .put(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "key_id": v1 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(retired.status(), 400);
+
+    // Someone else's key can't be pinned, even with the same fingerprint
+    let others: Value = client
+        .get(format!("{}/developers/me/public_keys", base))
+        .bearer_auth(&other_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let foreign = client
+        .put(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "key_id": others[0]["id"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(foreign.status(), 404);
+
+    let unpinned: Value = client
+        .put(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "key_id": null }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(unpinned["key_id"].is_null());
+    assert_eq!(unpinned["key"]["source"], "inherited");
+    assert_eq!(unpinned["key"]["version"], 2);
+}
+
+#[tokio::test]
+async fn forms_can_own_their_key() {
+    let app = TestApp::spawn().await;
+    let pool = app.pool.clone();
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let api_key = register(&client, &base, &PublicKey::x25519_pem(&[5; 32])).await;
+
+    let invalid = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Broken", "public_key": "not a key" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+
+    let inherited: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Inherited" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(inherited["key"]["source"], "inherited");
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&api_key)
+        .json(&json!({ "name": "Own key", "public_key": X25519_PUBLIC }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+    assert_eq!(form["key"]["source"], "own");
+    assert_eq!(form["key"]["version"], 1);
+    assert_eq!(form["key"]["form_id"], form_id);
+    assert_eq!(
+        form["key"]["fingerprint"],
+        fingerprint(X25519_PUBLIC).unwrap()
+    );
+
+    let v1 = submit(&pool, form_id).await;
+    assert_eq!(v1.to_string(), form["key"]["id"].as_str().unwrap());
+
+    let rekeyed = client
+        .put(format!("{}/forms/{}/public_key", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "public_key": PublicKey::x25519_pem(&[6; 32]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rekeyed.status(), 200);
+    let rekeyed: Value = rekeyed.json().await.unwrap();
+    assert_eq!(rekeyed["key"]["source"], "own");
+    assert_eq!(rekeyed["key"]["version"], 2);
+
+    let reused = client
+        .put(format!("{}/forms/{}/public_key", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "public_key": X25519_PUBLIC }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reused.status(), 400);
+
+    let current: Value = client
+        .get(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(current["id"], rekeyed["key"]["id"]);
+    assert_eq!(
+        submit(&pool, form_id).await.to_string(),
+        current["id"].as_str().unwrap()
+    );
+
+    // Form keys never replace the developer's own key
+    let keys: Value = client
+        .get(format!("{}/developers/me/public_keys", base))
+        .bearer_auth(&api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let keys = keys.as_array().unwrap();
+    assert_eq!(keys.len(), 3);
+    assert!(keys[0]["form_id"].is_null());
+    assert!(keys[0]["retired_at"].is_null());
+    assert_eq!(keys[2]["version"], 1);
+    assert_eq!(keys[2]["submissions"], 1);
+    assert!(!keys[2]["retired_at"].is_null());
+}
@@ -0,0 +1,254 @@
+use chrono::Utc;
+use formvault::models::public_key::PublicKey;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::testing::TestApp;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Response};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// What browsers send when following a form post
+const BROWSER_ACCEPT: &str =
+    "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,*/*;q=0.8";
+
+/// A client that shows redirects instead of following them
+fn browser() -> Client {
+    Client::builder().redirect(Policy::none()).build().unwrap()
+}
+
+/// A verified developer's API key and a form with a required email field
+async fn contact_form(client: &Client, app: &TestApp, settings: Value) -> (String, Value) {
+    let registered: Value = client
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Redirects",
+            "email": format!("redirects-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[48; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let api_key = registered["api_key"].as_str().unwrap().to_string();
+
+    let mut body = json!({ "name": "Contact <us>" });
+    body.as_object_mut()
+        .unwrap()
+        .extend(settings.as_object().unwrap().clone());
+    let form: Value = client
+        .post(app.url("/forms"))
+        .bearer_auth(&api_key)
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let added = client
+        .post(format!(
+            "{}/forms/{}/fields",
+            app.base_url,
+            form["id"].as_str().unwrap()
+        ))
+        .bearer_auth(&api_key)
+        .json(&json!({
+            "name": "email",
+            "field_type": "email",
+            "required": true,
+            "validation_rules": {},
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(added.status(), 201);
+    (api_key, form)
+}
+
+fn location(response: &Response) -> &str {
+    response.headers()["location"].to_str().unwrap()
+}
+
+#[tokio::test]
+async fn html_form_posts_are_redirected_to_the_hosted_pages() {
+    let app = TestApp::spawn().await;
+    let base = &app.base_url;
+    let client = browser();
+    let (_, form) = contact_form(&client, &app, json!({})).await;
+    let form_id = form["id"].as_str().unwrap();
+    let form_url = format!("{}/f/{}", base, form_id);
+
+    let posted = client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .form(&[("email", "ada@example.com")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(posted.status(), 303);
+    assert_eq!(location(&posted), format!("/f/{}/thanks", form_id));
+
+    let thanks = client
+        .get(format!("{}{}", base, location(&posted)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(thanks.status(), 200);
+    assert!(
+        thanks.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    assert!(thanks.headers().contains_key("content-security-policy"));
+    let page = thanks.text().await.unwrap();
+    assert!(page.contains("Thank you"));
+    // The form's name is escaped
+    assert!(page.contains("Contact &lt;us&gt;"));
+
+    // Without a redirect URL, failures are shown in place
+    let failed = client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .header("Referer", "https://example.com/contact")
+        .form(&[("email", "not an email")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(failed.status(), 400);
+    let page = failed.text().await.unwrap();
+    assert!(page.contains("Your response was not sent"));
+    assert!(page.contains("email"));
+    assert!(page.contains("href=\"https://example.com/contact\""));
+
+    let missing = client
+        .get(format!("{}/f/{}/thanks", base, Uuid::new_v4()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+    assert!(missing.text().await.unwrap().contains("Form not found"));
+}
+
+#[tokio::test]
+async fn html_form_posts_follow_the_forms_redirect_urls() {
+    let app = TestApp::spawn().await;
+    let base = &app.base_url;
+    let client = browser();
+    let (api_key, form) = contact_form(
+        &client,
+        &app,
+        json!({
+            "success_url": "https://example.com/thanks",
+            "error_url": "https://example.com/oops?lang=en",
+            "allowed_origins": ["https://example.com"],
+        }),
+    )
+    .await;
+    assert_eq!(form["success_url"], "https://example.com/thanks");
+    let form_id = form["id"].as_str().unwrap();
+    let form_url = format!("{}/f/{}", base, form_id);
+
+    let posted = client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .header("Origin", "https://example.com")
+        .form(&[("email", "ada@example.com")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(posted.status(), 303);
+    assert_eq!(location(&posted), "https://example.com/thanks");
+
+    let failed = client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .header("Origin", "https://example.com")
+        .form(&[("email", "")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(failed.status(), 303);
+    assert_eq!(
+        location(&failed),
+        "https://example.com/oops?lang=en&error=VALIDATION_ERROR"
+    );
+
+    let refused = client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .header("Origin", "https://evil.test")
+        .form(&[("email", "ada@example.com")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(refused.status(), 303);
+    assert_eq!(
+        location(&refused),
+        "https://example.com/oops?lang=en&error=ORIGIN_NOT_ALLOWED"
+    );
+
+    // JavaScript clients still get JSON, whatever they post
+    let fetched = client
+        .post(&form_url)
+        .header("Accept", "*/*")
+        .header("Origin", "https://example.com")
+        .form(&[("email", "ada@example.com")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(fetched.status(), 201);
+    let receipt: Value = fetched.json().await.unwrap();
+    assert_eq!(receipt["form_id"], form_id);
+    let rejected = client
+        .post(&form_url)
+        .header("Accept", "application/json, text/html")
+        .json(&json!({ "email": "" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rejected.status(), 400);
+    let error: Value = rejected.json().await.unwrap();
+    assert_eq!(error["code"], "VALIDATION_ERROR");
+
+    // Redirect URLs are checked, and an empty one goes back to the hosted page
+    let invalid = client
+        .patch(format!("{}/forms/{}", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "success_url": "javascript:alert(1)", "error_url": "/oops" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+    let error: Value = invalid.json().await.unwrap();
+    assert_eq!(error["details"].as_array().unwrap().len(), 2);
+
+    let cleared: Value = client
+        .patch(format!("{}/forms/{}", base, form_id))
+        .bearer_auth(&api_key)
+        .json(&json!({ "success_url": "" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(cleared["success_url"], Value::Null);
+    assert_eq!(cleared["error_url"], "https://example.com/oops?lang=en");
+    assert_eq!(cleared["allowed_origins"], json!(["https://example.com"]));
+
+    let posted = client
+        .post(&form_url)
+        .header("Accept", BROWSER_ACCEPT)
+        .form(&[("email", "ada@example.com")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(location(&posted), format!("/f/{}/thanks", form_id));
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use chrono::Utc;
+use formvault::decrypt::{PrivateKey, decrypt_form_data};
+use formvault::models::public_key::PublicKey;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::repositories::encryption::{Envelope, SealSecrets, seal_with};
+use formvault::sdk;
+use formvault::testing::TestApp;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha384};
+use uuid::Uuid;
+
+/// Browsers refuse the bundle when its hash changes, so `/sdk/v1` is frozen.
+/// Changes ship as a new version next to it.
+const V1_INTEGRITY: &str =
+    "sha384-d8RTFUU1KF4ozLUHJmjPd/9VmgijI/LDkfSfZvuQcbNtohmzLIlvxAH77dmfeQhU";
+
+#[derive(Deserialize)]
+struct Vectors {
+    vectors: Vec<Vector>,
+}
+
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    public_key: String,
+    private_key: String,
+    plaintext: String,
+    content_key: String,
+    nonce: String,
+    ephemeral_secret: Option<String>,
+    encrypted_data: String,
+    encrypted_key: Option<String>,
+}
+
+/// An envelope the SDK sealed with fresh randomness
+#[derive(Deserialize)]
+struct Sealed {
+    name: String,
+    encrypted_data: String,
+    encrypted_key: String,
+    key_id: String,
+}
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn vectors() -> Vec<Vector> {
+    let json = std::fs::read_to_string(fixtures().join("envelope_vectors.json")).unwrap();
+    serde_json::from_str::<Vectors>(&json).unwrap().vectors
+}
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(fixtures().join(name)).unwrap()
+}
+
+/// Run a script under `tests/js` with Node.js, or `None` when it isn't installed
+fn node(script: &str, args: &[&str]) -> Option<Output> {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/js")
+        .join(script);
+    match Command::new("node").arg(script).args(args).output() {
+        Ok(output) => Some(output),
+        Err(e) => {
+            eprintln!("skipping: node is not available ({})", e);
+            None
+        }
+    }
+}
+
+fn bytes<const N: usize>(text: &str) -> [u8; N] {
+    URL_SAFE_NO_PAD.decode(text).unwrap().try_into().unwrap()
+}
+
+/// The fields of a vector's plaintext
+fn fields(vector: &Vector) -> HashMap<String, String> {
+    serde_json::from_str(&vector.plaintext).unwrap()
+}
+
+fn decrypt(vector: &Vector, encrypted_data: &str, encrypted_key: &str) -> HashMap<String, String> {
+    let key = PrivateKey::from_pem(&fixture(&vector.private_key)).unwrap();
+    decrypt_form_data(encrypted_data, encrypted_key, &[key]).unwrap()
+}
+
+#[test]
+fn rust_envelopes_match_the_test_vectors() {
+    let vectors = vectors();
+    assert!(vectors.iter().any(|vector| vector.encrypted_key.is_some()));
+
+    for vector in &vectors {
+        let public_key = PublicKey::from_pem(&fixture(&vector.public_key)).unwrap();
+        let secrets = SealSecrets {
+            content_key: bytes(&vector.content_key),
+            nonce: bytes(&vector.nonce),
+            ephemeral_secret: vector
+                .ephemeral_secret
+                .as_deref()
+                .map(bytes)
+                .unwrap_or_default(),
+        };
+        let (encrypted_data, encrypted_key) =
+            seal_with(vector.plaintext.as_bytes(), &public_key, &secrets)
+                .unwrap()
+                .encode();
+
+        assert_eq!(encrypted_data, vector.encrypted_data, "{}", vector.name);
+        if let Some(expected) = &vector.encrypted_key {
+            assert_eq!(&encrypted_key, expected, "{}", vector.name);
+            assert_eq!(
+                decrypt(vector, &vector.encrypted_data, expected),
+                fields(vector),
+                "{}",
+                vector.name
+            );
+        }
+        assert_eq!(
+            decrypt(vector, &encrypted_data, &encrypted_key),
+            fields(vector),
+            "{}",
+            vector.name
+        );
+    }
+}
+
+/// The SDK must reproduce the vectors, and what it seals must open here.
+/// Needs Node.js 20 or later; skipped without it.
+#[test]
+fn javascript_sdk_is_interoperable_with_rust() {
+    let vectors_file = fixtures().join("envelope_vectors.json");
+    let Some(output) = node("envelope_vectors.js", &[vectors_file.to_str().unwrap()]) else {
+        return;
+    };
+    assert!(
+        output.status.success(),
+        "the SDK does not match the vectors:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let sealed: Vec<Sealed> = serde_json::from_slice(&output.stdout).unwrap();
+    let vectors = vectors();
+    assert_eq!(sealed.len(), vectors.len());
+    for (sealed, vector) in sealed.iter().zip(&vectors) {
+        assert_eq!(sealed.name, vector.name);
+        assert_eq!(sealed.key_id, vector.name);
+        // Fresh randomness every time
+        assert_ne!(sealed.encrypted_data, vector.encrypted_data);
+        Envelope::decode(&sealed.encrypted_data, &sealed.encrypted_key).unwrap();
+        assert_eq!(
+            decrypt(vector, &sealed.encrypted_data, &sealed.encrypted_key),
+            fields(vector),
+            "{}",
+            vector.name
+        );
+    }
+}
+
+#[tokio::test]
+async fn sdk_is_served_with_its_integrity_hash() {
+    let app = TestApp::spawn().await;
+    let base = &app.base_url;
+
+    let bundle = reqwest::get(format!("{}/sdk/v1/formvault.js", base))
+        .await
+        .unwrap();
+    assert_eq!(bundle.status(), 200);
+    let header = |name: &str| bundle.headers()[name].to_str().unwrap().to_string();
+    assert!(header("content-type").starts_with("text/javascript"));
+    assert_eq!(header("access-control-allow-origin"), "*");
+    assert!(header("cache-control").contains("immutable"));
+    assert_eq!(header("x-content-type-options"), "nosniff");
+    let body = bundle.bytes().await.unwrap();
+    let integrity = format!("sha384-{}", STANDARD.encode(Sha384::digest(&body)));
+    assert_eq!(integrity, *sdk::INTEGRITY);
+    assert_eq!(
+        integrity, V1_INTEGRITY,
+        "the v1 bundle changed; ship the change as a new version"
+    );
+
+    let release: Value = reqwest::get(format!("{}/sdk/v1", base))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(release["version"], sdk::VERSION);
+    assert_eq!(release["path"], "/sdk/v1/formvault.js");
+    assert_eq!(release["integrity"], integrity);
+
+    let missing = reqwest::get(format!("{}/sdk/v2/formvault.js", base))
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn sdk_fetches_the_key_and_submits_an_envelope() {
+    let app = TestApp::spawn().await;
+    let base = &app.base_url;
+    let client = reqwest::Client::new();
+    let registered: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "SDK",
+            "email": format!("sdk-{}@example.com", Uuid::new_v4()),
+            "public_key": fixture("x25519.pub.pem"),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let api_key = registered["api_key"].as_str().unwrap();
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(api_key)
+        .json(&json!({ "name": "Confidential", "zero_knowledge": true }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+
+    // The server runs on this thread, so node must not block it
+    let args = [base.clone(), form_id.to_string()];
+    let output = tokio::task::spawn_blocking(move || {
+        node(
+            "submit.js",
+            &[
+                &args[0],
+                &args[1],
+                r#"{"message":"Hëllo from the browser"}"#,
+            ],
+        )
+    })
+    .await
+    .unwrap();
+    let Some(output) = output else {
+        return;
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let receipt: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(receipt["form_id"], form_id);
+    assert_eq!(receipt["key_id"], form["key"]["id"]);
+
+    let submission: Value = client
+        .get(format!(
+            "{}/forms/{}/submissions/{}",
+            base,
+            form_id,
+            receipt["id"].as_str().unwrap()
+        ))
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let key = PrivateKey::from_pem(&fixture("x25519.pem")).unwrap();
+    let data = decrypt_form_data(
+        submission["encrypted_data"].as_str().unwrap(),
+        submission["encrypted_key"].as_str().unwrap(),
+        &[key],
+    )
+    .unwrap();
+    assert_eq!(data["message"], "Hëllo from the browser");
+}
@@ -0,0 +1,289 @@
+use formvault::models::public_key::PublicKey;
+use formvault::testing::TestApp;
+use reqwest::header::{COOKIE, SET_COOKIE, USER_AGENT};
+use serde_json::{Value, json};
+
+const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// A verified developer and the `Set-Cookie` headers of a new session
+struct SignedIn {
+    api_key: String,
+    /// `formvault_session=...`, as a browser would send it back
+    session_cookie: String,
+    csrf_token: String,
+    set_cookies: Vec<String>,
+}
+
+impl SignedIn {
+    fn cookie(&self) -> String {
+        format!(
+            "{}; formvault_csrf={}",
+            self.session_cookie, self.csrf_token
+        )
+    }
+}
+
+async fn sign_in(app: &TestApp, email: &str) -> SignedIn {
+    let client = reqwest::Client::new();
+    let registered: Value = client
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Dashboard",
+            "email": email,
+            "public_key": PublicKey::x25519_pem(&[8; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    app.verify_email(email).await;
+
+    let requested = client
+        .post(app.url("/auth/login"))
+        .json(&json!({ "email": email }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(requested.status(), 202);
+    let link = app.outbox.last_to(email).unwrap();
+
+    let started = client
+        .post(app.url("/auth/session"))
+        .header(USER_AGENT, "dashboard-test")
+        .json(&json!({ "token": link.token().unwrap() }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(started.status(), 201);
+    let set_cookies: Vec<String> = started
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .map(|value| value.to_str().unwrap().to_string())
+        .collect();
+    let body: Value = started.json().await.unwrap();
+
+    let session_cookie = set_cookies
+        .iter()
+        .find(|cookie| cookie.starts_with("formvault_session="))
+        .and_then(|cookie| cookie.split(';').next())
+        .unwrap()
+        .to_string();
+    SignedIn {
+        api_key: registered["api_key"].as_str().unwrap().to_string(),
+        session_cookie,
+        csrf_token: body["csrf_token"].as_str().unwrap().to_string(),
+        set_cookies,
+    }
+}
+
+#[tokio::test]
+async fn session_cookies_are_http_only_secure_and_same_site() {
+    let app = TestApp::spawn().await;
+    let signed_in = sign_in(&app, "cookies@example.com").await;
+
+    let attributes = |name: &str| {
+        signed_in
+            .set_cookies
+            .iter()
+            .find(|cookie| cookie.starts_with(&format!("{}=", name)))
+            .unwrap()
+            .to_string()
+    };
+    let session = attributes("formvault_session");
+    for attribute in [
+        "HttpOnly",
+        "Secure",
+        "SameSite=Strict",
+        "Path=/",
+        "Max-Age=28800",
+    ] {
+        assert!(
+            session.contains(attribute),
+            "{} lacks {}",
+            session,
+            attribute
+        );
+    }
+    // The dashboard reads the CSRF token back after a reload
+    let csrf = attributes("formvault_csrf");
+    assert!(csrf.contains("Secure"));
+    assert!(!csrf.contains("HttpOnly"));
+    assert!(csrf.starts_with(&format!("formvault_csrf={};", signed_in.csrf_token)));
+
+    // Neither token is stored in plaintext
+    let token = signed_in
+        .session_cookie
+        .trim_start_matches("formvault_session=");
+    let (token_hash, csrf_hash, user_agent): (String, String, Option<String>) =
+        sqlx::query_as("SELECT token_hash, csrf_token_hash, user_agent FROM sessions")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_ne!(token_hash, token);
+    assert_ne!(csrf_hash, signed_in.csrf_token);
+    assert_eq!(user_agent.as_deref(), Some("dashboard-test"));
+}
+
+#[tokio::test]
+async fn sessions_need_a_csrf_token_to_change_anything() {
+    let app = TestApp::spawn().await;
+    let signed_in = sign_in(&app, "csrf@example.com").await;
+    let client = reqwest::Client::new();
+
+    let me = client
+        .get(app.url("/developers/me"))
+        .header(COOKIE, signed_in.cookie())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(me.status(), 200);
+    let me: Value = me.json().await.unwrap();
+    assert_eq!(me["email"], "csrf@example.com");
+
+    let create = |csrf_token: Option<&str>| {
+        let mut request = client
+            .post(app.url("/forms"))
+            .header(COOKIE, signed_in.cookie())
+            .json(&json!({ "name": "From the dashboard" }));
+        if let Some(token) = csrf_token {
+            request = request.header(CSRF_HEADER, token);
+        }
+        request.send()
+    };
+    for refused in [None, Some("forged")] {
+        let response = create(refused).await.unwrap();
+        assert_eq!(response.status(), 403);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "FORBIDDEN");
+    }
+    assert_eq!(
+        create(Some(&signed_in.csrf_token)).await.unwrap().status(),
+        201
+    );
+
+    // API keys are not sent by browsers on their own and need no token
+    let with_key = client
+        .post(app.url("/forms"))
+        .bearer_auth(&signed_in.api_key)
+        .json(&json!({ "name": "From a service" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(with_key.status(), 201);
+
+    // The audit log tells the two principals apart
+    let actors: Vec<String> = sqlx::query_scalar(
+        "SELECT actor_type FROM audit_events WHERE action = 'form.created' ORDER BY seq",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(actors, ["session", "api_key"]);
+}
+
+#[tokio::test]
+async fn sessions_have_no_api_key_to_rotate() {
+    let app = TestApp::spawn().await;
+    let signed_in = sign_in(&app, "rotate@example.com").await;
+
+    let response = reqwest::Client::new()
+        .post(app.url("/developers/me/api_key"))
+        .header(COOKIE, signed_in.cookie())
+        .header(CSRF_HEADER, &signed_in.csrf_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn signing_out_revokes_the_session_and_clears_its_cookies() {
+    let app = TestApp::spawn().await;
+    let signed_in = sign_in(&app, "signout@example.com").await;
+    let client = reqwest::Client::new();
+
+    let signed_out = client
+        .delete(app.url("/auth/session"))
+        .header(COOKIE, signed_in.cookie())
+        .header(CSRF_HEADER, &signed_in.csrf_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(signed_out.status(), 204);
+    let cleared: Vec<_> = signed_out.headers().get_all(SET_COOKIE).iter().collect();
+    assert_eq!(cleared.len(), 2);
+    assert!(
+        cleared
+            .iter()
+            .all(|cookie| cookie.to_str().unwrap().contains("Max-Age=0"))
+    );
+
+    let me = client
+        .get(app.url("/developers/me"))
+        .header(COOKIE, signed_in.cookie())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(me.status(), 401);
+}
+
+#[tokio::test]
+async fn sessions_can_be_listed_and_revoked_from_elsewhere() {
+    let app = TestApp::spawn().await;
+    let signed_in = sign_in(&app, "lost@example.com").await;
+    let client = reqwest::Client::new();
+
+    let sessions: Value = client
+        .get(app.url("/auth/sessions"))
+        .bearer_auth(&signed_in.api_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let sessions = sessions.as_array().unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["user_agent"], "dashboard-test");
+    assert!(sessions[0].get("token_hash").is_none());
+    assert!(sessions[0].get("csrf_token_hash").is_none());
+    let id = sessions[0]["id"].as_str().unwrap();
+
+    let revoke = || {
+        client
+            .delete(app.url(&format!("/auth/sessions/{}", id)))
+            .bearer_auth(&signed_in.api_key)
+            .send()
+    };
+    assert_eq!(revoke().await.unwrap().status(), 204);
+    assert_eq!(revoke().await.unwrap().status(), 404);
+
+    let me = client
+        .get(app.url("/developers/me"))
+        .header(COOKIE, signed_in.cookie())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(me.status(), 401);
+}
+
+#[tokio::test]
+async fn expired_sessions_are_rejected() {
+    let app = TestApp::spawn().await;
+    let signed_in = sign_in(&app, "expiry@example.com").await;
+
+    sqlx::query("UPDATE sessions SET expires_at = NOW() - INTERVAL '1 second'")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let me = reqwest::Client::new()
+        .get(app.url("/developers/me"))
+        .header(COOKIE, signed_in.cookie())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(me.status(), 401);
+}
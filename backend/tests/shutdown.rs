@@ -0,0 +1,219 @@
+use std::net::{SocketAddr, TcpListener};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use formvault::jobs::WorkerConfig;
+use formvault::models::formvault::FormVault;
+use formvault::models::public_key::PublicKey;
+use formvault::notifications::Notifier;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::testing::TestDatabase;
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+struct Running {
+    base: String,
+    pool: PgPool,
+    stop: oneshot::Sender<()>,
+    server: JoinHandle<std::io::Result<()>>,
+    /// Outlives `pool`, which the server closes on shutdown
+    database: TestDatabase,
+}
+
+async fn serve(timeout: Duration) -> Running {
+    let database = TestDatabase::from_env().await;
+    let pool = database.pool.clone();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    let workers = WorkerConfig {
+        queue: format!("test-{}", Uuid::new_v4()),
+        concurrency: 1,
+        ..WorkerConfig::default()
+    };
+    let (stop, stopped) = oneshot::channel();
+    let server = FormVault::new(pool.clone(), listener, Notifier::new(None), workers)
+        .with_shutdown_timeout(timeout)
+        .with_shutdown_signal(async {
+            let _ = stopped.await;
+        })
+        .start()
+        .unwrap();
+
+    Running {
+        base: format!("http://{}", addr),
+        pool,
+        stop,
+        server: tokio::spawn(server),
+        database,
+    }
+}
+
+async fn create_form(app: &Running) -> String {
+    let base = &app.base;
+    let client = reqwest::Client::new();
+    let registered: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Shutdown",
+            "email": format!("shutdown-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[3; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    // Forms only accept submissions once the account is verified
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(registered["api_key"].as_str().unwrap())
+        .json(&json!({ "name": "Shutdown" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    form["id"].as_str().unwrap().to_string()
+}
+
+/// Hold the form row so storing a submission to it blocks on the foreign
+/// key check, and return once a submission is waiting
+async fn block_submissions(
+    database: &TestDatabase,
+    conn: &mut PgConnection,
+    form_id: &str,
+    submit: impl FnOnce(),
+) {
+    sqlx::query("BEGIN").execute(&mut *conn).await.unwrap();
+    sqlx::query("SELECT id FROM form_schemas WHERE id = $1::uuid FOR UPDATE")
+        .bind(form_id)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    submit();
+
+    // Not on `conn`: activity is a snapshot for the rest of a transaction
+    let monitor = database.connect().await;
+    let started = Instant::now();
+    loop {
+        let blocked: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM pg_stat_activity WHERE $1 = ANY(pg_blocking_pids(pid))",
+        )
+        .bind(pid)
+        .fetch_one(&monitor)
+        .await
+        .unwrap();
+        if blocked > 0 {
+            return;
+        }
+        assert!(started.elapsed() < Duration::from_secs(5), "never blocked");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn submit(base: &str, form_id: &str) -> JoinHandle<reqwest::Result<reqwest::Response>> {
+    let url = format!("{}/f/{}", base, form_id);
+    tokio::spawn(async move {
+        reqwest::Client::new()
+            .post(url)
+            .json(&json!({ "message": "sent during a deploy" }))
+            .send()
+            .await
+    })
+}
+
+#[tokio::test]
+async fn in_flight_submissions_are_stored_before_stopping() {
+    let app = serve(Duration::from_secs(10)).await;
+    let form_id = create_form(&app).await;
+
+    let mut lock = app
+        .database
+        .connect()
+        .await
+        .acquire()
+        .await
+        .unwrap()
+        .detach();
+    let mut pending = None;
+    block_submissions(&app.database, &mut lock, &form_id, || {
+        pending = Some(submit(&app.base, &form_id))
+    })
+    .await;
+
+    app.stop.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(
+        !app.server.is_finished(),
+        "the server waits for the submission"
+    );
+
+    sqlx::query("COMMIT").execute(&mut lock).await.unwrap();
+    let response = pending.unwrap().await.unwrap().unwrap();
+    assert_eq!(response.status(), 201);
+    let receipt: Value = response.json().await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), app.server)
+        .await
+        .expect("the server stops once drained")
+        .unwrap()
+        .unwrap();
+    assert!(app.pool.is_closed());
+
+    let stored: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM form_submissions WHERE id = $1::uuid")
+            .bind(receipt["id"].as_str().unwrap())
+            .fetch_one(&mut lock)
+            .await
+            .unwrap();
+    assert_eq!(stored, 1);
+}
+
+#[tokio::test]
+async fn shutdown_gives_up_at_the_deadline() {
+    let app = serve(Duration::from_millis(300)).await;
+    let form_id = create_form(&app).await;
+
+    let mut lock = app
+        .database
+        .connect()
+        .await
+        .acquire()
+        .await
+        .unwrap()
+        .detach();
+    let mut pending = None;
+    block_submissions(&app.database, &mut lock, &form_id, || {
+        pending = Some(submit(&app.base, &form_id))
+    })
+    .await;
+
+    let started = Instant::now();
+    app.stop.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), app.server)
+        .await
+        .expect("the deadline is enforced")
+        .unwrap()
+        .unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(300));
+
+    // The abandoned submission is not reported as stored
+    let response = pending.unwrap().await.unwrap();
+    assert!(response.is_err() || !response.unwrap().status().is_success());
+    sqlx::query("ROLLBACK").execute(&mut lock).await.unwrap();
+}
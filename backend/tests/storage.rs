@@ -0,0 +1,373 @@
+use chrono::Utc;
+use formvault::errors::FormVaultError;
+use formvault::models::forms::form_schema::FormSchema;
+use formvault::models::forms::submission::SubmissionStatus;
+use formvault::models::forms::{FormSubmission, SubmissionMetadata};
+use formvault::models::public_key::{KeyAlgorithm, PublicKey, PublicKeyVersion};
+use formvault::models::users::developer::Developer;
+use formvault::models::users::registration::Registration;
+use formvault::models::users::team::{Team, TeamRole};
+use formvault::repositories::api_keys::find_api_keys_by_developer;
+use formvault::repositories::audit::find_events;
+use formvault::repositories::developers_repository::create_developer;
+use formvault::repositories::public_keys::create_public_key;
+use formvault::repositories::teams::{create_team, find_role};
+use formvault::storage::Storage;
+use formvault::testing::TestApp;
+use uuid::Uuid;
+
+fn developer(email: &str) -> Developer {
+    Developer::new(
+        "Storage".to_string(),
+        email.to_string(),
+        PublicKey::x25519_pem(&[3; 32]),
+        KeyAlgorithm::X25519,
+    )
+}
+
+fn submission(form_id: Uuid, n: i64) -> FormSubmission {
+    let mut submission = FormSubmission::new(
+        form_id,
+        format!("ciphertext-{}", n),
+        "wrapped-key".to_string(),
+        SubmissionMetadata {
+            ip_address: Some("203.0.113.7".to_string()),
+            user_agent: None,
+            referrer: Some("https://example.com/contact".to_string()),
+            country: None,
+        },
+    );
+    // Distinct, ordered timestamps regardless of clock resolution
+    submission.created_at = Utc::now() - chrono::Duration::minutes(10 - n);
+    submission
+}
+
+/// Run the same checks against any backend. `team_id` and `key_id` must be
+/// valid references for it.
+async fn conforms(storage: Storage, team_id: Uuid, key_id: Uuid) {
+    // Developers
+    let dev = developer("storage@example.com");
+    storage.developers().insert(&dev).await.unwrap();
+    assert!(matches!(
+        storage
+            .developers()
+            .insert(&developer("storage@example.com"))
+            .await,
+        Err(FormVaultError::DuplicateEmail)
+    ));
+
+    let found = storage
+        .developers()
+        .find_by_email("storage@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id(), dev.id());
+    assert_eq!(found.public_key_algorithm(), KeyAlgorithm::X25519);
+    assert!(found.is_active());
+    assert!(!found.is_verified());
+    assert!(
+        storage
+            .developers()
+            .find_by_email("nobody@example.com")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let other = developer("other@example.com");
+    storage.developers().insert(&other).await.unwrap();
+    let mut renamed = found.clone();
+    renamed.set_contact("Renamed".to_string(), "other@example.com".to_string());
+    assert!(matches!(
+        storage.developers().update(&renamed).await,
+        Err(FormVaultError::DuplicateEmail)
+    ));
+    renamed.set_contact("Renamed".to_string(), "renamed@example.com".to_string());
+    storage.developers().update(&renamed).await.unwrap();
+    let verified_at = Utc::now() - chrono::Duration::hours(1);
+    storage
+        .developers()
+        .mark_verified(dev.id(), verified_at)
+        .await
+        .unwrap();
+    // Verifying again keeps the first time
+    storage
+        .developers()
+        .mark_verified(dev.id(), Utc::now())
+        .await
+        .unwrap();
+    storage.developers().deactivate(dev.id()).await.unwrap();
+    let found = storage
+        .developers()
+        .find_by_id(dev.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.name(), "Renamed");
+    assert_eq!(found.email(), "renamed@example.com");
+    assert!(!found.is_active());
+    assert_eq!(
+        found.email_verified_at().map(|at| at.timestamp_micros()),
+        Some(verified_at.timestamp_micros())
+    );
+
+    // Signups
+    let signup = |key: u8| {
+        Registration::new(
+            "Signup".to_string(),
+            "signup@example.com".to_string(),
+            PublicKey::x25519_pem(&[key; 32]),
+            None,
+        )
+        .unwrap()
+        .0
+    };
+    let registration = signup(5);
+    storage.developers().register(&registration).await.unwrap();
+    let found = storage
+        .developers()
+        .find_by_email("signup@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id(), registration.developer.id());
+    assert!(matches!(
+        storage.developers().register(&signup(6)).await,
+        Err(FormVaultError::DuplicateEmail)
+    ));
+
+    // Forms
+    let older = FormSchema::new("Older".to_string(), dev.id(), team_id);
+    let mut newer = FormSchema::new("Newer".to_string(), dev.id(), team_id);
+    newer.created_at = older.created_at + chrono::Duration::seconds(1);
+    storage.forms().insert(&older).await.unwrap();
+    storage.forms().insert(&newer).await.unwrap();
+    let forms = storage.forms().find_by_developer(dev.id()).await.unwrap();
+    assert_eq!(
+        forms.iter().map(|f| f.id).collect::<Vec<_>>(),
+        vec![newer.id, older.id]
+    );
+
+    newer.key_id = Some(key_id);
+    storage.forms().update_key(&newer).await.unwrap();
+    let form = storage.forms().find_by_id(newer.id).await.unwrap().unwrap();
+    assert_eq!(form.key_id, Some(key_id));
+    assert_eq!(form.team_id, team_id);
+    assert!(form.allowed_origins.is_empty());
+
+    newer
+        .set_allowed_origins(vec!["https://*.example.com".to_string()])
+        .unwrap();
+    newer
+        .set_redirect_urls(Some("https://example.com/thanks".to_string()), None)
+        .unwrap();
+    newer.zero_knowledge = true;
+    storage.forms().update_settings(&newer).await.unwrap();
+    let form = storage.forms().find_by_id(newer.id).await.unwrap().unwrap();
+    assert_eq!(form.allowed_origins, vec!["https://*.example.com"]);
+    assert_eq!(
+        form.success_url.as_deref(),
+        Some("https://example.com/thanks")
+    );
+    assert_eq!(form.error_url, None);
+    assert!(form.zero_knowledge);
+    assert_eq!(form.key_id, Some(key_id));
+    assert!(
+        storage
+            .forms()
+            .find_by_id(Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Submissions
+    assert!(matches!(
+        storage
+            .submissions()
+            .insert(&submission(Uuid::new_v4(), 0))
+            .await,
+        Err(FormVaultError::FormNotFound)
+    ));
+
+    let stored: Vec<_> = (0..3).map(|n| submission(older.id, n)).collect();
+    for s in &stored {
+        storage.submissions().insert(s).await.unwrap();
+    }
+    let all = storage
+        .submissions()
+        .find_by_form(older.id, None, 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        all.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![stored[2].id, stored[1].id, stored[0].id]
+    );
+    let page = storage
+        .submissions()
+        .find_by_form(older.id, Some(1), 1)
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].id, stored[1].id);
+    assert!(
+        storage
+            .submissions()
+            .find_by_form(newer.id, None, 0)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let mut failed = storage
+        .submissions()
+        .find_by_id(stored[0].id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(failed.encrypted_data, "ciphertext-0");
+    assert_eq!(failed.status, SubmissionStatus::New);
+    assert_eq!(
+        failed.metadata.referrer.as_deref(),
+        Some("https://example.com/contact")
+    );
+    failed.status = SubmissionStatus::Failed;
+    failed.failure_reason = Some("webhook returned 502".to_string());
+    storage.submissions().update_status(&failed).await.unwrap();
+    let reread = storage
+        .submissions()
+        .find_by_id(failed.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reread.status, SubmissionStatus::Failed);
+    assert_eq!(
+        reread.failure_reason.as_deref(),
+        Some("webhook returned 502")
+    );
+
+    assert!(matches!(
+        storage
+            .submissions()
+            .update_status(&submission(older.id, 9))
+            .await,
+        Err(FormVaultError::SubmissionNotFound)
+    ));
+}
+
+#[tokio::test]
+async fn memory_backend_conforms() {
+    conforms(Storage::memory(), Uuid::new_v4(), Uuid::new_v4()).await;
+}
+
+#[tokio::test]
+async fn sqlite_backend_conforms() {
+    let storage = Storage::sqlite("sqlite::memory:").await.unwrap();
+    conforms(storage, Uuid::new_v4(), Uuid::new_v4()).await;
+}
+
+#[tokio::test]
+async fn postgres_backend_conforms() {
+    let app = TestApp::spawn().await;
+
+    // Forms reference a team and a key version in PostgreSQL
+    let team = Team::new("Storage".to_string());
+    create_team(&app.pool, &team).await.unwrap();
+    let owner = developer("owner@example.com");
+    create_developer(&app.pool, &owner).await.unwrap();
+    let key = PublicKeyVersion::new(owner.id(), 1, PublicKey::x25519_pem(&[4; 32])).unwrap();
+    create_public_key(&app.pool, &key).await.unwrap();
+
+    conforms(Storage::postgres(app.pool.clone()), team.id, key.id).await;
+}
+
+#[tokio::test]
+async fn postgres_signups_are_stored_in_one_transaction() {
+    let app = TestApp::spawn().await;
+    let storage = Storage::postgres(app.pool.clone());
+    let taken = Team::new("Taken".to_string());
+    create_team(&app.pool, &taken).await.unwrap();
+
+    let signup = || {
+        Registration::new(
+            "Signup".to_string(),
+            "atomic@example.com".to_string(),
+            PublicKey::x25519_pem(&[8; 32]),
+            Some("203.0.113.7".to_string()),
+        )
+        .unwrap()
+        .0
+    };
+    // The team insert fails after the developer's, which is rolled back
+    let mut failing = signup();
+    failing.team.id = taken.id;
+    assert!(storage.developers().register(&failing).await.is_err());
+    assert!(
+        storage
+            .developers()
+            .find_by_email("atomic@example.com")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // so signing up again works
+    let registration = signup();
+    storage.developers().register(&registration).await.unwrap();
+    let developer_id = registration.developer.id();
+    assert_eq!(
+        find_role(&app.pool, registration.team.id, developer_id)
+            .await
+            .unwrap(),
+        Some(TeamRole::Owner)
+    );
+    let keys = find_api_keys_by_developer(&app.pool, developer_id)
+        .await
+        .unwrap();
+    assert_eq!(keys.len(), 1);
+    let events = find_events(&app.pool, developer_id, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].ip_address.as_deref(), Some("203.0.113.7"));
+
+    // Members see the team's forms, whoever created them
+    let colleague = developer("colleague@example.com");
+    create_developer(&app.pool, &colleague).await.unwrap();
+    let shared = FormSchema::new("Shared".to_string(), colleague.id(), registration.team.id);
+    let elsewhere = FormSchema::new("Elsewhere".to_string(), colleague.id(), taken.id);
+    storage.forms().insert(&shared).await.unwrap();
+    storage.forms().insert(&elsewhere).await.unwrap();
+    let forms = storage.forms().find_by_member(developer_id).await.unwrap();
+    assert_eq!(
+        forms.iter().map(|f| f.id).collect::<Vec<_>>(),
+        vec![shared.id]
+    );
+}
+
+#[tokio::test]
+async fn sqlite_files_keep_their_data() {
+    let path = std::env::temp_dir().join(format!("formvault-{}.db", Uuid::new_v4()));
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+    let dev = developer("kept@example.com");
+
+    Storage::sqlite(&url)
+        .await
+        .unwrap()
+        .developers()
+        .insert(&dev)
+        .await
+        .unwrap();
+
+    // Reopening runs the migrations again, which must leave the data alone
+    let reopened = Storage::sqlite(&url).await.unwrap();
+    let found = reopened.developers().find_by_id(dev.id()).await.unwrap();
+    assert_eq!(
+        found.map(|d| d.email().to_string()).as_deref(),
+        Some("kept@example.com")
+    );
+
+    drop(reopened);
+    std::fs::remove_file(&path).ok();
+}
@@ -1 +1,201 @@
-use formvault::repositories;
+use formvault::models::public_key::PublicKey;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+
+fn registration(email: &str) -> Value {
+    json!({
+        "name": "Subscriber",
+        "email": email,
+        "public_key": PublicKey::x25519_pem(&[9; 32]),
+    })
+}
+
+async fn register(app: &TestApp, body: &Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(app.url("/developers"))
+        .json(body)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn developer_count(app: &TestApp) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM developers")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn registering_stores_exactly_one_developer() {
+    let app = TestApp::spawn().await;
+
+    let response = register(&app, &registration("dev@example.com")).await;
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["api_key"].as_str().is_some_and(|key| !key.is_empty()));
+
+    // The database is this test's own, so the count is exact
+    assert_eq!(developer_count(&app).await, 1);
+    let email: String = sqlx::query_scalar("SELECT email FROM developers")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(email, "dev@example.com");
+}
+
+#[tokio::test]
+async fn registering_an_email_twice_is_rejected() {
+    let app = TestApp::spawn().await;
+
+    assert_eq!(
+        register(&app, &registration("twice@example.com"))
+            .await
+            .status(),
+        201
+    );
+    let response = register(&app, &registration("twice@example.com")).await;
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "DUPLICATE_EMAIL");
+    assert_eq!(developer_count(&app).await, 1);
+}
+
+#[tokio::test]
+async fn emails_differing_only_in_case_are_one_address() {
+    let app = TestApp::spawn().await;
+
+    let response = register(&app, &registration("Case@Example.com")).await;
+    assert_eq!(response.status(), 201);
+    let response = register(&app, &registration("case@example.COM")).await;
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "DUPLICATE_EMAIL");
+
+    let email: String = sqlx::query_scalar("SELECT email FROM developers")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(email, "case@example.com");
+
+    // Login links find the account whatever the case
+    let sent = app.outbox.sent().len();
+    let login = reqwest::Client::new()
+        .post(app.url("/auth/login"))
+        .json(&json!({ "email": "CASE@example.com" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(login.status().is_success());
+    assert_eq!(app.outbox.sent().len(), sent + 1);
+    assert!(app.outbox.last_to("case@example.com").is_some());
+}
+
+#[tokio::test]
+async fn registering_with_an_invalid_public_key_stores_nothing() {
+    let app = TestApp::spawn().await;
+
+    let mut body = registration("badkey@example.com");
+    body["public_key"] = json!("not a key");
+    let response = register(&app, &body).await;
+    assert_eq!(response.status(), 400);
+    assert_eq!(developer_count(&app).await, 0);
+}
+
+#[tokio::test]
+async fn registering_with_an_invalid_email_stores_nothing() {
+    let app = TestApp::spawn().await;
+
+    for email in [
+        "",
+        "no-at-sign",
+        "two@@example.com",
+        "spaces in@example.com",
+    ] {
+        let response = register(&app, &registration(email)).await;
+        assert_eq!(response.status(), 400, "{:?} was accepted", email);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "INVALID_INPUT");
+    }
+    assert_eq!(developer_count(&app).await, 0);
+    assert!(app.outbox.sent().is_empty());
+}
+
+#[tokio::test]
+async fn apps_do_not_share_a_database() {
+    let first = TestApp::spawn().await;
+    let second = TestApp::spawn().await;
+    assert_ne!(first.database_name(), second.database_name());
+
+    // The same email registers on both, and each only sees its own
+    for app in [&first, &second] {
+        assert_eq!(
+            register(app, &registration("same@example.com"))
+                .await
+                .status(),
+            201
+        );
+        assert_eq!(developer_count(app).await, 1);
+    }
+}
+
+#[tokio::test]
+async fn the_database_is_dropped_with_the_app() {
+    let app = TestApp::spawn().await;
+    let name = app.database_name().to_string();
+    let url = std::env::var("DATABASE_URL").unwrap();
+    let server = sqlx::PgPool::connect(&url).await.unwrap();
+
+    let exists = |name: String| {
+        let server = server.clone();
+        async move {
+            sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM pg_database WHERE datname = $1)",
+            )
+            .bind(name)
+            .fetch_one(&server)
+            .await
+            .unwrap()
+        }
+    };
+
+    assert!(exists(name.clone()).await);
+    drop(app);
+    assert!(!exists(name).await);
+}
+
+#[tokio::test]
+async fn concurrent_signups_with_one_email_register_once() {
+    let app = TestApp::spawn().await;
+    let body = registration("race@example.com");
+
+    let mut signups = tokio::task::JoinSet::new();
+    for _ in 0..8 {
+        let (url, body) = (app.url("/developers"), body.clone());
+        signups.spawn(async move {
+            reqwest::Client::new()
+                .post(url)
+                .json(&body)
+                .send()
+                .await
+                .unwrap()
+        });
+    }
+    let responses = signups.join_all().await;
+    let mut statuses: Vec<u16> = responses.iter().map(|r| r.status().as_u16()).collect();
+    statuses.sort();
+    assert_eq!(statuses, [201, 400, 400, 400, 400, 400, 400, 400]);
+
+    for response in responses.into_iter().filter(|r| r.status() == 400) {
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "DUPLICATE_EMAIL");
+    }
+
+    // The losers left nothing behind, not even their key version
+    assert_eq!(developer_count(&app).await, 1);
+    let keys: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM public_keys")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(keys, 1);
+}
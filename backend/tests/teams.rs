@@ -0,0 +1,450 @@
+use formvault::models::public_key::PublicKey;
+use formvault::models::users::team::{Permission, TeamRole};
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+/// Register a fresh developer and return their email and admin API key
+async fn register(client: &reqwest::Client, base: &str, name: &str) -> (String, String) {
+    let email = format!("{}-{}@example.com", name.to_lowercase(), Uuid::new_v4());
+    let body: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": name,
+            "email": email,
+            "public_key": PublicKey::x25519_pem(&[7; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    (email, body["api_key"].as_str().unwrap().to_string())
+}
+
+/// The first team listed for the key, which is the personal team
+async fn personal_team(client: &reqwest::Client, base: &str, key: &str) -> Value {
+    let teams: Value = client
+        .get(format!("{}/teams", base))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    teams[0].clone()
+}
+
+#[test]
+fn roles_are_ordered_by_privilege() {
+    assert!(TeamRole::Viewer.can(Permission::ReadSubmissions));
+    assert!(!TeamRole::Viewer.can(Permission::ManageForms));
+    assert!(TeamRole::Editor.can(Permission::ManageForms));
+    assert!(!TeamRole::Editor.can(Permission::ManageKeys));
+    assert!(TeamRole::Admin.can(Permission::ManageMembers));
+    assert!(TeamRole::Owner.can(Permission::ManageMembers));
+}
+
+#[tokio::test]
+async fn registration_creates_a_personal_team() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let (_, key) = register(&client, &base, "Ada").await;
+
+    let team = personal_team(&client, &base, &key).await;
+    assert_eq!(team["name"], "Ada's team");
+    assert_eq!(team["role"], "owner");
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&key)
+        .json(&json!({ "name": "Contact" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(form["team_id"], team["id"]);
+}
+
+#[tokio::test]
+async fn viewer_can_read_but_not_change_notifications() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let (_, owner_key) = register(&client, &base, "Owner").await;
+    let (viewer_email, viewer_key) = register(&client, &base, "Viewer").await;
+    let team = personal_team(&client, &base, &owner_key).await;
+    let team_id = team["id"].as_str().unwrap();
+
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&owner_key)
+        .json(&json!({ "name": "Shared" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+
+    // Outsiders can't even tell the form exists
+    let hidden = client
+        .get(format!("{}/forms/{}/submissions", base, form_id))
+        .bearer_auth(&viewer_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(hidden.status(), 404);
+
+    let added = client
+        .post(format!("{}/teams/{}/members", base, team_id))
+        .bearer_auth(&owner_key)
+        .json(&json!({ "email": viewer_email, "role": "viewer" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(added.status(), 201);
+
+    let submissions = client
+        .get(format!("{}/forms/{}/submissions", base, form_id))
+        .bearer_auth(&viewer_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(submissions.status(), 200);
+
+    let listed = client
+        .get(format!("{}/forms/{}/notifications", base, form_id))
+        .bearer_auth(&viewer_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(listed.status(), 200);
+
+    let webhook = json!({ "channel": "webhook", "destination": "https://example.com/hook" });
+    let denied = client
+        .post(format!("{}/forms/{}/notifications", base, form_id))
+        .bearer_auth(&viewer_key)
+        .json(&webhook)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(denied.status(), 403);
+
+    let created = client
+        .post(format!("{}/forms/{}/notifications", base, form_id))
+        .bearer_auth(&owner_key)
+        .json(&webhook)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(created.status(), 201);
+
+    let invalid = client
+        .post(format!("{}/forms/{}/notifications", base, form_id))
+        .bearer_auth(&owner_key)
+        .json(&json!({ "channel": "slack", "destination": "not a url" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+
+    // Viewers can't create forms in the team either
+    let form_denied = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(&viewer_key)
+        .json(&json!({ "name": "Nope", "team_id": team_id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(form_denied.status(), 403);
+}
+
+#[tokio::test]
+async fn ownership_is_protected() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+    let (owner_email, owner_key) = register(&client, &base, "Owner").await;
+    let (admin_email, admin_key) = register(&client, &base, "Admin").await;
+    let team = personal_team(&client, &base, &owner_key).await;
+    let team_id = team["id"].as_str().unwrap();
+
+    client
+        .post(format!("{}/teams/{}/members", base, team_id))
+        .bearer_auth(&owner_key)
+        .json(&json!({ "email": admin_email, "role": "admin" }))
+        .send()
+        .await
+        .unwrap();
+
+    let members: Value = client
+        .get(format!("{}/teams/{}/members", base, team_id))
+        .bearer_auth(&admin_key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let owner_id = members
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["email"] == owner_email.as_str())
+        .unwrap()["developer_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // Admins manage members but cannot touch owners
+    let demote = client
+        .patch(format!("{}/teams/{}/members/{}", base, team_id, owner_id))
+        .bearer_auth(&admin_key)
+        .json(&json!({ "role": "viewer" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(demote.status(), 403);
+
+    // The last owner cannot step down
+    let leave = client
+        .delete(format!("{}/teams/{}/members/{}", base, team_id, owner_id))
+        .bearer_auth(&owner_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(leave.status(), 400);
+}
+
+/// The id of the team member with `email`
+async fn member_id(
+    client: &reqwest::Client,
+    base: &str,
+    key: &str,
+    team_id: &str,
+    email: &str,
+) -> String {
+    let members: Value = client
+        .get(format!("{}/teams/{}/members", base, team_id))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    members
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["email"] == email)
+        .unwrap()["developer_id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Create a form in the team and return its id
+async fn create_form(client: &reqwest::Client, base: &str, key: &str, team_id: &str) -> String {
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(key)
+        .json(&json!({ "name": "Team form", "team_id": team_id }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    form["id"].as_str().unwrap().to_string()
+}
+
+/// The form's key as the dashboard shows it
+async fn form_key(client: &reqwest::Client, base: &str, key: &str, form_id: &str) -> Value {
+    client
+        .get(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(key)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn only_key_managers_can_re_key_team_forms() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.as_str();
+    let client = reqwest::Client::new();
+    let (_, owner_key) = register(&client, base, "Owner").await;
+    let (editor_email, editor_key) = register(&client, base, "Editor").await;
+    let (admin_email, admin_key) = register(&client, base, "Admin").await;
+    let team = personal_team(&client, base, &owner_key).await;
+    let team_id = team["id"].as_str().unwrap();
+
+    for (email, role) in [(&editor_email, "editor"), (&admin_email, "admin")] {
+        let added = client
+            .post(format!("{}/teams/{}/members", base, team_id))
+            .bearer_auth(&owner_key)
+            .json(&json!({ "email": email, "role": role }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(added.status(), 201);
+    }
+
+    // An editor's form is pinned to the key they have now, so rotating
+    // their own key leaves it alone
+    let form_id = create_form(&client, base, &editor_key, team_id).await;
+    let pinned = form_key(&client, base, &owner_key, &form_id).await;
+    assert_eq!(pinned["source"], "pinned");
+
+    let rotated = client
+        .put(format!("{}/developers/me/public_key", base))
+        .bearer_auth(&editor_key)
+        .json(&json!({ "public_key": PublicKey::x25519_pem(&[9; 32]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rotated.status(), 200);
+    let published: Value = client
+        .get(app.url(&format!("/f/{}/key", form_id)))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(published["key_id"], pinned["id"]);
+
+    // Neither the editor nor the owner can make it follow the editor's key
+    let unpin = json!({ "key_id": null });
+    let denied = client
+        .put(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&editor_key)
+        .json(&unpin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(denied.status(), 403);
+    let refused = client
+        .put(format!("{}/forms/{}/key", base, form_id))
+        .bearer_auth(&owner_key)
+        .json(&unpin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(refused.status(), 400);
+
+    // An admin's form follows their key until they are demoted
+    let admin_form = create_form(&client, base, &admin_key, team_id).await;
+    let inherited = form_key(&client, base, &owner_key, &admin_form).await;
+    assert_eq!(inherited["source"], "inherited");
+    let admin_id = member_id(&client, base, &owner_key, team_id, &admin_email).await;
+    let demoted = client
+        .patch(format!("{}/teams/{}/members/{}", base, team_id, admin_id))
+        .bearer_auth(&owner_key)
+        .json(&json!({ "role": "editor" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(demoted.status(), 200);
+    let pinned_on_demotion = form_key(&client, base, &owner_key, &admin_form).await;
+    assert_eq!(pinned_on_demotion["source"], "pinned");
+    assert_eq!(pinned_on_demotion["id"], inherited["id"]);
+
+    // Once the editor is removed, nothing is encrypted to their key
+    let editor_id = member_id(&client, base, &owner_key, team_id, &editor_email).await;
+    let removed = client
+        .delete(format!("{}/teams/{}/members/{}", base, team_id, editor_id))
+        .bearer_auth(&owner_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(removed.status(), 204);
+    let revoked = client
+        .get(app.url(&format!("/f/{}/key", form_id)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoked.status(), 409);
+    let body: Value = revoked.json().await.unwrap();
+    assert_eq!(body["code"], "KEY_REVOKED");
+
+    // A new key of the form's own brings it back
+    let rekeyed = client
+        .put(format!("{}/forms/{}/public_key", base, form_id))
+        .bearer_auth(&owner_key)
+        .json(&json!({ "public_key": PublicKey::x25519_pem(&[11; 32]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rekeyed.status(), 200);
+    let published: Value = client
+        .get(app.url(&format!("/f/{}/key", form_id)))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_ne!(published["key_id"], pinned["id"]);
+}
+
+#[tokio::test]
+async fn concurrent_demotions_keep_an_owner() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.as_str();
+    let client = reqwest::Client::new();
+    let (first_email, first_key) = register(&client, base, "First").await;
+    let (second_email, second_key) = register(&client, base, "Second").await;
+    let team = personal_team(&client, base, &first_key).await;
+    let team_id = team["id"].as_str().unwrap();
+
+    let added = client
+        .post(format!("{}/teams/{}/members", base, team_id))
+        .bearer_auth(&first_key)
+        .json(&json!({ "email": second_email, "role": "owner" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(added.status(), 201);
+    let first_id = member_id(&client, base, &first_key, team_id, &first_email).await;
+    let second_id = member_id(&client, base, &first_key, team_id, &second_email).await;
+
+    // Each owner demotes the other at the same time; only one may win
+    let demote = |key: &str, developer_id: &str| {
+        client
+            .patch(format!(
+                "{}/teams/{}/members/{}",
+                base, team_id, developer_id
+            ))
+            .bearer_auth(key)
+            .json(&json!({ "role": "editor" }))
+            .send()
+    };
+    let (first, second) = tokio::join!(
+        demote(&first_key, &second_id),
+        demote(&second_key, &first_id)
+    );
+    let mut statuses = [first.unwrap().status(), second.unwrap().status()];
+    statuses.sort();
+    assert_eq!(statuses, [200, 400]);
+
+    let owners: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM team_members WHERE team_id = $1::uuid AND role = 'owner'",
+    )
+    .bind(team_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(owners, 1);
+}
@@ -0,0 +1,118 @@
+use chrono::Utc;
+use formvault::models::public_key::PublicKey;
+use formvault::repositories::developers_repository::mark_email_verified;
+use formvault::testing::TestApp;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+fn request_id(response: &reqwest::Response) -> String {
+    response.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn errors_carry_the_request_id() {
+    let app = TestApp::spawn().await;
+    let response = reqwest::get(app.url("/forms")).await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let id = request_id(&response);
+    assert!(Uuid::parse_str(&id).is_ok(), "generated IDs are UUIDs");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["request_id"], id);
+    assert_eq!(body["code"], "UNAUTHORIZED");
+}
+
+#[tokio::test]
+async fn request_ids_from_proxies_are_kept_when_sane() {
+    let app = TestApp::spawn().await;
+    let client = reqwest::Client::new();
+    let url = app.url("/health_check");
+
+    let kept = client
+        .get(&url)
+        .header("X-Request-Id", "edge-7f3a:1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(request_id(&kept), "edge-7f3a:1");
+
+    for rejected in ["has spaces", &"x".repeat(129), ""] {
+        let response = client
+            .get(&url)
+            .header("X-Request-Id", rejected)
+            .send()
+            .await
+            .unwrap();
+        assert!(Uuid::parse_str(&request_id(&response)).is_ok());
+    }
+}
+
+#[tokio::test]
+async fn jobs_remember_the_request_that_enqueued_them() {
+    let app = TestApp::spawn().await;
+    let base = app.base_url.clone();
+    let client = reqwest::Client::new();
+
+    let registered: Value = client
+        .post(format!("{}/developers", base))
+        .json(&json!({
+            "name": "Tracing",
+            "email": format!("tracing-{}@example.com", Uuid::new_v4()),
+            "public_key": PublicKey::x25519_pem(&[9; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    // Forms only accept submissions once the account is verified
+    let developer_id = Uuid::parse_str(registered["id"].as_str().unwrap()).unwrap();
+    mark_email_verified(&app.pool, developer_id, Utc::now())
+        .await
+        .unwrap();
+    let api_key = registered["api_key"].as_str().unwrap();
+    let form: Value = client
+        .post(format!("{}/forms", base))
+        .bearer_auth(api_key)
+        .json(&json!({ "name": "Tracing" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+    let created = client
+        .post(format!("{}/forms/{}/notifications", base, form_id))
+        .bearer_auth(api_key)
+        .json(&json!({ "channel": "webhook", "destination": "http://127.0.0.1:1/hook" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(created.status().is_success());
+
+    let request_id = format!("complaint-{}", Uuid::new_v4());
+    let receipt: Value = client
+        .post(format!("{}/f/{}", base, form_id))
+        .header("X-Request-Id", &request_id)
+        .json(&json!({ "message": "hi" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let stored: Option<String> =
+        sqlx::query_scalar("SELECT request_id FROM jobs WHERE payload->>'submission_id' = $1")
+            .bind(receipt["id"].as_str().unwrap())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(stored, Some(request_id));
+}
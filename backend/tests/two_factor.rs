@@ -0,0 +1,490 @@
+use chrono::Utc;
+use formvault::models::public_key::PublicKey;
+use formvault::testing::TestApp;
+use reqwest::Method;
+use reqwest::header::{COOKIE, SET_COOKIE};
+use serde_json::{Value, json};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const TOTP_HEADER: &str = "X-TOTP-Code";
+
+/// A verified developer with both an API key and a dashboard session
+struct Account {
+    email: String,
+    api_key: String,
+    cookie: String,
+    csrf_token: String,
+}
+
+async fn sign_up(app: &TestApp, email: &str) -> Account {
+    let client = reqwest::Client::new();
+    let registered: Value = client
+        .post(app.url("/developers"))
+        .json(&json!({
+            "name": "Two Factor",
+            "email": email,
+            "public_key": PublicKey::x25519_pem(&[6; 32]),
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    app.verify_email(email).await;
+
+    let started = start_session(app, email, None).await;
+    assert_eq!(started.status(), 201);
+    let session_cookie = started
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find(|cookie| cookie.starts_with("formvault_session="))
+        .and_then(|cookie| cookie.split(';').next())
+        .unwrap()
+        .to_string();
+    let body: Value = started.json().await.unwrap();
+
+    Account {
+        email: email.to_string(),
+        api_key: registered["api_key"].as_str().unwrap().to_string(),
+        cookie: session_cookie,
+        csrf_token: body["csrf_token"].as_str().unwrap().to_string(),
+    }
+}
+
+/// Follow a fresh login link, with `code` as the second factor
+async fn start_session(app: &TestApp, email: &str, code: Option<&str>) -> reqwest::Response {
+    let client = reqwest::Client::new();
+    client
+        .post(app.url("/auth/login"))
+        .json(&json!({ "email": email }))
+        .send()
+        .await
+        .unwrap();
+    let link = app.outbox.last_to(email).unwrap();
+    client
+        .post(app.url("/auth/session"))
+        .json(&json!({ "token": link.token().unwrap(), "code": code }))
+        .send()
+        .await
+        .unwrap()
+}
+
+impl Account {
+    /// A request from the dashboard session
+    fn in_dashboard(&self, app: &TestApp, method: Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, app.url(path))
+            .header(COOKIE, &self.cookie)
+            .header("X-CSRF-Token", &self.csrf_token)
+    }
+
+    /// A request with the API key
+    fn with_key(&self, app: &TestApp, method: Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, app.url(path))
+            .bearer_auth(&self.api_key)
+    }
+
+    /// Enroll and confirm an authenticator; returns it with the recovery codes
+    async fn enable_two_factor(&self, app: &TestApp) -> (TOTP, Vec<String>) {
+        let enrolled: Value = self
+            .in_dashboard(app, Method::POST, "/developers/me/two_factor")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let totp = authenticator(enrolled["secret"].as_str().unwrap());
+
+        let confirmed = self
+            .in_dashboard(app, Method::POST, "/developers/me/two_factor/confirm")
+            .json(&json!({ "code": code_at(&totp, 0) }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(confirmed.status(), 200);
+        let body: Value = confirmed.json().await.unwrap();
+        let codes = body["recovery_codes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|code| code.as_str().unwrap().to_string())
+            .collect();
+        (totp, codes)
+    }
+}
+
+/// The authenticator app, set up with `secret`
+fn authenticator(secret: &str) -> TOTP {
+    TOTP::new_unchecked(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        Secret::Encoded(secret.to_string()).to_bytes().unwrap(),
+        None,
+        String::new(),
+    )
+}
+
+/// The code `steps` 30-second steps from now; the server allows one either way
+fn code_at(totp: &TOTP, steps: i64) -> String {
+    totp.generate((Utc::now().timestamp() + steps * 30) as u64)
+}
+
+async fn error_code(response: reqwest::Response) -> String {
+    let body: Value = response.json().await.unwrap();
+    body["code"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn enrolling_takes_a_session_and_a_code_from_the_app() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "enroll@example.com").await;
+
+    // A leaked API key can't put its own authenticator in front of the account
+    let with_key = account
+        .with_key(&app, Method::POST, "/developers/me/two_factor")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(with_key.status(), 403);
+
+    let enrolled = account
+        .in_dashboard(&app, Method::POST, "/developers/me/two_factor")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(enrolled.status(), 201);
+    let enrolled: Value = enrolled.json().await.unwrap();
+    let secret = enrolled["secret"].as_str().unwrap();
+    let uri = enrolled["provisioning_uri"].as_str().unwrap();
+    assert!(uri.starts_with("otpauth://totp/FormVault:enroll%40example.com?"));
+    assert!(uri.contains(&format!("secret={}", secret)));
+    assert!(uri.contains("issuer=FormVault"));
+
+    let wrong = account
+        .in_dashboard(&app, Method::POST, "/developers/me/two_factor/confirm")
+        .json(&json!({ "code": "000000" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(wrong.status(), 400);
+    let status: Value = account
+        .with_key(&app, Method::GET, "/developers/me/two_factor")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["enabled"], false);
+
+    let confirmed = account
+        .in_dashboard(&app, Method::POST, "/developers/me/two_factor/confirm")
+        .json(&json!({ "code": code_at(&authenticator(secret), 0) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(confirmed.status(), 200);
+    let body: Value = confirmed.json().await.unwrap();
+    let codes = body["recovery_codes"].as_array().unwrap();
+    assert_eq!(codes.len(), 10);
+
+    // Only hashes of the recovery codes are kept
+    let hashes: Vec<String> = sqlx::query_scalar("SELECT code_hash FROM recovery_codes")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(hashes.len(), 10);
+    for code in codes {
+        let plain = code.as_str().unwrap().replace('-', "");
+        assert!(!hashes.iter().any(|hash| hash.contains(&plain)));
+    }
+
+    let status: Value = account
+        .with_key(&app, Method::GET, "/developers/me/two_factor")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["enabled"], true);
+    assert_eq!(status["recovery_codes_left"], 10);
+
+    // Enrolling again would swap the authenticator without a code
+    let again = account
+        .in_dashboard(&app, Method::POST, "/developers/me/two_factor")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(again.status(), 400);
+}
+
+#[tokio::test]
+async fn sensitive_actions_need_the_second_factor() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "sensitive@example.com").await;
+    let (totp, recovery_codes) = account.enable_two_factor(&app).await;
+
+    let rotate = || account.with_key(&app, Method::POST, "/developers/me/api_key");
+    let missing = rotate().send().await.unwrap();
+    assert_eq!(missing.status(), 403);
+    assert_eq!(error_code(missing).await, "TWO_FACTOR_REQUIRED");
+    let wrong = rotate().header(TOTP_HEADER, "123456").send().await.unwrap();
+    assert_eq!(wrong.status(), 403);
+
+    // The code confirming enrollment was for this step, so use the next one
+    let code = code_at(&totp, 1);
+    let rotated = rotate().header(TOTP_HEADER, &code).send().await.unwrap();
+    assert_eq!(rotated.status(), 200);
+    let rotated: Value = rotated.json().await.unwrap();
+    let api_key = rotated["api_key"].as_str().unwrap();
+
+    let rotate_public_key = || {
+        reqwest::Client::new()
+            .put(app.url("/developers/me/public_key"))
+            .bearer_auth(api_key)
+            .json(&json!({ "public_key": PublicKey::x25519_pem(&[7; 32]) }))
+    };
+    // A code works once, even within its 30 seconds
+    let replayed = rotate_public_key()
+        .header(TOTP_HEADER, &code)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(replayed.status(), 403);
+
+    // Recovery codes stand in for the app, once each
+    let recovered = rotate_public_key()
+        .header(TOTP_HEADER, recovery_codes[0].to_uppercase())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(recovered.status(), 200);
+    let reused = rotate_public_key()
+        .header(TOTP_HEADER, &recovery_codes[0])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(reused.status(), 403);
+
+    let deactivate = || {
+        reqwest::Client::new()
+            .delete(app.url("/developers/me"))
+            .bearer_auth(api_key)
+    };
+    assert_eq!(deactivate().send().await.unwrap().status(), 403);
+    let deactivated = deactivate()
+        .header(TOTP_HEADER, &recovery_codes[1])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(deactivated.status(), 204);
+}
+
+#[tokio::test]
+async fn api_keys_need_the_second_factor() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "keys@example.com").await;
+    let (_, recovery_codes) = account.enable_two_factor(&app).await;
+
+    let create = || {
+        account
+            .in_dashboard(&app, Method::POST, "/api_keys")
+            .json(&json!({ "name": "CI", "scopes": ["read_submissions"] }))
+    };
+    let missing = create().send().await.unwrap();
+    assert_eq!(missing.status(), 403);
+    assert_eq!(error_code(missing).await, "TWO_FACTOR_REQUIRED");
+    let created = create()
+        .header(TOTP_HEADER, &recovery_codes[0])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(created.status(), 201);
+    let created: Value = created.json().await.unwrap();
+
+    let path = format!("/api_keys/{}", created["id"].as_str().unwrap());
+    let revoke = || account.in_dashboard(&app, Method::DELETE, &path);
+    let missing = revoke().send().await.unwrap();
+    assert_eq!(missing.status(), 403);
+    assert_eq!(error_code(missing).await, "TWO_FACTOR_REQUIRED");
+    let revoked = revoke()
+        .header(TOTP_HEADER, &recovery_codes[1])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoked.status(), 204);
+}
+
+#[tokio::test]
+async fn form_keys_need_the_second_factor() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "formkeys@example.com").await;
+    let form: Value = account
+        .with_key(&app, Method::POST, "/forms")
+        .json(&json!({ "name": "Keyed" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let form_id = form["id"].as_str().unwrap();
+    let (_, recovery_codes) = account.enable_two_factor(&app).await;
+
+    let rekey_path = format!("/forms/{}/public_key", form_id);
+    let rekey = || {
+        account
+            .with_key(&app, Method::PUT, &rekey_path)
+            .json(&json!({ "public_key": PublicKey::x25519_pem(&[8; 32]) }))
+    };
+    let missing = rekey().send().await.unwrap();
+    assert_eq!(missing.status(), 403);
+    assert_eq!(error_code(missing).await, "TWO_FACTOR_REQUIRED");
+    let rekeyed = rekey()
+        .header(TOTP_HEADER, &recovery_codes[0])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rekeyed.status(), 200);
+
+    let pin_path = format!("/forms/{}/key", form_id);
+    let unpin = || {
+        account
+            .in_dashboard(&app, Method::PUT, &pin_path)
+            .json(&json!({ "key_id": null }))
+    };
+    let missing = unpin().send().await.unwrap();
+    assert_eq!(missing.status(), 403);
+    assert_eq!(error_code(missing).await, "TWO_FACTOR_REQUIRED");
+    let unpinned = unpin()
+        .header(TOTP_HEADER, &recovery_codes[1])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unpinned.status(), 200);
+}
+
+#[tokio::test]
+async fn login_links_need_the_second_factor() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "login2fa@example.com").await;
+    let (totp, _) = account.enable_two_factor(&app).await;
+
+    let without = start_session(&app, &account.email, None).await;
+    assert_eq!(without.status(), 403);
+    assert_eq!(error_code(without).await, "TWO_FACTOR_REQUIRED");
+
+    // The link survives a missing code, so the person can type one in
+    let link = app.outbox.last_to(&account.email).unwrap();
+    let started = reqwest::Client::new()
+        .post(app.url("/auth/session"))
+        .json(&json!({ "token": link.token().unwrap(), "code": code_at(&totp, 1) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(started.status(), 201);
+}
+
+#[tokio::test]
+async fn wrong_codes_lock_the_second_factor_for_a_while() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "lockout@example.com").await;
+    let (totp, recovery_codes) = account.enable_two_factor(&app).await;
+
+    let deactivate = |code: &str| {
+        account
+            .with_key(&app, Method::DELETE, "/developers/me")
+            .header(TOTP_HEADER, code)
+    };
+    for _ in 0..5 {
+        assert_eq!(deactivate("999999").send().await.unwrap().status(), 403);
+    }
+
+    // Even right codes are refused until the lockout ends
+    for code in [code_at(&totp, 1), recovery_codes[0].clone()] {
+        let locked = deactivate(&code).send().await.unwrap();
+        assert_eq!(locked.status(), 429);
+        assert_eq!(error_code(locked).await, "TOO_MANY_ATTEMPTS");
+    }
+
+    sqlx::query("UPDATE totp_credentials SET locked_until = NOW() - INTERVAL '1 second'")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        deactivate(&code_at(&totp, 1))
+            .send()
+            .await
+            .unwrap()
+            .status(),
+        204
+    );
+}
+
+#[tokio::test]
+async fn recovery_codes_can_be_replaced_and_two_factor_turned_off() {
+    let app = TestApp::spawn().await;
+    let account = sign_up(&app, "disable@example.com").await;
+    let (totp, old_codes) = account.enable_two_factor(&app).await;
+
+    let regenerated = account
+        .with_key(
+            &app,
+            Method::POST,
+            "/developers/me/two_factor/recovery_codes",
+        )
+        .header(TOTP_HEADER, code_at(&totp, 1))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(regenerated.status(), 200);
+    let body: Value = regenerated.json().await.unwrap();
+    let new_codes = body["recovery_codes"].as_array().unwrap();
+    assert_eq!(new_codes.len(), 10);
+
+    let disable = || account.with_key(&app, Method::DELETE, "/developers/me/two_factor");
+    assert_eq!(disable().send().await.unwrap().status(), 403);
+    // The old codes went with the regeneration
+    let stale = disable()
+        .header(TOTP_HEADER, &old_codes[0])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stale.status(), 403);
+    let disabled = disable()
+        .header(TOTP_HEADER, new_codes[0].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(disabled.status(), 204);
+
+    // Without two-factor authentication nothing asks for a code
+    let rotated = account
+        .with_key(&app, Method::POST, "/developers/me/api_key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rotated.status(), 200);
+    let actions: Vec<String> = sqlx::query_scalar(
+        "SELECT action FROM audit_events WHERE action LIKE 'two_factor.%' ORDER BY seq",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        actions,
+        [
+            "two_factor.enabled",
+            "two_factor.recovery_codes_regenerated",
+            "two_factor.disabled"
+        ]
+    );
+}